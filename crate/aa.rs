@@ -1,5 +1,4 @@
-use crate::color::color_delta;
-use crate::read_u32_ne;
+use crate::color::{color_delta, Component};
 
 /// Check if a pixel is likely a part of anti-aliasing;
 /// based on "Anti-aliased Pixel and Intensity Slope Detector" paper by V. Vysniauskas, 2009.
@@ -14,19 +13,23 @@ use crate::read_u32_ne;
 /// 2. Relaxed sibling check: changed from requiring has_many_siblings in both images
 ///    (a AND b) to either image (a OR b). For 1px-wide strokes, the stroke-side
 ///    extreme never has 3+ identical siblings because the feature is too narrow.
-pub fn antialiased(
-    img: &[u8],
+///
+/// Neighbours are taken `step` pixels away (1 for the usual 3×3 neighbourhood), so an
+/// anti-aliased edge spread over two device pixels of a 2x capture is recognised like one
+/// pixel wide at 1x.
+#[allow(clippy::too_many_arguments)]
+pub fn antialiased<T: Component>(
+    img: &[T],
     x1: usize,
     y1: usize,
     width: usize,
     height: usize,
-    img_a: &[u8],
-    img_b: &[u8],
+    step: usize,
+    img_a: &[T],
+    img_b: &[T],
 ) -> bool {
-    let x0 = x1.saturating_sub(1);
-    let y0 = y1.saturating_sub(1);
-    let x2 = (x1 + 1).min(width - 1);
-    let y2 = (y1 + 1).min(height - 1);
+    let (x0, x2) = neighbours(x1, step, width);
+    let (y0, y2) = neighbours(y1, step, height);
     let pos = (y1 * width + x1) * 4;
     let mut zeroes: i32 = if x1 == x0 || x1 == x2 || y1 == y0 || y1 == y2 { 1 } else { 0 };
     let mut min: f64 = 0.0;
@@ -39,8 +42,8 @@ pub fn antialiased(
     let mut n: usize = 0;
 
     // Pass 1: find min/max brightness deltas and count equal neighbours
-    for x in x0..=x2 {
-        for y in y0..=y2 {
+    for x in (x0..=x2).step_by(step) {
+        for y in (y0..=y2).step_by(step) {
             if x == x1 && y == y1 {
                 continue;
             }
@@ -73,8 +76,8 @@ pub fn antialiased(
         let delta = deltas[i];
         if delta == min || delta == max {
             let (x, y) = coords[i];
-            if has_many_siblings(img_a, x, y, width, height)
-                || has_many_siblings(img_b, x, y, width, height)
+            if has_many_siblings(img_a, x, y, width, height, step)
+                || has_many_siblings(img_b, x, y, width, height, step)
             {
                 return true;
             }
@@ -83,24 +86,22 @@ pub fn antialiased(
     false
 }
 
-/// Check if a pixel has 3+ adjacent pixels of the same colour.
-/// Uses unchecked u32 reads from byte buffer for fast comparison.
+/// Check if a pixel has 3+ adjacent pixels (`step` pixels away) of the same colour.
+/// Byte buffers are compared a pixel at a time with unchecked u32 reads.
 #[inline]
-fn has_many_siblings(img: &[u8], x1: usize, y1: usize, width: usize, height: usize) -> bool {
-    let x0 = x1.saturating_sub(1);
-    let y0 = y1.saturating_sub(1);
-    let x2 = (x1 + 1).min(width - 1);
-    let y2 = (y1 + 1).min(height - 1);
-    let val = read_u32_ne(img, (y1 * width + x1) * 4);
+fn has_many_siblings<T: Component>(img: &[T], x1: usize, y1: usize, width: usize, height: usize, step: usize) -> bool {
+    let (x0, x2) = neighbours(x1, step, width);
+    let (y0, y2) = neighbours(y1, step, height);
+    let pos = (y1 * width + x1) * 4;
     let mut zeroes: i32 = if x1 == x0 || x1 == x2 || y1 == y0 || y1 == y2 { 1 } else { 0 };
 
     // Go through 8 adjacent pixels
-    for x in x0..=x2 {
-        for y in y0..=y2 {
+    for x in (x0..=x2).step_by(step) {
+        for y in (y0..=y2).step_by(step) {
             if x == x1 && y == y1 {
                 continue;
             }
-            if val == read_u32_ne(img, (y * width + x) * 4) {
+            if T::same_pixel(img, pos, img, (y * width + x) * 4) {
                 zeroes += 1;
             }
             if zeroes > 2 {
@@ -110,3 +111,10 @@ fn has_many_siblings(img: &[u8], x1: usize, y1: usize, width: usize, height: usi
     }
     false
 }
+
+/// The first and last coordinate of the neighbours `step` away from `c` along an axis of
+/// `len` pixels, each `c` itself where it would fall outside.
+#[inline(always)]
+fn neighbours(c: usize, step: usize, len: usize) -> (usize, usize) {
+    (c.checked_sub(step).unwrap_or(c), if c + step < len { c + step } else { c })
+}
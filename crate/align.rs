@@ -0,0 +1,147 @@
+//! Row alignment between two versions of a page, to tell layout shifts from pixel changes.
+//!
+//! A banner inserted near the top pushes everything below it down, and a plain comparison
+//! reports the whole rest of the page as changed. [`align_rows`] matches identical rows
+//! between the images (a longest common subsequence over per-row hashes) and splits them
+//! into bands: rows that stayed put, rows that moved, rows only one image has, and rows
+//! that genuinely changed. The images may differ in height.
+
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::ops::Range;
+
+/// Largest LCS table, in cells, built for the rows left after trimming the common start and
+/// end; beyond it the remaining rows are reported as one [`BandKind::Changed`] band.
+const MAX_TABLE_CELLS: usize = 1 << 24;
+
+/// How a band of rows relates between the two images.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BandKind {
+    /// Identical rows at the same position.
+    Unchanged,
+    /// Identical rows that moved vertically.
+    Shifted,
+    /// Rows only the second image has.
+    Inserted,
+    /// Rows only the first image has.
+    Removed,
+    /// Rows that differ between the images.
+    Changed,
+}
+
+/// A run of rows: `rows1` in the first image corresponds to `rows2` in the second. One of
+/// the ranges is empty for [`BandKind::Inserted`] and [`BandKind::Removed`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RowBand {
+    pub kind: BandKind,
+    pub rows1: Range<u32>,
+    pub rows2: Range<u32>,
+}
+
+impl RowBand {
+    /// Rows the content moved by (positive is down); 0 unless [`BandKind::Shifted`].
+    pub fn offset(&self) -> i64 {
+        match self.kind {
+            BandKind::Shifted => self.rows2.start as i64 - self.rows1.start as i64,
+            _ => 0,
+        }
+    }
+}
+
+/// Hash of each row of `img`, top to bottom.
+pub(crate) fn row_hashes(img: &[u8], width: u32, height: u32) -> Vec<u64> {
+    let row = width as usize * 4;
+    debug_assert_eq!(img.len(), row * height as usize);
+    if row == 0 {
+        return vec![0; height as usize];
+    }
+    img.chunks_exact(row)
+        .map(|r| {
+            let mut hasher = DefaultHasher::new();
+            r.hash(&mut hasher);
+            hasher.finish()
+        })
+        .collect()
+}
+
+/// Pairs `(i, j)` of matching rows in a longest common subsequence of `a` and `b`, in order,
+/// or `None` if the table would exceed [`MAX_TABLE_CELLS`].
+fn lcs(a: &[u64], b: &[u64]) -> Option<Vec<(usize, usize)>> {
+    let (n, m) = (a.len(), b.len());
+    if n.checked_mul(m)? > MAX_TABLE_CELLS {
+        return None;
+    }
+    // table[i * (m + 1) + j]: LCS length of a[i..] and b[j..]. The cell limit keeps
+    // min(n, m), and so every length, well within u16.
+    let mut table = vec![0u16; (n + 1) * (m + 1)];
+    for i in (0..n).rev() {
+        for j in (0..m).rev() {
+            table[i * (m + 1) + j] = if a[i] == b[j] {
+                table[(i + 1) * (m + 1) + j + 1] + 1
+            } else {
+                table[(i + 1) * (m + 1) + j].max(table[i * (m + 1) + j + 1])
+            };
+        }
+    }
+    let (mut i, mut j, mut pairs) = (0, 0, Vec::new());
+    while i < n && j < m {
+        if a[i] == b[j] {
+            pairs.push((i, j));
+            (i, j) = (i + 1, j + 1);
+        } else if table[(i + 1) * (m + 1) + j] >= table[i * (m + 1) + j + 1] {
+            i += 1;
+        } else {
+            j += 1;
+        }
+    }
+    Some(pairs)
+}
+
+/// Align the rows of two images of the same `width` and split them into bands, in order.
+///
+/// Together the bands cover every row of both images exactly once.
+pub fn align_rows(img1: &[u8], height1: u32, img2: &[u8], height2: u32, width: u32) -> Vec<RowBand> {
+    align_hashes(&row_hashes(img1, width, height1), &row_hashes(img2, width, height2))
+}
+
+/// [`align_rows`] over precomputed [`row_hashes`].
+pub(crate) fn align_hashes(h1: &[u64], h2: &[u64]) -> Vec<RowBand> {
+    let prefix = h1.iter().zip(h2).take_while(|(a, b)| a == b).count();
+    let suffix = h1[prefix..].iter().rev().zip(h2[prefix..].iter().rev()).take_while(|(a, b)| a == b).count();
+    let (end1, end2) = (h1.len() - suffix, h2.len() - suffix);
+
+    let mut pairs: Vec<(usize, usize)> = (0..prefix).map(|i| (i, i)).collect();
+    if let Some(middle) = lcs(&h1[prefix..end1], &h2[prefix..end2]) {
+        pairs.extend(middle.into_iter().map(|(i, j)| (i + prefix, j + prefix)));
+    }
+    pairs.extend((0..suffix).map(|k| (end1 + k, end2 + k)));
+
+    let mut bands = Vec::new();
+    let range = |a: usize, b: usize| a as u32..b as u32;
+    let (mut i, mut j) = (0, 0);
+    let mut k = 0;
+    while k < pairs.len() {
+        let (si, sj) = pairs[k];
+        push_gap(&mut bands, range(i, si), range(j, sj));
+        let mut len = 1;
+        while k + len < pairs.len() && pairs[k + len] == (si + len, sj + len) {
+            len += 1;
+        }
+        let kind = if si == sj { BandKind::Unchanged } else { BandKind::Shifted };
+        bands.push(RowBand { kind, rows1: range(si, si + len), rows2: range(sj, sj + len) });
+        (i, j, k) = (si + len, sj + len, k + len);
+    }
+    push_gap(&mut bands, range(i, h1.len()), range(j, h2.len()));
+    bands
+}
+
+/// Classify the unmatched rows between two runs of matching ones.
+fn push_gap(bands: &mut Vec<RowBand>, rows1: Range<u32>, rows2: Range<u32>) {
+    let kind = match (rows1.is_empty(), rows2.is_empty()) {
+        (true, true) => return,
+        (false, false) => BandKind::Changed,
+        (false, true) => BandKind::Removed,
+        (true, false) => BandKind::Inserted,
+    };
+    bands.push(RowBand { kind, rows1, rows2 });
+}
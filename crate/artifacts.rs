@@ -0,0 +1,153 @@
+//! Failure artifacts for CI: the inputs, diff images and a JSON summary of a comparison,
+//! written to one directory per test so a single upload step can collect them all.
+//!
+//! ```text
+//! target/pixelmatch/<test-name>/
+//! ├── baseline.png
+//! ├── candidate.png
+//! ├── diff.png        # diff drawn over the faded baseline
+//! ├── diff-mask.png   # diff on a transparent background
+//! └── metadata.json
+//! ```
+
+use std::fmt::Write as _;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::io::{write_png, Image};
+use crate::{pixelmatch, MatchResult, Options};
+
+/// Environment variable overriding the artifact root (default `target/pixelmatch`).
+pub const DIR_ENV: &str = "PIXELMATCH_ARTIFACTS_DIR";
+
+/// Writes the artifacts of one comparison into its own directory.
+#[derive(Debug, Clone)]
+pub struct DiffArtifacts {
+    name: String,
+    dir: PathBuf,
+}
+
+impl DiffArtifacts {
+    /// Artifacts for the test `name`, written to `<root>/<name>/`. Characters other than
+    /// ASCII alphanumerics, `-`, `_`, `.` and `/` are replaced with `_`, so a
+    /// `module::test` path can be used as is.
+    ///
+    /// The root is `$PIXELMATCH_ARTIFACTS_DIR` if set, otherwise `pixelmatch` under
+    /// `$CARGO_TARGET_DIR`, or `target/pixelmatch` under `$CARGO_MANIFEST_DIR` (or the
+    /// working directory outside Cargo).
+    pub fn new(name: impl Into<String>) -> Self {
+        let root = match std::env::var_os(DIR_ENV) {
+            Some(dir) => PathBuf::from(dir),
+            None => std::env::var_os("CARGO_TARGET_DIR")
+                .map(PathBuf::from)
+                .unwrap_or_else(|| {
+                    std::env::var_os("CARGO_MANIFEST_DIR").map_or_else(PathBuf::new, PathBuf::from).join("target")
+                })
+                .join("pixelmatch"),
+        };
+        Self::with_root(root, name)
+    }
+
+    /// Artifacts for the test `name`, written to `<root>/<name>/`.
+    pub fn with_root(root: impl Into<PathBuf>, name: impl Into<String>) -> Self {
+        let name = name.into();
+        let dir = root.into().join(sanitize(&name));
+        Self { name, dir }
+    }
+
+    /// The directory the artifacts are written to.
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Replace the directory's contents with the artifacts of comparing `baseline` with
+    /// `candidate`.
+    ///
+    /// Returns the comparison result, or `None` when the dimensions differ; in that case
+    /// only the two inputs and `metadata.json` are written.
+    pub fn write(&self, baseline: &Image, candidate: &Image, options: &Options) -> io::Result<Option<MatchResult>> {
+        match fs::remove_dir_all(&self.dir) {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => return Err(e),
+            _ => {}
+        }
+        fs::create_dir_all(&self.dir)?;
+        write_png(self.dir.join("baseline.png"), &baseline.data, baseline.width, baseline.height)?;
+        write_png(self.dir.join("candidate.png"), &candidate.data, candidate.width, candidate.height)?;
+
+        let (width, height) = (baseline.width, baseline.height);
+        let result = if (width, height) == (candidate.width, candidate.height) {
+            let mut diff = vec![0u8; baseline.data.len()];
+            let result = pixelmatch(&baseline.data, &candidate.data, Some(&mut diff), width, height, options)
+                .map_err(io::Error::other)?;
+            write_png(self.dir.join("diff.png"), &diff, width, height)?;
+
+            // Mask mode leaves unchanged pixels untouched, so start from transparent.
+            diff.fill(0);
+            let mask_options = Options { diff_mask: true, ..options.clone() };
+            pixelmatch(&baseline.data, &candidate.data, Some(&mut diff), width, height, &mask_options)
+                .map_err(io::Error::other)?;
+            write_png(self.dir.join("diff-mask.png"), &diff, width, height)?;
+            Some(result)
+        } else {
+            None
+        };
+
+        fs::write(self.dir.join("metadata.json"), self.metadata(baseline, candidate, options, result))?;
+        Ok(result)
+    }
+
+    fn metadata(&self, baseline: &Image, candidate: &Image, options: &Options, result: Option<MatchResult>) -> String {
+        let mut out = String::from("{\n");
+        let _ = writeln!(out, r#"  "name": "{}","#, json_escape(&self.name));
+        let _ = writeln!(out, r#"  "baseline": {{"width": {}, "height": {}}},"#, baseline.width, baseline.height);
+        let _ = writeln!(out, r#"  "candidate": {{"width": {}, "height": {}}},"#, candidate.width, candidate.height);
+        let _ = writeln!(
+            out,
+            r#"  "options": {{"threshold": {}, "detect_anti_aliasing": {}}},"#,
+            options.threshold, options.detect_anti_aliasing
+        );
+        match result {
+            Some(r) => {
+                let total = baseline.width as u64 * baseline.height as u64;
+                let ratio = if total == 0 { 0.0 } else { r.diff_count as f64 / total as f64 };
+                let _ = writeln!(out, r#"  "diff_count": {},"#, r.diff_count);
+                let _ = writeln!(out, r#"  "aa_count": {},"#, r.aa_count);
+                let _ = writeln!(out, r#"  "identical": {},"#, r.identical);
+                let _ = writeln!(out, r#"  "total_pixels": {total},"#);
+                let _ = writeln!(out, r#"  "diff_ratio": {ratio},"#);
+                out.push_str(r#"  "files": ["baseline.png", "candidate.png", "diff.png", "diff-mask.png"]"#);
+            }
+            None => {
+                out.push_str("  \"error\": \"dimension mismatch\",\n");
+                out.push_str(r#"  "files": ["baseline.png", "candidate.png"]"#);
+            }
+        }
+        out.push_str("\n}\n");
+        out
+    }
+}
+
+fn sanitize(name: &str) -> String {
+    let name: String = name
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.' | '/') { c } else { '_' })
+        .collect();
+    // Keep the directory inside the root.
+    name.split('/').filter(|s| !s.is_empty() && *s != "." && *s != "..").collect::<Vec<_>>().join("/")
+}
+
+fn json_escape(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            c if (c as u32) < 0x20 => {
+                let _ = write!(out, "\\u{:04x}", c as u32);
+            }
+            c => out.push(c),
+        }
+    }
+    out
+}
@@ -0,0 +1,86 @@
+//! Frame-by-frame comparison of sprite sheets and texture atlases.
+//!
+//! A single diff count over a 4096×4096 atlas says that something changed, not which
+//! sprite. [`compare_frames`] compares each named frame as an image of its own, in
+//! parallel, so neighbouring frames do not affect each other's anti-aliasing detection or
+//! post-processing, and returns a result per frame.
+
+use rayon::prelude::*;
+
+use crate::{ignore_mask, normalize_dpr, pixelmatch, validate_buffers, MatchResult, Options, PixelmatchError, Rect};
+
+/// A named sub-rectangle of an atlas.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Frame {
+    pub name: String,
+    pub rect: Rect,
+}
+
+impl Frame {
+    pub fn new(name: impl Into<String>, rect: Rect) -> Self {
+        Self { name: name.into(), rect }
+    }
+}
+
+/// Result of comparing one [`Frame`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FrameResult {
+    pub name: String,
+    pub rect: Rect,
+    pub result: MatchResult,
+}
+
+/// Compare `frames` of two equally sized atlases, returning a result per frame in order.
+///
+/// `options` apply to every frame. Ignore regions, polygons and masks are given in atlas
+/// coordinates, as is every frame when the atlases have different
+/// [`device_pixel_ratio`](Options::device_pixel_ratio)s: `width` and `height` then describe
+/// the lower-ratio atlas, as for [`pixelmatch`]. Fails with
+/// [`PixelmatchError::RegionOutOfBounds`] if a frame extends past the atlas.
+pub fn compare_frames(
+    img1: &[u8],
+    img2: &[u8],
+    width: u32,
+    height: u32,
+    frames: &[Frame],
+    options: &Options,
+) -> Result<Vec<FrameResult>, PixelmatchError> {
+    options.validate()?;
+    let [img1, img2] = normalize_dpr(img1, img2, width, height, options)?;
+    let (img1, img2) = (&*img1, &*img2);
+    validate_buffers(img1, img2, None, width, height)?;
+    let fits = |start: u32, len: u32, size: u32| start.checked_add(len).is_some_and(|end| end <= size);
+    for frame in frames {
+        let r = frame.rect;
+        if !(fits(r.x, r.width, width) && fits(r.y, r.height, height)) {
+            return Err(PixelmatchError::RegionOutOfBounds { region: r, width, height });
+        }
+    }
+    let ignore = ignore_mask(options, width as usize, height as usize)?;
+
+    frames
+        .par_iter()
+        .map(|frame| {
+            let r = frame.rect;
+            let frame_options = Options {
+                ignore_regions: Vec::new(),
+                ignore_polygons: Vec::new(),
+                ignore_mask: ignore.as_deref().map(|mask| crop(mask, width, 1, r).into_iter().map(u8::from).collect()),
+                include_only: false,
+                // The images are already at the lower ratio; keep it for `density_scaling`.
+                device_pixel_ratio: options.device_pixel_ratio.map(|[a, b]| [a.min(b); 2]),
+                ..options.clone()
+            };
+            let (crop1, crop2) = (crop(img1, width, 4, r), crop(img2, width, 4, r));
+            let result = pixelmatch(&crop1, &crop2, None, r.width, r.height, &frame_options)?;
+            Ok(FrameResult { name: frame.name.clone(), rect: r, result })
+        })
+        .collect()
+}
+
+/// Copy the pixels of `rect` out of a row-major image `width` pixels wide with
+/// `channels` entries per pixel.
+fn crop<T: Copy>(data: &[T], width: u32, channels: usize, rect: Rect) -> Vec<T> {
+    let (w, x, rw) = (width as usize * channels, rect.x as usize * channels, rect.width as usize * channels);
+    (rect.y as usize..(rect.y + rect.height) as usize).flat_map(|y| &data[y * w + x..][..rw]).copied().collect()
+}
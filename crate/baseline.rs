@@ -0,0 +1,302 @@
+//! Golden-file management for custom test harnesses.
+//!
+//! A [`BaselineStore`] maps names to baseline images and, optionally, the options they
+//! should be compared with. [`FsBaselineStore`] keeps them on disk as `<name>.png` and
+//! `<name>.toml`; implement the trait to keep baselines elsewhere (an object store, a
+//! database) while reusing the compare/record/approve logic.
+
+use std::fmt::Write as _;
+use std::fs;
+use std::io;
+use std::path::{Component, Path, PathBuf};
+
+use crate::io::{read_png, write_png, Image};
+use crate::{pixelmatch, MatchResult, Options, Rect};
+
+/// Outcome of comparing a candidate against a stored baseline.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BaselineComparison {
+    /// No baseline is stored under the name.
+    Missing,
+    /// The baseline and candidate have different dimensions.
+    DimensionMismatch { baseline: (u32, u32), candidate: (u32, u32) },
+    /// The images were compared.
+    Compared(MatchResult),
+}
+
+/// Storage for named baseline images and their per-baseline options.
+pub trait BaselineStore {
+    /// The baseline stored under `name`, if any.
+    fn load(&self, name: &str) -> io::Result<Option<Image>>;
+
+    /// Store `image` as the baseline for `name`, replacing any existing one.
+    fn save(&self, name: &str, image: &Image) -> io::Result<()>;
+
+    /// The options stored for `name`, if any.
+    fn load_options(&self, name: &str) -> io::Result<Option<Options>>;
+
+    /// Store the options used to compare against `name`.
+    fn save_options(&self, name: &str, options: &Options) -> io::Result<()>;
+
+    /// Compare `candidate` with the baseline for `name`, using the stored options if
+    /// present and `defaults` otherwise.
+    fn compare(&self, name: &str, candidate: &Image, defaults: &Options) -> io::Result<BaselineComparison> {
+        let Some(baseline) = self.load(name)? else {
+            return Ok(BaselineComparison::Missing);
+        };
+        let stored = self.load_options(name)?;
+        let options = stored.as_ref().unwrap_or(defaults);
+        let sizes = ((baseline.width, baseline.height), (candidate.width, candidate.height));
+        let Some((width, height)) = options.compared_size(sizes.0, sizes.1) else {
+            return Ok(BaselineComparison::DimensionMismatch { baseline: sizes.0, candidate: sizes.1 });
+        };
+        let result =
+            pixelmatch(&baseline.data, &candidate.data, None, width, height, options).map_err(io::Error::other)?;
+        Ok(BaselineComparison::Compared(result))
+    }
+
+    /// Store `image` as the baseline for `name` only if none exists yet. Returns whether
+    /// it was recorded.
+    fn record_new(&self, name: &str, image: &Image) -> io::Result<bool> {
+        if self.load(name)?.is_some() {
+            return Ok(false);
+        }
+        self.save(name, image)?;
+        Ok(true)
+    }
+
+    /// Accept `image` as the new baseline for `name`.
+    fn approve(&self, name: &str, image: &Image) -> io::Result<()> {
+        self.save(name, image)
+    }
+}
+
+/// A [`BaselineStore`] backed by a directory: `<root>/<name>.png` holds the image and
+/// `<root>/<name>.toml` the options. Names may contain `/` to group baselines.
+///
+/// The options file holds `threshold`, `detect_anti_aliasing`, `alpha`, `aa_color`,
+/// `diff_color`, `diff_color_alt`, `diff_mask`, `shift_tolerance`, `subpixel_tolerance`,
+/// `jpeg_tolerance`, `scroll_compensation`, `isolation_radius`, `morphology` (as
+/// `"dilate:<r>"`/`"erode:<r>"` strings), `label_shift`, `pre_blur`, `quantize`, `median_filter`,
+/// `edges` (as a `"sobel:<t>"`/`"canny:<low>,<high>"` string), `downscale`,
+/// `device_pixel_ratio`, `density_scaling`, `ignore_regions` (as `[x, y, w, h]` arrays),
+/// `include_only` and `ignore_hatch`; missing keys take their defaults.
+/// `ignore_polygons` and `ignore_mask` are not stored.
+#[derive(Debug, Clone)]
+pub struct FsBaselineStore {
+    root: PathBuf,
+}
+
+impl FsBaselineStore {
+    /// A store rooted at `root`. The directory is created on first write.
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    /// The root directory.
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Path of the baseline image for `name`.
+    pub fn image_path(&self, name: &str) -> io::Result<PathBuf> {
+        self.path(name, "png")
+    }
+
+    /// Path of the options file for `name`.
+    pub fn options_path(&self, name: &str) -> io::Result<PathBuf> {
+        self.path(name, "toml")
+    }
+
+    fn path(&self, name: &str, extension: &str) -> io::Result<PathBuf> {
+        let relative = Path::new(name);
+        if name.is_empty() || !relative.components().all(|c| matches!(c, Component::Normal(_))) {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, format!("invalid baseline name \"{name}\"")));
+        }
+        Ok(self.root.join(format!("{name}.{extension}")))
+    }
+}
+
+impl BaselineStore for FsBaselineStore {
+    fn load(&self, name: &str) -> io::Result<Option<Image>> {
+        match read_png(self.image_path(name)?) {
+            Ok(image) => Ok(Some(image)),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    fn save(&self, name: &str, image: &Image) -> io::Result<()> {
+        let path = self.image_path(name)?;
+        create_parent(&path)?;
+        write_png(path, &image.data, image.width, image.height)
+    }
+
+    fn load_options(&self, name: &str) -> io::Result<Option<Options>> {
+        let path = self.options_path(name)?;
+        match fs::read_to_string(&path) {
+            Ok(text) => parse_options(&text)
+                .map(Some)
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, format!("{}: {e}", path.display()))),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    fn save_options(&self, name: &str, options: &Options) -> io::Result<()> {
+        let path = self.options_path(name)?;
+        create_parent(&path)?;
+        fs::write(path, format_options(options))
+    }
+}
+
+fn create_parent(path: &Path) -> io::Result<()> {
+    match path.parent() {
+        Some(parent) => fs::create_dir_all(parent),
+        None => Ok(()),
+    }
+}
+
+fn format_options(o: &Options) -> String {
+    let rgb = |c: [u8; 3]| format!("[{}, {}, {}]", c[0], c[1], c[2]);
+    let mut out = String::new();
+    let _ = writeln!(out, "threshold = {:?}", o.threshold);
+    let _ = writeln!(out, "detect_anti_aliasing = {}", o.detect_anti_aliasing);
+    let _ = writeln!(out, "alpha = {:?}", o.alpha);
+    let _ = writeln!(out, "aa_color = {}", rgb(o.aa_color));
+    let _ = writeln!(out, "diff_color = {}", rgb(o.diff_color));
+    if let Some(alt) = o.diff_color_alt {
+        let _ = writeln!(out, "diff_color_alt = {}", rgb(alt));
+    }
+    let _ = writeln!(out, "diff_mask = {}", o.diff_mask);
+    if o.shift_tolerance > 0 {
+        let _ = writeln!(out, "shift_tolerance = {}", o.shift_tolerance);
+    }
+    if o.subpixel_tolerance {
+        let _ = writeln!(out, "subpixel_tolerance = true");
+    }
+    if let Some(t) = o.jpeg_tolerance {
+        let _ = writeln!(out, "jpeg_tolerance = {t:?}");
+    }
+    if o.scroll_compensation > 0 {
+        let _ = writeln!(out, "scroll_compensation = {}", o.scroll_compensation);
+    }
+    if o.isolation_radius > 0 {
+        let _ = writeln!(out, "isolation_radius = {}", o.isolation_radius);
+    }
+    if !o.morphology.is_empty() {
+        let ops: Vec<String> = o.morphology.iter().map(|op| format!("\"{op}\"")).collect();
+        let _ = writeln!(out, "morphology = [{}]", ops.join(", "));
+    }
+    if o.label_shift > 0 {
+        let _ = writeln!(out, "label_shift = {}", o.label_shift);
+    }
+    if let Some(sigma) = o.pre_blur {
+        let _ = writeln!(out, "pre_blur = {sigma:?}");
+    }
+    if let Some(colors) = o.quantize {
+        let _ = writeln!(out, "quantize = {colors}");
+    }
+    if o.median_filter {
+        let _ = writeln!(out, "median_filter = true");
+    }
+    if let Some(detector) = o.edges {
+        let _ = writeln!(out, "edges = \"{detector}\"");
+    }
+    if let Some(scale) = o.downscale {
+        let _ = writeln!(out, "downscale = {scale:?}");
+    }
+    if let Some([dpr1, dpr2]) = o.device_pixel_ratio {
+        let _ = writeln!(out, "device_pixel_ratio = [{dpr1:?}, {dpr2:?}]");
+    }
+    if o.density_scaling {
+        let _ = writeln!(out, "density_scaling = true");
+    }
+    if !o.ignore_regions.is_empty() {
+        let regions: Vec<String> =
+            o.ignore_regions.iter().map(|r| format!("[{}, {}, {}, {}]", r.x, r.y, r.width, r.height)).collect();
+        let _ = writeln!(out, "ignore_regions = [{}]", regions.join(", "));
+    }
+    if o.include_only {
+        let _ = writeln!(out, "include_only = true");
+    }
+    if let Some(hatch) = o.ignore_hatch {
+        let _ = writeln!(out, "ignore_hatch = {}", rgb(hatch));
+    }
+    out
+}
+
+fn parse_options(text: &str) -> Result<Options, String> {
+    let mut o = Options::default();
+    for (i, line) in text.lines().enumerate() {
+        let line = line.split('#').next().unwrap_or_default().trim();
+        if line.is_empty() {
+            continue;
+        }
+        let (key, value) = line.split_once('=').ok_or_else(|| format!("line {}: expected key = value", i + 1))?;
+        let (key, value) = (key.trim(), value.trim());
+        let invalid = || format!("line {}: invalid value for {key}: {value}", i + 1);
+        let numbers = || -> Result<Vec<u32>, String> {
+            value
+                .split(|c: char| matches!(c, '[' | ']' | ',') || c.is_whitespace())
+                .filter(|s| !s.is_empty())
+                .map(|s| s.parse().map_err(|_| invalid()))
+                .collect()
+        };
+        let rgb = || -> Result<[u8; 3], String> {
+            match numbers()?[..] {
+                [r, g, b] if r.max(g).max(b) <= 255 => Ok([r as u8, g as u8, b as u8]),
+                _ => Err(invalid()),
+            }
+        };
+        match key {
+            "threshold" => o.threshold = value.parse().map_err(|_| invalid())?,
+            "detect_anti_aliasing" => o.detect_anti_aliasing = value.parse().map_err(|_| invalid())?,
+            "alpha" => o.alpha = value.parse().map_err(|_| invalid())?,
+            "aa_color" => o.aa_color = rgb()?,
+            "diff_color" => o.diff_color = rgb()?,
+            "diff_color_alt" => o.diff_color_alt = Some(rgb()?),
+            "diff_mask" => o.diff_mask = value.parse().map_err(|_| invalid())?,
+            "shift_tolerance" => o.shift_tolerance = value.parse().map_err(|_| invalid())?,
+            "subpixel_tolerance" => o.subpixel_tolerance = value.parse().map_err(|_| invalid())?,
+            "jpeg_tolerance" => o.jpeg_tolerance = Some(value.parse().map_err(|_| invalid())?),
+            "scroll_compensation" => o.scroll_compensation = value.parse().map_err(|_| invalid())?,
+            "isolation_radius" => o.isolation_radius = value.parse().map_err(|_| invalid())?,
+            "morphology" => {
+                o.morphology = value
+                    .trim_matches(|c| c == '[' || c == ']')
+                    .split(',')
+                    .map(|op| op.trim().trim_matches('"'))
+                    .filter(|op| !op.is_empty())
+                    .map(|op| op.parse().map_err(|_| invalid()))
+                    .collect::<Result<_, _>>()?;
+            }
+            "label_shift" => o.label_shift = value.parse().map_err(|_| invalid())?,
+            "pre_blur" => o.pre_blur = Some(value.parse().map_err(|_| invalid())?),
+            "quantize" => o.quantize = Some(value.parse().map_err(|_| invalid())?),
+            "median_filter" => o.median_filter = value.parse().map_err(|_| invalid())?,
+            "edges" => o.edges = Some(value.trim_matches('"').parse().map_err(|_| invalid())?),
+            "downscale" => o.downscale = Some(value.parse().map_err(|_| invalid())?),
+            "device_pixel_ratio" => {
+                let ratios: Vec<f32> = value
+                    .trim_matches(|c| c == '[' || c == ']')
+                    .split(',')
+                    .map(|r| r.trim().parse().map_err(|_| invalid()))
+                    .collect::<Result<_, _>>()?;
+                o.device_pixel_ratio = Some(ratios.try_into().map_err(|_| invalid())?);
+            }
+            "density_scaling" => o.density_scaling = value.parse().map_err(|_| invalid())?,
+            "ignore_regions" => {
+                let n = numbers()?;
+                if !n.len().is_multiple_of(4) {
+                    return Err(invalid());
+                }
+                o.ignore_regions = n.chunks_exact(4).map(|r| Rect::new(r[0], r[1], r[2], r[3])).collect();
+            }
+            "include_only" => o.include_only = value.parse().map_err(|_| invalid())?,
+            "ignore_hatch" => o.ignore_hatch = Some(rgb()?),
+            _ => return Err(format!("line {}: unknown key {key}", i + 1)),
+        }
+    }
+    Ok(o)
+}
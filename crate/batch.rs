@@ -0,0 +1,39 @@
+use rayon::prelude::*;
+
+use crate::{pixelmatch, MatchResult, Options, PixelmatchError};
+
+/// One image pair of a batch comparison.
+#[derive(Debug)]
+pub struct BatchItem<'a> {
+    pub img1: &'a [u8],
+    pub img2: &'a [u8],
+    /// Where to write the diff image, if wanted.
+    pub output: Option<&'a mut [u8]>,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl<'a> BatchItem<'a> {
+    /// A pair compared without writing a diff image.
+    pub fn new(img1: &'a [u8], img2: &'a [u8], width: u32, height: u32) -> Self {
+        Self { img1, img2, output: None, width, height }
+    }
+
+    /// Write the diff image of this pair to `output`.
+    pub fn with_output(mut self, output: &'a mut [u8]) -> Self {
+        self.output = Some(output);
+        self
+    }
+}
+
+/// Compare many image pairs in parallel with the same options.
+///
+/// Pairs are distributed across the rayon thread pool (each comparison is itself
+/// row-parallel, so small and large images balance out). Results are returned in input
+/// order; an invalid pair yields an error without affecting the others.
+pub fn pixelmatch_batch(items: &mut [BatchItem<'_>], options: &Options) -> Vec<Result<MatchResult, PixelmatchError>> {
+    items
+        .par_iter_mut()
+        .map(|item| pixelmatch(item.img1, item.img2, item.output.as_deref_mut(), item.width, item.height, options))
+        .collect()
+}
@@ -0,0 +1,94 @@
+//! Smoothing filters used by [`Options::pre_blur`](crate::Options::pre_blur) and
+//! [`Options::median_filter`](crate::Options::median_filter).
+
+use rayon::prelude::*;
+
+/// Normalised 1-D Gaussian kernel covering ±3 sigma.
+fn kernel(sigma: f32) -> Vec<f32> {
+    let radius = (3.0 * sigma).ceil() as i64;
+    let weights: Vec<f32> =
+        (-radius..=radius).map(|i| (-((i * i) as f32) / (2.0 * sigma * sigma)).exp()).collect();
+    let total: f32 = weights.iter().sum();
+    weights.into_iter().map(|w| w / total).collect()
+}
+
+/// Blur an RGBA image with a separable Gaussian of standard deviation `sigma` pixels.
+///
+/// Colour channels are weighted by alpha, as in [`crate::resize::resize_box`], so transparent
+/// pixels do not darken their neighbours; edges are clamped. A `sigma` that is not a
+/// positive number returns a copy of the image.
+pub fn gaussian_blur(img: &[u8], width: u32, height: u32, sigma: f32) -> Vec<u8> {
+    let (w, h) = (width as usize, height as usize);
+    debug_assert_eq!(img.len(), w * h * 4);
+    if !sigma.is_finite() || sigma <= 0.0 || w == 0 || h == 0 {
+        return img.to_vec();
+    }
+    let kernel = kernel(sigma);
+    let radius = (kernel.len() / 2) as isize;
+    let tap = |i: usize, k: usize, len: usize| (i as isize + k as isize - radius).clamp(0, len as isize - 1) as usize;
+
+    // Premultiplied copy, blurred along rows.
+    let premultiplied: Vec<[f32; 4]> = img
+        .chunks_exact(4)
+        .map(|p| {
+            let a = p[3] as f32;
+            [p[0] as f32 * a, p[1] as f32 * a, p[2] as f32 * a, a]
+        })
+        .collect();
+    let mut rows = vec![[0.0f32; 4]; w * h];
+    rows.par_chunks_mut(w).enumerate().for_each(|(y, row)| {
+        let src = &premultiplied[y * w..(y + 1) * w];
+        for (x, out) in row.iter_mut().enumerate() {
+            for (k, &weight) in kernel.iter().enumerate() {
+                let p = src[tap(x, k, w)];
+                out.iter_mut().zip(p).for_each(|(o, v)| *o += v * weight);
+            }
+        }
+    });
+
+    // Blur along columns and un-premultiply.
+    let mut out = vec![0u8; img.len()];
+    out.par_chunks_mut(w * 4).enumerate().for_each(|(y, row)| {
+        for x in 0..w {
+            let mut sum = [0.0f32; 4];
+            for (k, &weight) in kernel.iter().enumerate() {
+                let p = rows[tap(y, k, h) * w + x];
+                sum.iter_mut().zip(p).for_each(|(s, v)| *s += v * weight);
+            }
+            let a = sum[3];
+            let px = &mut row[x * 4..x * 4 + 4];
+            if a > 0.0 {
+                for c in 0..3 {
+                    px[c] = (sum[c] / a).round().clamp(0.0, 255.0) as u8;
+                }
+            }
+            px[3] = a.round().clamp(0.0, 255.0) as u8;
+        }
+    });
+    out
+}
+
+/// Replace every channel of every pixel with the median of its 3×3 neighbourhood, removing
+/// isolated outliers (salt-and-pepper noise) while keeping edges sharp. Edges are clamped.
+pub fn median_3x3(img: &[u8], width: u32, height: u32) -> Vec<u8> {
+    let (w, h) = (width as usize, height as usize);
+    debug_assert_eq!(img.len(), w * h * 4);
+    let mut out = vec![0u8; img.len()];
+    if w == 0 {
+        return out;
+    }
+    out.par_chunks_mut(w * 4).enumerate().for_each(|(y, row)| {
+        let ys = [y.saturating_sub(1), y, (y + 1).min(h - 1)];
+        for x in 0..w {
+            let xs = [x.saturating_sub(1), x, (x + 1).min(w - 1)];
+            for c in 0..4 {
+                let mut window = [0u8; 9];
+                for (i, v) in window.iter_mut().enumerate() {
+                    *v = img[(ys[i / 3] * w + xs[i % 3]) * 4 + c];
+                }
+                row[x * 4 + c] = *window.select_nth_unstable(4).1;
+            }
+        }
+    });
+    out
+}
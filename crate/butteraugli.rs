@@ -0,0 +1,115 @@
+//! A Butteraugli-style psychovisual distance (`butteraugli` feature), for codec work where
+//! scores should be tied to the just-noticeable difference (JND).
+//!
+//! Both images are converted to an opsin-like XYB colour space (cube-root compressed cone
+//! responses: red-green opponent X, luminance Y, blue B), split into low, middle and high
+//! spatial frequency bands, and compared band by band. Differences in the middle and high
+//! bands are masked by local contrast, since detail is harder to see on busy texture than
+//! on a flat area. This follows the structure of Google's Butteraugli with simplified
+//! constants; scores are on a similar scale but not identical to libjxl's.
+
+use rayon::prelude::*;
+
+use crate::ssim::gaussian;
+use crate::{validate_buffers, PixelmatchError};
+
+/// Offset added to the cone responses before compression, so dark areas do not dominate.
+const OPSIN_BIAS: f64 = 0.003793;
+/// Cone response mixing of linear red, green and blue, per L, M and S.
+const OPSIN: [[f64; 3]; 3] = [[0.30, 0.622, 0.078], [0.23, 0.692, 0.078], [0.2434, 0.2048, 0.5518]];
+
+/// Standard deviations, in pixels, separating the high/middle and middle/low bands.
+const SIGMA_HIGH: f32 = 1.5;
+const SIGMA_LOW: f32 = 7.0;
+/// Standard deviation of the window local contrast is measured over.
+const SIGMA_MASK: f32 = 2.5;
+/// Local contrast, in Y units, at which masking halves the visibility of a difference.
+const MASK_CONTRAST: f32 = 0.02;
+
+/// Difference in each X, Y, B channel that is just noticeable in each band, low to high
+/// frequency. The low-frequency Y entry makes a flat 1% brightness step on mid-grey about
+/// 1.0; the eye is most sensitive to red-green contrast and least to blue, and least to the
+/// finest detail.
+const JND: [[f32; 3]; 3] = [[0.0008, 0.007, 0.02], [0.0008, 0.005, 0.02], [0.003, 0.01, 0.06]];
+
+/// Result of [`butteraugli`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Butteraugli {
+    /// Largest per-pixel distance: below about 1.0 the images are unlikely to be told
+    /// apart, up to about 2.0 a difference is noticeable on close inspection, and above
+    /// that it is plainly visible.
+    pub score: f64,
+    /// 3-norm of the per-pixel distances, which rates how much of the image is affected
+    /// rather than only the worst spot.
+    pub norm: f64,
+    /// Per-pixel distance in row-major order, in JND units; render it with
+    /// [`crate::ssim::heatmap`] to see where the differences are.
+    pub map: Vec<f32>,
+}
+
+fn to_linear(c: f64) -> f64 {
+    let c = c / 255.0;
+    if c <= 0.04045 { c / 12.92 } else { ((c + 0.055) / 1.055).powf(2.4) }
+}
+
+/// X, Y and B planes of an RGBA image, with translucent pixels blended over white.
+fn xyb(img: &[u8]) -> [Vec<f32>; 3] {
+    let pixels: Vec<[f32; 3]> = img
+        .par_chunks_exact(4)
+        .map(|p| {
+            let alpha = p[3] as f64 / 255.0;
+            let rgb: [f64; 3] = std::array::from_fn(|c| to_linear(255.0 + (p[c] as f64 - 255.0) * alpha));
+            let [l, m, s] = OPSIN.map(|mix| {
+                let response = mix[0] * rgb[0] + mix[1] * rgb[1] + mix[2] * rgb[2] + OPSIN_BIAS;
+                response.cbrt() - OPSIN_BIAS.cbrt()
+            });
+            [((l - m) / 2.0) as f32, ((l + m) / 2.0) as f32, s as f32]
+        })
+        .collect();
+    std::array::from_fn(|c| pixels.iter().map(|p| p[c]).collect())
+}
+
+/// Low, middle and high frequency bands of a plane; they add up to the plane.
+fn bands(plane: &[f32], w: usize, h: usize) -> [Vec<f32>; 3] {
+    let (blurred, low) = rayon::join(|| gaussian(plane, w, h, SIGMA_HIGH), || gaussian(plane, w, h, SIGMA_LOW));
+    let middle = blurred.iter().zip(&low).map(|(b, l)| b - l).collect();
+    let high = plane.iter().zip(&blurred).map(|(p, b)| p - b).collect();
+    [low, middle, high]
+}
+
+/// Butteraugli-style distance between two equally sized RGBA images; identical images
+/// score 0.0. See [`Butteraugli`] for how to read the result.
+pub fn butteraugli(img1: &[u8], img2: &[u8], width: u32, height: u32) -> Result<Butteraugli, PixelmatchError> {
+    validate_buffers(img1, img2, None, width, height)?;
+    let (w, h) = (width as usize, height as usize);
+    if w == 0 || h == 0 {
+        return Ok(Butteraugli { score: 0.0, norm: 0.0, map: Vec::new() });
+    }
+    let decompose = |img: &[u8]| xyb(img).map(|plane| bands(&plane, w, h));
+    let (b1, b2) = rayon::join(|| decompose(img1), || decompose(img2));
+
+    // Contrast masking from the busier of the two images' Y detail.
+    let detail = |b: &[Vec<f32>; 3], i: usize| b[1][i].abs() + b[2][i].abs();
+    let activity: Vec<f32> = (0..w * h).map(|i| detail(&b1[1], i).max(detail(&b2[1], i))).collect();
+    let mask = gaussian(&activity, w, h, SIGMA_MASK);
+
+    let map: Vec<f32> = (0..w * h)
+        .into_par_iter()
+        .map(|i| {
+            let masking = MASK_CONTRAST / (MASK_CONTRAST + mask[i]);
+            let mut sum = 0.0;
+            for (channel, (c1, c2)) in b1.iter().zip(&b2).enumerate() {
+                for band in 0..3 {
+                    let visibility = if band == 0 { 1.0 } else { masking };
+                    let d = (c1[band][i] - c2[band][i]) / JND[band][channel] * visibility;
+                    sum += d * d;
+                }
+            }
+            sum.sqrt()
+        })
+        .collect();
+
+    let score = map.par_iter().copied().reduce(|| 0.0, f32::max) as f64;
+    let cubes = map.par_iter().map(|&d| (d as f64).powi(3)).sum::<f64>();
+    Ok(Butteraugli { score, norm: (cubes / map.len() as f64).cbrt(), map })
+}
@@ -0,0 +1,283 @@
+//! C ABI for embedding the engine in C, C++, Go (cgo), .NET (P/Invoke) and similar.
+//!
+//! `pixelmatch_version` and `pixelmatch_compare_flat` use only scalars and pointers, so they
+//! can be bound from `Deno.dlopen` and `bun:ffi`; the npm native build exports them too.
+//!
+//! The matching header is `include/pixelmatch.h`, generated by
+//! `cbindgen --config cbindgen.toml --output include/pixelmatch.h`.
+
+use std::ffi::{c_char, CStr};
+use std::panic::{catch_unwind, AssertUnwindSafe};
+
+use crate::{pixelmatch, Options, PixelmatchError};
+
+/// Comparison options. Start from `pixelmatch_default_options()` and override fields.
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct PixelmatchOptions {
+    /// Matching threshold (0 to 1); smaller is more sensitive.
+    pub threshold: f64,
+    /// Whether to detect and exclude anti-aliased pixels from the diff count.
+    pub detect_anti_aliasing: bool,
+    /// Opacity of the original image in the diff output.
+    pub alpha: f64,
+    /// Colour of anti-aliased pixels in the diff output.
+    pub aa_color: [u8; 3],
+    /// Colour of different pixels in the diff output.
+    pub diff_color: [u8; 3],
+    /// Whether `diff_color_alt` is used for dark-on-light differences.
+    pub has_diff_color_alt: bool,
+    /// Alternative diff colour for dark-on-light differences.
+    pub diff_color_alt: [u8; 3],
+    /// Draw the diff over a transparent background (a mask).
+    pub diff_mask: bool,
+}
+
+impl From<&PixelmatchOptions> for Options {
+    fn from(o: &PixelmatchOptions) -> Self {
+        Options {
+            threshold: o.threshold,
+            detect_anti_aliasing: o.detect_anti_aliasing,
+            alpha: o.alpha,
+            aa_color: o.aa_color,
+            diff_color: o.diff_color,
+            diff_color_alt: o.has_diff_color_alt.then_some(o.diff_color_alt),
+            diff_mask: o.diff_mask,
+            ..Default::default()
+        }
+    }
+}
+
+/// Result of a comparison.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PixelmatchResult {
+    /// Number of mismatched pixels.
+    pub diff_count: u32,
+    /// Number of anti-aliased pixels detected.
+    pub aa_count: u32,
+    /// Whether the two images are byte-identical.
+    pub identical: bool,
+}
+
+/// Status code returned by `pixelmatch_compare`.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PixelmatchStatus {
+    Ok = 0,
+    /// A required pointer argument was null.
+    NullPointer = 1,
+    /// Width * height overflows addressable memory.
+    DimensionOverflow = 2,
+    /// `len` does not match width * height * 4.
+    BufferLengthMismatch = 3,
+    /// An option value was rejected.
+    InvalidArgument = 4,
+    /// An internal error; no result was produced.
+    Internal = 5,
+}
+
+impl From<PixelmatchError> for PixelmatchStatus {
+    fn from(e: PixelmatchError) -> Self {
+        match e {
+            PixelmatchError::DimensionOverflow => Self::DimensionOverflow,
+            PixelmatchError::BufferLengthMismatch { .. }
+            | PixelmatchError::ImageSizeMismatch { .. }
+            | PixelmatchError::OutputSizeMismatch { .. } => Self::BufferLengthMismatch,
+            PixelmatchError::IgnoreMaskSizeMismatch { .. }
+            | PixelmatchError::BandSizeMismatch { .. }
+            | PixelmatchError::IncompleteImage { .. }
+            | PixelmatchError::InvalidThreshold(_)
+            | PixelmatchError::InvalidAlpha(_)
+            | PixelmatchError::InvalidBlur(_)
+            | PixelmatchError::InvalidColorCount(_)
+            | PixelmatchError::InvalidScale(_)
+            | PixelmatchError::InvalidPixelRatio(_)
+            | PixelmatchError::ConflictingOptions(_)
+            | PixelmatchError::InvalidAngle(_)
+            | PixelmatchError::InvalidTolerance(_)
+            | PixelmatchError::RegionOutOfBounds { .. }
+            | PixelmatchError::InvalidJson(_)
+            | PixelmatchError::InvalidEnv { .. } => Self::InvalidArgument,
+        }
+    }
+}
+
+/// The default options.
+#[no_mangle]
+pub extern "C" fn pixelmatch_default_options() -> PixelmatchOptions {
+    let d = Options::default();
+    PixelmatchOptions {
+        threshold: d.threshold,
+        detect_anti_aliasing: d.detect_anti_aliasing,
+        alpha: d.alpha,
+        aa_color: d.aa_color,
+        diff_color: d.diff_color,
+        has_diff_color_alt: d.diff_color_alt.is_some(),
+        diff_color_alt: d.diff_color_alt.unwrap_or(d.diff_color),
+        diff_mask: d.diff_mask,
+    }
+}
+
+/// Compare two RGBA images of `len` bytes each (`width * height * 4`).
+///
+/// `output` may be null; otherwise it receives the `len`-byte diff image. `options` may be
+/// null for the defaults. On success `*result` is filled in and `PIXELMATCH_STATUS_OK` is
+/// returned; on failure `*result` is left untouched.
+///
+/// # Safety
+/// `img1` and `img2` must point to `len` readable bytes, `output` (if not null) to `len`
+/// writable bytes not overlapping the inputs, and `options` (if not null) and `result` to
+/// valid structs.
+#[no_mangle]
+pub unsafe extern "C" fn pixelmatch_compare(
+    img1: *const u8,
+    img2: *const u8,
+    output: *mut u8,
+    len: usize,
+    width: u32,
+    height: u32,
+    options: *const PixelmatchOptions,
+    result: *mut PixelmatchResult,
+) -> PixelmatchStatus {
+    let options = options.as_ref().map_or_else(Options::default, Options::from);
+    compare(img1, img2, output, len, width, height, &options, result)
+}
+
+/// `pixelmatch_compare` with the options given as a NUL-terminated UTF-8 JSON object, which
+/// reaches every option rather than only those in `PixelmatchOptions`. Keys are camelCase
+/// (`{"threshold": 0.2, "ignoreRegions": [{"x": 0, "y": 0, "width": 10, "height": 10}]}`);
+/// missing keys keep their defaults. `options_json` may be null for the defaults.
+/// Malformed JSON, unknown keys and invalid values give `PIXELMATCH_STATUS_INVALID_ARGUMENT`.
+///
+/// # Safety
+/// As for `pixelmatch_compare`; `options_json` (if not null) must point to a NUL-terminated
+/// string.
+#[no_mangle]
+pub unsafe extern "C" fn pixelmatch_compare_json(
+    img1: *const u8,
+    img2: *const u8,
+    output: *mut u8,
+    len: usize,
+    width: u32,
+    height: u32,
+    options_json: *const c_char,
+    result: *mut PixelmatchResult,
+) -> PixelmatchStatus {
+    let options = if options_json.is_null() {
+        Options::default()
+    } else {
+        let Ok(json) = CStr::from_ptr(options_json).to_str() else {
+            return PixelmatchStatus::InvalidArgument;
+        };
+        match Options::from_json(json) {
+            Ok(options) => options,
+            Err(e) => return e.into(),
+        }
+    };
+    compare(img1, img2, output, len, width, height, &options, result)
+}
+
+/// The body of the `pixelmatch_compare` variants once their options are read.
+#[allow(clippy::too_many_arguments)]
+unsafe fn compare(
+    img1: *const u8,
+    img2: *const u8,
+    output: *mut u8,
+    len: usize,
+    width: u32,
+    height: u32,
+    options: &Options,
+    result: *mut PixelmatchResult,
+) -> PixelmatchStatus {
+    if img1.is_null() || img2.is_null() || result.is_null() {
+        return PixelmatchStatus::NullPointer;
+    }
+    let img1 = std::slice::from_raw_parts(img1, len);
+    let img2 = std::slice::from_raw_parts(img2, len);
+    let output = (!output.is_null()).then(|| std::slice::from_raw_parts_mut(output, len));
+    let compared = catch_unwind(AssertUnwindSafe(|| pixelmatch(img1, img2, output, width, height, options)));
+    match compared {
+        Ok(Ok(r)) => {
+            *result = PixelmatchResult { diff_count: r.diff_count, aa_count: r.aa_count, identical: r.identical };
+            PixelmatchStatus::Ok
+        }
+        Ok(Err(e)) => e.into(),
+        Err(_) => PixelmatchStatus::Internal,
+    }
+}
+
+/// A static, NUL-terminated description of a status code.
+#[no_mangle]
+pub extern "C" fn pixelmatch_status_message(status: PixelmatchStatus) -> *const c_char {
+    let message: &'static [u8] = match status {
+        PixelmatchStatus::Ok => b"ok\0",
+        PixelmatchStatus::NullPointer => b"a required pointer argument was null\0",
+        PixelmatchStatus::DimensionOverflow => b"width * height overflows addressable memory\0",
+        PixelmatchStatus::BufferLengthMismatch => b"buffer length does not match width * height * 4\0",
+        PixelmatchStatus::InvalidArgument => b"invalid argument\0",
+        PixelmatchStatus::Internal => b"internal error\0",
+    };
+    message.as_ptr().cast()
+}
+
+/// The library version, as a static NUL-terminated string.
+#[no_mangle]
+pub extern "C" fn pixelmatch_version() -> *const c_char {
+    concat!(env!("CARGO_PKG_VERSION"), "\0").as_ptr().cast()
+}
+
+/// Number of `double`s read from the `options` array of `pixelmatch_compare_flat`.
+pub const PIXELMATCH_FLAT_OPTIONS_LEN: usize = 14;
+
+/// Number of `uint32_t`s written to the `result` array of `pixelmatch_compare_flat`.
+pub const PIXELMATCH_FLAT_RESULT_LEN: usize = 3;
+
+/// `pixelmatch_compare` for FFIs without struct support (Deno, Bun).
+///
+/// `options` is null for the defaults, or `PIXELMATCH_FLAT_OPTIONS_LEN` doubles:
+/// `[threshold, detect_anti_aliasing, alpha, aa_r, aa_g, aa_b, diff_r, diff_g, diff_b,
+/// has_diff_color_alt, alt_r, alt_g, alt_b, diff_mask]`, with booleans as 0 or 1. `result`
+/// receives `[diff_count, aa_count, identical]`. Returns a `PixelmatchStatus` value.
+///
+/// # Safety
+/// As for `pixelmatch_compare`; `options` (if not null) must point to
+/// `PIXELMATCH_FLAT_OPTIONS_LEN` doubles and `result` to `PIXELMATCH_FLAT_RESULT_LEN`
+/// writable `uint32_t`s.
+#[no_mangle]
+pub unsafe extern "C" fn pixelmatch_compare_flat(
+    img1: *const u8,
+    img2: *const u8,
+    output: *mut u8,
+    len: usize,
+    width: u32,
+    height: u32,
+    options: *const f64,
+    result: *mut u32,
+) -> i32 {
+    if result.is_null() {
+        return PixelmatchStatus::NullPointer as i32;
+    }
+    let options = (!options.is_null()).then(|| {
+        let o = std::slice::from_raw_parts(options, PIXELMATCH_FLAT_OPTIONS_LEN);
+        let rgb = |i: usize| [o[i] as u8, o[i + 1] as u8, o[i + 2] as u8];
+        PixelmatchOptions {
+            threshold: o[0],
+            detect_anti_aliasing: o[1] != 0.0,
+            alpha: o[2],
+            aa_color: rgb(3),
+            diff_color: rgb(6),
+            has_diff_color_alt: o[9] != 0.0,
+            diff_color_alt: rgb(10),
+            diff_mask: o[13] != 0.0,
+        }
+    });
+    let options_ptr = options.as_ref().map_or(std::ptr::null(), |o| o as *const PixelmatchOptions);
+    let mut r = PixelmatchResult::default();
+    let status = pixelmatch_compare(img1, img2, output, len, width, height, options_ptr, &mut r);
+    if status == PixelmatchStatus::Ok {
+        let out = std::slice::from_raw_parts_mut(result, PIXELMATCH_FLAT_RESULT_LEN);
+        out.copy_from_slice(&[r.diff_count, r.aa_count, u32::from(r.identical)]);
+    }
+    status as i32
+}
@@ -0,0 +1,163 @@
+//! Classification of differing pixels by which part of the colour changed.
+//!
+//! A failure made entirely of [`DiffCause::ChromaOnly`] or [`DiffCause::AlphaOnly`] pixels
+//! usually points at colour management or compositing, while [`DiffCause::Structural`]
+//! pixels point at content or layout changes. [`channel_counts`] breaks the same pixels
+//! down by RGBA channel: a colour-profile regression tends to move one or two channels, an
+//! alpha-compositing one the alpha channel, and general rendering changes all of them.
+
+use rayon::prelude::*;
+
+use crate::color::{read_rgba, yiq_terms};
+use crate::{diff_mask, normalize_dpr, scroll, Options, PixelmatchError};
+
+/// Why a pixel was counted as different.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DiffCause {
+    /// Only the alpha channel differs.
+    AlphaOnly,
+    /// The brightness difference alone exceeds the threshold; the colour tint does not.
+    LuminanceOnly,
+    /// The colour tint difference alone exceeds the threshold; the brightness does not.
+    ChromaOnly,
+    /// Both brightness and tint differ beyond the threshold.
+    Structural,
+}
+
+impl DiffCause {
+    /// Classify a pixel pair that differs beyond `max_delta` (the squared-delta bound derived
+    /// from `threshold`). When neither part exceeds the bound on its own, the larger decides.
+    fn of(img1: &[u8], img2: &[u8], pos: usize, max_delta: f64) -> Self {
+        let ([r1, g1, b1, _], [r2, g2, b2, _]) = (read_rgba(img1, pos), read_rgba(img2, pos));
+        if (r1, g1, b1) == (r2, g2, b2) {
+            return Self::AlphaOnly;
+        }
+        let (luma, chroma) = yiq_terms(img1, img2, pos, pos);
+        match (luma > max_delta, chroma > max_delta) {
+            (true, true) => Self::Structural,
+            (true, false) => Self::LuminanceOnly,
+            (false, true) => Self::ChromaOnly,
+            (false, false) if luma >= chroma => Self::LuminanceOnly,
+            (false, false) => Self::ChromaOnly,
+        }
+    }
+}
+
+/// Number of differing pixels per [`DiffCause`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct CauseCounts {
+    pub alpha_only: u32,
+    pub luminance_only: u32,
+    pub chroma_only: u32,
+    pub structural: u32,
+}
+
+impl CauseCounts {
+    /// Count the classified pixels of a map returned by [`diff_causes`].
+    pub fn tally(causes: &[Option<DiffCause>]) -> Self {
+        let mut counts = Self::default();
+        for cause in causes.iter().flatten() {
+            *counts.get_mut(*cause) += 1;
+        }
+        counts
+    }
+
+    /// The count for `cause`.
+    pub fn get(&self, cause: DiffCause) -> u32 {
+        match cause {
+            DiffCause::AlphaOnly => self.alpha_only,
+            DiffCause::LuminanceOnly => self.luminance_only,
+            DiffCause::ChromaOnly => self.chroma_only,
+            DiffCause::Structural => self.structural,
+        }
+    }
+
+    fn get_mut(&mut self, cause: DiffCause) -> &mut u32 {
+        match cause {
+            DiffCause::AlphaOnly => &mut self.alpha_only,
+            DiffCause::LuminanceOnly => &mut self.luminance_only,
+            DiffCause::ChromaOnly => &mut self.chroma_only,
+            DiffCause::Structural => &mut self.structural,
+        }
+    }
+
+    /// Total number of differing pixels.
+    pub fn total(&self) -> u32 {
+        self.alpha_only + self.luminance_only + self.chroma_only + self.structural
+    }
+}
+
+/// Number of differing pixels in which each channel changed substantially: by at least half
+/// as much as the channel that changed most at that pixel. A pixel can count towards several
+/// channels, e.g. a grey that got darker counts towards red, green and blue.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ChannelCounts {
+    pub red: u32,
+    pub green: u32,
+    pub blue: u32,
+    pub alpha: u32,
+}
+
+/// The [`ChannelCounts`] of the pixels [`diff_mask`] marks, judged from the same pixels as
+/// [`diff_causes`]. Pixels counted only through `morphology` dilation have no changed
+/// channel and are left out. This compares the images again rather than reading anything
+/// from a [`MatchResult`](crate::MatchResult), which carries no per-channel counts.
+pub fn channel_counts(
+    img1: &[u8],
+    img2: &[u8],
+    width: u32,
+    height: u32,
+    options: &Options,
+) -> Result<ChannelCounts, PixelmatchError> {
+    let changed = classify_marked(img1, img2, width, height, options, |img1, img2, pos| {
+        let (p1, p2) = (read_rgba(img1, pos), read_rgba(img2, pos));
+        let delta: [u8; 4] = std::array::from_fn(|c| p1[c].abs_diff(p2[c]));
+        let max = delta.iter().copied().max().unwrap_or(0);
+        delta.map(|d| max > 0 && u16::from(d) * 2 >= u16::from(max))
+    })?;
+    Ok(changed.iter().flatten().fold(ChannelCounts::default(), |counts, [r, g, b, a]| ChannelCounts {
+        red: counts.red + u32::from(*r),
+        green: counts.green + u32::from(*g),
+        blue: counts.blue + u32::from(*b),
+        alpha: counts.alpha + u32::from(*a),
+    }))
+}
+
+/// The cause of every pixel [`diff_mask`] marks, and `None` for the others, in row-major
+/// order.
+///
+/// Causes are judged from the original pixels (after `device_pixel_ratio` and
+/// `scroll_compensation` are applied, but before any pre-filtering), so pixels counted
+/// only through `morphology` dilation may have equal colours; they are reported as
+/// [`DiffCause::Structural`].
+pub fn diff_causes(
+    img1: &[u8],
+    img2: &[u8],
+    width: u32,
+    height: u32,
+    options: &Options,
+) -> Result<Vec<Option<DiffCause>>, PixelmatchError> {
+    let max_delta = 35215.0 * options.threshold * options.threshold;
+    classify_marked(img1, img2, width, height, options, |img1, img2, pos| {
+        let equal = read_rgba(img1, pos) == read_rgba(img2, pos);
+        if equal { DiffCause::Structural } else { DiffCause::of(img1, img2, pos, max_delta) }
+    })
+}
+
+/// `classify` applied to the byte offset of every pixel [`diff_mask`] marks, with the
+/// images after `device_pixel_ratio` and `scroll_compensation`; `None` for the others.
+fn classify_marked<T: Send>(
+    img1: &[u8],
+    img2: &[u8],
+    width: u32,
+    height: u32,
+    options: &Options,
+    classify: impl Fn(&[u8], &[u8], usize) -> T + Sync,
+) -> Result<Vec<Option<T>>, PixelmatchError> {
+    let mask = diff_mask(img1, img2, width, height, options)?;
+    let [img1, img2] = normalize_dpr(img1, img2, width, height, options)?;
+    let offset = scroll::detect_offset(&img1, &img2, width, height, options.scroll_compensation);
+    let scrolled = (offset != 0).then(|| scroll::shift_rows(&img2, width, height, offset));
+    let img2 = scrolled.as_deref().unwrap_or(&img2);
+    Ok(mask.par_iter().enumerate().map(|(i, &set)| set.then(|| classify(&img1, img2, i * 4))).collect())
+}
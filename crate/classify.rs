@@ -0,0 +1,252 @@
+//! Per-pixel classification for tuning `threshold` and anti-aliasing detection.
+//!
+//! A diff image shows which pixels counted but not why the rest did not. [`classify_pixels`]
+//! records the decision [`pixelmatch`](crate::pixelmatch) made for every pixel, and
+//! [`classification_image`] draws it with one colour per [`PixelClass`], so a threshold
+//! that is too strict shows up as [`PixelClass::BelowThreshold`] pixels turning into
+//! differences and an over-eager anti-aliasing detector as yellow or cyan inside changed
+//! areas. [`pixelmatch_with_callback`] hands the same decisions for the differing and
+//! anti-aliased pixels to a closure, e.g. to feed a spatial index without a diff buffer.
+
+use rayon::prelude::*;
+
+use crate::aa::antialiased;
+use crate::{
+    color_delta, counted, extend_span, ignore_mask, normalize_dpr, prefilter, raw_diff_mask, refine_mask,
+    refines_mask, scroll, validate_buffers, Component, MatchResult, Options, PixelmatchError, Tally,
+};
+
+/// Why a pixel was or was not counted as different.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PixelClass {
+    /// Excluded by the ignore options.
+    Ignored,
+    /// Byte-identical in both images.
+    Equal,
+    /// Different, but by no more than `threshold`.
+    BelowThreshold,
+    /// Beyond the threshold, but anti-aliasing in the first image.
+    AntialiasedFirst,
+    /// Beyond the threshold, but anti-aliasing in the second image.
+    AntialiasedSecond,
+    /// Beyond the threshold, but absorbed by a tolerance (`shift_tolerance`,
+    /// `subpixel_tolerance`, `jpeg_tolerance`) or cleared by mask post-processing.
+    Tolerated,
+    /// Counted, darker in the second image (drawn in `diff_color_alt`).
+    DiffDark,
+    /// Counted, lighter in the second image or otherwise changed (drawn in `diff_color`).
+    DiffLight,
+}
+
+impl PixelClass {
+    /// The opaque colour [`classification_image`] draws the class in.
+    pub fn color(self) -> [u8; 4] {
+        match self {
+            Self::Ignored => [96, 96, 96, 255],
+            Self::Equal => [0, 0, 0, 255],
+            Self::BelowThreshold => [0, 0, 255, 255],
+            Self::AntialiasedFirst => [255, 255, 0, 255],
+            Self::AntialiasedSecond => [0, 255, 255, 255],
+            Self::Tolerated => [0, 200, 0, 255],
+            Self::DiffDark => [255, 0, 255, 255],
+            Self::DiffLight => [255, 0, 0, 255],
+        }
+    }
+
+    /// Whether the pixel counts towards `diff_count`.
+    pub fn is_diff(self) -> bool {
+        matches!(self, Self::DiffDark | Self::DiffLight)
+    }
+
+    /// Whether the pixel counts towards `aa_count`.
+    pub fn is_antialiased(self) -> bool {
+        matches!(self, Self::AntialiasedFirst | Self::AntialiasedSecond)
+    }
+}
+
+/// The class of every pixel, in row-major order, as [`pixelmatch`](crate::pixelmatch)
+/// decided it with `options`. Pixels classed [`PixelClass::DiffDark`] or
+/// [`PixelClass::DiffLight`] are exactly those counted in `diff_count`, and the two
+/// anti-aliased classes together those in `aa_count`. `downscale` is rejected, since its
+/// decisions are made on reduced copies.
+pub fn classify_pixels(
+    img1: &[u8],
+    img2: &[u8],
+    width: u32,
+    height: u32,
+    options: &Options,
+) -> Result<Vec<PixelClass>, PixelmatchError> {
+    with_classifier(img1, img2, width, height, options, |c| {
+        let mut classes = vec![PixelClass::Equal; c.w * c.h];
+        classes.par_chunks_mut(c.w.max(1)).enumerate().for_each(|(y, row)| {
+            row.iter_mut().enumerate().for_each(|(x, class)| *class = c.classify(x, y).1);
+        });
+        classes
+    })
+}
+
+/// Rows classified in parallel before their pixels are passed to the callback in order.
+const CALLBACK_BAND: usize = 64;
+
+/// Compare two images like [`pixelmatch`](crate::pixelmatch), calling `callback` with the
+/// position, colour delta and class of every differing or anti-aliased pixel, in row-major
+/// order, instead of drawing a diff. The delta is negative where the second image is
+/// darker. Rows are classified in parallel a band at a time, so only the reported pixels of
+/// one band are held in memory. `downscale` is rejected, as for [`classify_pixels`].
+pub fn pixelmatch_with_callback(
+    img1: &[u8],
+    img2: &[u8],
+    width: u32,
+    height: u32,
+    options: &Options,
+    mut callback: impl FnMut(u32, u32, f64, PixelClass),
+) -> Result<MatchResult, PixelmatchError> {
+    with_classifier(img1, img2, width, height, options, |c| {
+        let mut tally = Tally::default();
+        if c.identical {
+            return MatchResult { identical: true, ..Default::default() }.with_total_pixels(c.w * c.h);
+        }
+        for band in (0..c.h).step_by(CALLBACK_BAND) {
+            let rows: Vec<Vec<(usize, f64, PixelClass)>> = (band..(band + CALLBACK_BAND).min(c.h))
+                .into_par_iter()
+                .map(|y| {
+                    (0..c.w)
+                        .map(|x| (x, c.classify(x, y)))
+                        .filter(|(_, (_, class))| class.is_diff() || class.is_antialiased())
+                        .map(|(x, (delta, class))| (x, delta, class))
+                        .collect()
+                })
+                .collect();
+            for (y, row) in (band..).zip(rows) {
+                let mut span = None;
+                let (mut diff, mut aa) = (0, 0);
+                for (x, delta, class) in row {
+                    if class.is_diff() {
+                        diff += 1;
+                        extend_span(&mut span, x);
+                    } else {
+                        aa += 1;
+                    }
+                    callback(x as u32, y as u32, delta, class);
+                }
+                tally = tally.merge(Tally::row(y, (diff, aa, span)));
+            }
+        }
+        let result = MatchResult {
+            diff_count: tally.diff,
+            aa_count: tally.aa,
+            scroll_offset: c.scroll_offset,
+            moved_labels: c.moved_labels,
+            diff_bounds: tally.bounds,
+            ..Default::default()
+        };
+        result.with_total_pixels(c.w * c.h)
+    })
+}
+
+/// The working copies and masks of one comparison, prepared as [`pixelmatch`](crate::pixelmatch)
+/// prepares them, from which each pixel can be classified independently.
+struct Classifier<'a> {
+    img1: &'a [u8],
+    img2: &'a [u8],
+    w: usize,
+    h: usize,
+    ignore: Option<&'a [bool]>,
+    refined: Option<&'a [bool]>,
+    max_delta: f64,
+    aa_step: usize,
+    options: &'a Options,
+    identical: bool,
+    scroll_offset: i32,
+    moved_labels: u32,
+}
+
+impl Classifier<'_> {
+    /// The colour delta and class of the pixel at (x, y).
+    fn classify(&self, x: usize, y: usize) -> (f64, PixelClass) {
+        let (w, h, options) = (self.w, self.h, self.options);
+        let (img1, img2) = (self.img1, self.img2);
+        let pos = (y * w + x) * 4;
+        if self.ignore.is_some_and(|m| m[y * w + x]) {
+            return (0.0, PixelClass::Ignored);
+        }
+        let equal = u8::same_pixel(img1, pos, img2, pos);
+        let delta = if equal { 0.0 } else { color_delta(img1, img2, pos, pos, false) };
+        let refined_row = self.refined.map(|m| &m[y * w..(y + 1) * w]);
+        let forced = refined_row.is_some_and(|r| r[x]);
+        let aa = |a: &[u8], b: &[u8]| {
+            !forced && options.detect_anti_aliasing && antialiased(a, x, y, w, h, self.aa_step, a, b)
+        };
+        let class = if !(delta.abs() > self.max_delta || forced) {
+            if equal { PixelClass::Equal } else { PixelClass::BelowThreshold }
+        } else if aa(img1, img2) {
+            PixelClass::AntialiasedFirst
+        } else if aa(img2, img1) {
+            PixelClass::AntialiasedSecond
+        } else if !counted(img1, img2, refined_row, x, y, w, h, self.max_delta, options) {
+            PixelClass::Tolerated
+        } else if delta < 0.0 {
+            PixelClass::DiffDark
+        } else {
+            PixelClass::DiffLight
+        };
+        (delta, class)
+    }
+}
+
+/// Validate the inputs, prepare the working copies and masks as `compare` does, and run `f`
+/// with a [`Classifier`] over them.
+fn with_classifier<R>(
+    img1: &[u8],
+    img2: &[u8],
+    width: u32,
+    height: u32,
+    options: &Options,
+    f: impl FnOnce(&Classifier<'_>) -> R,
+) -> Result<R, PixelmatchError> {
+    options.validate()?;
+    if options.downscale.is_some() {
+        return Err(PixelmatchError::ConflictingOptions("downscale is not supported when classifying pixels"));
+    }
+    let scaled = options.scaled_for_density();
+    let options = &*scaled;
+    let [img1, img2] = normalize_dpr(img1, img2, width, height, options)?;
+    let (img1, img2) = (&*img1, &*img2);
+    validate_buffers(img1, img2, None, width, height)?;
+    let (w, h) = (width as usize, height as usize);
+    let ignore = ignore_mask(options, w, h)?;
+    let identical = img1 == img2;
+    let max_scroll = if identical { 0 } else { options.scroll_compensation };
+    let offset = scroll::detect_offset(img1, img2, width, height, max_scroll);
+    let scrolled = (offset != 0).then(|| scroll::shift_rows(img2, width, height, offset));
+    let img2 = scrolled.as_deref().unwrap_or(img2);
+    let filtered = (!identical).then(|| prefilter(img1, img2, width, height, options)).flatten();
+    let (img1, img2) = filtered.as_ref().map_or((img1, img2), |(a, b)| (a, b));
+
+    let max_delta = 35215.0 * options.threshold * options.threshold;
+    let mut moved_labels = 0;
+    let refined = (!identical && refines_mask(options)).then(|| {
+        let mut mask = raw_diff_mask(img1, img2, w, h, max_delta, options, ignore.as_deref());
+        moved_labels = refine_mask(&mut mask, img1, img2, width, height, options, ignore.as_deref());
+        mask
+    });
+    Ok(f(&Classifier {
+        img1,
+        img2,
+        w,
+        h,
+        ignore: ignore.as_deref(),
+        refined: refined.as_deref(),
+        max_delta,
+        aa_step: options.aa_step(),
+        options,
+        identical,
+        scroll_offset: offset,
+        moved_labels,
+    }))
+}
+
+/// Draw `classes` as an opaque RGBA image, each pixel in its [`PixelClass::color`].
+pub fn classification_image(classes: &[PixelClass]) -> Vec<u8> {
+    classes.par_iter().flat_map_iter(|class| class.color()).collect()
+}
@@ -0,0 +1,82 @@
+use std::io::{self, BufRead, Write};
+use std::path::PathBuf;
+
+use clap::Args;
+
+use crate::batch::{collect_pairs, compare_all, Comparison, Outcome, Pair};
+use crate::ComparisonArgs;
+
+/// Copy failing candidates over their baselines.
+#[derive(Args, Debug)]
+pub struct ApproveArgs {
+    /// Directory containing the baseline images.
+    pub baseline_dir: PathBuf,
+    /// Directory containing the candidate images (same relative layout).
+    pub candidate_dir: PathBuf,
+    /// Only approve images whose relative path contains this string (repeatable).
+    #[arg(short, long = "filter", value_name = "NAME")]
+    pub filters: Vec<String>,
+    /// Ask for confirmation before approving each image.
+    #[arg(short, long)]
+    pub interactive: bool,
+    /// Also record candidates that have no baseline yet.
+    #[arg(long)]
+    pub new: bool,
+    /// Print what would be approved without copying anything.
+    #[arg(long)]
+    pub dry_run: bool,
+    #[command(flatten)]
+    pub comparison: ComparisonArgs,
+}
+
+pub fn run(args: &ApproveArgs) -> Result<u8, String> {
+    let pairs = collect_pairs(&args.baseline_dir, &args.candidate_dir)
+        .map_err(|e| format!("{}: {e}", args.candidate_dir.display()))?;
+    let comparisons = compare_all(&pairs, &args.comparison.settings()?, None)?;
+
+    let stdin = io::stdin();
+    let mut lines = stdin.lock().lines();
+    let mut approved = 0;
+    for Comparison { pair, outcome, .. } in &comparisons {
+        let reason = match outcome {
+            Outcome::Pass { .. } => continue,
+            Outcome::MissingBaseline if !args.new => continue,
+            Outcome::MissingBaseline => "new".to_string(),
+            Outcome::Fail { result, .. } => format!("{} different pixels", result.diff_count),
+            Outcome::DimensionMismatch { baseline: (bw, bh), candidate: (cw, ch) } => {
+                format!("dimensions changed from {bw}x{bh} to {cw}x{ch}")
+            }
+            Outcome::Error(e) => {
+                eprintln!("skipping {}: {e}", pair.name);
+                continue;
+            }
+        };
+        if !args.filters.is_empty() && !args.filters.iter().any(|f| pair.name.contains(f.as_str())) {
+            continue;
+        }
+        if args.interactive {
+            print!("approve {} ({reason})? [y/N] ", pair.name);
+            io::stdout().flush().map_err(|e| e.to_string())?;
+            let answer = lines.next().transpose().map_err(|e| e.to_string())?.unwrap_or_default();
+            if !matches!(answer.trim(), "y" | "Y" | "yes") {
+                continue;
+            }
+        }
+        if !args.dry_run {
+            approve_pair(pair)?;
+        }
+        println!("approved: {} ({reason})", pair.name);
+        approved += 1;
+    }
+    println!("{approved} baseline(s) {}", if args.dry_run { "would be updated" } else { "updated" });
+    Ok(crate::EXIT_OK)
+}
+
+/// Copy the candidate over its baseline, creating parent directories as needed.
+pub fn approve_pair(pair: &Pair) -> Result<(), String> {
+    if let Some(parent) = pair.baseline.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| format!("{}: {e}", parent.display()))?;
+    }
+    std::fs::copy(&pair.candidate, &pair.baseline).map_err(|e| format!("{}: {e}", pair.baseline.display()))?;
+    Ok(())
+}
@@ -0,0 +1,223 @@
+use std::io;
+use std::path::{Path, PathBuf};
+
+use pixelmatch::io::{decode_png, encode_png, read_png, write_png, Image};
+use pixelmatch::{pixelmatch, MatchResult, Options};
+use rayon::prelude::*;
+
+use crate::progress::Progress;
+use crate::Settings;
+
+/// A baseline/candidate pair matched by relative path.
+#[derive(Debug, Clone)]
+pub struct Pair {
+    /// Path relative to the baseline and candidate roots, with `/` separators.
+    pub name: String,
+    pub baseline: PathBuf,
+    pub candidate: PathBuf,
+}
+
+/// Outcome of comparing one pair.
+#[derive(Debug, Clone)]
+pub enum Outcome {
+    /// Images match within the configured tolerance.
+    Pass { result: MatchResult, width: u32, height: u32 },
+    /// Images differ.
+    Fail { result: MatchResult, width: u32, height: u32 },
+    /// No baseline exists for the candidate.
+    MissingBaseline,
+    /// Baseline and candidate have different dimensions.
+    DimensionMismatch { baseline: (u32, u32), candidate: (u32, u32) },
+    /// One of the images could not be read, compared or written.
+    Error(String),
+}
+
+impl Outcome {
+    pub fn passed(&self) -> bool {
+        matches!(self, Self::Pass { .. })
+    }
+}
+
+/// Limits on how many pixels may differ before a comparison fails. With no limit set, any
+/// difference fails; with both set, both must hold.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Tolerance {
+    pub max_diff_pixels: Option<u64>,
+    /// Fraction of the image (0 to 1).
+    pub max_diff_ratio: Option<f64>,
+}
+
+impl Tolerance {
+    /// Whether `diff_count` differing pixels out of `total` is within the limits.
+    pub fn allows(&self, diff_count: u32, total: u64) -> bool {
+        if self.max_diff_pixels.is_none() && self.max_diff_ratio.is_none() {
+            return diff_count == 0;
+        }
+        let ratio = if total == 0 { 0.0 } else { diff_count as f64 / total as f64 };
+        self.max_diff_pixels.is_none_or(|max| u64::from(diff_count) <= max)
+            && self.max_diff_ratio.is_none_or(|max| ratio <= max)
+    }
+}
+
+/// A compared pair together with its outcome.
+#[derive(Debug, Clone)]
+pub struct Comparison {
+    pub pair: Pair,
+    pub outcome: Outcome,
+    /// Where the diff image was written, if it was.
+    pub diff: Option<PathBuf>,
+}
+
+/// Collect all PNG files under `candidate_root`, paired with the same relative path under
+/// `baseline_root`. Pairs are sorted by name.
+pub fn collect_pairs(baseline_root: &Path, candidate_root: &Path) -> io::Result<Vec<Pair>> {
+    let mut files = Vec::new();
+    walk_pngs(candidate_root, candidate_root, &mut files)?;
+    files.sort();
+    Ok(files
+        .into_iter()
+        .map(|rel| Pair {
+            name: rel.components().map(|c| c.as_os_str().to_string_lossy()).collect::<Vec<_>>().join("/"),
+            baseline: baseline_root.join(&rel),
+            candidate: candidate_root.join(&rel),
+        })
+        .collect())
+}
+
+fn walk_pngs(root: &Path, dir: &Path, out: &mut Vec<PathBuf>) -> io::Result<()> {
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            walk_pngs(root, &path, out)?;
+        } else if path.extension().is_some_and(|e| e.eq_ignore_ascii_case("png")) {
+            out.push(path.strip_prefix(root).unwrap_or(&path).to_path_buf());
+        }
+    }
+    Ok(())
+}
+
+/// Compare a single pair, writing the diff image to `diff` if the comparison fails (or always,
+/// if `always_write` is set).
+pub fn compare_pair(
+    pair: &Pair,
+    options: &Options,
+    tolerance: &Tolerance,
+    diff: Option<&Path>,
+    always_write: bool,
+) -> Comparison {
+    compare_pair_inner(pair, options, tolerance, diff, always_write, false).0
+}
+
+/// Like [`compare_pair`], but also return the diff image if the images could be compared.
+pub fn compare_pair_with_diff(
+    pair: &Pair,
+    options: &Options,
+    tolerance: &Tolerance,
+    diff: Option<&Path>,
+    always_write: bool,
+) -> (Comparison, Option<Image>) {
+    compare_pair_inner(pair, options, tolerance, diff, always_write, true)
+}
+
+fn compare_pair_inner(
+    pair: &Pair,
+    options: &Options,
+    tolerance: &Tolerance,
+    diff: Option<&Path>,
+    always_write: bool,
+    keep_diff: bool,
+) -> (Comparison, Option<Image>) {
+    let (outcome, written, image) = match compare_inner(pair, options, tolerance, diff, always_write, keep_diff) {
+        Ok(done) => done,
+        Err(e) => (Outcome::Error(e), false, None),
+    };
+    let comparison = Comparison { pair: pair.clone(), outcome, diff: diff.filter(|_| written).map(Path::to_path_buf) };
+    (comparison, image)
+}
+
+fn compare_inner(
+    pair: &Pair,
+    options: &Options,
+    tolerance: &Tolerance,
+    diff_path: Option<&Path>,
+    always_write: bool,
+    keep_diff: bool,
+) -> Result<(Outcome, bool, Option<Image>), String> {
+    if !is_stdio(&pair.baseline) && !pair.baseline.exists() {
+        return Ok((Outcome::MissingBaseline, false, None));
+    }
+    let baseline = load(&pair.baseline)?;
+    let candidate = load(&pair.candidate)?;
+    let sizes = ((baseline.width, baseline.height), (candidate.width, candidate.height));
+    let Some((width, height)) = options.compared_size(sizes.0, sizes.1) else {
+        let outcome = Outcome::DimensionMismatch { baseline: sizes.0, candidate: sizes.1 };
+        return Ok((outcome, false, None));
+    };
+
+    let mut diff = (diff_path.is_some() || keep_diff).then(|| vec![0u8; width as usize * height as usize * 4]);
+    let result = pixelmatch(&baseline.data, &candidate.data, diff.as_deref_mut(), width, height, options)
+        .map_err(|e| e.to_string())?;
+    let outcome = if tolerance.allows(result.diff_count, u64::from(width) * u64::from(height)) {
+        Outcome::Pass { result, width, height }
+    } else {
+        Outcome::Fail { result, width, height }
+    };
+
+    let mut written = false;
+    if let (Some(path), Some(diff)) = (diff_path, &diff) {
+        if is_stdio(path) {
+            let stdout = io::stdout().lock();
+            encode_png(io::BufWriter::new(stdout), diff, width, height)
+                .map_err(|e| format!("stdout: {e}"))?;
+            written = true;
+        } else if always_write || !outcome.passed() {
+            if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+                std::fs::create_dir_all(parent).map_err(|e| format!("{}: {e}", parent.display()))?;
+            }
+            write_png(path, diff, width, height).map_err(|e| format!("{}: {e}", path.display()))?;
+            written = true;
+        }
+    }
+    let image = diff.filter(|_| keep_diff).map(|data| Image { data, width, height });
+    Ok((outcome, written, image))
+}
+
+/// Whether `path` is `-`, standing for stdin (inputs) or stdout (the diff image).
+pub fn is_stdio(path: &Path) -> bool {
+    path.as_os_str() == "-"
+}
+
+fn load(path: &Path) -> Result<Image, String> {
+    if is_stdio(path) {
+        decode_png(io::BufReader::new(io::stdin().lock())).map_err(|e| format!("stdin: {e}"))
+    } else {
+        read_png(path).map_err(|e| format!("{}: {e}", path.display()))
+    }
+}
+
+/// Compare all pairs in parallel on `settings.jobs` worker threads, preserving input order and
+/// reporting progress on stderr. Diff images of failing pairs are written under `diff_dir`
+/// using the same relative layout.
+pub fn compare_all(pairs: &[Pair], settings: &Settings, diff_dir: Option<&Path>) -> Result<Vec<Comparison>, String> {
+    let pool = rayon::ThreadPoolBuilder::new()
+        .num_threads(settings.jobs())
+        .build()
+        .map_err(|e| format!("failed to start worker threads: {e}"))?;
+    let progress = Progress::new(pairs.len(), settings.progress());
+    let comparisons = pool.install(|| {
+        pairs
+            .par_iter()
+            .map(|pair| {
+                let diff = diff_dir.map(|dir| dir.join(&pair.name));
+                let comparison = match settings.resolve(&pair.name) {
+                    Ok((options, tolerance)) => compare_pair(pair, &options, &tolerance, diff.as_deref(), false),
+                    Err(e) => Comparison { pair: pair.clone(), outcome: Outcome::Error(e), diff: None },
+                };
+                progress.finish(&comparison);
+                comparison
+            })
+            .collect()
+    });
+    progress.clear();
+    Ok(comparisons)
+}
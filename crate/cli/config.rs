@@ -0,0 +1,323 @@
+//! `pixelmatch.toml` configuration.
+//!
+//! ```toml
+//! threshold = 0.1
+//! max_diff_ratio = 0.001
+//! diff_color = [255, 0, 255]
+//! ignore = [{ x = 0, y = 0, width = 200, height = 40 }]
+//! ignore_polygons = [[[300, 0], [340, 40], [300, 80]]]
+//! ignore_hatch = [0, 128, 255]
+//!
+//! [presets.text]
+//! threshold = 0.2
+//! detect_anti_aliasing = true
+//!
+//! [[overrides]]
+//! files = ["docs/**", "*-text.png"]
+//! preset = "text"
+//! ignore = [{ x = 10, y = 10, width = 50, height = 50 }]
+//! ```
+//!
+//! Settings are layered: library defaults, then the top-level settings, then every override
+//! whose `files` pattern matches the pair name (in file order), then command-line flags.
+//! A `preset` key applies the named preset before the other keys of the same table. Names not
+//! defined under `[presets]` refer to the built-in `Preset`s (`strict`, `lenient`, `text-heavy`,
+//! `photo`, `map-tiles`). Unknown keys are an error, so a misspelt setting is not ignored.
+
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+
+use pixelmatch::edges::EdgeDetector;
+use pixelmatch::morph::MorphOp;
+use pixelmatch::{Options, Polygon, Preset, Rect};
+use serde::Deserialize;
+
+use crate::batch::Tolerance;
+
+/// Default configuration file name, looked up in the working directory.
+pub const DEFAULT_CONFIG: &str = "pixelmatch.toml";
+
+/// A set of comparison settings; unset keys leave the underlying value untouched.
+#[derive(Deserialize, Debug, Default, Clone)]
+pub struct OptionSet {
+    pub preset: Option<String>,
+    pub threshold: Option<f64>,
+    pub detect_anti_aliasing: Option<bool>,
+    pub alpha: Option<f64>,
+    pub aa_color: Option<[u8; 3]>,
+    pub diff_color: Option<[u8; 3]>,
+    pub diff_color_alt: Option<[u8; 3]>,
+    pub diff_mask: Option<bool>,
+    pub shift_tolerance: Option<u32>,
+    pub subpixel_tolerance: Option<bool>,
+    pub jpeg_tolerance: Option<f64>,
+    pub scroll_compensation: Option<u32>,
+    pub isolation_radius: Option<u32>,
+    /// Dilate/erode steps such as `["dilate:2", "erode:2"]`, replacing those already configured.
+    pub morphology: Option<Vec<MorphConfig>>,
+    pub label_shift: Option<u32>,
+    pub pre_blur: Option<f32>,
+    pub quantize: Option<u32>,
+    pub median_filter: Option<bool>,
+    /// Edge detector to compare edge maps with, such as `"canny:0.1,0.25"`.
+    pub edges: Option<EdgeConfig>,
+    pub downscale: Option<f32>,
+    /// Device-pixel ratios of the baseline and candidate, e.g. `[1.0, 2.0]`.
+    pub device_pixel_ratio: Option<[f32; 2]>,
+    pub density_scaling: Option<bool>,
+    pub max_diff_pixels: Option<u64>,
+    pub max_diff_ratio: Option<f64>,
+    /// Regions to ignore, added to those already configured.
+    pub ignore: Option<Vec<RectConfig>>,
+    /// Polygons to ignore as lists of `[x, y]` points, added to those already configured.
+    pub ignore_polygons: Option<Vec<Vec<[f64; 2]>>>,
+    /// Compare only inside the ignore regions, polygons and mask instead of outside them.
+    pub include_only: Option<bool>,
+    /// Colour of the hatch drawn over ignored regions in diff images.
+    pub ignore_hatch: Option<[u8; 3]>,
+    /// Keys that are not settings, rejected by [`Config::load`]. `deny_unknown_fields` does
+    /// not work through `flatten`.
+    #[serde(flatten)]
+    pub unknown: BTreeMap<String, toml::Value>,
+}
+
+/// A morphology step written as `"dilate:<radius>"` or `"erode:<radius>"`.
+#[derive(Deserialize, Debug, Clone, Copy)]
+#[serde(try_from = "String")]
+pub struct MorphConfig(pub MorphOp);
+
+impl TryFrom<String> for MorphConfig {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse().map(Self)
+    }
+}
+
+/// An edge detector written as `"sobel:<threshold>"` or `"canny:<low>,<high>"`.
+#[derive(Deserialize, Debug, Clone, Copy)]
+#[serde(try_from = "String")]
+pub struct EdgeConfig(pub EdgeDetector);
+
+impl TryFrom<String> for EdgeConfig {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse().map(Self)
+    }
+}
+
+#[derive(Deserialize, Debug, Clone, Copy)]
+#[serde(deny_unknown_fields)]
+pub struct RectConfig {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(untagged)]
+pub enum Patterns {
+    One(String),
+    Many(Vec<String>),
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct Override {
+    /// Glob pattern(s) matched against the pair name (`*` stays within a path segment,
+    /// `**` crosses segments, `?` matches one character).
+    pub files: Patterns,
+    #[serde(flatten)]
+    pub options: OptionSet,
+}
+
+#[derive(Deserialize, Debug, Default, Clone)]
+pub struct Config {
+    #[serde(flatten)]
+    pub base: OptionSet,
+    #[serde(default)]
+    pub presets: HashMap<String, OptionSet>,
+    #[serde(default)]
+    pub overrides: Vec<Override>,
+}
+
+impl Config {
+    /// Load the configuration at `path`.
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let text = std::fs::read_to_string(path).map_err(|e| format!("{}: {e}", path.display()))?;
+        let config: Config = toml::from_str(&text).map_err(|e| format!("{}: {e}", path.display()))?;
+        config.check_keys().map_err(|e| format!("{}: {e}", path.display()))?;
+        // Resolve once up front so unknown presets are reported before any comparison runs.
+        config.resolve("")?;
+        for o in &config.overrides {
+            config.apply(&mut Default::default(), &o.options, 0)?;
+        }
+        Ok(config)
+    }
+
+    /// Reject keys that are not settings, so a misspelt one does not silently keep the default.
+    fn check_keys(&self) -> Result<(), String> {
+        let tables = std::iter::once(("the top level".to_string(), &self.base))
+            .chain(self.presets.iter().map(|(name, set)| (format!("[presets.{name}]"), set)))
+            .chain(self.overrides.iter().enumerate().map(|(i, o)| (format!("[[overrides]] #{}", i + 1), &o.options)));
+        for (table, set) in tables {
+            if let Some(key) = set.unknown.keys().next() {
+                return Err(format!("unknown key `{key}` in {table}"));
+            }
+        }
+        Ok(())
+    }
+
+    /// Load `explicit` if given, otherwise `pixelmatch.toml` from the working directory if present.
+    pub fn discover(explicit: Option<&Path>) -> Result<Option<Self>, String> {
+        match explicit {
+            Some(path) => Self::load(path).map(Some),
+            None if Path::new(DEFAULT_CONFIG).is_file() => Self::load(Path::new(DEFAULT_CONFIG)).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// Resolve the options and pass/fail tolerance for the pair with the given name.
+    pub fn resolve(&self, name: &str) -> Result<(Options, Tolerance), String> {
+        let mut resolved = Default::default();
+        self.apply(&mut resolved, &self.base, 0)?;
+        for o in &self.overrides {
+            let matched = match &o.files {
+                Patterns::One(p) => glob_match(p, name),
+                Patterns::Many(ps) => ps.iter().any(|p| glob_match(p, name)),
+            };
+            if matched {
+                self.apply(&mut resolved, &o.options, 0)?;
+            }
+        }
+        Ok(resolved)
+    }
+
+    fn apply(&self, resolved: &mut (Options, Tolerance), set: &OptionSet, depth: usize) -> Result<(), String> {
+        if let Some(name) = &set.preset {
+            if depth > 8 {
+                return Err(format!("preset \"{name}\" is recursive"));
+            }
+            match self.presets.get(name) {
+                Some(preset) => self.apply(resolved, preset, depth + 1)?,
+                None => {
+                    let builtin = Options::preset(name.parse::<Preset>()?);
+                    resolved.0.threshold = builtin.threshold;
+                    resolved.0.detect_anti_aliasing = builtin.detect_anti_aliasing;
+                }
+            }
+        }
+        set.apply_to(&mut resolved.0, &mut resolved.1);
+        Ok(())
+    }
+}
+
+impl OptionSet {
+    /// Apply the keys that are set (ignoring `preset`).
+    pub fn apply_to(&self, options: &mut Options, tolerance: &mut Tolerance) {
+        if let Some(v) = self.threshold {
+            options.threshold = v;
+        }
+        if let Some(v) = self.detect_anti_aliasing {
+            options.detect_anti_aliasing = v;
+        }
+        if let Some(v) = self.alpha {
+            options.alpha = v;
+        }
+        if let Some(v) = self.aa_color {
+            options.aa_color = v;
+        }
+        if let Some(v) = self.diff_color {
+            options.diff_color = v;
+        }
+        if let Some(v) = self.diff_color_alt {
+            options.diff_color_alt = Some(v);
+        }
+        if let Some(v) = self.diff_mask {
+            options.diff_mask = v;
+        }
+        if let Some(v) = self.shift_tolerance {
+            options.shift_tolerance = v;
+        }
+        if let Some(v) = self.subpixel_tolerance {
+            options.subpixel_tolerance = v;
+        }
+        if let Some(v) = self.jpeg_tolerance {
+            options.jpeg_tolerance = Some(v);
+        }
+        if let Some(v) = self.scroll_compensation {
+            options.scroll_compensation = v;
+        }
+        if let Some(v) = self.isolation_radius {
+            options.isolation_radius = v;
+        }
+        if let Some(ops) = &self.morphology {
+            options.morphology = ops.iter().map(|op| op.0).collect();
+        }
+        if let Some(v) = self.label_shift {
+            options.label_shift = v;
+        }
+        if let Some(v) = self.pre_blur {
+            options.pre_blur = Some(v);
+        }
+        if let Some(v) = self.quantize {
+            options.quantize = Some(v);
+        }
+        if let Some(v) = self.median_filter {
+            options.median_filter = v;
+        }
+        if let Some(v) = self.edges {
+            options.edges = Some(v.0);
+        }
+        if let Some(v) = self.downscale {
+            options.downscale = Some(v);
+        }
+        if let Some(v) = self.device_pixel_ratio {
+            options.device_pixel_ratio = Some(v);
+        }
+        if let Some(v) = self.density_scaling {
+            options.density_scaling = v;
+        }
+        if let Some(v) = self.max_diff_pixels {
+            tolerance.max_diff_pixels = Some(v);
+        }
+        if let Some(v) = self.max_diff_ratio {
+            tolerance.max_diff_ratio = Some(v);
+        }
+        if let Some(rects) = &self.ignore {
+            options.ignore_regions.extend(rects.iter().map(|r| Rect::new(r.x, r.y, r.width, r.height)));
+        }
+        if let Some(polygons) = &self.ignore_polygons {
+            options
+                .ignore_polygons
+                .extend(polygons.iter().map(|p| Polygon::new(p.iter().map(|&[x, y]| (x, y)).collect())));
+        }
+        if let Some(v) = self.include_only {
+            options.include_only = v;
+        }
+        if let Some(v) = self.ignore_hatch {
+            options.ignore_hatch = Some(v);
+        }
+    }
+}
+
+/// Match `name` against a glob pattern with `*`, `**` and `?` wildcards.
+pub fn glob_match(pattern: &str, name: &str) -> bool {
+    fn go(p: &[u8], n: &[u8]) -> bool {
+        match p {
+            [] => n.is_empty(),
+            [b'*', b'*', rest @ ..] => {
+                // `**/` may also match zero directories
+                let rest_no_slash = rest.strip_prefix(b"/").unwrap_or(rest);
+                (0..=n.len()).any(|i| go(rest, &n[i..]) || go(rest_no_slash, &n[i..]))
+            }
+            [b'*', rest @ ..] => {
+                (0..=n.len()).take_while(|&i| i == 0 || n[i - 1] != b'/').any(|i| go(rest, &n[i..]))
+            }
+            [b'?', rest @ ..] => n.first().is_some_and(|&c| c != b'/') && go(rest, &n[1..]),
+            [c, rest @ ..] => n.first() == Some(c) && go(rest, &n[1..]),
+        }
+    }
+    go(pattern.as_bytes(), name.as_bytes())
+}
@@ -0,0 +1,56 @@
+use std::path::PathBuf;
+
+use clap::Args;
+use pixelmatch::flaky::FlakyMask;
+use pixelmatch::io::read_png;
+
+use crate::ComparisonArgs;
+
+/// Find the areas that vary between repeated captures and write them as an ignore mask.
+#[derive(Args, Debug)]
+pub struct FlakyArgs {
+    /// Two or more captures of the same, unchanged screen.
+    #[arg(required = true, num_args = 2..)]
+    pub captures: Vec<PathBuf>,
+    /// Output mask PNG, for use with `--ignore-mask`.
+    #[arg(short, long, default_value = "pixelmatch-ignore.png")]
+    pub out: PathBuf,
+    /// Grow each unstable area by this many pixels.
+    #[arg(long, default_value_t = 2)]
+    pub padding: u32,
+    #[command(flatten)]
+    pub comparison: ComparisonArgs,
+}
+
+pub fn run(args: &FlakyArgs) -> Result<u8, String> {
+    let images = args
+        .captures
+        .iter()
+        .map(|path| read_png(path).map_err(|e| format!("{}: {e}", path.display())))
+        .collect::<Result<Vec<_>, _>>()?;
+    let (width, height) = (images[0].width, images[0].height);
+    let mismatch = args.captures.iter().zip(&images).find(|(_, i)| (i.width, i.height) != (width, height));
+    if let Some((path, image)) = mismatch {
+        return Err(format!("{}: {}x{} does not match {width}x{height}", path.display(), image.width, image.height));
+    }
+
+    let name = args.captures[0].file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default();
+    let (options, _) = args.comparison.settings()?.resolve(&name)?;
+    let captures: Vec<&[u8]> = images.iter().map(|i| i.data.as_slice()).collect();
+    let mask = FlakyMask::detect(&captures, width, height, &options, args.padding).map_err(|e| e.to_string())?;
+    mask.save(&args.out).map_err(|e| format!("{}: {e}", args.out.display()))?;
+
+    let total = width as f64 * height as f64;
+    let regions = mask.regions();
+    println!(
+        "{} unstable region(s), {} pixels ({:.2}%) written to {}",
+        regions.len(),
+        mask.count(),
+        if total > 0.0 { mask.count() as f64 / total * 100.0 } else { 0.0 },
+        args.out.display()
+    );
+    for r in &regions {
+        println!("  {},{},{},{}", r.x, r.y, r.width, r.height);
+    }
+    Ok(crate::EXIT_OK)
+}
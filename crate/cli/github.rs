@@ -0,0 +1,62 @@
+//! GitHub Actions output: one `::error` workflow command per failing comparison, so failures
+//! are annotated inline in the Actions UI, and a Markdown job summary appended to the file
+//! named by `GITHUB_STEP_SUMMARY` (when set).
+
+use std::io::Write;
+
+use pixelmatch::report::MarkdownReport;
+
+use crate::batch::{Comparison, Outcome};
+use crate::report::entry_from_comparison;
+
+/// Print annotations for the failing comparisons and append the job summary.
+pub fn report(comparisons: &[Comparison]) -> Result<(), String> {
+    for c in comparisons {
+        if let Some(line) = annotation(c) {
+            println!("{line}");
+        }
+    }
+    let Some(path) = std::env::var_os("GITHUB_STEP_SUMMARY").filter(|p| !p.is_empty()) else {
+        return Ok(());
+    };
+    let entries: Vec<_> = comparisons.iter().map(entry_from_comparison).collect();
+    let summary = MarkdownReport { inline_images: false, ..Default::default() }.render(&entries);
+    let mut file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .map_err(|e| format!("{}: {e}", path.to_string_lossy()))?;
+    writeln!(file, "{summary}").map_err(|e| format!("{}: {e}", path.to_string_lossy()))
+}
+
+/// The workflow command for a comparison, or `None` if it passed.
+fn annotation(c: &Comparison) -> Option<String> {
+    let message = match &c.outcome {
+        Outcome::Pass { .. } => return None,
+        Outcome::Fail { result, width, height } => {
+            let ratio = result.diff_count as f64 / (*width as f64 * *height as f64).max(1.0);
+            format!("{} different pixels ({:.2}%)", result.diff_count, ratio * 100.0)
+        }
+        Outcome::MissingBaseline => "no baseline".to_string(),
+        Outcome::DimensionMismatch { baseline: (bw, bh), candidate: (cw, ch) } => {
+            format!("dimensions changed from {bw}x{bh} to {cw}x{ch}")
+        }
+        Outcome::Error(e) => e.clone(),
+    };
+    Some(format!(
+        "::error file={},title={}::{}",
+        escape_property(&c.pair.candidate.to_string_lossy()),
+        escape_property(&format!("Visual diff: {}", c.pair.name)),
+        escape_data(&message)
+    ))
+}
+
+/// Escape a workflow command message.
+pub fn escape_data(s: &str) -> String {
+    s.replace('%', "%25").replace('\r', "%0D").replace('\n', "%0A")
+}
+
+/// Escape a workflow command property value.
+fn escape_property(s: &str) -> String {
+    escape_data(s).replace(':', "%3A").replace(',', "%2C")
+}
@@ -0,0 +1,607 @@
+mod approve;
+mod batch;
+mod config;
+mod flaky;
+mod github;
+mod metrics;
+mod montage;
+mod output;
+mod preview;
+mod progress;
+mod report;
+mod review;
+mod tiles;
+#[cfg(feature = "tui")]
+mod view;
+
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::time::Instant;
+
+use clap::{Args, Parser, Subcommand};
+use pixelmatch::io::{read_png, write_png, Image};
+use pixelmatch::classify::{classification_image, classify_pixels};
+use pixelmatch::edges::EdgeDetector;
+use pixelmatch::morph::MorphOp;
+use pixelmatch::{Options, Rect};
+
+use batch::{
+    collect_pairs, compare_all, compare_pair, compare_pair_with_diff, is_stdio, Comparison, Outcome, Pair, Tolerance,
+};
+use output::Format;
+
+/// Exit codes of the text format, matching the JS package's CLI.
+const EXIT_OK: u8 = 0;
+const EXIT_USAGE: u8 = 64;
+const EXIT_DIMENSIONS: u8 = 65;
+const EXIT_DIFFERENT: u8 = 66;
+
+/// Exit codes of the machine-readable formats.
+const EXIT_MACHINE_DIFFERENT: u8 = 1;
+const EXIT_MACHINE_ERROR: u8 = 2;
+
+/// Pixel-level image comparison.
+///
+/// Compares two PNG images (or two directories of PNG images, matched by relative path)
+/// and prints the number of mismatched pixels.
+///
+/// Any difference fails unless `--max-diff-pixels` or `--max-diff-ratio` allow it.
+///
+/// With `--format text` (the default) the exit code is 66 if the comparison fails, 65 if the
+/// image dimensions do not match and 64 on invalid usage or I/O errors. With `--format json`,
+/// `ndjson` or `github` it is 0 if everything passed, 1 if any comparison failed and 2 on
+/// invalid usage or I/O errors.
+#[derive(Parser, Debug)]
+#[command(name = "pixelmatch", version, args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
+struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+    #[command(flatten)]
+    compare: CompareArgs,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Copy failing candidates over their baselines.
+    Approve(approve::ApproveArgs),
+    /// Write an HTML visual report for a batch of comparisons.
+    Report(report::ReportArgs),
+    /// Tile the worst diffs of a run into a single labelled PNG.
+    Montage(montage::MontageArgs),
+    /// List, approve or reject the failures recorded with `--review`.
+    Review(review::ReviewArgs),
+    /// Find the areas that vary between repeated captures and write them as an ignore mask.
+    Flaky(flaky::FlakyArgs),
+    /// Compare two map tile trees laid out as `{z}/{x}/{y}.png`, with totals per zoom level.
+    Tiles(tiles::TilesArgs),
+    /// Review failing pairs in a terminal UI and approve or reject them.
+    #[cfg(feature = "tui")]
+    View(view::ViewArgs),
+}
+
+/// Options controlling how pixels are compared, shared by all commands.
+///
+/// Flags take precedence over `PIXELMATCH_*` environment variables (see
+/// `Options::apply_env`, plus `PIXELMATCH_MAX_DIFF_PIXELS` and `PIXELMATCH_MAX_DIFF_RATIO`),
+/// which take precedence over the configuration file.
+#[derive(Args, Debug, Clone)]
+struct ComparisonArgs {
+    /// Configuration file [default: pixelmatch.toml, if present].
+    #[arg(short, long)]
+    config: Option<PathBuf>,
+    /// Matching threshold (0 to 1); smaller is more sensitive [default: 0.1].
+    #[arg(short, long)]
+    threshold: Option<f64>,
+    /// Whether to detect and exclude anti-aliased pixels from the diff count [default: true].
+    #[arg(long)]
+    detect_anti_aliasing: Option<bool>,
+    /// Opacity of the original image in the diff output [default: 0.1].
+    #[arg(long)]
+    alpha: Option<f64>,
+    /// Draw the diff over a transparent background (a mask).
+    #[arg(long)]
+    diff_mask: bool,
+    /// Do not count pixels whose content moved by at most this many pixels [default: 0].
+    #[arg(long, value_name = "PX")]
+    shift_tolerance: Option<u32>,
+    /// Do not count pixels that match the other image shifted by up to half a pixel.
+    #[arg(long)]
+    subpixel_tolerance: bool,
+    /// Tolerate differences up to this threshold where JPEG compression leaves artefacts
+    /// (8x8 block boundaries and blocks with sharp edges).
+    #[arg(long, value_name = "THRESHOLD")]
+    jpeg_tolerance: Option<f64>,
+    /// Detect a vertical scroll offset of up to this many rows and compensate for it [default: 0].
+    #[arg(long, value_name = "ROWS")]
+    scroll_compensation: Option<u32>,
+    /// Do not count differing pixels with no other difference within this many pixels [default: 0].
+    #[arg(long, value_name = "PX")]
+    isolation_radius: Option<u32>,
+    /// Dilate or erode the diff mask before counting, as `dilate:R` or `erode:R` (repeatable,
+    /// applied in order).
+    #[arg(long = "morph", value_name = "OP")]
+    morphology: Vec<MorphOp>,
+    /// Do not count small high-contrast clusters, such as map labels, that reappear in the
+    /// other image moved by up to this many pixels; they are reported separately [default: 0].
+    #[arg(long, value_name = "PX")]
+    label_shift: Option<u32>,
+    /// Blur both images with a Gaussian of this standard deviation before comparing.
+    #[arg(long, value_name = "SIGMA")]
+    pre_blur: Option<f32>,
+    /// Map both images to a shared palette of this many colours (2 to 256) before comparing,
+    /// hiding truncation differences between palette-based renderers.
+    #[arg(long, value_name = "COLORS")]
+    quantize: Option<u32>,
+    /// Apply a 3x3 median filter to both images before comparing, removing speckle noise.
+    #[arg(long)]
+    median_filter: bool,
+    /// Compare edge maps instead of colours, ignoring fill-colour-only changes, with
+    /// `sobel:T` or `canny:LOW,HIGH` (thresholds 0 to 1).
+    #[arg(long, value_name = "DETECTOR")]
+    edges: Option<EdgeDetector>,
+    /// Compare at this fraction of the original size, e.g. 0.5 for a fast rough check.
+    #[arg(long, value_name = "FACTOR")]
+    downscale: Option<f32>,
+    /// Device-pixel ratios of the two images as `baseline,candidate`, e.g. `1,2` to check a
+    /// retina capture against a standard one; the higher-ratio image is scaled down first.
+    #[arg(long = "dpr", value_name = "R1,R2", value_parser = parse_dpr)]
+    device_pixel_ratio: Option<[f32; 2]>,
+    /// Treat the options as tuned for 1x captures and scale the threshold and pixel
+    /// tolerances by the lower `--dpr` ratio.
+    #[arg(long)]
+    density_scaling: bool,
+    /// Pass if at most this many pixels differ [default: any difference fails].
+    #[arg(long, value_name = "N")]
+    max_diff_pixels: Option<u64>,
+    /// Pass if at most this fraction of pixels differ, e.g. 0.001 for 0.1%.
+    #[arg(long, value_name = "RATIO", value_parser = parse_ratio)]
+    max_diff_ratio: Option<f64>,
+    /// Ignore a region given as `x,y,width,height` (repeatable).
+    #[arg(long = "ignore", value_name = "X,Y,W,H", value_parser = parse_rect)]
+    ignore: Vec<Rect>,
+    /// Ignore the pixels that are neither black nor transparent in this PNG (same size as the
+    /// images).
+    #[arg(long, value_name = "PNG")]
+    ignore_mask: Option<PathBuf>,
+    /// Compare only inside the `--ignore` regions and `--ignore-mask` instead of outside them.
+    #[arg(long)]
+    include_only: bool,
+    /// Number of images to compare in parallel [default: number of CPUs].
+    #[arg(short, long, value_name = "N")]
+    jobs: Option<usize>,
+    /// Do not report progress on stderr while comparing directories.
+    #[arg(long)]
+    no_progress: bool,
+}
+
+fn parse_ratio(s: &str) -> Result<f64, String> {
+    match s.parse::<f64>() {
+        Ok(v) if (0.0..=1.0).contains(&v) => Ok(v),
+        Ok(_) => Err("must be between 0 and 1".to_string()),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+fn parse_dpr(s: &str) -> Result<[f32; 2], String> {
+    let parts: Result<Vec<f32>, _> = s.split(',').map(|p| p.trim().parse::<f32>()).collect();
+    match parts.map_err(|e| e.to_string())?[..] {
+        [r1, r2] if r1 > 0.0 && r2 > 0.0 && r1.is_finite() && r2.is_finite() => Ok([r1, r2]),
+        [_, _] => Err("ratios must be positive".to_string()),
+        _ => Err("expected baseline,candidate".to_string()),
+    }
+}
+
+fn parse_rect(s: &str) -> Result<Rect, String> {
+    let parts: Result<Vec<u32>, _> = s.split(',').map(|p| p.trim().parse::<u32>()).collect();
+    match parts.map_err(|e| e.to_string())?[..] {
+        [x, y, width, height] => Ok(Rect::new(x, y, width, height)),
+        _ => Err("expected x,y,width,height".to_string()),
+    }
+}
+
+impl ComparisonArgs {
+    /// Load the configuration file and ignore mask and combine them with the command-line flags.
+    fn settings(&self) -> Result<Settings, String> {
+        let ignore_mask = match &self.ignore_mask {
+            Some(path) => {
+                let image = read_png(path).map_err(|e| format!("{}: {e}", path.display()))?;
+                Some(image.data.chunks_exact(4).map(|px| u8::from(px[3] != 0 && px[..3] != [0; 3])).collect())
+            }
+            None => None,
+        };
+        let config = config::Config::discover(self.config.as_deref())?;
+        // Check the environment once so `resolve` can apply it without failing.
+        Options::default().apply_env().map_err(|e| e.to_string())?;
+        let env_tolerance = env_tolerance()?;
+        Ok(Settings { config, args: self.clone(), ignore_mask, env_tolerance })
+    }
+}
+
+/// The tolerance set by `PIXELMATCH_MAX_DIFF_PIXELS` and `PIXELMATCH_MAX_DIFF_RATIO`.
+fn env_tolerance() -> Result<Tolerance, String> {
+    let pixels = |s: &str| s.parse::<u64>().map_err(|e| e.to_string());
+    Ok(Tolerance {
+        max_diff_pixels: env_var("PIXELMATCH_MAX_DIFF_PIXELS", pixels)?,
+        max_diff_ratio: env_var("PIXELMATCH_MAX_DIFF_RATIO", parse_ratio)?,
+    })
+}
+
+/// The environment variable `name` read by `parse`, or `None` if it is unset or empty.
+fn env_var<T>(name: &str, parse: impl Fn(&str) -> Result<T, String>) -> Result<Option<T>, String> {
+    match std::env::var(name) {
+        Ok(v) if v.trim().is_empty() => Ok(None),
+        Ok(v) => parse(v.trim()).map(Some).map_err(|e| format!("invalid environment variable {name}: {e}")),
+        Err(std::env::VarError::NotPresent) => Ok(None),
+        Err(e) => Err(format!("invalid environment variable {name}: {e}")),
+    }
+}
+
+/// Resolves the options for each compared pair.
+struct Settings {
+    config: Option<config::Config>,
+    args: ComparisonArgs,
+    /// One byte per pixel, non-zero where ignored.
+    ignore_mask: Option<Vec<u8>>,
+    env_tolerance: Tolerance,
+}
+
+impl Settings {
+    /// Worker threads for batch comparisons; 0 lets rayon pick.
+    fn jobs(&self) -> usize {
+        self.args.jobs.unwrap_or(0)
+    }
+
+    fn progress(&self) -> bool {
+        !self.args.no_progress
+    }
+
+    fn resolve(&self, name: &str) -> Result<(Options, Tolerance), String> {
+        let (mut options, mut tolerance) = match &self.config {
+            // Presets are validated when the file is loaded.
+            Some(config) => config.resolve(name).expect("configuration was validated on load"),
+            None => Default::default(),
+        };
+        options.apply_env().map_err(|e| e.to_string())?;
+        if let Some(v) = self.env_tolerance.max_diff_pixels {
+            tolerance.max_diff_pixels = Some(v);
+        }
+        if let Some(v) = self.env_tolerance.max_diff_ratio {
+            tolerance.max_diff_ratio = Some(v);
+        }
+        if let Some(v) = self.args.threshold {
+            options.threshold = v;
+        }
+        if let Some(v) = self.args.detect_anti_aliasing {
+            options.detect_anti_aliasing = v;
+        }
+        if let Some(v) = self.args.alpha {
+            options.alpha = v;
+        }
+        if self.args.diff_mask {
+            options.diff_mask = true;
+        }
+        if let Some(v) = self.args.shift_tolerance {
+            options.shift_tolerance = v;
+        }
+        if self.args.subpixel_tolerance {
+            options.subpixel_tolerance = true;
+        }
+        if let Some(v) = self.args.jpeg_tolerance {
+            options.jpeg_tolerance = Some(v);
+        }
+        if let Some(v) = self.args.scroll_compensation {
+            options.scroll_compensation = v;
+        }
+        if let Some(v) = self.args.isolation_radius {
+            options.isolation_radius = v;
+        }
+        if !self.args.morphology.is_empty() {
+            options.morphology.clone_from(&self.args.morphology);
+        }
+        if let Some(v) = self.args.label_shift {
+            options.label_shift = v;
+        }
+        if let Some(v) = self.args.pre_blur {
+            options.pre_blur = Some(v);
+        }
+        if let Some(v) = self.args.quantize {
+            options.quantize = Some(v);
+        }
+        if self.args.median_filter {
+            options.median_filter = true;
+        }
+        if let Some(v) = self.args.edges {
+            options.edges = Some(v);
+        }
+        if let Some(v) = self.args.downscale {
+            options.downscale = Some(v);
+        }
+        if let Some(v) = self.args.device_pixel_ratio {
+            options.device_pixel_ratio = Some(v);
+        }
+        if self.args.density_scaling {
+            options.density_scaling = true;
+        }
+        options.ignore_regions.extend_from_slice(&self.args.ignore);
+        if self.ignore_mask.is_some() {
+            options.ignore_mask.clone_from(&self.ignore_mask);
+        }
+        if self.args.include_only {
+            options.include_only = true;
+        }
+        if let Some(v) = self.args.max_diff_pixels {
+            tolerance.max_diff_pixels = Some(v);
+        }
+        if let Some(v) = self.args.max_diff_ratio {
+            tolerance.max_diff_ratio = Some(v);
+        }
+        Ok((options, tolerance))
+    }
+}
+
+#[derive(Args, Debug)]
+struct CompareArgs {
+    /// First (baseline) image or directory, or `-` to read a PNG from stdin.
+    #[arg(required = true)]
+    image1: Option<PathBuf>,
+    /// Second (candidate) image or directory, or `-` to read a PNG from stdin.
+    #[arg(required = true)]
+    image2: Option<PathBuf>,
+    /// Where to write the diff image (a directory when comparing directories), or `-` for
+    /// stdout, in which case the summary is printed to stderr.
+    diff: Option<PathBuf>,
+    /// Output format.
+    #[arg(long, value_enum, default_value_t)]
+    format: Format,
+    /// Show the diff inline in terminals with image support (single pair, text format only).
+    #[arg(long, value_enum, value_name = "PROTOCOL", num_args = 0..=1, default_missing_value = "auto")]
+    preview: Option<preview::Preview>,
+    /// Maximum width/height of the inline preview.
+    #[arg(long, default_value_t = 480)]
+    preview_size: u32,
+    /// Record failures of a directory comparison in this review manifest (see `review`).
+    #[arg(long, value_name = "MANIFEST")]
+    review: Option<PathBuf>,
+    /// Also write a heatmap of structural dissimilarity (DSSIM) to this PNG, showing texture
+    /// and structure changes (single pair of files only).
+    #[arg(long, value_name = "PNG")]
+    dssim_map: Option<PathBuf>,
+    /// Also write an image colouring each pixel by why it did or did not count, for tuning
+    /// the threshold and anti-aliasing detection (single pair of files only): black equal,
+    /// blue below the threshold, yellow/cyan anti-aliased in the first/second image, green
+    /// tolerated, red/magenta different, grey ignored.
+    #[arg(long, value_name = "PNG")]
+    classify_map: Option<PathBuf>,
+    #[command(flatten)]
+    metrics: metrics::MetricArgs,
+    #[command(flatten)]
+    comparison: ComparisonArgs,
+}
+
+fn main() -> ExitCode {
+    let cli = match Cli::try_parse() {
+        Ok(cli) => cli,
+        Err(e) => {
+            let _ = e.print();
+            if !e.use_stderr() {
+                return ExitCode::from(EXIT_OK);
+            }
+            // Parsing failed, so the requested format has to be recovered by hand.
+            let machine_format = |f: &str| matches!(f, "json" | "ndjson" | "github");
+            let args: Vec<String> = std::env::args().collect();
+            let machine = args.iter().any(|a| a.strip_prefix("--format=").is_some_and(machine_format))
+                || args.windows(2).any(|w| w[0] == "--format" && machine_format(&w[1]));
+            return ExitCode::from(if machine { EXIT_MACHINE_ERROR } else { EXIT_USAGE });
+        }
+    };
+    let (result, format) = match &cli.command {
+        Some(Command::Approve(args)) => (approve::run(args), Format::Text),
+        Some(Command::Report(args)) => (report::run(args), Format::Text),
+        Some(Command::Montage(args)) => (montage::run(args), Format::Text),
+        Some(Command::Review(args)) => (review::run(args), Format::Text),
+        Some(Command::Flaky(args)) => (flaky::run(args), Format::Text),
+        Some(Command::Tiles(args)) => (tiles::run(args), Format::Text),
+        #[cfg(feature = "tui")]
+        Some(Command::View(args)) => (view::run(args), Format::Text),
+        None => (compare(&cli.compare), cli.compare.format),
+    };
+    match result {
+        Ok(code) => ExitCode::from(code),
+        Err(message) => {
+            eprintln!("{message}");
+            if format == Format::Github {
+                println!("::error title=pixelmatch::{}", github::escape_data(&message));
+            }
+            ExitCode::from(if format == Format::Text { EXIT_USAGE } else { EXIT_MACHINE_ERROR })
+        }
+    }
+}
+
+fn compare(args: &CompareArgs) -> Result<u8, String> {
+    // Both are required by clap unless a subcommand is given.
+    let (Some(path1), Some(path2)) = (&args.image1, &args.image2) else {
+        return Err("two images are required".to_string());
+    };
+    let settings = args.comparison.settings()?;
+
+    if path1.is_dir() && path2.is_dir() {
+        if let Some(flag) = [("--dssim-map", args.dssim_map.is_some()), ("--classify-map", args.classify_map.is_some())]
+            .into_iter()
+            .find_map(|(flag, given)| given.then_some(flag))
+            .or(args.metrics.requested())
+        {
+            return Err(format!("{flag} needs a single pair of images"));
+        }
+        let pairs = collect_pairs(path1, path2).map_err(|e| format!("{}: {e}", path2.display()))?;
+        let comparisons = compare_all(&pairs, &settings, args.diff.as_deref())?;
+        if let Some(manifest) = &args.review {
+            review::record(manifest, &comparisons)?;
+        }
+        return Ok(report(&comparisons, args.format));
+    }
+
+    if is_stdio(path1) && is_stdio(path2) {
+        return Err("only one image can be read from stdin".to_string());
+    }
+    // Keep stdout clean for the PNG bytes when the diff is written there.
+    let diff_to_stdout = args.diff.as_deref().is_some_and(is_stdio);
+    if diff_to_stdout && args.format != Format::Text {
+        return Err("machine-readable output cannot be combined with writing the diff to stdout".to_string());
+    }
+    if let Some(flag) = args.metrics.printed().filter(|_| args.format != Format::Text) {
+        return Err(format!("{flag} prints to the text output and cannot be combined with machine-readable output"));
+    }
+    for path in [path1, path2].into_iter().filter(|p| !is_stdio(p)) {
+        if !path.is_file() {
+            return Err(format!("{}: not a file", path.display()));
+        }
+    }
+    if let Some(path) = &args.dssim_map {
+        write_dssim_map(path1, path2, path)?;
+    }
+    let pair = Pair {
+        name: path2.display().to_string(),
+        baseline: path1.clone(),
+        candidate: path2.clone(),
+    };
+    let (options, tolerance) = settings.resolve(&pair.name)?;
+    if let Some(path) = &args.classify_map {
+        write_classify_map(path1, path2, path, &options)?;
+    }
+    let metrics = args.metrics.compute(path1, path2, &options)?;
+    let start = Instant::now();
+    let protocol = match args.preview {
+        Some(p) if args.format == Format::Text && !diff_to_stdout => p.resolve(),
+        _ => preview::Preview::None,
+    };
+    let (comparison, diff) = if protocol == preview::Preview::None {
+        (compare_pair(&pair, &options, &tolerance, args.diff.as_deref(), true), None)
+    } else {
+        compare_pair_with_diff(&pair, &options, &tolerance, args.diff.as_deref(), true)
+    };
+    if args.format != Format::Text {
+        return Ok(report(std::slice::from_ref(&comparison), args.format));
+    }
+
+    let mut text = String::new();
+    let code = match &comparison.outcome {
+        outcome @ (Outcome::Pass { result, width, height } | Outcome::Fail { result, width, height }) => {
+            let _ = writeln!(text, "matched in: {:.3}ms", start.elapsed().as_secs_f64() * 1000.0);
+            let total = *width as f64 * *height as f64;
+            let percentage = if total > 0.0 { result.diff_count as f64 / total * 100.0 } else { 0.0 };
+            let _ = writeln!(text, "different pixels: {}", result.diff_count);
+            let _ = writeln!(text, "error: {}%", (percentage * 100.0).round() / 100.0);
+            if result.aa_count > 0 {
+                let _ = writeln!(text, "anti-aliased pixels: {}", result.aa_count);
+            }
+            if result.scroll_offset != 0 {
+                let _ = writeln!(text, "scroll offset: {} rows", result.scroll_offset);
+            }
+            if result.moved_labels > 0 {
+                let _ = writeln!(text, "moved labels: {}", result.moved_labels);
+            }
+            if let Some((x0, y0, x1, y1)) = result.diff_bounds {
+                let _ = writeln!(text, "changed area: {x0},{y0} to {x1},{y1}");
+            }
+            text.push_str(&metrics);
+            if outcome.passed() { EXIT_OK } else { EXIT_DIFFERENT }
+        }
+        Outcome::DimensionMismatch { baseline: (bw, bh), candidate: (cw, ch) } => {
+            let _ = writeln!(text, "Image dimensions do not match: {bw}x{bh} vs {cw}x{ch}");
+            EXIT_DIMENSIONS
+        }
+        Outcome::MissingBaseline => return Err(format!("{}: not found", path1.display())),
+        Outcome::Error(e) => return Err(e.clone()),
+    };
+    if let Some(image) = diff.filter(|_| code == EXIT_DIFFERENT) {
+        text.extend(preview::render(&image, protocol, args.preview_size));
+    }
+    if diff_to_stdout {
+        eprint!("{text}");
+    } else {
+        print!("{text}");
+    }
+    Ok(code)
+}
+
+/// Read two image files for an extra metric `flag`.
+fn load_images(path1: &Path, path2: &Path, flag: &str) -> Result<(Image, Image), String> {
+    if is_stdio(path1) || is_stdio(path2) {
+        return Err(format!("{flag} cannot read images from stdin"));
+    }
+    let load = |path: &Path| read_png(path).map_err(|e| format!("{}: {e}", path.display()));
+    Ok((load(path1)?, load(path2)?))
+}
+
+/// Like [`load_images`], for a metric that needs the images at the same size.
+fn load_same_size(path1: &Path, path2: &Path, flag: &str) -> Result<(Image, Image), String> {
+    let (image1, image2) = load_images(path1, path2, flag)?;
+    if (image1.width, image1.height) != (image2.width, image2.height) {
+        return Err(format!("{flag} needs images of the same size"));
+    }
+    Ok((image1, image2))
+}
+
+/// Write the DSSIM heatmap of two image files to `out`.
+fn write_dssim_map(path1: &Path, path2: &Path, out: &Path) -> Result<(), String> {
+    let (image1, image2) = load_same_size(path1, path2, "--dssim-map")?;
+    let mut rgba = vec![0; image1.data.len()];
+    pixelmatch::ssim::pixelmatch_ssim(&image1.data, &image2.data, Some(&mut rgba), image1.width, image1.height)
+        .map_err(|e| e.to_string())?;
+    write_png(out, &rgba, image1.width, image1.height).map_err(|e| format!("{}: {e}", out.display()))
+}
+
+/// Write the per-pixel classification of two image files under `options` to `out`.
+fn write_classify_map(path1: &Path, path2: &Path, out: &Path, options: &Options) -> Result<(), String> {
+    let (image1, image2) = load_same_size(path1, path2, "--classify-map")?;
+    let classes = classify_pixels(&image1.data, &image2.data, image1.width, image1.height, options)
+        .map_err(|e| e.to_string())?;
+    let rgba = classification_image(&classes);
+    write_png(out, &rgba, image1.width, image1.height).map_err(|e| format!("{}: {e}", out.display()))
+}
+
+/// Print the results of a batch in the requested format and return the exit code.
+fn report(comparisons: &[Comparison], format: Format) -> u8 {
+    match format {
+        Format::Text => {
+            for c in comparisons {
+                match &c.outcome {
+                    Outcome::Pass { .. } => {}
+                    Outcome::Fail { result, .. } => {
+                        println!("FAIL {}: {} different pixels", c.pair.name, result.diff_count)
+                    }
+                    Outcome::MissingBaseline => println!("NEW  {}: no baseline", c.pair.name),
+                    Outcome::DimensionMismatch { baseline: (bw, bh), candidate: (cw, ch) } => {
+                        println!("FAIL {}: dimensions changed from {bw}x{bh} to {cw}x{ch}", c.pair.name)
+                    }
+                    Outcome::Error(e) => println!("ERR  {}: {e}", c.pair.name),
+                }
+            }
+            let passed = comparisons.iter().filter(|c| c.outcome.passed()).count();
+            println!("{passed} of {} comparisons passed", comparisons.len());
+        }
+        Format::Json => println!("{}", output::to_json(comparisons)),
+        Format::Ndjson => {
+            for c in comparisons {
+                println!("{}", output::to_ndjson_line(c));
+            }
+        }
+        Format::Github => {
+            if let Err(e) = github::report(comparisons) {
+                eprintln!("{e}");
+                return EXIT_MACHINE_ERROR;
+            }
+        }
+    }
+
+    let errored = comparisons.iter().any(|c| matches!(c.outcome, Outcome::Error(_)));
+    let failed = comparisons.iter().any(|c| !c.outcome.passed());
+    match (format, errored, failed) {
+        (Format::Text, true, _) => EXIT_USAGE,
+        (Format::Text, false, true) => EXIT_DIFFERENT,
+        (_, true, _) => EXIT_MACHINE_ERROR,
+        (_, false, true) => EXIT_MACHINE_DIFFERENT,
+        (_, false, false) => EXIT_OK,
+    }
+}
@@ -0,0 +1,170 @@
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
+
+use clap::Args;
+use pixelmatch::causes::channel_counts;
+use pixelmatch::deltas::delta_stats;
+use pixelmatch::io::write_png;
+use pixelmatch::normals::compare_normals;
+use pixelmatch::Options;
+
+use crate::{load_images, load_same_size};
+
+/// Extra metrics `compare` prints after the match summary of a single pair of files. Each is
+/// its own pass over the images, run only when its flag is given.
+#[derive(Args, Debug)]
+pub struct MetricArgs {
+    /// Also print the gradient magnitude similarity deviation (GMSD), a structural score
+    /// that ignores uniform brightness shifts (single pair of files, text output only).
+    #[arg(long)]
+    pub gmsd: bool,
+    /// Also print the peak signal-to-noise ratio (PSNR) in decibels, the usual figure for
+    /// codec and rendering quality (single pair of files, text output only).
+    #[arg(long)]
+    pub psnr: bool,
+    /// Also print the largest, mean and 95th percentile colour delta of the differing pixels,
+    /// in threshold units, to tell one badly wrong pixel from many borderline ones (single
+    /// pair of files, text output only).
+    #[arg(long)]
+    pub delta_stats: bool,
+    /// Also print luma and per-channel histogram distances (chi-square, intersection, earth
+    /// mover's), which reveal global colour casts and brightness shifts (single pair of
+    /// files, text output only).
+    #[arg(long)]
+    pub histogram: bool,
+    /// Also print how many differing pixels changed in each of the red, green, blue and alpha
+    /// channels, which separates colour-profile and alpha-compositing regressions from
+    /// general rendering changes (single pair of files, text output only).
+    #[arg(long)]
+    pub channels: bool,
+    /// Also print the FLIP perceptual difference, the mean error (0 to 1) a viewer flipping
+    /// between the images would see (single pair of files, text output only).
+    #[arg(long)]
+    pub flip: bool,
+    /// Also write the per-pixel FLIP error map to this PNG (single pair of files only).
+    #[arg(long, value_name = "PNG")]
+    pub flip_map: Option<PathBuf>,
+    /// Viewing distance for FLIP, in pixels per degree of visual angle; the default is a
+    /// 0.7 m wide 4K monitor seen from 0.7 m.
+    #[arg(long, value_name = "PPD", default_value_t = pixelmatch::flip::DEFAULT_PPD)]
+    pub flip_ppd: f64,
+    /// Also read both images as normal maps and print how many pixels' normals differ by
+    /// more than this many degrees (single pair of files, text output only).
+    #[arg(long, value_name = "DEGREES")]
+    pub normal_angle: Option<f64>,
+    /// Also print the Butteraugli-style psychovisual distance, where about 1.0 is just
+    /// noticeable (single pair of files, text output only).
+    #[cfg(feature = "butteraugli")]
+    #[arg(long)]
+    pub butteraugli: bool,
+}
+
+impl MetricArgs {
+    /// The first metric flag given that prints to the text output, if any.
+    pub fn printed(&self) -> Option<&'static str> {
+        let flags = [
+            ("--gmsd", self.gmsd),
+            ("--psnr", self.psnr),
+            ("--delta-stats", self.delta_stats),
+            ("--histogram", self.histogram),
+            ("--channels", self.channels),
+            ("--flip", self.flip),
+            ("--normal-angle", self.normal_angle.is_some()),
+            #[cfg(feature = "butteraugli")]
+            ("--butteraugli", self.butteraugli),
+        ];
+        flags.into_iter().find_map(|(flag, given)| given.then_some(flag))
+    }
+
+    /// The first metric flag given, including those that only write a file.
+    pub fn requested(&self) -> Option<&'static str> {
+        self.printed().or(self.flip_map.as_ref().map(|_| "--flip-map"))
+    }
+
+    /// Compute the requested metrics of two image files, writing `--flip-map` if given, and
+    /// return the lines to print, in the order they follow the match summary.
+    pub fn compute(&self, path1: &Path, path2: &Path, options: &Options) -> Result<String, String> {
+        let mut text = String::new();
+        if self.gmsd {
+            let (image1, image2) = load_same_size(path1, path2, "--gmsd")?;
+            let score = pixelmatch::gmsd::gmsd(&image1.data, &image2.data, image1.width, image1.height);
+            let _ = writeln!(text, "gmsd: {:.4}", score.map_err(|e| e.to_string())?);
+        }
+        if self.psnr {
+            let (image1, image2) = load_same_size(path1, path2, "--psnr")?;
+            let score = pixelmatch::psnr::psnr(&image1.data, &image2.data, image1.width, image1.height);
+            let _ = writeln!(text, "psnr: {:.2} dB", score.map_err(|e| e.to_string())?);
+        }
+        if self.delta_stats {
+            let (image1, image2) = load_same_size(path1, path2, "--delta-stats")?;
+            let stats = delta_stats(&image1.data, &image2.data, image1.width, image1.height, options);
+            let d = stats.map_err(|e| e.to_string())?;
+            let _ = writeln!(
+                text,
+                "deltas: max {:.4}, mean {:.4}, p95 {:.4} over {} differing pixels",
+                d.max, d.mean, d.p95, d.differing
+            );
+        }
+        if self.flip || self.flip_map.is_some() {
+            let score = flip_error(path1, path2, self.flip_ppd, self.flip_map.as_deref())?;
+            if self.flip {
+                let _ = writeln!(text, "flip: {score:.4}");
+            }
+        }
+        if let Some(max_angle) = self.normal_angle {
+            let (image1, image2) = load_same_size(path1, path2, "--normal-angle")?;
+            let (w, h) = (image1.width, image1.height);
+            let diff = compare_normals(&image1.data, &image2.data, None, w, h, max_angle, options);
+            let n = diff.map_err(|e| e.to_string())?;
+            let _ = writeln!(
+                text,
+                "normals over {max_angle}°: {} (max {:.2}°, mean {:.2}°)",
+                n.diff_count, n.max_angle, n.mean_angle
+            );
+        }
+        #[cfg(feature = "butteraugli")]
+        if self.butteraugli {
+            let (image1, image2) = load_same_size(path1, path2, "--butteraugli")?;
+            let score = pixelmatch::butteraugli::butteraugli(&image1.data, &image2.data, image1.width, image1.height);
+            let b = score.map_err(|e| e.to_string())?;
+            let _ = writeln!(text, "butteraugli: {:.4} (3-norm {:.4})", b.score, b.norm);
+        }
+        if self.channels {
+            let (image1, image2) = load_same_size(path1, path2, "--channels")?;
+            let counts = channel_counts(&image1.data, &image2.data, image1.width, image1.height, options);
+            let c = counts.map_err(|e| e.to_string())?;
+            let _ = writeln!(
+                text,
+                "changed channels: red {}, green {}, blue {}, alpha {}",
+                c.red, c.green, c.blue, c.alpha
+            );
+        }
+        if self.histogram {
+            let (image1, image2) = load_images(path1, path2, "--histogram")?;
+            let h = pixelmatch::histogram::compare(&image1.data, &image2.data);
+            for (name, d) in [("luma", h.luma), ("red", h.red), ("green", h.green), ("blue", h.blue)] {
+                let _ = writeln!(
+                    text,
+                    "{name} histogram: chi-square {:.4}, intersection {:.4}, emd {:.4}",
+                    d.chi_square, d.intersection, d.emd
+                );
+            }
+        }
+        Ok(text)
+    }
+}
+
+/// Mean FLIP error of two image files at `ppd`, also writing the error map to `out` if given.
+fn flip_error(path1: &Path, path2: &Path, ppd: f64, out: Option<&Path>) -> Result<f64, String> {
+    if !(ppd.is_finite() && ppd > 0.0) {
+        return Err(format!("--flip-ppd must be a positive number, got {ppd}"));
+    }
+    let (image1, image2) = load_same_size(path1, path2, "--flip")?;
+    let map = pixelmatch::flip::flip_map(&image1.data, &image2.data, image1.width, image1.height, ppd)
+        .map_err(|e| e.to_string())?;
+    if let Some(out) = out {
+        let rgba = pixelmatch::ssim::heatmap(&map, 1.0);
+        write_png(out, &rgba, image1.width, image1.height).map_err(|e| format!("{}: {e}", out.display()))?;
+    }
+    Ok(map.iter().map(|&v| v as f64).sum::<f64>() / map.len().max(1) as f64)
+}
@@ -0,0 +1,214 @@
+use std::path::PathBuf;
+
+use clap::{Args, ValueEnum};
+use pixelmatch::io::{read_png, write_png, Image};
+use pixelmatch::pixelmatch;
+use pixelmatch::resize::thumbnail;
+use rayon::prelude::*;
+
+use crate::batch::{collect_pairs, compare_all, Outcome};
+use crate::ComparisonArgs;
+
+/// Tile the worst diffs of a run into a single labelled PNG.
+#[derive(Args, Debug)]
+pub struct MontageArgs {
+    /// Directory containing the baseline images.
+    pub baseline_dir: PathBuf,
+    /// Directory containing the candidate images (same relative layout).
+    pub candidate_dir: PathBuf,
+    /// Output PNG.
+    #[arg(short, long, default_value = "pixelmatch-montage.png")]
+    pub out: PathBuf,
+    /// Number of diffs to include.
+    #[arg(short = 'n', long, default_value_t = 12)]
+    pub top: usize,
+    /// How to rank the failing comparisons.
+    #[arg(long, value_enum, default_value_t)]
+    pub sort: Severity,
+    /// Number of tiles per row.
+    #[arg(long, default_value_t = 4)]
+    pub columns: usize,
+    /// Maximum width/height of each diff tile.
+    #[arg(long, default_value_t = 240)]
+    pub tile_size: u32,
+    #[command(flatten)]
+    pub comparison: ComparisonArgs,
+}
+
+/// Ranking of failing comparisons.
+#[derive(ValueEnum, Debug, Clone, Copy, Default)]
+pub enum Severity {
+    /// Fraction of differing pixels.
+    #[default]
+    Ratio,
+    /// Number of differing pixels.
+    Pixels,
+}
+
+const PADDING: u32 = 8;
+const LABEL_HEIGHT: u32 = 2 * LINE_HEIGHT + 4;
+const BACKGROUND: [u8; 4] = [48, 48, 48, 255];
+const TEXT: [u8; 4] = [255, 255, 255, 255];
+const TEXT_DIM: [u8; 4] = [190, 190, 190, 255];
+
+pub fn run(args: &MontageArgs) -> Result<u8, String> {
+    let pairs = collect_pairs(&args.baseline_dir, &args.candidate_dir)
+        .map_err(|e| format!("{}: {e}", args.candidate_dir.display()))?;
+    let settings = args.comparison.settings()?;
+    let mut failed: Vec<_> = compare_all(&pairs, &settings, None)?
+        .into_iter()
+        .filter_map(|c| match c.outcome {
+            Outcome::Fail { result, width, height } => {
+                let ratio = result.diff_count as f64 / (width as f64 * height as f64);
+                Some((c.pair, result.diff_count, ratio))
+            }
+            _ => None,
+        })
+        .collect();
+    if failed.is_empty() {
+        println!("no failing comparisons; nothing to tile");
+        return Ok(crate::EXIT_OK);
+    }
+    match args.sort {
+        Severity::Ratio => failed.sort_by(|a, b| b.2.total_cmp(&a.2)),
+        Severity::Pixels => failed.sort_by_key(|f| std::cmp::Reverse(f.1)),
+    }
+    let shown = &failed[..failed.len().min(args.top.max(1))];
+
+    let tiles = shown
+        .par_iter()
+        .map(|(pair, count, ratio)| {
+            let baseline = read_png(&pair.baseline).map_err(|e| format!("{}: {e}", pair.baseline.display()))?;
+            let candidate = read_png(&pair.candidate).map_err(|e| format!("{}: {e}", pair.candidate.display()))?;
+            let (options, _) = settings.resolve(&pair.name)?;
+            let mut diff = Image::new(baseline.width, baseline.height);
+            pixelmatch(&baseline.data, &candidate.data, Some(&mut diff.data), diff.width, diff.height, &options)
+                .map_err(|e| format!("{}: {e}", pair.name))?;
+            let (data, width, height) = thumbnail(&diff.data, diff.width, diff.height, args.tile_size);
+            let stats = format!("{count} px ({:.2}%)", ratio * 100.0);
+            Ok((Image { data, width, height }, pair.name.clone(), stats))
+        })
+        .collect::<Result<Vec<_>, String>>()?;
+
+    let columns = args.columns.clamp(1, tiles.len()) as u32;
+    let rows = tiles.len().div_ceil(columns as usize) as u32;
+    let cell_w = args.tile_size + PADDING;
+    let cell_h = args.tile_size + LABEL_HEIGHT + PADDING;
+    let mut sheet = Image::new(columns * cell_w + PADDING, rows * cell_h + PADDING);
+    for px in sheet.data.chunks_exact_mut(4) {
+        px.copy_from_slice(&BACKGROUND);
+    }
+    let max_chars = (args.tile_size / GLYPH_ADVANCE) as usize;
+    for (i, (tile, name, stats)) in tiles.iter().enumerate() {
+        let x = PADDING + (i as u32 % columns) * cell_w;
+        let y = PADDING + (i as u32 / columns) * cell_h;
+        blit(&mut sheet, tile, x + (args.tile_size - tile.width) / 2, y);
+        let label_y = y + args.tile_size + 2;
+        draw_text(&mut sheet, x, label_y, &ellipsize(name, max_chars), TEXT);
+        draw_text(&mut sheet, x, label_y + LINE_HEIGHT, stats, TEXT_DIM);
+    }
+
+    write_png(&args.out, &sheet.data, sheet.width, sheet.height).map_err(|e| format!("{}: {e}", args.out.display()))?;
+    println!("montage of {} of {} failing comparisons written to {}", tiles.len(), failed.len(), args.out.display());
+    Ok(crate::EXIT_DIFFERENT)
+}
+
+/// Copy `src` into `dst` at (x, y), compositing over the existing pixels.
+fn blit(dst: &mut Image, src: &Image, x: u32, y: u32) {
+    for sy in 0..src.height {
+        for sx in 0..src.width {
+            let s = ((sy * src.width + sx) * 4) as usize;
+            let d = (((y + sy) * dst.width + x + sx) * 4) as usize;
+            let a = src.data[s + 3] as u32;
+            for c in 0..3 {
+                dst.data[d + c] = ((src.data[s + c] as u32 * a + dst.data[d + c] as u32 * (255 - a)) / 255) as u8;
+            }
+        }
+    }
+}
+
+/// Keep the end of `s` (the file name is the most useful part of a path) within `max` characters.
+fn ellipsize(s: &str, max: usize) -> String {
+    let n = s.chars().count();
+    if n <= max {
+        return s.to_string();
+    }
+    let tail: String = s.chars().skip(n + 2 - max.max(2)).collect();
+    format!("..{tail}")
+}
+
+const GLYPH_ADVANCE: u32 = 6;
+const LINE_HEIGHT: u32 = 10;
+
+/// Draw `text` with the built-in 5x7 font; characters without a glyph are drawn as `?`.
+fn draw_text(img: &mut Image, x: u32, y: u32, text: &str, color: [u8; 4]) {
+    for (i, c) in text.chars().enumerate() {
+        let gx = x + i as u32 * GLYPH_ADVANCE;
+        if gx + 5 > img.width || y + 7 > img.height {
+            break;
+        }
+        for (row, bits) in glyph(c).iter().enumerate() {
+            for col in 0..5 {
+                if bits & (0x10 >> col) != 0 {
+                    let p = (((y + row as u32) * img.width + gx + col) * 4) as usize;
+                    img.data[p..p + 4].copy_from_slice(&color);
+                }
+            }
+        }
+    }
+}
+
+/// 5x7 bitmap of an ASCII character (lowercase is drawn as uppercase), one byte per row.
+fn glyph(c: char) -> [u8; 7] {
+    match c.to_ascii_uppercase() {
+        ' ' => [0; 7],
+        '0' => [0x0E, 0x11, 0x13, 0x15, 0x19, 0x11, 0x0E],
+        '1' => [0x04, 0x0C, 0x04, 0x04, 0x04, 0x04, 0x0E],
+        '2' => [0x0E, 0x11, 0x01, 0x02, 0x04, 0x08, 0x1F],
+        '3' => [0x1F, 0x02, 0x04, 0x02, 0x01, 0x11, 0x0E],
+        '4' => [0x02, 0x06, 0x0A, 0x12, 0x1F, 0x02, 0x02],
+        '5' => [0x1F, 0x10, 0x1E, 0x01, 0x01, 0x11, 0x0E],
+        '6' => [0x06, 0x08, 0x10, 0x1E, 0x11, 0x11, 0x0E],
+        '7' => [0x1F, 0x01, 0x02, 0x04, 0x08, 0x08, 0x08],
+        '8' => [0x0E, 0x11, 0x11, 0x0E, 0x11, 0x11, 0x0E],
+        '9' => [0x0E, 0x11, 0x11, 0x0F, 0x01, 0x02, 0x0C],
+        'A' => [0x0E, 0x11, 0x11, 0x11, 0x1F, 0x11, 0x11],
+        'B' => [0x1E, 0x11, 0x11, 0x1E, 0x11, 0x11, 0x1E],
+        'C' => [0x0E, 0x11, 0x10, 0x10, 0x10, 0x11, 0x0E],
+        'D' => [0x1C, 0x12, 0x11, 0x11, 0x11, 0x12, 0x1C],
+        'E' => [0x1F, 0x10, 0x10, 0x1E, 0x10, 0x10, 0x1F],
+        'F' => [0x1F, 0x10, 0x10, 0x1E, 0x10, 0x10, 0x10],
+        'G' => [0x0E, 0x11, 0x10, 0x17, 0x11, 0x11, 0x0F],
+        'H' => [0x11, 0x11, 0x11, 0x1F, 0x11, 0x11, 0x11],
+        'I' => [0x0E, 0x04, 0x04, 0x04, 0x04, 0x04, 0x0E],
+        'J' => [0x07, 0x02, 0x02, 0x02, 0x02, 0x12, 0x0C],
+        'K' => [0x11, 0x12, 0x14, 0x18, 0x14, 0x12, 0x11],
+        'L' => [0x10, 0x10, 0x10, 0x10, 0x10, 0x10, 0x1F],
+        'M' => [0x11, 0x1B, 0x15, 0x15, 0x11, 0x11, 0x11],
+        'N' => [0x11, 0x11, 0x19, 0x15, 0x13, 0x11, 0x11],
+        'O' => [0x0E, 0x11, 0x11, 0x11, 0x11, 0x11, 0x0E],
+        'P' => [0x1E, 0x11, 0x11, 0x1E, 0x10, 0x10, 0x10],
+        'Q' => [0x0E, 0x11, 0x11, 0x11, 0x15, 0x12, 0x0D],
+        'R' => [0x1E, 0x11, 0x11, 0x1E, 0x14, 0x12, 0x11],
+        'S' => [0x0F, 0x10, 0x10, 0x0E, 0x01, 0x01, 0x1E],
+        'T' => [0x1F, 0x04, 0x04, 0x04, 0x04, 0x04, 0x04],
+        'U' => [0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x0E],
+        'V' => [0x11, 0x11, 0x11, 0x11, 0x11, 0x0A, 0x04],
+        'W' => [0x11, 0x11, 0x11, 0x15, 0x15, 0x15, 0x0A],
+        'X' => [0x11, 0x11, 0x0A, 0x04, 0x0A, 0x11, 0x11],
+        'Y' => [0x11, 0x11, 0x11, 0x0A, 0x04, 0x04, 0x04],
+        'Z' => [0x1F, 0x01, 0x02, 0x04, 0x08, 0x10, 0x1F],
+        '.' => [0x00, 0x00, 0x00, 0x00, 0x00, 0x0C, 0x0C],
+        ',' => [0x00, 0x00, 0x00, 0x00, 0x0C, 0x04, 0x08],
+        '-' => [0x00, 0x00, 0x00, 0x1F, 0x00, 0x00, 0x00],
+        '_' => [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x1F],
+        '/' => [0x00, 0x01, 0x02, 0x04, 0x08, 0x10, 0x00],
+        '%' => [0x18, 0x19, 0x02, 0x04, 0x08, 0x13, 0x03],
+        '(' => [0x02, 0x04, 0x08, 0x08, 0x08, 0x04, 0x02],
+        ')' => [0x08, 0x04, 0x02, 0x02, 0x02, 0x04, 0x08],
+        ':' => [0x00, 0x0C, 0x0C, 0x00, 0x0C, 0x0C, 0x00],
+        '+' => [0x00, 0x04, 0x04, 0x1F, 0x04, 0x04, 0x00],
+        '=' => [0x00, 0x00, 0x1F, 0x00, 0x1F, 0x00, 0x00],
+        _ => [0x0E, 0x11, 0x01, 0x02, 0x04, 0x00, 0x04],
+    }
+}
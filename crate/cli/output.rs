@@ -0,0 +1,150 @@
+//! Machine-readable output formats.
+//!
+//! Every comparison is reported as one record (schema version 1):
+//!
+//! | Field           | Type            | Description                                                                  |
+//! | --------------- | --------------- | ---------------------------------------------------------------------------- |
+//! | `name`          | string          | Pair name (relative path in directory mode, candidate path otherwise)        |
+//! | `baseline`      | string          | Path of the baseline (first) image                                           |
+//! | `candidate`     | string          | Path of the candidate (second) image                                         |
+//! | `status`        | string          | `pass`, `fail`, `missing_baseline`, `dimension_mismatch` or `error`          |
+//! | `width`         | number \| null  | Image width, when both images were decoded with matching dimensions          |
+//! | `height`        | number \| null  | Image height, as above                                                       |
+//! | `diff_count`    | number \| null  | Number of mismatched pixels                                                  |
+//! | `aa_count`      | number \| null  | Number of anti-aliased pixels detected                                       |
+//! | `identical`     | boolean \| null | Whether the images are byte-identical                                        |
+//! | `diff_ratio`    | number \| null  | `diff_count / (width * height)`                                              |
+//! | `scroll_offset` | number \| null  | Rows of scroll compensation applied, positive if the candidate sat lower     |
+//! | `moved_labels`  | number \| null  | Moved label clusters `--label-shift` left out of `diff_count`                |
+//! | `diff_bounds`   | array \| null   | `[min_x, min_y, max_x, max_y]` (inclusive) of the counted pixels, if any     |
+//! | `diff`          | string \| null  | Path of the written diff image                                               |
+//! | `message`       | string \| null  | Human-readable detail for `dimension_mismatch` and `error`                   |
+//!
+//! `--format ndjson` prints one record per line. `--format json` prints a single document:
+//! `{"schema_version": 1, "passed": bool, "summary": {"total", "passed", "failed", "errors"}, "results": [record]}`.
+
+use clap::ValueEnum;
+use serde::Serialize;
+
+use crate::batch::{Comparison, Outcome};
+
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// Output format of comparison results.
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Format {
+    /// Human-readable text.
+    #[default]
+    Text,
+    /// A single JSON document.
+    Json,
+    /// One JSON record per line.
+    Ndjson,
+    /// GitHub Actions workflow commands (`::error`) plus a job summary.
+    Github,
+}
+
+#[derive(Serialize)]
+pub struct Record<'a> {
+    name: &'a str,
+    baseline: String,
+    candidate: String,
+    status: &'static str,
+    width: Option<u32>,
+    height: Option<u32>,
+    diff_count: Option<u32>,
+    aa_count: Option<u32>,
+    identical: Option<bool>,
+    diff_ratio: Option<f64>,
+    scroll_offset: Option<i32>,
+    moved_labels: Option<u32>,
+    diff_bounds: Option<[u32; 4]>,
+    diff: Option<String>,
+    message: Option<String>,
+}
+
+impl<'a> Record<'a> {
+    pub fn new(comparison: &'a Comparison) -> Self {
+        let pair = &comparison.pair;
+        let mut record = Record {
+            name: &pair.name,
+            baseline: pair.baseline.display().to_string(),
+            candidate: pair.candidate.display().to_string(),
+            status: status(&comparison.outcome),
+            width: None,
+            height: None,
+            diff_count: None,
+            aa_count: None,
+            identical: None,
+            diff_ratio: None,
+            scroll_offset: None,
+            moved_labels: None,
+            diff_bounds: None,
+            diff: comparison.diff.as_ref().map(|p| p.display().to_string()),
+            message: None,
+        };
+        match &comparison.outcome {
+            Outcome::Pass { result, width, height } | Outcome::Fail { result, width, height } => {
+                record.width = Some(*width);
+                record.height = Some(*height);
+                record.diff_count = Some(result.diff_count);
+                record.aa_count = Some(result.aa_count);
+                record.identical = Some(result.identical);
+                record.diff_ratio = Some(result.diff_ratio());
+                record.scroll_offset = Some(result.scroll_offset);
+                record.moved_labels = Some(result.moved_labels);
+                record.diff_bounds = result.diff_bounds.map(|(x0, y0, x1, y1)| [x0, y0, x1, y1]);
+            }
+            Outcome::MissingBaseline => {}
+            Outcome::DimensionMismatch { baseline: (bw, bh), candidate: (cw, ch) } => {
+                record.message = Some(format!("Image dimensions do not match: {bw}x{bh} vs {cw}x{ch}"));
+            }
+            Outcome::Error(e) => record.message = Some(e.clone()),
+        }
+        record
+    }
+}
+
+fn status(outcome: &Outcome) -> &'static str {
+    match outcome {
+        Outcome::Pass { .. } => "pass",
+        Outcome::Fail { .. } => "fail",
+        Outcome::MissingBaseline => "missing_baseline",
+        Outcome::DimensionMismatch { .. } => "dimension_mismatch",
+        Outcome::Error(_) => "error",
+    }
+}
+
+#[derive(Serialize)]
+struct Summary {
+    total: usize,
+    passed: usize,
+    failed: usize,
+    errors: usize,
+}
+
+#[derive(Serialize)]
+struct Document<'a> {
+    schema_version: u32,
+    passed: bool,
+    summary: Summary,
+    results: Vec<Record<'a>>,
+}
+
+/// Serialise comparisons as a single JSON document.
+pub fn to_json(comparisons: &[Comparison]) -> String {
+    let errors = comparisons.iter().filter(|c| matches!(c.outcome, Outcome::Error(_))).count();
+    let passed = comparisons.iter().filter(|c| c.outcome.passed()).count();
+    let document = Document {
+        schema_version: SCHEMA_VERSION,
+        passed: passed == comparisons.len(),
+        summary: Summary { total: comparisons.len(), passed, failed: comparisons.len() - passed - errors, errors },
+        results: comparisons.iter().map(Record::new).collect(),
+    };
+    serde_json::to_string_pretty(&document).expect("serialising to a string cannot fail")
+}
+
+/// Serialise one comparison as a single-line JSON record.
+pub fn to_ndjson_line(comparison: &Comparison) -> String {
+    serde_json::to_string(&Record::new(comparison)).expect("serialising to a string cannot fail")
+}
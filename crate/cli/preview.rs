@@ -0,0 +1,140 @@
+//! Inline image previews for terminals that support a graphics protocol.
+
+use std::fmt::Write;
+use std::io::IsTerminal;
+
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use clap::ValueEnum;
+use pixelmatch::io::{encode_png, Image};
+use pixelmatch::resize::thumbnail;
+
+/// Terminal graphics protocol used for `--preview`.
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Preview {
+    /// Detect the protocol from the environment; no preview if none is recognised or stdout is
+    /// not a terminal.
+    Auto,
+    /// Kitty graphics protocol (kitty, Ghostty, WezTerm).
+    Kitty,
+    /// iTerm2 inline images (iTerm2, WezTerm, Tabby).
+    Iterm,
+    /// DEC sixel graphics (foot, mlterm, xterm -ti vt340, ...).
+    Sixel,
+    /// Never show a preview.
+    None,
+}
+
+impl Preview {
+    /// Resolve `Auto` to a concrete protocol (or `None`).
+    pub fn resolve(self) -> Self {
+        if self != Self::Auto {
+            return self;
+        }
+        if !std::io::stdout().is_terminal() {
+            return Self::None;
+        }
+        let var = |name: &str| std::env::var(name).unwrap_or_default();
+        let (term, program) = (var("TERM"), var("TERM_PROGRAM"));
+        if std::env::var_os("KITTY_WINDOW_ID").is_some() || term.contains("kitty") || program == "ghostty" {
+            Self::Kitty
+        } else if matches!(program.as_str(), "iTerm.app" | "WezTerm" | "Tabby") {
+            Self::Iterm
+        } else if ["foot", "mlterm", "sixel", "contour"].iter().any(|t| term.contains(t)) {
+            Self::Sixel
+        } else {
+            Self::None
+        }
+    }
+}
+
+/// Encode `image`, downscaled to fit within `max_size` pixels, as escape sequences for the
+/// given protocol. Returns `None` for `Auto`/`None`.
+pub fn render(image: &Image, protocol: Preview, max_size: u32) -> Option<String> {
+    let (data, width, height) = thumbnail(&image.data, image.width, image.height, max_size);
+    let mut out = String::new();
+    match protocol {
+        Preview::Kitty => {
+            let encoded = STANDARD.encode(png_bytes(&data, width, height)?);
+            let chunks: Vec<&[u8]> = encoded.as_bytes().chunks(4096).collect();
+            for (i, chunk) in chunks.iter().enumerate() {
+                let more = u8::from(i + 1 < chunks.len());
+                let control = if i == 0 { format!("a=T,f=100,m={more}") } else { format!("m={more}") };
+                let _ = write!(out, "\x1b_G{control};{}\x1b\\", std::str::from_utf8(chunk).ok()?);
+            }
+        }
+        Preview::Iterm => {
+            let png = png_bytes(&data, width, height)?;
+            let _ = write!(out, "\x1b]1337;File=inline=1;size={}:{}\x07", png.len(), STANDARD.encode(&png));
+        }
+        Preview::Sixel => out = sixel(&data, width, height),
+        Preview::Auto | Preview::None => return None,
+    }
+    out.push('\n');
+    Some(out)
+}
+
+fn png_bytes(data: &[u8], width: u32, height: u32) -> Option<Vec<u8>> {
+    let mut png = Vec::new();
+    encode_png(&mut png, data, width, height).ok()?;
+    Some(png)
+}
+
+/// Encode RGBA pixels (composited onto white) as sixels using a 6x6x6 colour cube.
+fn sixel(data: &[u8], width: u32, height: u32) -> String {
+    let (w, h) = (width as usize, height as usize);
+    let level = |c: u8, a: u8| {
+        let blended = 255.0 + (c as f64 - 255.0) * a as f64 / 255.0;
+        (blended / 51.0).round() as usize
+    };
+    let index: Vec<usize> = data
+        .chunks_exact(4)
+        .map(|px| level(px[0], px[3]) * 36 + level(px[1], px[3]) * 6 + level(px[2], px[3]))
+        .collect();
+
+    let mut out = format!("\x1bPq\"1;1;{w};{h}");
+    for i in 0..216 {
+        let pct = |l: usize| l * 20;
+        let _ = write!(out, "#{i};2;{};{};{}", pct(i / 36), pct(i / 6 % 6), pct(i % 6));
+    }
+    for band in (0..h).step_by(6) {
+        let rows = (h - band).min(6);
+        let mut used = [false; 216];
+        for y in band..band + rows {
+            for &c in &index[y * w..(y + 1) * w] {
+                used[c] = true;
+            }
+        }
+        for color in (0..216).filter(|&c| used[c]) {
+            let _ = write!(out, "#{color}");
+            let mut run: Option<(u8, usize)> = None;
+            for x in 0..w {
+                let bits = (0..rows).fold(0u8, |b, r| b | (u8::from(index[(band + r) * w + x] == color) << r));
+                run = match run {
+                    Some((prev, n)) if prev == bits => Some((prev, n + 1)),
+                    Some((prev, n)) => {
+                        push_run(&mut out, prev, n);
+                        Some((bits, 1))
+                    }
+                    None => Some((bits, 1)),
+                };
+            }
+            if let Some((prev, n)) = run {
+                push_run(&mut out, prev, n);
+            }
+            out.push('$');
+        }
+        out.push('-');
+    }
+    out.push_str("\x1b\\");
+    out
+}
+
+fn push_run(out: &mut String, bits: u8, n: usize) {
+    let c = (63 + bits) as char;
+    if n > 3 {
+        let _ = write!(out, "!{n}{c}");
+    } else {
+        out.extend(std::iter::repeat_n(c, n));
+    }
+}
@@ -0,0 +1,77 @@
+use std::io::IsTerminal;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+
+use indicatif::{ProgressBar, ProgressStyle};
+
+use crate::batch::{Comparison, Outcome};
+
+/// Reports batch progress on stderr: an interactive bar with a live failure counter when
+/// attached to a terminal, one line per finished pair otherwise.
+pub struct Progress {
+    mode: Mode,
+    total: usize,
+    done: AtomicUsize,
+    failed: AtomicUsize,
+}
+
+enum Mode {
+    Bar(ProgressBar),
+    Log,
+    Hidden,
+}
+
+impl Progress {
+    pub fn new(total: usize, enabled: bool) -> Self {
+        let mode = if !enabled || total == 0 {
+            Mode::Hidden
+        } else if std::io::stderr().is_terminal() {
+            let bar = ProgressBar::new(total as u64);
+            bar.set_style(
+                ProgressStyle::with_template("{spinner} [{elapsed_precise}] {bar:40} {pos}/{len} {msg} ({eta})")
+                    .expect("valid template"),
+            );
+            bar.set_message("0 failed");
+            bar.enable_steady_tick(Duration::from_millis(100));
+            Mode::Bar(bar)
+        } else {
+            Mode::Log
+        };
+        Self { mode, total, done: AtomicUsize::new(0), failed: AtomicUsize::new(0) }
+    }
+
+    /// Record a finished comparison. Safe to call from worker threads.
+    pub fn finish(&self, comparison: &Comparison) {
+        let done = self.done.fetch_add(1, Ordering::Relaxed) + 1;
+        let passed = comparison.outcome.passed();
+        let failed = if passed {
+            self.failed.load(Ordering::Relaxed)
+        } else {
+            self.failed.fetch_add(1, Ordering::Relaxed) + 1
+        };
+        match &self.mode {
+            Mode::Bar(bar) => {
+                bar.inc(1);
+                bar.set_message(format!("{failed} failed"));
+            }
+            Mode::Log => {
+                let status = match comparison.outcome {
+                    Outcome::Pass { .. } => "pass",
+                    Outcome::Fail { .. } | Outcome::DimensionMismatch { .. } => "FAIL",
+                    Outcome::MissingBaseline => "NEW",
+                    Outcome::Error(_) => "ERR",
+                };
+                let width = self.total.to_string().len();
+                eprintln!("[{done:>width$}/{}] {status} {}", self.total, comparison.pair.name);
+            }
+            Mode::Hidden => {}
+        }
+    }
+
+    /// Remove the progress bar, if any.
+    pub fn clear(&self) {
+        if let Mode::Bar(bar) = &self.mode {
+            bar.finish_and_clear();
+        }
+    }
+}
@@ -0,0 +1,194 @@
+use std::path::{Component, Path, PathBuf};
+
+use clap::Args;
+use pixelmatch::io::{read_png, write_png};
+use pixelmatch::report::{HtmlReport, ReportEntry};
+use pixelmatch::resize::thumbnail;
+use pixelmatch::MatchResult;
+use rayon::prelude::*;
+
+use crate::batch::{collect_pairs, compare_all, Comparison, Outcome};
+use crate::ComparisonArgs;
+
+/// Write an HTML visual report for a batch of comparisons.
+#[derive(Args, Debug)]
+pub struct ReportArgs {
+    /// Directory containing the baseline images.
+    #[arg(required_unless_present = "from")]
+    pub baseline_dir: Option<PathBuf>,
+    /// Directory containing the candidate images (same relative layout).
+    #[arg(required_unless_present = "from")]
+    pub candidate_dir: Option<PathBuf>,
+    /// Build the report from a prior `--format json` run instead of comparing.
+    #[arg(long, value_name = "RESULTS_JSON", conflicts_with_all = ["baseline_dir", "candidate_dir"])]
+    pub from: Option<PathBuf>,
+    /// Output directory for index.html and the copied images.
+    #[arg(short, long, default_value = "pixelmatch-report")]
+    pub out: PathBuf,
+    /// Report title.
+    #[arg(long, default_value = "Visual diff report")]
+    pub title: String,
+    /// Include passing comparisons in the report.
+    #[arg(long)]
+    pub show_passing: bool,
+    /// Maximum width/height of the thumbnails shown inline.
+    #[arg(long, default_value_t = 320)]
+    pub thumbnail_size: u32,
+    #[command(flatten)]
+    pub comparison: ComparisonArgs,
+}
+
+/// A report row together with the source files it refers to.
+struct Item {
+    entry: ReportEntry,
+    baseline: Option<PathBuf>,
+    candidate: Option<PathBuf>,
+    diff: Option<PathBuf>,
+}
+
+pub fn run(args: &ReportArgs) -> Result<u8, String> {
+    std::fs::create_dir_all(&args.out).map_err(|e| format!("{}: {e}", args.out.display()))?;
+    let items = match (&args.from, &args.baseline_dir, &args.candidate_dir) {
+        (Some(from), _, _) => load_results(from)?,
+        (None, Some(baselines), Some(candidates)) => {
+            let pairs = collect_pairs(baselines, candidates).map_err(|e| format!("{}: {e}", candidates.display()))?;
+            let comparisons = compare_all(&pairs, &args.comparison.settings()?, Some(&args.out.join("diff")))?;
+            comparisons.iter().map(item_from_comparison).collect()
+        }
+        _ => return Err("either --from or a baseline and candidate directory is required".to_string()),
+    };
+
+    let entries = items
+        .into_par_iter()
+        .filter(|item| args.show_passing || !item.entry.passed)
+        .map(|item| publish(item, &args.out, args.thumbnail_size))
+        .collect::<Result<Vec<_>, String>>()?;
+
+    let report = HtmlReport { title: args.title.clone(), show_passing: args.show_passing };
+    let index = args.out.join("index.html");
+    std::fs::write(&index, report.render(&entries)).map_err(|e| format!("{}: {e}", index.display()))?;
+
+    let failed = entries.iter().filter(|e| !e.passed).count();
+    println!("report written to {} ({failed} failed)", index.display());
+    Ok(if failed > 0 { crate::EXIT_DIFFERENT } else { crate::EXIT_OK })
+}
+
+fn item_from_comparison(c: &Comparison) -> Item {
+    Item {
+        entry: entry_from_comparison(c),
+        baseline: Some(c.pair.baseline.clone()).filter(|p| p.exists()),
+        candidate: Some(c.pair.candidate.clone()).filter(|p| p.exists()),
+        diff: c.diff.clone(),
+    }
+}
+
+/// Convert a comparison into a report entry without image links.
+pub fn entry_from_comparison(c: &Comparison) -> ReportEntry {
+    let (result, message) = match &c.outcome {
+        Outcome::Pass { result, width, height } | Outcome::Fail { result, width, height } => {
+            (MatchResult { total_pixels: *width as u64 * *height as u64, ..*result }, None)
+        }
+        Outcome::MissingBaseline => (MatchResult::default(), Some("No baseline".to_string())),
+        Outcome::DimensionMismatch { baseline: (bw, bh), candidate: (cw, ch) } => {
+            (MatchResult::default(), Some(format!("Dimensions changed from {bw}x{bh} to {cw}x{ch}")))
+        }
+        Outcome::Error(e) => (MatchResult::default(), Some(e.clone())),
+    };
+    let mut entry = ReportEntry::new(&c.pair.name, result);
+    entry.passed = c.outcome.passed();
+    entry.message = message;
+    entry
+}
+
+/// Load the records of a `--format json` document.
+fn load_results(path: &Path) -> Result<Vec<Item>, String> {
+    let text = std::fs::read_to_string(path).map_err(|e| format!("{}: {e}", path.display()))?;
+    let doc: serde_json::Value = serde_json::from_str(&text).map_err(|e| format!("{}: {e}", path.display()))?;
+    let results = doc["results"].as_array().ok_or_else(|| format!("{}: missing results array", path.display()))?;
+
+    let path_of = |v: &serde_json::Value| v.as_str().map(PathBuf::from).filter(|p| p.exists());
+    results
+        .iter()
+        .map(|r| {
+            let name = r["name"].as_str().ok_or_else(|| format!("{}: record without a name", path.display()))?;
+            // Names become paths under --out, so they must not climb out of it.
+            if name.is_empty() || !Path::new(name).components().all(|c| matches!(c, Component::Normal(_))) {
+                return Err(format!("{}: record name {name:?} is not a relative path", path.display()));
+            }
+            let bounds: Option<Vec<u32>> =
+                r["diff_bounds"].as_array().map(|a| a.iter().map(|n| n.as_u64().unwrap_or(0) as u32).collect());
+            let result = MatchResult {
+                diff_count: r["diff_count"].as_u64().unwrap_or(0) as u32,
+                total_pixels: r["width"].as_u64().unwrap_or(0) * r["height"].as_u64().unwrap_or(0),
+                aa_count: r["aa_count"].as_u64().unwrap_or(0) as u32,
+                identical: r["identical"].as_bool().unwrap_or(false),
+                scroll_offset: r["scroll_offset"].as_i64().unwrap_or(0) as i32,
+                moved_labels: r["moved_labels"].as_u64().unwrap_or(0) as u32,
+                diff_bounds: match bounds.as_deref() {
+                    Some(&[x0, y0, x1, y1]) => Some((x0, y0, x1, y1)),
+                    _ => None,
+                },
+            };
+            let mut entry = ReportEntry::new(name, result);
+            entry.passed = r["status"] == "pass";
+            entry.message = r["message"].as_str().map(str::to_string).or_else(|| match r["status"].as_str() {
+                Some("missing_baseline") => Some("No baseline".to_string()),
+                _ => None,
+            });
+            Ok(Item {
+                entry,
+                baseline: path_of(&r["baseline"]),
+                candidate: path_of(&r["candidate"]),
+                diff: path_of(&r["diff"]),
+            })
+        })
+        .collect()
+}
+
+/// Copy an item's images into the report directory, write thumbnails and fill in the URLs.
+fn publish(item: Item, out: &Path, thumbnail_size: u32) -> Result<ReportEntry, String> {
+    let mut entry = item.entry;
+    for (kind, source) in [("baseline", &item.baseline), ("candidate", &item.candidate), ("diff", &item.diff)] {
+        let Some(source) = source else { continue };
+        let rel = format!("{kind}/{}", entry.name);
+        let full = out.join(&rel);
+        if let Some(parent) = full.parent() {
+            std::fs::create_dir_all(parent).map_err(|e| format!("{}: {e}", parent.display()))?;
+        }
+        // Diffs produced by this command are already in place.
+        if source.canonicalize().ok() != full.canonicalize().ok() {
+            std::fs::copy(source, &full).map_err(|e| format!("{}: {e}", source.display()))?;
+        }
+
+        let image = read_png(&full).map_err(|e| format!("{}: {e}", full.display()))?;
+        let (thumb, tw, th) = thumbnail(&image.data, image.width, image.height, thumbnail_size);
+        let thumb_rel = format!("thumbnails/{rel}");
+        let thumb_path = out.join(&thumb_rel);
+        if let Some(parent) = thumb_path.parent() {
+            std::fs::create_dir_all(parent).map_err(|e| format!("{}: {e}", parent.display()))?;
+        }
+        write_png(&thumb_path, &thumb, tw, th).map_err(|e| format!("{}: {e}", thumb_path.display()))?;
+
+        let (url, thumb_url) = match kind {
+            "baseline" => (&mut entry.baseline_url, &mut entry.baseline_thumbnail_url),
+            "candidate" => (&mut entry.candidate_url, &mut entry.candidate_thumbnail_url),
+            _ => (&mut entry.diff_url, &mut entry.diff_thumbnail_url),
+        };
+        *url = Some(url_path(&rel));
+        *thumb_url = Some(url_path(&thumb_rel));
+    }
+    Ok(entry)
+}
+
+/// `rel` as a relative URL, percent-encoding each segment so spaces, `#` and `?` in image
+/// names stay part of the path.
+fn url_path(rel: &str) -> String {
+    let mut url = String::with_capacity(rel.len());
+    for b in rel.bytes() {
+        match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' | b'/' => url.push(b as char),
+            _ => url.push_str(&format!("%{b:02X}")),
+        }
+    }
+    url
+}
@@ -0,0 +1,85 @@
+use std::path::{Path, PathBuf};
+
+use clap::Args;
+use pixelmatch::review::{Review, ReviewEntry};
+
+use crate::batch::{Comparison, Outcome};
+
+/// List, approve or reject the failures recorded in a review manifest.
+#[derive(Args, Debug)]
+pub struct ReviewArgs {
+    /// Review manifest written by a `--review` run.
+    pub manifest: PathBuf,
+    /// Copy this entry's candidate over its baseline (repeatable).
+    #[arg(long = "approve", value_name = "NAME")]
+    pub approve: Vec<String>,
+    /// Keep this entry's baseline and mark it rejected (repeatable).
+    #[arg(long = "reject", value_name = "NAME")]
+    pub reject: Vec<String>,
+    /// Approve every pending entry.
+    #[arg(long, conflicts_with = "approve")]
+    pub approve_all: bool,
+    /// Drop approved and rejected entries from the manifest.
+    #[arg(long)]
+    pub prune: bool,
+}
+
+pub fn run(args: &ReviewArgs) -> Result<u8, String> {
+    let mut review = Review::open(&args.manifest).map_err(|e| e.to_string())?;
+    let approve: Vec<String> = if args.approve_all {
+        review.pending().map(|e| e.name.clone()).collect()
+    } else {
+        args.approve.clone()
+    };
+    for name in &approve {
+        review.approve(name).map_err(|e| e.to_string())?;
+        println!("approved: {name}");
+    }
+    for name in &args.reject {
+        review.reject(name).map_err(|e| e.to_string())?;
+        println!("rejected: {name}");
+    }
+    if args.prune {
+        review.prune();
+        review.save().map_err(|e| e.to_string())?;
+    }
+    if approve.is_empty() && args.reject.is_empty() {
+        for entry in review.pending() {
+            let detail = match (entry.diff_count, &entry.message) {
+                (Some(n), _) => format!("{n} different pixels"),
+                (None, Some(message)) => message.clone(),
+                (None, None) => String::new(),
+            };
+            println!("PENDING {}: {detail}", entry.name);
+        }
+    }
+    println!("{} pending", review.pending().count());
+    Ok(crate::EXIT_OK)
+}
+
+/// Record the failures of a batch run as pending entries in the manifest at `path`, and
+/// drop the entries of pairs that now pass.
+pub fn record(path: &Path, comparisons: &[Comparison]) -> Result<(), String> {
+    let mut review = Review::open(path).map_err(|e| e.to_string())?;
+    for c in comparisons {
+        let mut entry = ReviewEntry::new(&c.pair.name, &c.pair.baseline, &c.pair.candidate);
+        entry.diff.clone_from(&c.diff);
+        match &c.outcome {
+            Outcome::Pass { .. } => {
+                review.remove(&c.pair.name);
+                continue;
+            }
+            Outcome::Error(_) => continue,
+            Outcome::Fail { result, width, height } => {
+                entry.diff_count = Some(result.diff_count);
+                entry.total_pixels = Some(u64::from(*width) * u64::from(*height));
+            }
+            Outcome::MissingBaseline => entry.message = Some("no baseline".to_string()),
+            Outcome::DimensionMismatch { baseline: (bw, bh), candidate: (cw, ch) } => {
+                entry.message = Some(format!("dimensions changed from {bw}x{bh} to {cw}x{ch}"));
+            }
+        }
+        review.record(entry);
+    }
+    review.save().map_err(|e| e.to_string())
+}
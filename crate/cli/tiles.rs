@@ -0,0 +1,93 @@
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use clap::Args;
+use pixelmatch::tiles::{compare_tile_trees_with, TileId, TileOutcome};
+use pixelmatch::Options;
+
+use crate::ComparisonArgs;
+
+/// Compare two slippy-map tile trees laid out as `{z}/{x}/{y}.png`, with totals per zoom level.
+#[derive(Args, Debug)]
+pub struct TilesArgs {
+    /// Root of the baseline tile tree.
+    pub baseline_dir: PathBuf,
+    /// Root of the candidate tile tree.
+    pub candidate_dir: PathBuf,
+    #[command(flatten)]
+    pub comparison: ComparisonArgs,
+}
+
+/// Compare the trees and print per-zoom totals, then the tiles that failed and those
+/// skipped because one tree lacks them. Configuration overrides match tile names such as
+/// `14/8192/5461`.
+pub fn run(args: &TilesArgs) -> Result<u8, String> {
+    for dir in [&args.baseline_dir, &args.candidate_dir] {
+        if !dir.is_dir() {
+            return Err(format!("{}: not a directory", dir.display()));
+        }
+    }
+    let settings = args.comparison.settings()?;
+    let pool = rayon::ThreadPoolBuilder::new()
+        .num_threads(settings.jobs())
+        .build()
+        .map_err(|e| format!("failed to start worker threads: {e}"))?;
+    // `compare_tile_trees_with` cannot fail on options, so keep the first error for later.
+    let error = Mutex::new(None);
+    let options_for = |id: &TileId| match settings.resolve(&id.to_string()) {
+        Ok((options, _)) => options,
+        Err(e) => {
+            error.lock().unwrap_or_else(|e| e.into_inner()).get_or_insert(e);
+            Options::default()
+        }
+    };
+    let report = pool
+        .install(|| compare_tile_trees_with(&args.baseline_dir, &args.candidate_dir, options_for))
+        .map_err(|e| e.to_string())?;
+    if let Some(e) = error.into_inner().unwrap_or_else(|e| e.into_inner()) {
+        return Err(e);
+    }
+
+    println!("{:>4}  {:>8}  {:>9}  {:>6}  {:>7}  diff pixels", "zoom", "compared", "differing", "errors", "missing");
+    for z in report.zoom_stats() {
+        println!(
+            "{:>4}  {:>8}  {:>9}  {:>6}  {:>7}  {} ({:.2}%)",
+            z.zoom,
+            z.compared,
+            z.differing,
+            z.errors,
+            z.missing_baseline + z.missing_candidate,
+            z.diff_pixels,
+            z.diff_ratio() * 100.0
+        );
+    }
+
+    let mut failed = 0;
+    for c in &report.comparisons {
+        let message = match &c.outcome {
+            TileOutcome::Compared { result, width, height } => {
+                let (_, tolerance) = settings.resolve(&c.id.to_string())?;
+                if tolerance.allows(result.diff_count, u64::from(*width) * u64::from(*height)) {
+                    continue;
+                }
+                format!("{} different pixels", result.diff_count)
+            }
+            TileOutcome::DimensionMismatch { baseline: (bw, bh), candidate: (cw, ch) } => {
+                format!("dimensions do not match: {bw}x{bh} vs {cw}x{ch}")
+            }
+            TileOutcome::Error(e) => e.clone(),
+        };
+        println!("failed: {} ({message})", c.id);
+        failed += 1;
+    }
+    for id in &report.missing_baseline {
+        println!("missing baseline: {id}");
+    }
+    for id in &report.missing_candidate {
+        println!("missing candidate: {id}");
+    }
+
+    let missing = report.missing_baseline.len() + report.missing_candidate.len();
+    println!("{} tiles compared, {failed} failed, {missing} skipped as missing", report.comparisons.len());
+    Ok(if failed == 0 { crate::EXIT_OK } else { crate::EXIT_DIFFERENT })
+}
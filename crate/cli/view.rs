@@ -0,0 +1,331 @@
+//! `pixelmatch view`: a terminal UI for reviewing failing comparisons.
+//!
+//! Images are drawn with half-block characters (two pixels per cell) in 24-bit colour.
+//!
+//! | Key                 | Action                                       |
+//! | ------------------- | -------------------------------------------- |
+//! | `n` / `p`           | Next / previous pair                         |
+//! | `Tab`, `1` `2` `3`  | Cycle, or show baseline / candidate / diff   |
+//! | `+` / `-` / `0`     | Zoom in / out / fit to screen                |
+//! | Arrow keys          | Pan                                          |
+//! | `z` / `Z`           | Zoom into the next / previous diff cluster   |
+//! | `a`                 | Approve (copy the candidate to the baseline) |
+//! | `r`                 | Reject (leave the baseline unchanged)        |
+//! | `q` / `Esc`         | Quit                                         |
+
+use std::path::PathBuf;
+
+use clap::Args;
+use pixelmatch::io::{read_png, Image};
+use pixelmatch::regions::{find_regions, DiffRegion};
+use pixelmatch::{diff_mask, pixelmatch};
+use ratatui::buffer::Buffer;
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use ratatui::layout::{Constraint, Layout, Rect};
+use ratatui::style::{Color, Modifier, Style, Stylize};
+use ratatui::text::Line;
+use ratatui::widgets::Widget;
+use ratatui::{DefaultTerminal, Frame};
+
+use crate::approve::approve_pair;
+use crate::batch::{collect_pairs, compare_all, Comparison, Outcome};
+use crate::{ComparisonArgs, Settings};
+
+/// Page through failing pairs in a terminal UI and approve or reject them.
+#[derive(Args, Debug)]
+pub struct ViewArgs {
+    /// Directory containing the baseline images.
+    pub baseline_dir: PathBuf,
+    /// Directory containing the candidate images (same relative layout).
+    pub candidate_dir: PathBuf,
+    /// Also include passing pairs.
+    #[arg(long)]
+    pub all: bool,
+    #[command(flatten)]
+    pub comparison: ComparisonArgs,
+}
+
+pub fn run(args: &ViewArgs) -> Result<u8, String> {
+    let pairs = collect_pairs(&args.baseline_dir, &args.candidate_dir)
+        .map_err(|e| format!("{}: {e}", args.candidate_dir.display()))?;
+    let settings = args.comparison.settings()?;
+    let comparisons: Vec<Comparison> = compare_all(&pairs, &settings, None)?
+        .into_iter()
+        .filter(|c| args.all || !c.outcome.passed())
+        .collect();
+    if comparisons.is_empty() {
+        println!("nothing to review");
+        return Ok(crate::EXIT_OK);
+    }
+
+    let mut app = App::new(comparisons, &settings);
+    let mut terminal = ratatui::init();
+    let result = app.run(&mut terminal);
+    ratatui::restore();
+    result.map_err(|e| e.to_string())?;
+
+    let count = |d: Decision| app.decisions.iter().filter(|&&x| x == d).count();
+    println!("{} approved, {} rejected", count(Decision::Approved), count(Decision::Rejected));
+    Ok(crate::EXIT_OK)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Decision {
+    Pending,
+    Approved,
+    Rejected,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Layer {
+    Baseline,
+    Candidate,
+    Diff,
+}
+
+impl Layer {
+    fn next(self) -> Self {
+        match self {
+            Self::Baseline => Self::Candidate,
+            Self::Candidate => Self::Diff,
+            Self::Diff => Self::Baseline,
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            Self::Baseline => "baseline",
+            Self::Candidate => "candidate",
+            Self::Diff => "diff",
+        }
+    }
+}
+
+/// Decoded images of the selected pair.
+struct Loaded {
+    baseline: Option<Image>,
+    candidate: Option<Image>,
+    diff: Option<Image>,
+    regions: Vec<DiffRegion>,
+}
+
+struct App<'a> {
+    comparisons: Vec<Comparison>,
+    decisions: Vec<Decision>,
+    settings: &'a Settings,
+    index: usize,
+    layer: Layer,
+    loaded: Loaded,
+    /// Magnification relative to fit-to-screen.
+    zoom: f64,
+    /// Image point shown at the centre of the view.
+    center: (f64, f64),
+    region: Option<usize>,
+    status: String,
+}
+
+impl<'a> App<'a> {
+    fn new(comparisons: Vec<Comparison>, settings: &'a Settings) -> Self {
+        let decisions = vec![Decision::Pending; comparisons.len()];
+        let mut app = Self {
+            comparisons,
+            decisions,
+            settings,
+            index: 0,
+            layer: Layer::Diff,
+            loaded: Loaded { baseline: None, candidate: None, diff: None, regions: Vec::new() },
+            zoom: 1.0,
+            center: (0.0, 0.0),
+            region: None,
+            status: String::new(),
+        };
+        app.select(0);
+        app
+    }
+
+    fn run(&mut self, terminal: &mut DefaultTerminal) -> std::io::Result<()> {
+        loop {
+            terminal.draw(|frame| self.draw(frame))?;
+            let Event::Key(key) = event::read()? else { continue };
+            if key.kind != KeyEventKind::Press {
+                continue;
+            }
+            let step = 8.0 / self.zoom;
+            self.status.clear();
+            match key.code {
+                KeyCode::Char('q') | KeyCode::Esc => return Ok(()),
+                KeyCode::Char('n') | KeyCode::PageDown => {
+                    self.select((self.index + 1).min(self.comparisons.len() - 1))
+                }
+                KeyCode::Char('p') | KeyCode::PageUp => self.select(self.index.saturating_sub(1)),
+                KeyCode::Tab => self.layer = self.layer.next(),
+                KeyCode::Char('1') => self.layer = Layer::Baseline,
+                KeyCode::Char('2') => self.layer = Layer::Candidate,
+                KeyCode::Char('3') => self.layer = Layer::Diff,
+                KeyCode::Char('+') | KeyCode::Char('=') => self.zoom = (self.zoom * 2.0).min(64.0),
+                KeyCode::Char('-') => self.zoom = (self.zoom / 2.0).max(1.0),
+                KeyCode::Char('0') => self.fit(),
+                KeyCode::Left => self.center.0 -= step,
+                KeyCode::Right => self.center.0 += step,
+                KeyCode::Up => self.center.1 -= step,
+                KeyCode::Down => self.center.1 += step,
+                KeyCode::Char('z') => self.jump_region(true),
+                KeyCode::Char('Z') => self.jump_region(false),
+                KeyCode::Char('a') => self.decide(Decision::Approved),
+                KeyCode::Char('r') => self.decide(Decision::Rejected),
+                _ => {}
+            }
+        }
+    }
+
+    fn select(&mut self, index: usize) {
+        self.index = index;
+        self.loaded = self.load();
+        self.region = None;
+        self.fit();
+    }
+
+    fn load(&self) -> Loaded {
+        let pair = &self.comparisons[self.index].pair;
+        let baseline = read_png(&pair.baseline).ok();
+        let candidate = read_png(&pair.candidate).ok();
+        let (mut diff, mut regions) = (None, Vec::new());
+        if let (Some(a), Some(b), Ok((options, _))) = (&baseline, &candidate, self.settings.resolve(&pair.name)) {
+            let mut out = Image::new(a.width, a.height);
+            if pixelmatch(&a.data, &b.data, Some(&mut out.data), a.width, a.height, &options).is_ok() {
+                diff = Some(out);
+            }
+            if let Ok(mask) = diff_mask(&a.data, &b.data, a.width, a.height, &options) {
+                regions = find_regions(&mask, a.width, a.height);
+            }
+        }
+        Loaded { baseline, candidate, diff, regions }
+    }
+
+    fn image(&self) -> Option<&Image> {
+        let loaded = &self.loaded;
+        match self.layer {
+            Layer::Baseline => loaded.baseline.as_ref(),
+            Layer::Candidate => loaded.candidate.as_ref(),
+            Layer::Diff => loaded.diff.as_ref(),
+        }
+        .or(loaded.candidate.as_ref())
+    }
+
+    fn fit(&mut self) {
+        self.zoom = 1.0;
+        if let Some(img) = self.image() {
+            self.center = (img.width as f64 / 2.0, img.height as f64 / 2.0);
+        }
+    }
+
+    fn jump_region(&mut self, forward: bool) {
+        let n = self.loaded.regions.len();
+        if n == 0 {
+            self.status = "no diff clusters".to_string();
+            return;
+        }
+        let i = match (self.region, forward) {
+            (None, true) => 0,
+            (None, false) => n - 1,
+            (Some(i), true) => (i + 1) % n,
+            (Some(i), false) => (i + n - 1) % n,
+        };
+        let r = self.loaded.regions[i];
+        self.region = Some(i);
+        self.center = (r.x as f64 + r.width as f64 / 2.0, r.y as f64 + r.height as f64 / 2.0);
+        // Zoom so the cluster fills roughly a quarter of the image extent.
+        if let Some(img) = self.image() {
+            let extent = (img.width as f64 / r.width.max(1) as f64).min(img.height as f64 / r.height.max(1) as f64);
+            self.zoom = (extent / 4.0).clamp(1.0, 64.0);
+        }
+        self.status = format!("cluster {}/{n}: {} px", i + 1, r.pixel_count);
+    }
+
+    fn decide(&mut self, decision: Decision) {
+        let pair = &self.comparisons[self.index].pair;
+        if decision == Decision::Approved {
+            if let Err(e) = approve_pair(pair) {
+                self.status = e;
+                return;
+            }
+        }
+        self.decisions[self.index] = decision;
+        self.status = format!("{} {}", if decision == Decision::Approved { "approved" } else { "rejected" }, pair.name);
+        if self.index + 1 < self.comparisons.len() {
+            self.select(self.index + 1);
+        }
+    }
+
+    fn draw(&self, frame: &mut Frame) {
+        let [header, body, footer] =
+            Layout::vertical([Constraint::Length(1), Constraint::Min(0), Constraint::Length(1)]).areas(frame.area());
+        let c = &self.comparisons[self.index];
+        let detail = match &c.outcome {
+            Outcome::Pass { result, .. } | Outcome::Fail { result, .. } => format!("{} px", result.diff_count),
+            Outcome::MissingBaseline => "no baseline".to_string(),
+            Outcome::DimensionMismatch { baseline: (bw, bh), candidate: (cw, ch) } => {
+                format!("{bw}x{bh} → {cw}x{ch}")
+            }
+            Outcome::Error(e) => e.clone(),
+        };
+        let decision = match self.decisions[self.index] {
+            Decision::Pending => "".to_string().into(),
+            Decision::Approved => " [approved]".green(),
+            Decision::Rejected => " [rejected]".red(),
+        };
+        let title = Line::from(vec![
+            format!("[{}/{}] ", self.index + 1, self.comparisons.len()).into(),
+            c.pair.name.clone().bold(),
+            format!(" · {detail} · {} · {}x", self.layer.label(), self.zoom).into(),
+            decision,
+        ]);
+        frame.render_widget(title, header);
+        if let Some(img) = self.image() {
+            frame.render_widget(ImageView { image: img, zoom: self.zoom, center: self.center }, body);
+        }
+        let text = if self.status.is_empty() { HELP } else { &self.status };
+        frame.render_widget(Line::from(text).style(Style::new().add_modifier(Modifier::DIM)), footer);
+    }
+}
+
+const HELP: &str =
+    "n/p pair · tab/1/2/3 layer · +/-/0 zoom · arrows pan · z/Z cluster · a approve · r reject · q quit";
+
+/// Renders an RGBA image with two vertically stacked pixels per cell.
+struct ImageView<'a> {
+    image: &'a Image,
+    zoom: f64,
+    center: (f64, f64),
+}
+
+impl Widget for ImageView<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let img = self.image;
+        if area.is_empty() || img.width == 0 || img.height == 0 {
+            return;
+        }
+        let (cols, rows) = (area.width as f64, area.height as f64 * 2.0);
+        // Image pixels per terminal pixel.
+        let scale = (img.width as f64 / cols).max(img.height as f64 / rows) / self.zoom;
+        let origin = (self.center.0 - cols * scale / 2.0, self.center.1 - rows * scale / 2.0);
+        let sample = |px: u16, py: u16| -> Color {
+            let x = origin.0 + (px as f64 + 0.5) * scale;
+            let y = origin.1 + (py as f64 + 0.5) * scale;
+            if x < 0.0 || y < 0.0 || x >= img.width as f64 || y >= img.height as f64 {
+                return Color::Reset;
+            }
+            let i = (y as usize * img.width as usize + x as usize) * 4;
+            let [r, g, b, a] = [img.data[i], img.data[i + 1], img.data[i + 2], img.data[i + 3]];
+            // Blend onto white, as the diff output does.
+            let blend = |c: u8| (255.0 + (c as f64 - 255.0) * a as f64 / 255.0) as u8;
+            Color::Rgb(blend(r), blend(g), blend(b))
+        };
+        for row in 0..area.height {
+            for col in 0..area.width {
+                let cell = &mut buf[(area.x + col, area.y + row)];
+                cell.set_char('▀').set_fg(sample(col, row * 2)).set_bg(sample(col, row * 2 + 1));
+            }
+        }
+    }
+}
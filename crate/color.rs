@@ -1,57 +1,107 @@
-/// Read RGBA channels from a byte slice at the given byte offset.
+use num_traits::AsPrimitive;
+
+/// A colour channel type the comparison accepts: `u8` and `u16` spanning their full range,
+/// and `f32` from 0.0 to 1.0. The colour maths works on channels scaled to 0..=255, so a
+/// threshold means the same at every depth; `u8` skips the scaling and compares whole pixels
+/// as one `u32`.
+pub trait Component: Copy + PartialEq + AsPrimitive<f64> + Send + Sync {
+    /// The value of a channel at full intensity.
+    const MAX: f64;
+
+    /// The buffer as bytes, if the components are bytes. Lets generic code take the `u8`
+    /// fast paths.
+    #[inline(always)]
+    fn bytes(_data: &[Self]) -> Option<&[u8]> {
+        None
+    }
+
+    /// Whether the pixels at offsets `i` of `a` and `j` of `b` are equal.
+    #[inline(always)]
+    fn same_pixel(a: &[Self], i: usize, b: &[Self], j: usize) -> bool {
+        match (Self::bytes(a), Self::bytes(b)) {
+            (Some(a), Some(b)) => crate::read_u32_ne(a, i) == crate::read_u32_ne(b, j),
+            _ => read_rgba(a, i) == read_rgba(b, j),
+        }
+    }
+}
+
+impl Component for u8 {
+    const MAX: f64 = 255.0;
+
+    #[inline(always)]
+    fn bytes(data: &[u8]) -> Option<&[u8]> {
+        Some(data)
+    }
+}
+
+impl Component for u16 {
+    const MAX: f64 = 65535.0;
+}
+
+impl Component for f32 {
+    const MAX: f64 = 1.0;
+}
+
+/// Read the four components of a pixel at the given offset.
 ///
-/// # Safety
-/// Caller must ensure `off + 3 < data.len()`.
+/// Unchecked unless the `forbid-unsafe` feature is enabled: callers must ensure
+/// `i + 3 < data.len()`.
+#[cfg(not(feature = "forbid-unsafe"))]
 #[inline(always)]
-unsafe fn rgba_at_unchecked(data: &[u8], off: usize) -> (f64, f64, f64, f64) {
-    (
-        *data.get_unchecked(off) as f64,
-        *data.get_unchecked(off + 1) as f64,
-        *data.get_unchecked(off + 2) as f64,
-        *data.get_unchecked(off + 3) as f64,
-    )
+pub(crate) fn read_rgba<T: Copy>(data: &[T], i: usize) -> [T; 4] {
+    debug_assert!(i + 3 < data.len(), "pixel offset out of bounds");
+    // SAFETY: pixelmatch() validates buffer sizes; callers only pass offsets of pixels
+    // inside the image.
+    unsafe {
+        [*data.get_unchecked(i), *data.get_unchecked(i + 1), *data.get_unchecked(i + 2), *data.get_unchecked(i + 3)]
+    }
 }
 
-/// Calculate colour difference according to the paper "Measuring perceived colour difference
-/// using YIQ NTSC transmission colour space in mobile applications" by Y. Kotsarenko and F. Ramos.
-///
-/// `k` and `m` are byte offsets into the image data (multiples of 4).
-/// Caller must ensure `k + 3 < img1.len()` and `m + 3 < img2.len()`.
-#[inline]
-pub fn color_delta(img1: &[u8], img2: &[u8], k: usize, m: usize, y_only: bool) -> f64 {
-    debug_assert!(k + 3 < img1.len(), "k out of bounds");
-    debug_assert!(m + 3 < img2.len(), "m out of bounds");
-
-    // SAFETY: pixelmatch() validates buffer sizes before calling this function.
-    // k and m are always `(y * width + x) * 4` where x < width and y < height,
-    // so k + 3 and m + 3 are always within bounds.
-    unsafe { color_delta_inner(img1, img2, k, m, y_only) }
+#[cfg(feature = "forbid-unsafe")]
+#[inline(always)]
+pub(crate) fn read_rgba<T: Copy>(data: &[T], i: usize) -> [T; 4] {
+    [data[i], data[i + 1], data[i + 2], data[i + 3]]
 }
 
+/// Write the four bytes of a pixel at the given byte offset, with the same bounds contract
+/// as [`read_rgba`].
+#[cfg(not(feature = "forbid-unsafe"))]
 #[inline(always)]
-unsafe fn color_delta_inner(img1: &[u8], img2: &[u8], k: usize, m: usize, y_only: bool) -> f64 {
-    let (r1, g1, b1, a1) = rgba_at_unchecked(img1, k);
-    let (r2, g2, b2, a2) = rgba_at_unchecked(img2, m);
+pub(crate) fn write_rgba(output: &mut [u8], i: usize, rgba: [u8; 4]) {
+    debug_assert!(i + 3 < output.len(), "pixel offset out of bounds");
+    // SAFETY: pixelmatch() validates buffer sizes; callers only pass offsets of pixels
+    // inside the output.
+    unsafe {
+        *output.get_unchecked_mut(i) = rgba[0];
+        *output.get_unchecked_mut(i + 1) = rgba[1];
+        *output.get_unchecked_mut(i + 2) = rgba[2];
+        *output.get_unchecked_mut(i + 3) = rgba[3];
+    }
+}
 
-    let mut dr = r1 - r2;
-    let mut dg = g1 - g2;
-    let mut db = b1 - b2;
-    let da = a1 - a2;
+#[cfg(feature = "forbid-unsafe")]
+#[inline(always)]
+pub(crate) fn write_rgba(output: &mut [u8], i: usize, rgba: [u8; 4]) {
+    output[i..i + 4].copy_from_slice(&rgba);
+}
 
-    if dr == 0.0 && dg == 0.0 && db == 0.0 && da == 0.0 {
-        return 0.0;
-    }
+/// Read RGBA channels as floats scaled to 0..=255 at the given offset.
+#[inline(always)]
+pub(crate) fn rgba_at<T: Component>(data: &[T], off: usize) -> (f64, f64, f64, f64) {
+    let [r, g, b, a] = read_rgba(data, off).map(|c| c.as_() * (255.0 / T::MAX));
+    (r, g, b, a)
+}
 
-    if a1 < 255.0 || a2 < 255.0 {
-        // Blend pixels with background.
-        // The background pattern uses k (byte offset) to create a checkerboard-like dither.
-        let rb = 48.0 + 159.0 * ((k % 2) as f64);
-        let gb = 48.0 + 159.0 * (((k as f64 / 1.618033988749895_f64) as i64 % 2) as f64);
-        let bb = 48.0 + 159.0 * (((k as f64 / 2.618033988749895_f64) as i64 % 2) as f64);
-        dr = (r1 * a1 - r2 * a2 - rb * da) / 255.0;
-        dg = (g1 * a1 - g2 * a2 - gb * da) / 255.0;
-        db = (b1 * a1 - b2 * a2 - bb * da) / 255.0;
-    }
+/// Calculate colour difference according to the paper "Measuring perceived colour difference
+/// using YIQ NTSC transmission colour space in mobile applications" by Y. Kotsarenko and F. Ramos.
+///
+/// `k` and `m` are offsets into the image data (multiples of 4).
+/// Caller must ensure `k + 3 < img1.len()` and `m + 3 < img2.len()`.
+#[inline]
+pub fn color_delta<T: Component>(img1: &[T], img2: &[T], k: usize, m: usize, y_only: bool) -> f64 {
+    let Some([dr, dg, db]) = blended_delta(img1, img2, k, m) else {
+        return 0.0;
+    };
 
     let y = dr * 0.29889531 + dg * 0.58662247 + db * 0.11448223;
 
@@ -68,29 +118,56 @@ unsafe fn color_delta_inner(img1: &[u8], img2: &[u8], k: usize, m: usize, y_only
     if y > 0.0 { -delta } else { delta }
 }
 
+/// The luma and chroma parts of the magnitude of [`color_delta`], which is their sum.
+pub(crate) fn yiq_terms<T: Component>(img1: &[T], img2: &[T], k: usize, m: usize) -> (f64, f64) {
+    let Some([dr, dg, db]) = blended_delta(img1, img2, k, m) else {
+        return (0.0, 0.0);
+    };
+    let y = dr * 0.29889531 + dg * 0.58662247 + db * 0.11448223;
+    let i = dr * 0.59597799 - dg * 0.27417610 - db * 0.32180189;
+    let q = dr * 0.21147017 - dg * 0.52261711 + db * 0.31114694;
+    (0.5053 * y * y, 0.299 * i * i + 0.1957 * q * q)
+}
+
+/// Red, green and blue differences between the pixels at offsets `k` and `m`, after
+/// blending translucent pixels with a background, or `None` if the pixels are equal.
+#[inline(always)]
+fn blended_delta<T: Component>(img1: &[T], img2: &[T], k: usize, m: usize) -> Option<[f64; 3]> {
+    // pixelmatch() validates buffer sizes before calling this function. k and m are always
+    // `(y * width + x) * 4` where x < width and y < height, so k + 3 and m + 3 are in bounds.
+    let (r1, g1, b1, a1) = rgba_at(img1, k);
+    let (r2, g2, b2, a2) = rgba_at(img2, m);
+
+    let da = a1 - a2;
+    if r1 == r2 && g1 == g2 && b1 == b2 && da == 0.0 {
+        return None;
+    }
+    if a1 < 255.0 || a2 < 255.0 {
+        // Blend pixels with background.
+        // The background pattern uses k (byte offset) to create a checkerboard-like dither.
+        let rb = 48.0 + 159.0 * ((k % 2) as f64);
+        let gb = 48.0 + 159.0 * (((k as f64 / 1.618033988749895_f64) as i64 % 2) as f64);
+        let bb = 48.0 + 159.0 * (((k as f64 / 2.618033988749895_f64) as i64 % 2) as f64);
+        return Some([
+            (r1 * a1 - r2 * a2 - rb * da) / 255.0,
+            (g1 * a1 - g2 * a2 - gb * da) / 255.0,
+            (b1 * a1 - b2 * a2 - bb * da) / 255.0,
+        ]);
+    }
+    Some([r1 - r2, g1 - g2, b1 - b2])
+}
+
 /// Draw a pixel with the given colour at the specified byte offset.
 #[inline(always)]
 pub fn draw_pixel(output: &mut [u8], pos: usize, r: u8, g: u8, b: u8) {
-    // SAFETY: pixelmatch() validates buffer sizes; pos is always within bounds.
-    unsafe {
-        *output.get_unchecked_mut(pos) = r;
-        *output.get_unchecked_mut(pos + 1) = g;
-        *output.get_unchecked_mut(pos + 2) = b;
-        *output.get_unchecked_mut(pos + 3) = 255;
-    }
+    write_rgba(output, pos, [r, g, b, 255]);
 }
 
 /// Draw a grayscale pixel blended with white at the specified byte offset.
 #[inline(always)]
-pub fn draw_gray_pixel(img: &[u8], i: usize, alpha: f64, output: &mut [u8]) {
-    // SAFETY: pixelmatch() validates buffer sizes; i is always within bounds.
-    unsafe {
-        let (r, g, b, a) = rgba_at_unchecked(img, i);
-        let val = 255.0 + (r * 0.29889531 + g * 0.58662247 + b * 0.11448223 - 255.0) * alpha * a / 255.0;
-        let val_u8 = val as u8;
-        *output.get_unchecked_mut(i) = val_u8;
-        *output.get_unchecked_mut(i + 1) = val_u8;
-        *output.get_unchecked_mut(i + 2) = val_u8;
-        *output.get_unchecked_mut(i + 3) = 255;
-    }
+pub fn draw_gray_pixel<T: Component>(img: &[T], i: usize, alpha: f64, output: &mut [u8]) {
+    let (r, g, b, a) = rgba_at(img, i);
+    let val = 255.0 + (r * 0.29889531 + g * 0.58662247 + b * 0.11448223 - 255.0) * alpha * a / 255.0;
+    let val_u8 = val as u8;
+    write_rgba(output, i, [val_u8, val_u8, val_u8, 255]);
 }
@@ -0,0 +1,73 @@
+//! Consensus over repeated comparisons, for suppressing flaky differences.
+//!
+//! Instead of retrying a failed comparison and hoping the next capture passes, capture the
+//! candidate a few times, compare every capture with the baseline, and only count pixels
+//! that differ in at least `k` of the `n` runs: rendering noise moves around between runs,
+//! real changes do not.
+
+use crate::regions::{find_regions, DiffRegion};
+use crate::{diff_mask, Options, PixelmatchError};
+
+/// How many of several diff masks mark each pixel.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Consensus {
+    pub width: u32,
+    pub height: u32,
+    /// Number of masks merged.
+    pub runs: u32,
+    /// One entry per pixel in row-major order: the number of masks marking it.
+    pub votes: Vec<u32>,
+}
+
+impl Consensus {
+    /// Merge diff masks of `width * height` entries each, such as those returned by
+    /// [`diff_mask`].
+    pub fn from_masks(masks: &[&[bool]], width: u32, height: u32) -> Result<Self, PixelmatchError> {
+        let len = (width as usize).checked_mul(height as usize).ok_or(PixelmatchError::DimensionOverflow)?;
+        let mut votes = vec![0u32; len];
+        for mask in masks {
+            if mask.len() != len {
+                return Err(PixelmatchError::BufferLengthMismatch { expected: len, actual: mask.len() });
+            }
+            votes.iter_mut().zip(*mask).for_each(|(v, &m)| *v += u32::from(m));
+        }
+        Ok(Self { width, height, runs: masks.len() as u32, votes })
+    }
+
+    /// Compare each of `candidates` (repeated captures of the same screen) with `baseline`
+    /// and merge the resulting diff masks.
+    pub fn compare(
+        baseline: &[u8],
+        candidates: &[&[u8]],
+        width: u32,
+        height: u32,
+        options: &Options,
+    ) -> Result<Self, PixelmatchError> {
+        let masks =
+            candidates.iter().map(|c| diff_mask(baseline, c, width, height, options)).collect::<Result<Vec<_>, _>>()?;
+        let masks: Vec<&[bool]> = masks.iter().map(Vec::as_slice).collect();
+        Self::from_masks(&masks, width, height)
+    }
+
+    /// Pixels marked by at least `min_runs` masks; a `min_runs` of 0 is treated as 1.
+    pub fn mask(&self, min_runs: u32) -> Vec<bool> {
+        let min_runs = min_runs.max(1);
+        self.votes.iter().map(|&v| v >= min_runs).collect()
+    }
+
+    /// Number of pixels marked by at least `min_runs` masks.
+    pub fn count(&self, min_runs: u32) -> usize {
+        let min_runs = min_runs.max(1);
+        self.votes.iter().filter(|&&v| v >= min_runs).count()
+    }
+
+    /// Pixels marked by every mask, the strictest consensus.
+    pub fn unanimous(&self) -> Vec<bool> {
+        self.mask(self.runs)
+    }
+
+    /// Connected areas of the pixels marked by at least `min_runs` masks.
+    pub fn regions(&self, min_runs: u32) -> Vec<DiffRegion> {
+        find_regions(&self.mask(min_runs), self.width, self.height)
+    }
+}
@@ -0,0 +1,149 @@
+//! Summary statistics of per-pixel colour deltas.
+//!
+//! `diff_count` says how many pixels are past the threshold, not by how much: one pixel
+//! that is wildly wrong and thousands just over the line can give the same count.
+//! [`delta_stats`] describes how far apart the differing pixels are, in the units of
+//! [`Options::threshold`], so the two cases stand apart when triaging flaky tests. It is a
+//! second pass over the images, not part of [`MatchResult`](crate::MatchResult).
+//! [`delta_histogram`] buckets the deltas of every compared pixel, from which
+//! [`DeltaHistogram::threshold_for`] suggests a threshold for a new test suite.
+
+use rayon::prelude::*;
+
+use crate::{color_delta, ignore_mask, normalize_dpr, validate_buffers, Options, PixelmatchError};
+
+/// Statistics of the colour deltas of the compared pixels whose colours differ at all.
+///
+/// Deltas are in `threshold` units: the smallest [`Options::threshold`] at which the pixel
+/// would match, from 0.0 to 1.0 (black against white). A pixel counts in `diff_count` when
+/// its delta is above the threshold and it is not anti-aliased or otherwise tolerated.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct DeltaStats {
+    /// Compared pixels whose colours differ.
+    pub differing: u64,
+    /// Largest delta. 0 when no pixel differs, as are the others.
+    pub max: f64,
+    /// Mean delta of the differing pixels.
+    pub mean: f64,
+    /// 95th percentile (nearest rank) delta of the differing pixels.
+    pub p95: f64,
+}
+
+/// Delta statistics of two equally sized RGBA images. Ignored pixels and
+/// `device_pixel_ratio` are honoured; `threshold` and anti-aliasing detection do not
+/// affect the result.
+pub fn delta_stats(
+    img1: &[u8],
+    img2: &[u8],
+    width: u32,
+    height: u32,
+    options: &Options,
+) -> Result<DeltaStats, PixelmatchError> {
+    let mut deltas = differing_deltas(img1, img2, width, height, options)?;
+    let Some(max) = deltas.iter().copied().reduce(f32::max) else {
+        return Ok(DeltaStats::default());
+    };
+    let differing = deltas.len();
+    let mean = deltas.par_iter().map(|&d| d as f64).sum::<f64>() / differing as f64;
+    let rank = (differing as f64 * 0.95).ceil() as usize - 1;
+    let (_, p95, _) = deltas.select_nth_unstable_by(rank, f32::total_cmp);
+    Ok(DeltaStats { differing: differing as u64, max: max as f64, mean, p95: *p95 as f64 })
+}
+
+/// Counts of compared pixels by colour delta, in equal-width buckets over 0.0 to 1.0 in
+/// `threshold` units (see [`DeltaStats`]).
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct DeltaHistogram {
+    /// Pixel counts; bucket `i` holds deltas from `i / n` up to `(i + 1) / n`, the last one
+    /// including 1.0. Identical pixels are in the first.
+    pub buckets: Vec<u64>,
+}
+
+impl DeltaHistogram {
+    /// Width of each bucket in threshold units.
+    pub fn bucket_width(&self) -> f64 {
+        1.0 / self.buckets.len().max(1) as f64
+    }
+
+    /// Number of compared pixels.
+    pub fn total(&self) -> u64 {
+        self.buckets.iter().sum()
+    }
+
+    /// The smallest bucket boundary at or below which at least `fraction` (0.0 to 1.0) of
+    /// the compared pixels fall: a `threshold` under which about that share of pixels
+    /// would match. 0.0 for an empty histogram.
+    pub fn threshold_for(&self, fraction: f64) -> f64 {
+        let wanted = (fraction.clamp(0.0, 1.0) * self.total() as f64).ceil() as u64;
+        let mut seen = 0;
+        for (i, &count) in self.buckets.iter().enumerate() {
+            seen += count;
+            if seen >= wanted && seen > 0 {
+                return (i + 1) as f64 * self.bucket_width();
+            }
+        }
+        0.0
+    }
+}
+
+/// Histogram of the colour deltas of every compared pixel of two equally sized RGBA images,
+/// in `buckets` (at least one) equal-width buckets. Ignored pixels are left out and
+/// `device_pixel_ratio` is honoured, as for [`delta_stats`], which also shares its
+/// separate pass: no option adds the histogram to a `pixelmatch` result.
+pub fn delta_histogram(
+    img1: &[u8],
+    img2: &[u8],
+    width: u32,
+    height: u32,
+    options: &Options,
+    buckets: usize,
+) -> Result<DeltaHistogram, PixelmatchError> {
+    let n = buckets.max(1);
+    options.validate()?;
+    let [img1, img2] = normalize_dpr(img1, img2, width, height, options)?;
+    let (img1, img2) = (&*img1, &*img2);
+    let len = validate_buffers(img1, img2, None, width, height)?;
+    let ignore = ignore_mask(options, width as usize, height as usize)?;
+    let buckets = (0..len)
+        .into_par_iter()
+        .with_min_len(1024)
+        .filter(|&i| !ignore.as_ref().is_some_and(|m| m[i]))
+        .fold(
+            || vec![0u64; n],
+            |mut counts, i| {
+                let t = (color_delta(img1, img2, i * 4, i * 4, false).abs() / 35215.0).sqrt().min(1.0);
+                counts[((t * n as f64) as usize).min(n - 1)] += 1;
+                counts
+            },
+        )
+        .reduce(
+            || vec![0u64; n],
+            |mut a, b| {
+                a.iter_mut().zip(b).for_each(|(a, b)| *a += b);
+                a
+            },
+        );
+    Ok(DeltaHistogram { buckets })
+}
+
+/// The deltas, in threshold units, of the compared pixels whose colours differ.
+fn differing_deltas(
+    img1: &[u8],
+    img2: &[u8],
+    width: u32,
+    height: u32,
+    options: &Options,
+) -> Result<Vec<f32>, PixelmatchError> {
+    options.validate()?;
+    let [img1, img2] = normalize_dpr(img1, img2, width, height, options)?;
+    let (img1, img2) = (&*img1, &*img2);
+    let len = validate_buffers(img1, img2, None, width, height)?;
+    let ignore = ignore_mask(options, width as usize, height as usize)?;
+    Ok((0..len)
+        .into_par_iter()
+        .with_min_len(1024)
+        .filter(|&i| !ignore.as_ref().is_some_and(|m| m[i]) && img1[i * 4..][..4] != img2[i * 4..][..4])
+        .map(|i| (color_delta(img1, img2, i * 4, i * 4, false).abs() / 35215.0).sqrt().min(1.0) as f32)
+        .filter(|&d| d > 0.0)
+        .collect())
+}
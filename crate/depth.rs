@@ -0,0 +1,101 @@
+//! Comparison of single-channel `f32` buffers such as depth or object-ID buffers.
+//!
+//! Colour distance means nothing for these, so [`compare_depth`] compares the raw values
+//! with an absolute and a relative tolerance, the way float results are usually checked:
+//! two values match when they differ by at most `absolute + relative * max(|a|, |b|)`.
+//! With both tolerances at 0, as for ID buffers, values have to match exactly. NaN matches
+//! only NaN, and an infinity only the same infinity.
+
+use rayon::prelude::*;
+
+use crate::ssim::heatmap;
+use crate::PixelmatchError;
+
+/// How two depth buffers are compared.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DepthOptions {
+    /// Largest difference tolerated regardless of depth. Default: 1e-6
+    pub absolute: f32,
+    /// Largest difference tolerated as a fraction of the larger of the two values, so
+    /// distant surfaces get more room. Default: 0.0
+    pub relative: f32,
+    /// The buffers use reversed Z (1 at the near plane, 0 at the far plane); values are
+    /// converted to conventional depth (`1 - z`) before comparing, so `relative` scales with
+    /// distance either way. Default: false
+    pub reversed_z: bool,
+}
+
+impl Default for DepthOptions {
+    fn default() -> Self {
+        Self { absolute: 1e-6, relative: 0.0, reversed_z: false }
+    }
+}
+
+/// Result of [`compare_depth`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct DepthDiff {
+    /// Values that differ by more than the tolerance.
+    pub diff_count: u32,
+    /// Largest finite difference.
+    pub max_error: f32,
+    /// Absolute difference per pixel in row-major order, after any reversed-Z conversion;
+    /// infinite where only one value is NaN or infinite.
+    pub errors: Vec<f32>,
+}
+
+impl DepthDiff {
+    /// An opaque RGBA heatmap of [`errors`](Self::errors), scaled so `max_error` is the
+    /// brightest colour.
+    pub fn heatmap(&self) -> Vec<u8> {
+        heatmap(&self.errors, self.max_error)
+    }
+}
+
+/// Difference between two depth values, infinite when they cannot be compared.
+fn error(a: f32, b: f32) -> f32 {
+    if a == b || (a.is_nan() && b.is_nan()) {
+        0.0
+    } else if a.is_finite() && b.is_finite() {
+        (a - b).abs()
+    } else {
+        f32::INFINITY
+    }
+}
+
+/// Compare two single-channel `f32` buffers of `width` × `height` values.
+///
+/// Fails with [`PixelmatchError::InvalidTolerance`] if a tolerance is negative or NaN.
+pub fn compare_depth(
+    depth1: &[f32],
+    depth2: &[f32],
+    width: u32,
+    height: u32,
+    options: &DepthOptions,
+) -> Result<DepthDiff, PixelmatchError> {
+    if let Some(&t) = [options.absolute, options.relative].iter().find(|t| t.is_nan() || **t < 0.0) {
+        return Err(PixelmatchError::InvalidTolerance(t as f64));
+    }
+    let len = (width as usize).checked_mul(height as usize).ok_or(PixelmatchError::DimensionOverflow)?;
+    if depth1.len() != depth2.len() {
+        return Err(PixelmatchError::ImageSizeMismatch { img1_len: depth1.len(), img2_len: depth2.len() });
+    }
+    if depth1.len() != len {
+        return Err(PixelmatchError::BufferLengthMismatch { expected: len, actual: depth1.len() });
+    }
+
+    let normalize = |z: f32| if options.reversed_z { 1.0 - z } else { z };
+    let (errors, differs): (Vec<f32>, Vec<bool>) = depth1
+        .par_iter()
+        .zip(depth2)
+        .map(|(&a, &b)| {
+            let (a, b) = (normalize(a), normalize(b));
+            let e = error(a, b);
+            (e, e.is_infinite() || e > options.absolute + options.relative * a.abs().max(b.abs()))
+        })
+        .unzip();
+    Ok(DepthDiff {
+        diff_count: differs.iter().filter(|&&d| d).count() as u32,
+        max_error: errors.iter().copied().filter(|e| e.is_finite()).fold(0.0, f32::max),
+        errors,
+    })
+}
@@ -0,0 +1,167 @@
+//! Edge maps for comparing geometry rather than colour, used by
+//! [`Options::edges`](crate::Options::edges).
+//!
+//! Both images are reduced to their edges before comparing, so a theme or palette change
+//! that recolours fills leaves the result alone while a moved, resized or missing element
+//! still shows up. Gradient strengths are measured on luma with translucent pixels blended
+//! over white, and scaled so a black-to-white step edge is 1.0.
+
+use rayon::prelude::*;
+
+use crate::ssim::{gaussian, luma};
+
+/// Standard deviation of the smoothing applied before [`EdgeDetector::Canny`].
+const CANNY_SIGMA: f32 = 1.4;
+/// Sobel response to a black-to-white step edge.
+const SOBEL_MAX: f32 = 4.0 * 255.0;
+
+/// How edges are extracted.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum EdgeDetector {
+    /// Every pixel whose Sobel gradient is at least this strong (0 to 1). Fast, but edges
+    /// come out two or more pixels wide and noise is kept.
+    Sobel(f32),
+    /// Canny: Sobel on a smoothed copy, thinned to one-pixel lines, keeping gradients of at
+    /// least `high` and those of at least `low` connected to them.
+    Canny { low: f32, high: f32 },
+}
+
+impl EdgeDetector {
+    /// Low and high thresholds; both are the threshold for [`EdgeDetector::Sobel`].
+    pub(crate) fn thresholds(self) -> [f32; 2] {
+        match self {
+            Self::Sobel(t) => [t, t],
+            Self::Canny { low, high } => [low, high],
+        }
+    }
+}
+
+impl std::fmt::Display for EdgeDetector {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Sobel(t) => write!(f, "sobel:{t}"),
+            Self::Canny { low, high } => write!(f, "canny:{low},{high}"),
+        }
+    }
+}
+
+impl std::str::FromStr for EdgeDetector {
+    type Err = String;
+
+    /// Parse `sobel:<threshold>` or `canny:<low>,<high>`; the thresholds default to 0.25
+    /// and 0.1,0.25.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("invalid edge detector \"{s}\", expected sobel:<threshold> or canny:<low>,<high>");
+        let (kind, params) = s.split_once(':').map_or((s, None), |(k, p)| (k, Some(p)));
+        let number = |v: &str| v.trim().parse::<f32>().map_err(|_| invalid());
+        match (kind.trim(), params) {
+            ("sobel", None) => Ok(Self::Sobel(0.25)),
+            ("sobel", Some(t)) => Ok(Self::Sobel(number(t)?)),
+            ("canny", None) => Ok(Self::Canny { low: 0.1, high: 0.25 }),
+            ("canny", Some(p)) => {
+                let (low, high) = p.split_once(',').ok_or_else(invalid)?;
+                Ok(Self::Canny { low: number(low)?, high: number(high)? })
+            }
+            _ => Err(invalid()),
+        }
+    }
+}
+
+/// Sobel gradient of a plane, clamping at the edges, scaled by [`SOBEL_MAX`].
+fn sobel(plane: &[f32], w: usize, h: usize) -> Vec<[f32; 2]> {
+    (0..w * h)
+        .into_par_iter()
+        .map(|i| {
+            let (x, y) = (i % w, i / w);
+            let px = |dx: isize, dy: isize| {
+                let nx = (x as isize + dx).clamp(0, w as isize - 1) as usize;
+                let ny = (y as isize + dy).clamp(0, h as isize - 1) as usize;
+                plane[ny * w + nx]
+            };
+            let gx = px(1, -1) + 2.0 * px(1, 0) + px(1, 1) - px(-1, -1) - 2.0 * px(-1, 0) - px(-1, 1);
+            let gy = px(-1, 1) + 2.0 * px(0, 1) + px(1, 1) - px(-1, -1) - 2.0 * px(0, -1) - px(1, -1);
+            [gx / SOBEL_MAX, gy / SOBEL_MAX]
+        })
+        .collect()
+}
+
+/// Keep only gradients that are a maximum along their direction, thinning edges to lines.
+fn suppress_non_maxima(gradient: &[[f32; 2]], w: usize, h: usize) -> Vec<f32> {
+    let magnitude: Vec<f32> = gradient.iter().map(|[gx, gy]| gx.hypot(*gy)).collect();
+    (0..w * h)
+        .into_par_iter()
+        .map(|i| {
+            let (x, y, m) = (i % w, i / w, magnitude[i]);
+            if m == 0.0 {
+                return 0.0;
+            }
+            // The neighbours across the edge, along the gradient rounded to 45°.
+            let [gx, gy] = gradient[i];
+            let angle = gy.atan2(gx).to_degrees().rem_euclid(180.0);
+            let (dx, dy) = match angle {
+                a if !(22.5..157.5).contains(&a) => (1, 0),
+                a if a < 67.5 => (1, 1),
+                a if a < 112.5 => (0, 1),
+                _ => (-1, 1),
+            };
+            let at = |sx: isize, sy: isize| {
+                let (nx, ny) = (x as isize + sx, y as isize + sy);
+                if nx < 0 || ny < 0 || nx >= w as isize || ny >= h as isize {
+                    0.0
+                } else {
+                    magnitude[ny as usize * w + nx as usize]
+                }
+            };
+            if m >= at(dx, dy) && m >= at(-dx, -dy) { m } else { 0.0 }
+        })
+        .collect()
+}
+
+/// Keep pixels of at least `high`, and those of at least `low` connected to one of them.
+fn hysteresis(magnitude: &[f32], w: usize, h: usize, low: f32, high: f32) -> Vec<bool> {
+    let mut edges = vec![false; w * h];
+    let mut stack: Vec<usize> = (0..w * h).filter(|&i| magnitude[i] >= high).collect();
+    for &i in &stack {
+        edges[i] = true;
+    }
+    while let Some(i) = stack.pop() {
+        let (x, y) = (i % w, i / w);
+        for ny in y.saturating_sub(1)..(y + 2).min(h) {
+            for nx in x.saturating_sub(1)..(x + 2).min(w) {
+                let n = ny * w + nx;
+                if !edges[n] && magnitude[n] >= low {
+                    edges[n] = true;
+                    stack.push(n);
+                }
+            }
+        }
+    }
+    edges
+}
+
+/// Edge pixels of an RGBA image, one entry per pixel in row-major order.
+pub fn edge_mask(img: &[u8], width: u32, height: u32, detector: EdgeDetector) -> Vec<bool> {
+    let (w, h) = (width as usize, height as usize);
+    debug_assert_eq!(img.len(), w * h * 4);
+    if w == 0 || h == 0 {
+        return Vec::new();
+    }
+    let plane = luma(img);
+    // Pixels without any gradient are never edges, even with a zero threshold.
+    let [low, high] = detector.thresholds().map(|t| t.max(f32::MIN_POSITIVE));
+    match detector {
+        EdgeDetector::Sobel(_) => sobel(&plane, w, h).into_iter().map(|[gx, gy]| gx.hypot(gy) >= high).collect(),
+        EdgeDetector::Canny { .. } => {
+            let smoothed = gaussian(&plane, w, h, CANNY_SIGMA);
+            hysteresis(&suppress_non_maxima(&sobel(&smoothed, w, h), w, h), w, h, low, high)
+        }
+    }
+}
+
+/// An opaque RGBA image of an edge mask: black edges on white.
+pub fn edge_image(img: &[u8], width: u32, height: u32, detector: EdgeDetector) -> Vec<u8> {
+    edge_mask(img, width, height, detector)
+        .into_iter()
+        .flat_map(|edge| if edge { [0, 0, 0, 255] } else { [255; 4] })
+        .collect()
+}
@@ -0,0 +1,106 @@
+//! Option overrides from `PIXELMATCH_*` environment variables, so CI operators can loosen
+//! or tighten comparisons across many repositories without editing their test code.
+
+use serde::Deserialize;
+use serde_json::{Map, Value};
+
+use crate::json::{check_key, keys};
+use crate::{Options, PixelmatchError};
+
+impl Options {
+    /// Override fields from `PIXELMATCH_<FIELD>` environment variables, e.g.
+    /// `PIXELMATCH_THRESHOLD=0.2` or `PIXELMATCH_DETECT_ANTI_ALIASING=false`. Values are
+    /// written as in [`from_json`](Self::from_json) (`PIXELMATCH_AA_COLOR=[255,0,255]`), and
+    /// a value that is not JSON is taken as a string (`PIXELMATCH_EDGES=sobel:0.2`). Unset
+    /// and empty variables leave the field alone. Nothing is read unless this is called.
+    ///
+    /// Only the overridden fields are touched. On [`PixelmatchError::InvalidEnv`] the options
+    /// are left unchanged. The result is not validated; [`pixelmatch`](crate::pixelmatch)
+    /// does that.
+    pub fn apply_env(&mut self) -> Result<(), PixelmatchError> {
+        let mut patches = Vec::new();
+        for key in keys() {
+            let name = env_name(&key);
+            let Some(value) = std::env::var_os(&name).filter(|v| !v.is_empty()) else {
+                continue;
+            };
+            let Some(text) = value.to_str().map(str::trim) else {
+                return Err(PixelmatchError::InvalidEnv { name, reason: "not valid UTF-8".to_string() });
+            };
+            let value = serde_json::from_str(text).unwrap_or_else(|_| Value::String(text.to_string()));
+            if let Err(reason) = check_key(&key, &value) {
+                return Err(PixelmatchError::InvalidEnv { name, reason });
+            }
+            // Defaults everywhere except the overridden field, which `set_field` moves over.
+            let single = Value::Object(Map::from_iter([(key, value)]));
+            match Options::deserialize(&single) {
+                Ok(patch) => patches.push((name, patch)),
+                Err(e) => return Err(PixelmatchError::InvalidEnv { name, reason: e.to_string() }),
+            }
+        }
+        for (name, patch) in patches {
+            self.set_field(&name["PIXELMATCH_".len()..], patch);
+        }
+        Ok(())
+    }
+}
+
+/// Defines `Options::set_field`, listing every serialised field so that a new one fails to
+/// compile until it is added here.
+macro_rules! set_field {
+    ($($field:ident),*) => {
+        impl Options {
+            /// Replace the field named `field` in upper snake case with the one in `patch`.
+            fn set_field(&mut self, field: &str, patch: Options) {
+                let Options { $($field,)* ignore_mask: _ } = patch;
+                $(
+                    if field.eq_ignore_ascii_case(stringify!($field)) {
+                        self.$field = $field;
+                        return;
+                    }
+                )*
+                unreachable!("{field} is not an option field");
+            }
+        }
+    };
+}
+
+set_field!(
+    threshold,
+    detect_anti_aliasing,
+    alpha,
+    aa_color,
+    diff_color,
+    diff_color_alt,
+    diff_mask,
+    shift_tolerance,
+    subpixel_tolerance,
+    jpeg_tolerance,
+    scroll_compensation,
+    isolation_radius,
+    morphology,
+    label_shift,
+    pre_blur,
+    quantize,
+    median_filter,
+    edges,
+    downscale,
+    device_pixel_ratio,
+    density_scaling,
+    ignore_regions,
+    ignore_polygons,
+    include_only,
+    ignore_hatch
+);
+
+/// `PIXELMATCH_` followed by the camelCase `key` in upper snake case.
+fn env_name(key: &str) -> String {
+    let mut name = String::from("PIXELMATCH_");
+    for c in key.chars() {
+        if c.is_ascii_uppercase() {
+            name.push('_');
+        }
+        name.push(c.to_ascii_uppercase());
+    }
+    name
+}
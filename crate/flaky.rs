@@ -0,0 +1,81 @@
+//! Detection of unstable areas from repeated captures of the same screen.
+//!
+//! Capture a page a few times without changing anything, pass the captures to
+//! [`FlakyMask::detect`], and every pixel that varies between them (clocks, carousels,
+//! ads, cursors) is marked. Store the result with [`FlakyMask::save`] and feed
+//! [`FlakyMask::to_ignore_mask`] into [`Options::ignore_mask`] for later comparisons.
+
+use crate::morph::dilate;
+use crate::regions::{find_regions, DiffRegion};
+use crate::{diff_mask, Options, PixelmatchError};
+
+/// Pixels that varied between captures, optionally grown by a margin.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FlakyMask {
+    pub width: u32,
+    pub height: u32,
+    /// One entry per pixel in row-major order; `true` marks an unstable pixel.
+    pub mask: Vec<bool>,
+}
+
+impl FlakyMask {
+    /// Mark every pixel that differs between the first capture and any other, then grow
+    /// the marked areas by `padding` pixels in each direction to cover their soft edges.
+    ///
+    /// `options` controls what counts as a difference; note that anti-aliased pixels are
+    /// not marked unless `detect_anti_aliasing` is off. Fewer than two captures give an
+    /// empty mask.
+    pub fn detect(
+        captures: &[&[u8]],
+        width: u32,
+        height: u32,
+        options: &Options,
+        padding: u32,
+    ) -> Result<Self, PixelmatchError> {
+        let len = (width as usize).checked_mul(height as usize).ok_or(PixelmatchError::DimensionOverflow)?;
+        let mut mask = vec![false; len];
+        if let Some((first, rest)) = captures.split_first() {
+            for capture in rest {
+                let varied = diff_mask(first, capture, width, height, options)?;
+                mask.iter_mut().zip(varied).for_each(|(m, v)| *m |= v);
+            }
+        }
+        dilate(&mut mask, width, height, padding);
+        Ok(Self { width, height, mask })
+    }
+
+    /// Number of unstable pixels.
+    pub fn count(&self) -> usize {
+        self.mask.iter().filter(|&&m| m).count()
+    }
+
+    /// Connected unstable areas, for reporting or for turning into `ignore_regions`.
+    pub fn regions(&self) -> Vec<DiffRegion> {
+        find_regions(&self.mask, self.width, self.height)
+    }
+
+    /// One byte per pixel, 255 where unstable, as accepted by [`Options::ignore_mask`].
+    pub fn to_ignore_mask(&self) -> Vec<u8> {
+        self.mask.iter().map(|&m| if m { 255 } else { 0 }).collect()
+    }
+
+    /// RGBA image with unstable pixels opaque white and the rest transparent, readable by
+    /// both [`Options::ignore_mask`] and the CLI's `--ignore-mask`.
+    pub fn to_rgba(&self) -> Vec<u8> {
+        self.mask.iter().flat_map(|&m| if m { [255; 4] } else { [0; 4] }).collect()
+    }
+
+    /// Write the mask as a PNG (see [`to_rgba`](Self::to_rgba)).
+    #[cfg(feature = "png")]
+    pub fn save(&self, path: impl AsRef<std::path::Path>) -> std::io::Result<()> {
+        crate::io::write_png(path, &self.to_rgba(), self.width, self.height)
+    }
+
+    /// Read a mask PNG; pixels that are neither black nor transparent are unstable.
+    #[cfg(feature = "png")]
+    pub fn load(path: impl AsRef<std::path::Path>) -> std::io::Result<Self> {
+        let image = crate::io::read_png(path)?;
+        let mask = image.data.chunks_exact(4).map(|px| px[3] != 0 && px[..3] != [0; 3]).collect();
+        Ok(Self { width: image.width, height: image.height, mask })
+    }
+}
@@ -0,0 +1,222 @@
+//! The FLIP perceptual difference metric, after Andersson et al., "FLIP: A Difference
+//! Evaluator for Alternating Images" (2020).
+//!
+//! FLIP was designed for comparing renders: it models what a viewer flipping between the two
+//! images at a given distance would notice. Colours are filtered by the contrast sensitivity
+//! of the eye at the viewing distance, so detail too fine to resolve is ignored, and compared
+//! in a perceptual colour space; edges and points that appear, vanish or change contrast are
+//! weighted up. The result is an error map from 0.0 to 1.0 per pixel, and its mean is the
+//! FLIP score. This is the low-dynamic-range variant, for sRGB images.
+
+use rayon::prelude::*;
+
+use crate::{validate_buffers, PixelmatchError};
+
+/// Pixels per degree of visual angle for the reference setup of the paper: a 0.7 m wide
+/// 4K monitor viewed from 0.7 m.
+pub const DEFAULT_PPD: f64 = 67.0206;
+
+/// D65 white point in XYZ.
+const WHITE: [f64; 3] = [0.950428545, 1.0, 1.088900371];
+/// Linear sRGB to XYZ, and back.
+const RGB_TO_XYZ: [[f64; 3]; 3] = [[0.4124, 0.3576, 0.1805], [0.2126, 0.7152, 0.0722], [0.0193, 0.1192, 0.9505]];
+const XYZ_TO_RGB: [[f64; 3]; 3] = [[3.2406, -1.5372, -0.4986], [-0.9689, 1.8758, 0.0415], [0.0557, -0.2040, 1.0570]];
+
+/// Contrast sensitivity of the achromatic, red-green and blue-yellow channels, each a sum of
+/// two Gaussians `a * sqrt(pi / b) * exp(-pi^2 * r^2 / b)` over `r` in degrees: `[a1, b1, a2, b2]`.
+const CSF: [[f64; 4]; 3] = [[1.0, 0.0047, 0.0, 1e-5], [1.0, 0.0053, 0.0, 1e-5], [34.1, 0.04, 13.5, 0.025]];
+/// Largest `b` of [`CSF`], which sets the filter radius.
+const CSF_MAX_B: f64 = 0.04;
+
+/// Exponent compressing the colour difference, and the breakpoint (as a share of the largest
+/// difference) and error value at which the remapping of small differences changes slope.
+const QC: f64 = 0.7;
+const PC: f64 = 0.4;
+const PT: f64 = 0.95;
+/// Width of the feature detectors in degrees, and exponent of the feature difference.
+const FEATURE_WIDTH: f64 = 0.082;
+const QF: f64 = 0.5;
+
+/// Pixels per degree of visual angle for a screen `monitor_width` wide showing `resolution`
+/// pixels across, viewed from `distance` (in the same unit as the width).
+pub fn pixels_per_degree(distance: f64, monitor_width: f64, resolution: u32) -> f64 {
+    distance * (resolution as f64 / monitor_width) * std::f64::consts::PI / 180.0
+}
+
+fn mul(m: &[[f64; 3]; 3], v: [f64; 3]) -> [f64; 3] {
+    std::array::from_fn(|r| m[r][0] * v[0] + m[r][1] * v[1] + m[r][2] * v[2])
+}
+
+fn to_linear(c: f64) -> f64 {
+    if c <= 0.04045 { c / 12.92 } else { ((c + 0.055) / 1.055).powf(2.4) }
+}
+
+/// Linear sRGB of an RGBA pixel, blended over white.
+fn linear_rgb(p: &[u8]) -> [f64; 3] {
+    let alpha = p[3] as f64 / 255.0;
+    std::array::from_fn(|c| to_linear((255.0 + (p[c] as f64 - 255.0) * alpha) / 255.0))
+}
+
+/// Linear sRGB to the opponent YCxCz space (a linearised CIELAB).
+fn ycxcz(rgb: [f64; 3]) -> [f64; 3] {
+    let [x, y, z] = mul(&RGB_TO_XYZ, rgb);
+    let (x, y, z) = (x / WHITE[0], y / WHITE[1], z / WHITE[2]);
+    [116.0 * y - 16.0, 500.0 * (x - y), 200.0 * (y - z)]
+}
+
+/// YCxCz back to linear sRGB, clamped to the displayable range.
+fn ycxcz_to_rgb([l, cx, cz]: [f64; 3]) -> [f64; 3] {
+    let y = (l + 16.0) / 116.0;
+    let xyz = [(y + cx / 500.0) * WHITE[0], y * WHITE[1], (y - cz / 200.0) * WHITE[2]];
+    mul(&XYZ_TO_RGB, xyz).map(|c| c.clamp(0.0, 1.0))
+}
+
+/// CIELAB of linear sRGB with the Hunt adjustment, which scales chroma by lightness.
+fn hunt_lab(rgb: [f64; 3]) -> [f64; 3] {
+    let f = |t: f64| {
+        let delta: f64 = 6.0 / 29.0;
+        if t > delta.powi(3) { t.cbrt() } else { t / (3.0 * delta * delta) + 4.0 / 29.0 }
+    };
+    let [x, y, z] = mul(&RGB_TO_XYZ, rgb);
+    let (fx, fy, fz) = (f(x / WHITE[0]), f(y / WHITE[1]), f(z / WHITE[2]));
+    let l = 116.0 * fy - 16.0;
+    [l, 0.01 * l * 500.0 * (fx - fy), 0.01 * l * 200.0 * (fy - fz)]
+}
+
+/// HyAB distance: absolute lightness difference plus Euclidean chroma difference.
+fn hyab(a: [f64; 3], b: [f64; 3]) -> f64 {
+    (a[0] - b[0]).abs() + ((a[1] - b[1]).powi(2) + (a[2] - b[2]).powi(2)).sqrt()
+}
+
+/// Convolve a plane with `row_kernel` along rows and then `column_kernel` along columns,
+/// clamping at the edges. Kernels have an odd length and are centred.
+fn separable(plane: &[f64], w: usize, h: usize, row_kernel: &[f64], column_kernel: &[f64]) -> Vec<f64> {
+    let tap = |i: usize, k: usize, radius: usize, len: usize| (i + k).saturating_sub(radius).min(len - 1);
+    let (rr, cr) = (row_kernel.len() / 2, column_kernel.len() / 2);
+    let mut rows = vec![0.0; w * h];
+    rows.par_chunks_mut(w).enumerate().for_each(|(y, row)| {
+        let src = &plane[y * w..(y + 1) * w];
+        for (x, out) in row.iter_mut().enumerate() {
+            *out = row_kernel.iter().enumerate().map(|(k, &wt)| src[tap(x, k, rr, w)] * wt).sum();
+        }
+    });
+    let mut out = vec![0.0; w * h];
+    out.par_chunks_mut(w).enumerate().for_each(|(y, row)| {
+        for (x, o) in row.iter_mut().enumerate() {
+            *o = column_kernel.iter().enumerate().map(|(k, &wt)| rows[tap(y, k, cr, h) * w + x] * wt).sum();
+        }
+    });
+    out
+}
+
+/// Filter the three YCxCz planes with the contrast sensitivity functions.
+fn csf_filter(planes: &[Vec<f64>; 3], w: usize, h: usize, ppd: f64) -> [Vec<f64>; 3] {
+    let pi2 = std::f64::consts::PI.powi(2);
+    let radius = (3.0 * (CSF_MAX_B / (2.0 * pi2)).sqrt() * ppd).ceil() as i64;
+    std::array::from_fn(|c| {
+        let [a1, b1, a2, b2] = CSF[c];
+        // Each Gaussian term is separable; the filter is their sum, normalised as a whole.
+        let term = |a: f64, b: f64| {
+            let kernel: Vec<f64> =
+                (-radius..=radius).map(|i| (-pi2 * (i as f64 / ppd).powi(2) / b).exp()).collect();
+            let scale = a * (std::f64::consts::PI / b).sqrt();
+            let filtered: Vec<f64> = separable(&planes[c], w, h, &kernel, &kernel);
+            let filtered = filtered.into_iter().map(|v| v * scale).collect::<Vec<f64>>();
+            (filtered, scale * kernel.iter().sum::<f64>().powi(2))
+        };
+        let (f1, t1) = term(a1, b1);
+        if a2 == 0.0 {
+            return f1.into_iter().map(|v| v / t1).collect();
+        }
+        let (f2, t2) = term(a2, b2);
+        f1.iter().zip(&f2).map(|(x, y)| (x + y) / (t1 + t2)).collect()
+    })
+}
+
+/// Edge (first derivative) and point (second derivative) detector kernels, each with its
+/// positive weights summing to 1 and its negative weights to -1, and the matching Gaussian.
+fn feature_kernels(ppd: f64) -> (Vec<f64>, Vec<f64>, Vec<f64>) {
+    let sd = 0.5 * FEATURE_WIDTH * ppd;
+    let radius = (3.0 * sd).ceil() as i64;
+    let xs: Vec<f64> = (-radius..=radius).map(|i| i as f64).collect();
+    let gaussian: Vec<f64> = xs.iter().map(|x| (-x * x / (2.0 * sd * sd)).exp()).collect();
+    let total: f64 = gaussian.iter().sum();
+    let balance = |kernel: Vec<f64>| {
+        let positive: f64 = kernel.iter().filter(|&&v| v > 0.0).sum();
+        let negative: f64 = -kernel.iter().filter(|&&v| v < 0.0).sum::<f64>();
+        kernel.into_iter().map(|v| if v > 0.0 { v / positive } else if v < 0.0 { v / negative } else { 0.0 }).collect()
+    };
+    let edge = balance(xs.iter().zip(&gaussian).map(|(x, g)| -x * g).collect());
+    let point = balance(xs.iter().zip(&gaussian).map(|(x, g)| (x * x / (sd * sd) - 1.0) * g).collect());
+    (edge, point, gaussian.into_iter().map(|g| g / total).collect())
+}
+
+/// Edge and point magnitudes of a plane.
+fn features(plane: &[f64], w: usize, h: usize, ppd: f64) -> (Vec<f64>, Vec<f64>) {
+    let (edge, point, gaussian) = feature_kernels(ppd);
+    let magnitude = |kernel: &[f64]| {
+        let (gx, gy) =
+            rayon::join(|| separable(plane, w, h, kernel, &gaussian), || separable(plane, w, h, &gaussian, kernel));
+        gx.iter().zip(&gy).map(|(x, y)| x.hypot(*y)).collect::<Vec<f64>>()
+    };
+    rayon::join(|| magnitude(&edge), || magnitude(&point))
+}
+
+/// Per-pixel FLIP error of `test` against `reference`, two equally sized RGBA images viewed
+/// at `ppd` pixels per degree (see [`DEFAULT_PPD`] and [`pixels_per_degree`]): 0.0 where
+/// they look the same, up to 1.0. One entry per pixel in row-major order; render it with
+/// [`crate::ssim::heatmap`] and a maximum of 1.0. A `ppd` that is not a positive number
+/// uses [`DEFAULT_PPD`].
+pub fn flip_map(reference: &[u8], test: &[u8], width: u32, height: u32, ppd: f64) -> Result<Vec<f32>, PixelmatchError> {
+    validate_buffers(reference, test, None, width, height)?;
+    let (w, h) = (width as usize, height as usize);
+    if w == 0 || h == 0 {
+        return Ok(Vec::new());
+    }
+    let ppd = if ppd.is_finite() && ppd > 0.0 { ppd } else { DEFAULT_PPD };
+    let planes = |img: &[u8]| -> [Vec<f64>; 3] {
+        let pixels: Vec<[f64; 3]> = img.par_chunks_exact(4).map(|p| ycxcz(linear_rgb(p))).collect();
+        std::array::from_fn(|c| pixels.iter().map(|p| p[c]).collect())
+    };
+    let (p1, p2) = rayon::join(|| planes(reference), || planes(test));
+
+    // Colour pipeline: filter, back to RGB, and compare in Hunt-adjusted CIELAB.
+    let filtered = |p: &[Vec<f64>; 3]| -> Vec<[f64; 3]> {
+        let f = csf_filter(p, w, h, ppd);
+        (0..w * h).into_par_iter().map(|i| hunt_lab(ycxcz_to_rgb([f[0][i], f[1][i], f[2][i]]))).collect()
+    };
+    let (lab1, lab2) = rayon::join(|| filtered(&p1), || filtered(&p2));
+    let cmax = hyab(hunt_lab([0.0, 1.0, 0.0]), hunt_lab([0.0, 0.0, 1.0])).powf(QC);
+    let breakpoint = PC * cmax;
+
+    // Feature pipeline on normalised achromatic values of the unfiltered images.
+    let achromatic = |p: &[Vec<f64>; 3]| p[0].iter().map(|l| (l + 16.0) / 116.0).collect::<Vec<f64>>();
+    let ((e1, q1), (e2, q2)) = rayon::join(
+        || features(&achromatic(&p1), w, h, ppd),
+        || features(&achromatic(&p2), w, h, ppd),
+    );
+
+    Ok((0..w * h)
+        .into_par_iter()
+        .map(|i| {
+            let colour = hyab(lab1[i], lab2[i]).powf(QC);
+            let colour = if colour < breakpoint {
+                PT / breakpoint * colour
+            } else {
+                PT + (colour - breakpoint) / (cmax - breakpoint) * (1.0 - PT)
+            };
+            let feature = ((e1[i] - e2[i]).abs().max((q1[i] - q2[i]).abs()) / std::f64::consts::SQRT_2).powf(QF);
+            colour.powf(1.0 - feature).clamp(0.0, 1.0) as f32
+        })
+        .collect())
+}
+
+/// FLIP score of `test` against `reference`: the mean of [`flip_map`], 0.0 for images that
+/// look the same at the viewing distance.
+pub fn flip(reference: &[u8], test: &[u8], width: u32, height: u32, ppd: f64) -> Result<f64, PixelmatchError> {
+    let map = flip_map(reference, test, width, height, ppd)?;
+    if map.is_empty() {
+        return Ok(0.0);
+    }
+    Ok(map.par_iter().map(|&v| v as f64).sum::<f64>() / map.len() as f64)
+}
@@ -0,0 +1,81 @@
+use std::f64::consts::PI;
+use std::fmt::Write;
+
+use crate::regions::DiffRegion;
+
+/// Geographic extent of a rendered map tile.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GeoBounds {
+    /// A Web Mercator (slippy map) tile address.
+    Tile { z: u32, x: u32, y: u32 },
+    /// An explicit WGS84 bounding box in degrees, rendered in Web Mercator.
+    BBox { west: f64, south: f64, east: f64, north: f64 },
+}
+
+impl GeoBounds {
+    /// Project a pixel position within an image of the given size to (longitude, latitude).
+    ///
+    /// Pixel coordinates address pixel corners, so `(width, height)` is the bottom-right
+    /// corner of the tile. Latitude is interpolated in Web Mercator space.
+    pub fn project(&self, px: f64, py: f64, width: u32, height: u32) -> (f64, f64) {
+        let fx = px / width as f64;
+        let fy = py / height as f64;
+        match *self {
+            Self::Tile { z, x, y } => {
+                let n = 2f64.powi(z as i32);
+                let lon = (x as f64 + fx) / n * 360.0 - 180.0;
+                let lat = mercator_y_to_lat(PI * (1.0 - 2.0 * (y as f64 + fy) / n));
+                (lon, lat)
+            }
+            Self::BBox { west, south, east, north } => {
+                let lon = west + (east - west) * fx;
+                let top = lat_to_mercator_y(north);
+                let bottom = lat_to_mercator_y(south);
+                (lon, mercator_y_to_lat(top + (bottom - top) * fy))
+            }
+        }
+    }
+}
+
+fn lat_to_mercator_y(lat: f64) -> f64 {
+    (PI / 4.0 + lat.to_radians() / 2.0).tan().ln()
+}
+
+fn mercator_y_to_lat(y: f64) -> f64 {
+    y.sinh().atan().to_degrees()
+}
+
+/// Export diff regions as a GeoJSON `FeatureCollection` of bounding-box polygons.
+///
+/// `width` and `height` are the pixel dimensions of the compared tile. Each feature
+/// carries the region's pixel-space bounding box and pixel count as properties.
+pub fn regions_to_geojson(regions: &[DiffRegion], width: u32, height: u32, bounds: &GeoBounds) -> String {
+    let mut out = String::from(r#"{"type":"FeatureCollection","features":["#);
+    for (i, region) in regions.iter().enumerate() {
+        if i > 0 {
+            out.push(',');
+        }
+        let x0 = region.x as f64;
+        let y0 = region.y as f64;
+        let x1 = x0 + region.width as f64;
+        let y1 = y0 + region.height as f64;
+        // Counter-clockwise exterior ring, closed (RFC 7946 §3.1.6).
+        let ring = [(x0, y1), (x1, y1), (x1, y0), (x0, y0), (x0, y1)];
+
+        out.push_str(r#"{"type":"Feature","geometry":{"type":"Polygon","coordinates":[["#);
+        for (j, &(px, py)) in ring.iter().enumerate() {
+            let (lon, lat) = bounds.project(px, py, width, height);
+            if j > 0 {
+                out.push(',');
+            }
+            let _ = write!(out, "[{lon},{lat}]");
+        }
+        let _ = write!(
+            out,
+            r#"]]}},"properties":{{"x":{},"y":{},"width":{},"height":{},"pixel_count":{}}}}}"#,
+            region.x, region.y, region.width, region.height, region.pixel_count
+        );
+    }
+    out.push_str("]}");
+    out
+}
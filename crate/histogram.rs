@@ -0,0 +1,127 @@
+//! Histogram comparison, for global colour-cast and brightness regressions.
+//!
+//! Histograms ignore where pixels are, so they tell "the whole page got darker" or "reds
+//! shifted towards orange" apart from layout changes, and they work on images that are not
+//! aligned or not even the same size.
+//!
+//! The distances are not fields of [`MatchResult`](crate::MatchResult): [`compare`] is its own
+//! pass over both images, called next to [`crate::pixelmatch`] when they are wanted.
+
+use rayon::prelude::*;
+
+/// Number of bins, one per 8-bit level.
+pub const BINS: usize = 256;
+
+/// Counts of pixels per 8-bit level of one channel.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Histogram {
+    pub bins: [u64; BINS],
+}
+
+impl Histogram {
+    /// Histogram of channel `channel` (0 red, 1 green, 2 blue, 3 alpha) of an RGBA image.
+    pub fn channel(img: &[u8], channel: usize) -> Self {
+        Self::collect(img, |p| p[channel])
+    }
+
+    /// Histogram of the luma of an RGBA image, with translucent pixels blended over white.
+    pub fn luma(img: &[u8]) -> Self {
+        Self::collect(img, |p| {
+            let y = p[0] as f64 * 0.29889531 + p[1] as f64 * 0.58662247 + p[2] as f64 * 0.11448223;
+            (255.0 + (y - 255.0) * p[3] as f64 / 255.0).round() as u8
+        })
+    }
+
+    fn collect(img: &[u8], level: impl Fn(&[u8]) -> u8 + Sync) -> Self {
+        let bins = img
+            .par_chunks_exact(4)
+            .fold(
+                || [0u64; BINS],
+                |mut bins, p| {
+                    bins[level(p) as usize] += 1;
+                    bins
+                },
+            )
+            .reduce(
+                || [0u64; BINS],
+                |mut a, b| {
+                    a.iter_mut().zip(b).for_each(|(x, y)| *x += y);
+                    a
+                },
+            );
+        Self { bins }
+    }
+
+    /// Number of pixels counted.
+    pub fn total(&self) -> u64 {
+        self.bins.iter().sum()
+    }
+
+    /// The bins as fractions of the total; all zero for an empty histogram.
+    pub fn normalized(&self) -> [f64; BINS] {
+        let total = self.total().max(1) as f64;
+        self.bins.map(|b| b as f64 / total)
+    }
+}
+
+/// Symmetric chi-square distance of the normalised histograms: 0.0 when equal, 1.0 when
+/// they share no level.
+pub fn chi_square(a: &Histogram, b: &Histogram) -> f64 {
+    let (p, q) = (a.normalized(), b.normalized());
+    p.iter().zip(&q).filter(|(x, y)| *x + *y > 0.0).map(|(x, y)| (x - y) * (x - y) / (x + y)).sum::<f64>() / 2.0
+}
+
+/// Intersection of the normalised histograms: 1.0 when equal, 0.0 when they share no level.
+pub fn intersection(a: &Histogram, b: &Histogram) -> f64 {
+    let (p, q) = (a.normalized(), b.normalized());
+    p.iter().zip(&q).map(|(x, y)| x.min(*y)).sum()
+}
+
+/// Earth mover's distance of the normalised histograms, as a fraction of the full range:
+/// how far, on average, pixels would have to move in level to turn one into the other.
+/// 0.0 when equal, 1.0 when all pixels move from black to white.
+pub fn emd(a: &Histogram, b: &Histogram) -> f64 {
+    let (p, q) = (a.normalized(), b.normalized());
+    let (mut cdf_p, mut cdf_q, mut total) = (0.0, 0.0, 0.0);
+    for (x, y) in p.iter().zip(&q) {
+        cdf_p += x;
+        cdf_q += y;
+        total += f64::abs(cdf_p - cdf_q);
+    }
+    total / (BINS - 1) as f64
+}
+
+/// All three distances between two histograms.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Distances {
+    pub chi_square: f64,
+    pub intersection: f64,
+    pub emd: f64,
+}
+
+impl Distances {
+    pub fn between(a: &Histogram, b: &Histogram) -> Self {
+        Self { chi_square: chi_square(a, b), intersection: intersection(a, b), emd: emd(a, b) }
+    }
+}
+
+/// Histogram distances of two RGBA images per colour channel and for luma. The images may
+/// differ in size.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HistogramComparison {
+    pub red: Distances,
+    pub green: Distances,
+    pub blue: Distances,
+    pub luma: Distances,
+}
+
+/// Compare the histograms of two RGBA images.
+pub fn compare(img1: &[u8], img2: &[u8]) -> HistogramComparison {
+    let channel = |c: usize| Distances::between(&Histogram::channel(img1, c), &Histogram::channel(img2, c));
+    HistogramComparison {
+        red: channel(0),
+        green: channel(1),
+        blue: channel(2),
+        luma: Distances::between(&Histogram::luma(img1), &Histogram::luma(img2)),
+    }
+}
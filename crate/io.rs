@@ -0,0 +1,69 @@
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::Path;
+
+/// An 8-bit RGBA image.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Image {
+    /// Pixel data in RGBA order, `width * height * 4` bytes.
+    pub data: Vec<u8>,
+    /// Width in pixels.
+    pub width: u32,
+    /// Height in pixels.
+    pub height: u32,
+}
+
+impl Image {
+    /// Create a transparent black image of the given dimensions.
+    pub fn new(width: u32, height: u32) -> Self {
+        Self { data: vec![0; width as usize * height as usize * 4], width, height }
+    }
+}
+
+fn invalid_data(e: impl std::error::Error + Send + Sync + 'static) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, e)
+}
+
+/// Decode a PNG stream into an 8-bit RGBA image.
+///
+/// All colour types are expanded to RGBA and 16-bit channels are stripped to 8 bits,
+/// matching pngjs behaviour.
+pub fn decode_png<R: Read>(reader: R) -> io::Result<Image> {
+    let mut decoder = png::Decoder::new(reader);
+    decoder.set_transformations(png::Transformations::normalize_to_color8());
+    let mut reader = decoder.read_info().map_err(invalid_data)?;
+    let mut buf = vec![0u8; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut buf).map_err(invalid_data)?;
+    buf.truncate(info.buffer_size());
+
+    let data = match info.color_type {
+        png::ColorType::Rgba => buf,
+        png::ColorType::Rgb => buf.chunks_exact(3).flat_map(|p| [p[0], p[1], p[2], 255]).collect(),
+        png::ColorType::GrayscaleAlpha => buf.chunks_exact(2).flat_map(|p| [p[0], p[0], p[0], p[1]]).collect(),
+        png::ColorType::Grayscale => buf.iter().flat_map(|&v| [v, v, v, 255]).collect(),
+        png::ColorType::Indexed => {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "indexed PNG was not expanded"));
+        }
+    };
+    Ok(Image { data, width: info.width, height: info.height })
+}
+
+/// Encode 8-bit RGBA pixel data as PNG.
+pub fn encode_png<W: Write>(writer: W, data: &[u8], width: u32, height: u32) -> io::Result<()> {
+    let mut encoder = png::Encoder::new(writer, width, height);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    let mut writer = encoder.write_header().map_err(invalid_data)?;
+    writer.write_image_data(data).map_err(invalid_data)?;
+    writer.finish().map_err(invalid_data)
+}
+
+/// Read a PNG file into an 8-bit RGBA image.
+pub fn read_png(path: impl AsRef<Path>) -> io::Result<Image> {
+    decode_png(BufReader::new(File::open(path)?))
+}
+
+/// Write 8-bit RGBA pixel data to a PNG file.
+pub fn write_png(path: impl AsRef<Path>, data: &[u8], width: u32, height: u32) -> io::Result<()> {
+    encode_png(BufWriter::new(File::create(path)?), data, width, height)
+}
@@ -0,0 +1,43 @@
+//! Recognition of JPEG compression artefacts, used by
+//! [`Options::jpeg_tolerance`](crate::Options::jpeg_tolerance).
+//!
+//! JPEG encodes 8×8 blocks independently, so an image archived as JPEG differs from its
+//! lossless original in two typical places: along the block boundaries (blocking) and
+//! inside blocks that contain a sharp edge (ringing). Blocks are assumed to be aligned with
+//! the top-left corner of the image, as they are unless it was cropped after encoding.
+
+use crate::color::color_delta;
+
+/// Side of a JPEG block, in pixels.
+const BLOCK: usize = 8;
+
+/// Brightness step between neighbouring pixels that makes a sharp edge.
+const EDGE_STEP: f64 = 48.0;
+
+/// Whether (x, y) is on the outer rows or columns of its block.
+fn on_boundary(x: usize, y: usize) -> bool {
+    matches!(x % BLOCK, 0 | 7) || matches!(y % BLOCK, 0 | 7)
+}
+
+/// Whether the block containing (x, y) in `img` has two neighbouring pixels whose brightness
+/// differs by at least [`EDGE_STEP`].
+fn block_has_edge(img: &[u8], x: usize, y: usize, w: usize, h: usize) -> bool {
+    let (x0, y0) = (x / BLOCK * BLOCK, y / BLOCK * BLOCK);
+    let (x1, y1) = ((x0 + BLOCK).min(w), (y0 + BLOCK).min(h));
+    (y0..y1).any(|py| {
+        (x0..x1).any(|px| {
+            let pos = (py * w + px) * 4;
+            (px + 1 < x1 && color_delta(img, img, pos, pos + 4, true).abs() >= EDGE_STEP)
+                || (py + 1 < y1 && color_delta(img, img, pos, pos + w * 4, true).abs() >= EDGE_STEP)
+        })
+    })
+}
+
+/// Whether the pixel at (x, y) differs by at most `max_delta` and lies where JPEG
+/// compression leaves artefacts: on a block boundary, or in a block with a sharp edge in
+/// either image.
+pub fn artifact(img1: &[u8], img2: &[u8], x: usize, y: usize, w: usize, h: usize, max_delta: f64) -> bool {
+    let pos = (y * w + x) * 4;
+    color_delta(img1, img2, pos, pos, false).abs() <= max_delta
+        && (on_boundary(x, y) || block_has_edge(img1, x, y, w, h) || block_has_edge(img2, x, y, w, h))
+}
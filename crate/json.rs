@@ -0,0 +1,97 @@
+//! [`Options`] as JSON, shared by the bindings so they agree on keys, defaults and
+//! validation. Reading and writing go through the `serde` derives on [`Options`].
+
+use serde::de::Error as _;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde_json::{Map, Value};
+
+use crate::edges::EdgeDetector;
+use crate::morph::MorphOp;
+use crate::{Options, PixelmatchError};
+
+/// `MorphOp` and `EdgeDetector` are written as their `Display` strings (`"dilate:2"`,
+/// `"canny:0.1,0.3"`) and read back through `FromStr`.
+macro_rules! serde_as_string {
+    ($($ty:ty),*) => {$(
+        impl Serialize for $ty {
+            fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                serializer.collect_str(self)
+            }
+        }
+
+        impl<'de> Deserialize<'de> for $ty {
+            fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                String::deserialize(deserializer)?.parse().map_err(D::Error::custom)
+            }
+        }
+    )*};
+}
+
+serde_as_string!(MorphOp, EdgeDetector);
+
+/// Every key [`Options::from_json`] accepts.
+pub(crate) fn keys() -> Vec<String> {
+    match serde_json::to_value(Options::default()) {
+        Ok(Value::Object(fields)) => fields.into_iter().map(|(key, _)| key).collect(),
+        _ => unreachable!("Options serialise to an object"),
+    }
+}
+
+/// Check that `value` is a valid value for the option `key`, naming the key in the error.
+pub(crate) fn check_key(key: &str, value: &Value) -> Result<(), String> {
+    let single = Value::Object(Map::from_iter([(key.to_string(), value.clone())]));
+    match Options::deserialize(&single) {
+        Ok(_) => Ok(()),
+        Err(e) if e.to_string().starts_with("unknown field") => Err(format!("unknown option {key}")),
+        Err(e) => Err(format!("{key}: {e}")),
+    }
+}
+
+/// Options from an object of camelCase keys, not yet validated.
+pub(crate) fn from_fields(fields: Map<String, Value>) -> Result<Options, PixelmatchError> {
+    let object = Value::Object(fields);
+    Options::deserialize(&object).map_err(|e| {
+        // serde names the expected type but not the key, so find the key at fault.
+        let mut fields = object.as_object().into_iter().flatten();
+        let reason = fields.find_map(|(key, value)| check_key(key, value).err());
+        PixelmatchError::InvalidJson(reason.unwrap_or_else(|| e.to_string()))
+    })
+}
+
+/// Options from a JS options object, as the napi and wasm bindings receive one: unknown keys
+/// are ignored, as are `null` values, and the result is validated.
+#[cfg(any(feature = "napi", feature = "wasm"))]
+pub(crate) fn from_js_object(mut fields: Map<String, Value>) -> Result<Options, PixelmatchError> {
+    let keys = keys();
+    fields.retain(|key, value| !value.is_null() && keys.contains(key));
+    let options = from_fields(fields)?;
+    options.validate()?;
+    Ok(options)
+}
+
+impl Options {
+    /// Parse options from a JSON object and [`validate`](Self::validate) them.
+    ///
+    /// Keys are the camelCase field names (`threshold`, `detectAntiAliasing`, `aaColor`,
+    /// ...). Colours and `devicePixelRatio` are arrays, `morphology` a list of
+    /// `"dilate:<r>"`/`"erode:<r>"` strings, `edges` a `"sobel:<t>"`/`"canny:<low>,<high>"`
+    /// string, `ignoreRegions` a list of `{x, y, width, height}` objects and `ignorePolygons`
+    /// a list of `[[x, y], ...]` point lists; optional fields may be `null`. `ignoreMask` is
+    /// binary and has no key. Missing keys keep their defaults; unknown keys, wrongly typed
+    /// values and malformed JSON are rejected with [`PixelmatchError::InvalidJson`].
+    pub fn from_json(json: &str) -> Result<Self, PixelmatchError> {
+        let value = serde_json::from_str(json).map_err(|e| PixelmatchError::InvalidJson(e.to_string()))?;
+        let Value::Object(fields) = value else {
+            return Err(PixelmatchError::InvalidJson("expected an object".into()));
+        };
+        let options = from_fields(fields)?;
+        options.validate()?;
+        Ok(options)
+    }
+
+    /// Every field except `ignore_mask` as a compact JSON object that
+    /// [`from_json`](Self::from_json) reads back.
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("serialising to a string cannot fail")
+    }
+}
@@ -0,0 +1,113 @@
+//! Tolerance for map labels whose placement jitters between renders, used by
+//! [`Options::label_shift`](crate::Options::label_shift).
+//!
+//! Label placement in map renderers depends on collision order and font hinting, so the
+//! same label can land a few pixels away in the next render. That leaves small clusters of
+//! high-contrast differences at its old and new positions. [`moved_labels`] picks out such
+//! clusters and keeps those whose content reappears in the other image translated by a few
+//! pixels (see [`classify_regions`]), so they can be reported apart from real changes.
+
+use crate::color::color_delta;
+use crate::motion::{classify_regions, RegionChange};
+use crate::regions::{label_regions, DiffRegion};
+use crate::Options;
+
+/// Largest width and height of a cluster that can be a label.
+const MAX_LABEL_SIZE: u32 = 128;
+/// Smallest difference, as a `threshold` (0 to 1), that at least one pixel of a cluster
+/// must show for it to count as high contrast, as text against its halo or background does.
+const LABEL_CONTRAST: f64 = 0.4;
+
+/// A cluster of differing pixels explained by a label that moved.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MovedLabel {
+    /// The cluster, covering the label's old and new positions where they overlap.
+    pub region: DiffRegion,
+    /// Horizontal offset of the label in the second image; positive is right.
+    pub dx: i32,
+    /// Vertical offset of the label in the second image; positive is down.
+    pub dy: i32,
+}
+
+/// The clusters of `mask` (differing pixels of `img1` against `img2`, e.g. from
+/// [`diff_mask`](crate::diff_mask)) that are labels moved by up to `max_offset` pixels in
+/// each direction, in scan order.
+///
+/// A cluster is a candidate when its bounding box is at most 128 pixels across and tall
+/// and at least one of its pixels differs by more than a `threshold` of 0.4 would allow. It
+/// is kept when its content matches the other image at some offset, per
+/// `options.threshold`.
+pub fn moved_labels(
+    img1: &[u8],
+    img2: &[u8],
+    width: u32,
+    height: u32,
+    mask: &[bool],
+    max_offset: u32,
+    options: &Options,
+) -> Vec<MovedLabel> {
+    let (regions, labels) = label_regions(mask, width, height);
+    classify(img1, img2, width, height, &regions, &labels, max_offset, options)
+        .into_iter()
+        .zip(regions)
+        .filter_map(|(change, region)| match change {
+            Some(RegionChange::Moved { dx, dy }) => Some(MovedLabel { region, dx, dy }),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Clear the clusters of `mask` that [`moved_labels`] finds, returning how many there were.
+pub(crate) fn remove_moved_labels(
+    img1: &[u8],
+    img2: &[u8],
+    width: u32,
+    height: u32,
+    mask: &mut [bool],
+    max_offset: u32,
+    options: &Options,
+) -> u32 {
+    let (regions, labels) = label_regions(mask, width, height);
+    let changes = classify(img1, img2, width, height, &regions, &labels, max_offset, options);
+    let moved: Vec<bool> = changes.iter().map(|c| matches!(c, Some(RegionChange::Moved { .. }))).collect();
+    for (m, &label) in mask.iter_mut().zip(&labels) {
+        if label != 0 && moved[label as usize - 1] {
+            *m = false;
+        }
+    }
+    moved.iter().filter(|&&m| m).count() as u32
+}
+
+/// Classify every region that could be a label, leaving `None` for the others.
+#[allow(clippy::too_many_arguments)]
+fn classify(
+    img1: &[u8],
+    img2: &[u8],
+    width: u32,
+    height: u32,
+    regions: &[DiffRegion],
+    labels: &[u32],
+    max_offset: u32,
+    options: &Options,
+) -> Vec<Option<RegionChange>> {
+    let mut contrast = vec![0.0f64; regions.len()];
+    for (i, &label) in labels.iter().enumerate().filter(|(_, &label)| label != 0) {
+        let delta = color_delta(img1, img2, i * 4, i * 4, false).abs();
+        let max = &mut contrast[label as usize - 1];
+        *max = max.max(delta);
+    }
+    let min_contrast = 35215.0 * LABEL_CONTRAST * LABEL_CONTRAST;
+    let candidates: Vec<usize> = (0..regions.len())
+        .filter(|&i| {
+            let r = &regions[i];
+            r.width <= MAX_LABEL_SIZE && r.height <= MAX_LABEL_SIZE && contrast[i] > min_contrast
+        })
+        .collect();
+    let subset: Vec<DiffRegion> = candidates.iter().map(|&i| regions[i]).collect();
+    let mut changes = vec![None; regions.len()];
+    let classified = classify_regions(img1, img2, width, height, &subset, max_offset, options);
+    for (i, change) in candidates.into_iter().zip(classified) {
+        changes[i] = Some(change);
+    }
+    changes
+}
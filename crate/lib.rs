@@ -1,9 +1,76 @@
+#![cfg_attr(feature = "forbid-unsafe", forbid(unsafe_code))]
+
+#[cfg(all(feature = "forbid-unsafe", any(feature = "capi", feature = "napi", feature = "ruby")))]
+compile_error!("the `forbid-unsafe` feature cannot be combined with the capi, napi or ruby bindings");
+
+#[macro_use]
+mod trace;
+
 mod aa;
+pub mod align;
+#[cfg(feature = "png")]
+pub mod artifacts;
+pub mod atlas;
+#[cfg(feature = "png")]
+pub mod baseline;
+pub mod batch;
+pub mod blur;
+#[cfg(feature = "butteraugli")]
+pub mod butteraugli;
+#[cfg(feature = "capi")]
+pub mod capi;
+pub mod causes;
+pub mod classify;
 mod color;
+pub mod consensus;
+pub mod deltas;
+pub mod depth;
+pub mod edges;
+#[cfg(feature = "serde")]
+mod env;
+pub mod flaky;
+pub mod flip;
+pub mod geojson;
+pub mod gmsd;
+pub mod histogram;
+#[cfg(feature = "png")]
+pub mod io;
+mod jpeg;
+#[cfg(feature = "serde")]
+mod json;
+pub mod labels;
+pub mod metrics;
+pub mod morph;
+pub mod motion;
+pub mod normals;
+pub mod phash;
+pub mod prepared;
+pub mod quantize;
+pub mod profile;
+pub mod psnr;
+pub mod regions;
+pub mod report;
+pub mod resize;
+pub mod roi;
+pub mod rows;
+#[cfg(feature = "review")]
+pub mod review;
+pub mod scroll;
+#[cfg(feature = "png")]
+pub mod snapshot;
+pub mod ssim;
+pub mod stats;
+pub mod stream;
+#[cfg(feature = "png")]
+pub mod tiles;
+pub mod wide;
 
-use color::{color_delta, draw_gray_pixel, draw_pixel};
+pub use color::Component;
+use color::{color_delta, draw_gray_pixel, draw_pixel, read_rgba, rgba_at, write_rgba};
 use aa::antialiased;
 use rayon::prelude::*;
+use std::borrow::Cow;
+use std::ops::Range;
 
 /// Public re-export of color_delta for testing (FMA canary, property tests).
 pub fn color_delta_public(img1: &[u8], img2: &[u8], k: usize, m: usize, y_only: bool) -> f64 {
@@ -11,7 +78,12 @@ pub fn color_delta_public(img1: &[u8], img2: &[u8], k: usize, m: usize, y_only:
 }
 
 /// Options for pixel comparison.
+///
+/// With the `serde` feature, options (de)serialise as an object of camelCase keys, as read
+/// by [`Options::from_json`].
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default, deny_unknown_fields, rename_all = "camelCase"))]
 pub struct Options {
     /// Matching threshold (0 to 1); smaller is more sensitive. Default: 0.1
     pub threshold: f64,
@@ -27,6 +99,92 @@ pub struct Options {
     pub diff_color_alt: Option<[u8; 3]>,
     /// Draw the diff over a transparent background (a mask). Default: false
     pub diff_mask: bool,
+    /// Tolerate content shifted by up to this many pixels: a differing pixel is not counted
+    /// when each image's pixel has a match within this distance in the other image, which
+    /// absorbs small text and kerning shifts. Default: 0 (off)
+    pub shift_tolerance: u32,
+    /// Tolerate sub-pixel positioning differences: a differing pixel is not counted when it
+    /// matches a bilinearly interpolated sample of the other image within ±0.5 px. Default: false
+    pub subpixel_tolerance: bool,
+    /// Threshold (0 to 1, like `threshold`) up to which differences are tolerated where JPEG
+    /// compression leaves artefacts: on the boundary rows and columns of the 8×8 blocks and
+    /// inside blocks with a sharp edge. Lets a JPEG-archived baseline be compared with a
+    /// lossless candidate; only has an effect above `threshold`. Ignored with `downscale`.
+    /// Default: None
+    pub jpeg_tolerance: Option<f64>,
+    /// Detect a vertical scroll offset of up to this many rows between the images (see
+    /// [`scroll::detect_offset`]) and shift the second image back before comparing. The
+    /// offset is reported in [`MatchResult::scroll_offset`] and the diff is drawn against the
+    /// shifted image. Default: 0 (off)
+    pub scroll_compensation: u32,
+    /// Discount differing pixels with no other differing pixel within this many pixels in
+    /// any direction, treating them as rendering noise. Applied to the diff mask before
+    /// counting and drawing. Default: 0 (off)
+    pub isolation_radius: u32,
+    /// Dilate and erode steps applied in order to the diff mask (after `isolation_radius`)
+    /// before counting and drawing, e.g. a dilate then an erode to close gaps between
+    /// nearby differences. Pixels added by dilation count as different. Default: empty
+    pub morphology: Vec<morph::MorphOp>,
+    /// Do not count small high-contrast clusters of differing pixels, such as map labels
+    /// whose placement jitters between renders, whose content reappears in the other image
+    /// moved by up to this many pixels (see [`labels`]). Applied to the diff mask after
+    /// `morphology`; the clusters are counted in [`MatchResult::moved_labels`] and drawn as
+    /// unchanged. Default: 0 (off)
+    pub label_shift: u32,
+    /// Standard deviation in pixels of a Gaussian blur applied to working copies of both
+    /// images before comparing, to suppress single-pixel rasterisation noise. The diff
+    /// output is drawn from the blurred images. Default: None
+    pub pre_blur: Option<f32>,
+    /// Map working copies of both images to a shared palette of this many colours (2 to
+    /// 256), built from both with median cut (see [`quantize`]), before any other filter.
+    /// Small truncation differences between palette-based renderers then disappear. The
+    /// diff output is drawn from the quantised images. Default: None
+    pub quantize: Option<u32>,
+    /// Apply a 3×3 median filter to working copies of both images before comparing (and
+    /// before `pre_blur`), removing salt-and-pepper noise from camera or video captures. The
+    /// diff output is drawn from the filtered images. Default: false
+    pub median_filter: bool,
+    /// Compare edge maps of both images (see [`edges`]) instead of their colours, after
+    /// `median_filter` and `pre_blur`: fill-colour-only changes such as a new theme are
+    /// ignored while moved, resized or missing elements still differ. Thresholds lie in
+    /// 0..=1. The diff output is drawn from the edge maps. Default: None
+    pub edges: Option<edges::EdgeDetector>,
+    /// Compare box-filtered copies of both images at this fraction of their size, e.g. 0.5
+    /// for a rough check about four times faster. Counts are scaled back to full-size
+    /// pixels and the diff output is upscaled, so both are approximate. `shift_tolerance`
+    /// and `pre_blur` are scaled with the images. Default: None
+    pub downscale: Option<f32>,
+    /// Device-pixel ratios the first and second image were captured at, e.g. `[1.0, 2.0]`
+    /// to check a retina capture against a standard-density baseline. When they differ, the
+    /// image with the higher ratio is box-filtered down to the other's scale before
+    /// comparing: `width`, `height`, the output and the ignore options then describe the
+    /// lower-ratio image, and the other must be that size times the ratio between the two
+    /// (see [`resize::dpr_size`] and [`Options::compared_size`]). Default: None (both the same)
+    pub device_pixel_ratio: Option<[f32; 2]>,
+    /// Treat the other options as tuned for 1x captures and adapt them to the density the
+    /// images are compared at (the lower `device_pixel_ratio`): at ratio r, `threshold`
+    /// (capped at 1), `shift_tolerance`, `isolation_radius`, `morphology`, `label_shift` and
+    /// `pre_blur` are multiplied by r, and anti-aliasing detection looks at neighbours r
+    /// pixels away. A sub-pixel shift moves edges r times as far in device pixels and
+    /// changes their colours r times as much, so without this one configuration is r times
+    /// as strict on retina captures. See [`Options::scaled_for_density`]. Default: false
+    pub density_scaling: bool,
+    /// Regions excluded from comparison. Pixels inside are treated as equal and drawn
+    /// as the grayscale underlay. Default: empty
+    pub ignore_regions: Vec<Rect>,
+    /// Polygonal regions excluded like `ignore_regions`, for rotated or irregular areas.
+    /// Default: empty
+    pub ignore_polygons: Vec<Polygon>,
+    /// Per-pixel ignore mask, either one byte per pixel or RGBA (alpha is used). Non-zero
+    /// pixels are excluded like `ignore_regions`. Default: None
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub ignore_mask: Option<Vec<u8>>,
+    /// Invert `ignore_regions`, `ignore_polygons` and `ignore_mask`: compare only the pixels
+    /// they cover and ignore everything else. Default: false
+    pub include_only: bool,
+    /// Colour of diagonal hatch lines drawn over ignored pixels in diff output [R, G, B], so
+    /// masked areas stand out from unchanged ones. Default: None (underlay only)
+    pub ignore_hatch: Option<[u8; 3]>,
 }
 
 impl Default for Options {
@@ -39,7 +197,296 @@ impl Default for Options {
             diff_color: [255, 0, 0],
             diff_color_alt: None,
             diff_mask: false,
+            shift_tolerance: 0,
+            subpixel_tolerance: false,
+            jpeg_tolerance: None,
+            scroll_compensation: 0,
+            isolation_radius: 0,
+            morphology: Vec::new(),
+            label_shift: 0,
+            pre_blur: None,
+            quantize: None,
+            median_filter: false,
+            edges: None,
+            downscale: None,
+            device_pixel_ratio: None,
+            density_scaling: false,
+            ignore_regions: Vec::new(),
+            ignore_polygons: Vec::new(),
+            ignore_mask: None,
+            include_only: false,
+            ignore_hatch: None,
+        }
+    }
+}
+
+impl Options {
+    /// A tuned starting point for the given kind of content. Presets set `threshold` and
+    /// `detect_anti_aliasing`; every other field keeps its default.
+    pub fn preset(preset: Preset) -> Self {
+        let (threshold, detect_anti_aliasing) = match preset {
+            Preset::Strict => (0.0, false),
+            Preset::Lenient => (0.2, true),
+            Preset::TextHeavy => (0.15, true),
+            Preset::Photo => (0.2, false),
+            Preset::MapTiles => (0.1, true),
+        };
+        Self { threshold, detect_anti_aliasing, ..Default::default() }
+    }
+
+    /// Check that the options make sense together. Called by [`pixelmatch`], [`diff_mask`]
+    /// and [`stream::StreamMatcher::new`].
+    ///
+    /// `threshold`, `jpeg_tolerance` and `alpha` must lie in 0..=1 (NaN is rejected),
+    /// `pre_blur` must be finite and non-negative, `quantize` must lie in 2..=256, `edges`
+    /// thresholds must lie in 0..=1 with Canny's low one at most its high one, `downscale`
+    /// must lie in 0 (exclusive) to 1, both device-pixel ratios must be positive and finite,
+    /// and `include_only` needs at least one region, polygon or mask to compare.
+    pub fn validate(&self) -> Result<(), PixelmatchError> {
+        if !(0.0..=1.0).contains(&self.threshold) {
+            return Err(PixelmatchError::InvalidThreshold(self.threshold));
+        }
+        if let Some(t) = self.jpeg_tolerance.filter(|t| !(0.0..=1.0).contains(t)) {
+            return Err(PixelmatchError::InvalidThreshold(t));
+        }
+        if !(0.0..=1.0).contains(&self.alpha) {
+            return Err(PixelmatchError::InvalidAlpha(self.alpha));
+        }
+        if let Some(sigma) = self.pre_blur.filter(|s| !(s.is_finite() && *s >= 0.0)) {
+            return Err(PixelmatchError::InvalidBlur(sigma));
+        }
+        if let Some(colors) = self.quantize.filter(|c| !(2..=256).contains(c)) {
+            return Err(PixelmatchError::InvalidColorCount(colors));
+        }
+        if let Some(detector) = self.edges {
+            let [low, high] = detector.thresholds();
+            if let Some(&t) = [low, high].iter().find(|t| !(0.0..=1.0).contains(*t)) {
+                return Err(PixelmatchError::InvalidThreshold(t as f64));
+            }
+            if low > high {
+                return Err(PixelmatchError::ConflictingOptions("edge detector low threshold above high threshold"));
+            }
         }
+        if let Some(scale) = self.downscale.filter(|s| !(*s > 0.0 && *s <= 1.0)) {
+            return Err(PixelmatchError::InvalidScale(scale));
+        }
+        if let Some(&ratio) = self.device_pixel_ratio.iter().flatten().find(|r| !(r.is_finite() && **r > 0.0)) {
+            return Err(PixelmatchError::InvalidPixelRatio(ratio));
+        }
+        if self.include_only
+            && self.ignore_regions.is_empty()
+            && self.ignore_polygons.is_empty()
+            && self.ignore_mask.is_none()
+        {
+            return Err(PixelmatchError::ConflictingOptions("include_only without regions would compare nothing"));
+        }
+        Ok(())
+    }
+
+    /// The size images of `size1` and `size2` (width, height) are compared at: the size of
+    /// the image with the lower [`device_pixel_ratio`](Self::device_pixel_ratio), or `None`
+    /// if the other is not that size scaled by the ratio between the two.
+    pub fn compared_size(&self, size1: (u32, u32), size2: (u32, u32)) -> Option<(u32, u32)> {
+        let [dpr1, dpr2] = self.device_pixel_ratio.unwrap_or([1.0; 2]);
+        let (low, size) = if dpr1 <= dpr2 { (dpr1, size1) } else { (dpr2, size2) };
+        let scaled = |dpr| resize::dpr_size(size.0, size.1, low, dpr);
+        (scaled(dpr1) == size1 && scaled(dpr2) == size2).then_some(size)
+    }
+
+    /// The device-pixel ratio [`density_scaling`](Self::density_scaling) adapts to: the
+    /// lower `device_pixel_ratio`, or 1.0 when off or unset.
+    fn density(&self) -> f32 {
+        match self.device_pixel_ratio {
+            Some([dpr1, dpr2]) if self.density_scaling => dpr1.min(dpr2),
+            _ => 1.0,
+        }
+    }
+
+    /// Distance in pixels of the neighbours anti-aliasing detection looks at.
+    fn aa_step(&self) -> usize {
+        (self.density().round() as usize).max(1)
+    }
+
+    /// The options with [`density_scaling`](Self::density_scaling) applied: the pixel
+    /// tolerances and threshold multiplied by the density, or the options unchanged when it
+    /// is off or the density is 1. Comparisons call this themselves, so pass the original
+    /// options to them.
+    pub fn scaled_for_density(&self) -> Cow<'_, Options> {
+        let r = self.density();
+        if r == 1.0 {
+            return Cow::Borrowed(self);
+        }
+        let scale = |n: u32| (n as f32 * r).ceil() as u32;
+        Cow::Owned(Options {
+            threshold: (self.threshold * r as f64).min(1.0),
+            shift_tolerance: scale(self.shift_tolerance),
+            isolation_radius: scale(self.isolation_radius),
+            morphology: self.morphology.iter().map(|op| op.scaled(r)).collect(),
+            label_shift: scale(self.label_shift),
+            pre_blur: self.pre_blur.map(|sigma| sigma * r),
+            ..self.clone()
+        })
+    }
+}
+
+/// Comparison presets for common kinds of content; see [`Options::preset`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Preset {
+    /// Any change in any pixel counts, anti-aliased or not. For pixel-exact output such as
+    /// rendered icons or generated charts.
+    Strict,
+    /// Tolerates small colour shifts, e.g. from different GPUs or colour management.
+    Lenient,
+    /// Text-dominated pages: tolerates font rasterisation differences between machines
+    /// while still catching changed glyphs.
+    TextHeavy,
+    /// Photographs and other continuous-tone images, where anti-aliasing detection mostly
+    /// misclassifies texture and compression noise.
+    Photo,
+    /// Rendered map tiles: thin lines and labels over flat fills.
+    MapTiles,
+}
+
+impl Preset {
+    /// All presets.
+    pub const ALL: [Preset; 5] = [Self::Strict, Self::Lenient, Self::TextHeavy, Self::Photo, Self::MapTiles];
+
+    /// Kebab-case name, as accepted by `FromStr`.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Strict => "strict",
+            Self::Lenient => "lenient",
+            Self::TextHeavy => "text-heavy",
+            Self::Photo => "photo",
+            Self::MapTiles => "map-tiles",
+        }
+    }
+}
+
+impl std::str::FromStr for Preset {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL.into_iter().find(|p| p.name() == s).ok_or_else(|| format!("unknown preset \"{s}\""))
+    }
+}
+
+/// An axis-aligned rectangle in pixel coordinates.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(deny_unknown_fields))]
+pub struct Rect {
+    /// Left edge.
+    pub x: u32,
+    /// Top edge.
+    pub y: u32,
+    /// Width in pixels.
+    pub width: u32,
+    /// Height in pixels.
+    pub height: u32,
+}
+
+impl Rect {
+    pub fn new(x: u32, y: u32, width: u32, height: u32) -> Self {
+        Self { x, y, width, height }
+    }
+
+    /// Whether the pixel at (x, y) lies inside the rectangle.
+    pub fn contains(&self, x: u32, y: u32) -> bool {
+        x >= self.x && y >= self.y && x - self.x < self.width && y - self.y < self.height
+    }
+}
+
+/// A closed polygon in pixel coordinates. A pixel lies inside when its centre does, by the
+/// even-odd rule, so self-intersecting outlines leave their overlaps uncovered.
+#[derive(Debug, Clone, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(transparent))]
+pub struct Polygon {
+    /// Vertices as (x, y); the last one connects back to the first.
+    pub points: Vec<(f64, f64)>,
+}
+
+impl Polygon {
+    pub fn new(points: Vec<(f64, f64)>) -> Self {
+        Self { points }
+    }
+
+    /// Whether the centre of the pixel at (x, y) lies inside the polygon.
+    pub fn contains(&self, x: u32, y: u32) -> bool {
+        let (cx, cy) = (x as f64 + 0.5, y as f64 + 0.5);
+        self.crossings(cy).into_iter().filter(|&c| c <= cx).count() % 2 == 1
+    }
+
+    /// X coordinates where the horizontal line at `y` crosses the outline, sorted.
+    fn crossings(&self, y: f64) -> Vec<f64> {
+        let n = self.points.len();
+        let mut xs: Vec<f64> = (0..n)
+            .filter_map(|i| {
+                let (x0, y0) = self.points[i];
+                let (x1, y1) = self.points[(i + 1) % n];
+                ((y0 <= y) != (y1 <= y)).then(|| x0 + (y - y0) * (x1 - x0) / (y1 - y0))
+            })
+            .collect();
+        xs.sort_by(f64::total_cmp);
+        xs
+    }
+
+    /// Mark the pixels inside the polygon in a `w` x `h` row-major mask.
+    fn fill(&self, mask: &mut [bool], w: usize, h: usize) {
+        let (min_y, max_y) = self.points.iter().fold((f64::INFINITY, f64::NEG_INFINITY), |(lo, hi), &(_, y)| {
+            (lo.min(y), hi.max(y))
+        });
+        let y0 = (min_y - 0.5).ceil().clamp(0.0, h as f64) as usize;
+        let y1 = (max_y - 0.5).ceil().clamp(0.0, h as f64) as usize;
+        for y in y0..y1 {
+            for span in self.crossings(y as f64 + 0.5).chunks_exact(2) {
+                // Pixels whose centre x + 0.5 lies in [span[0], span[1]).
+                let x0 = (span[0] - 0.5).ceil().clamp(0.0, w as f64) as usize;
+                let x1 = (span[1] - 0.5).ceil().clamp(0.0, w as f64) as usize;
+                if x0 < x1 {
+                    mask[y * w + x0..y * w + x1].fill(true);
+                }
+            }
+        }
+    }
+}
+
+/// Build the per-pixel ignore mask for the given options, or `None` if nothing is ignored.
+fn ignore_mask(options: &Options, w: usize, h: usize) -> Result<Option<Vec<bool>>, PixelmatchError> {
+    let len = w * h;
+    let mut mask = match &options.ignore_mask {
+        Some(m) if m.len() == len => m.iter().map(|&v| v != 0).collect(),
+        Some(m) if m.len() == len * 4 => m.chunks_exact(4).map(|px| px[3] != 0).collect(),
+        Some(m) => return Err(PixelmatchError::IgnoreMaskSizeMismatch { pixels: len, actual: m.len() }),
+        None if options.ignore_regions.is_empty() && options.ignore_polygons.is_empty() => return Ok(None),
+        None => vec![false; len],
+    };
+    for r in &options.ignore_regions {
+        let x0 = (r.x as usize).min(w);
+        let x1 = (r.x as usize).saturating_add(r.width as usize).min(w);
+        let y0 = (r.y as usize).min(h);
+        let y1 = (r.y as usize).saturating_add(r.height as usize).min(h);
+        for y in y0..y1 {
+            mask[y * w + x0..y * w + x1].fill(true);
+        }
+    }
+    for polygon in &options.ignore_polygons {
+        polygon.fill(&mut mask, w, h);
+    }
+    if options.include_only {
+        mask.iter_mut().for_each(|m| *m = !*m);
+    }
+    Ok(Some(mask))
+}
+
+/// Draw the hatch lines of `color` over the ignored pixels of output row `y`.
+fn draw_ignore_hatch(out_row: &mut [u8], ignore_row: Option<&[bool]>, y: usize, color: Option<[u8; 3]>) {
+    let (Some(ignore_row), Some([r, g, b])) = (ignore_row, color) else {
+        return;
+    };
+    for (x, _) in ignore_row.iter().enumerate().filter(|&(x, &ignored)| ignored && (x + y).is_multiple_of(4)) {
+        draw_pixel(out_row, x * 4, r, g, b);
     }
 }
 
@@ -54,6 +501,36 @@ pub enum PixelmatchError {
     ImageSizeMismatch { img1_len: usize, img2_len: usize },
     /// Output buffer length does not match img1 length.
     OutputSizeMismatch { img1_len: usize, output_len: usize },
+    /// Ignore mask length is neither width * height nor width * height * 4.
+    IgnoreMaskSizeMismatch { pixels: usize, actual: usize },
+    /// A streamed band is not a whole number of rows, or runs past the last row.
+    BandSizeMismatch { row_bytes: usize, remaining_rows: usize, actual: usize },
+    /// A streamed comparison was finished before every row was pushed.
+    IncompleteImage { expected_rows: usize, received_rows: usize },
+    /// `threshold` or `jpeg_tolerance` is NaN or outside 0..=1.
+    InvalidThreshold(f64),
+    /// `alpha` is NaN or outside 0..=1.
+    InvalidAlpha(f64),
+    /// `pre_blur` is negative or not finite.
+    InvalidBlur(f32),
+    /// `quantize` is not in 2..=256.
+    InvalidColorCount(u32),
+    /// `downscale` is not in 0 (exclusive) to 1.
+    InvalidScale(f32),
+    /// A `device_pixel_ratio` is not a positive number.
+    InvalidPixelRatio(f32),
+    /// Two options contradict each other.
+    ConflictingOptions(&'static str),
+    /// A normal-map angle is NaN or outside 0..=180 degrees.
+    InvalidAngle(f64),
+    /// A depth comparison tolerance is negative or NaN.
+    InvalidTolerance(f64),
+    /// A region to compare, such as an atlas frame, extends past the image.
+    RegionOutOfBounds { region: Rect, width: u32, height: u32 },
+    /// Options JSON is malformed, or has an unknown key or a wrongly typed value.
+    InvalidJson(String),
+    /// A `PIXELMATCH_*` environment variable read by `Options::apply_env` has an invalid value.
+    InvalidEnv { name: String, reason: String },
 }
 
 impl std::fmt::Display for PixelmatchError {
@@ -72,6 +549,33 @@ impl std::fmt::Display for PixelmatchError {
                     "Output buffer size does not match image size. Image size: {img1_len}, output size: {output_len}"
                 )
             }
+            Self::IgnoreMaskSizeMismatch { pixels, actual } => {
+                let rgba = pixels * 4;
+                write!(f, "Ignore mask size does not match image size. Expecting {pixels} or {rgba}. Got {actual}")
+            }
+            Self::BandSizeMismatch { row_bytes, remaining_rows, actual } => {
+                let max = remaining_rows * row_bytes;
+                write!(f, "Band size must be a multiple of {row_bytes}, at most {max}. Got {actual}")
+            }
+            Self::IncompleteImage { expected_rows, received_rows } => {
+                write!(f, "Image is incomplete. Expecting {expected_rows} rows. Got {received_rows}")
+            }
+            Self::InvalidThreshold(v) => write!(f, "Threshold must be between 0 and 1. Got {v}"),
+            Self::InvalidAlpha(v) => write!(f, "Alpha must be between 0 and 1. Got {v}"),
+            Self::InvalidBlur(v) => write!(f, "Blur sigma must be a non-negative number. Got {v}"),
+            Self::InvalidColorCount(v) => write!(f, "Palette size must be between 2 and 256. Got {v}"),
+            Self::InvalidScale(v) => write!(f, "Downscale factor must be above 0 and at most 1. Got {v}"),
+            Self::InvalidPixelRatio(v) => write!(f, "Device pixel ratio must be a positive number. Got {v}"),
+            Self::ConflictingOptions(reason) => write!(f, "Conflicting options: {reason}"),
+            Self::InvalidAngle(v) => write!(f, "Angle must be between 0 and 180 degrees. Got {v}"),
+            Self::InvalidTolerance(v) => write!(f, "Tolerance must be a non-negative number. Got {v}"),
+            Self::RegionOutOfBounds { region: r, width, height } => write!(
+                f,
+                "Region {}x{} at {},{} extends past the {width}x{height} image",
+                r.width, r.height, r.x, r.y
+            ),
+            Self::InvalidJson(reason) => write!(f, "Invalid options JSON: {reason}"),
+            Self::InvalidEnv { name, reason } => write!(f, "Invalid environment variable {name}: {reason}"),
         }
     }
 }
@@ -79,73 +583,220 @@ impl std::fmt::Display for PixelmatchError {
 impl std::error::Error for PixelmatchError {}
 
 /// Result of a pixel comparison.
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
 pub struct MatchResult {
     /// Number of mismatched pixels.
     pub diff_count: u32,
+    /// Number of pixels compared, `width * height`.
+    pub total_pixels: u64,
     /// Number of anti-aliased pixels detected.
     pub aa_count: u32,
     /// Whether the two images are byte-identical.
     pub identical: bool,
+    /// Rows by which `Options::scroll_compensation` shifted the second image; positive when
+    /// its content sat lower. 0 when no offset was detected or compensation is off.
+    pub scroll_offset: i32,
+    /// Clusters of differing pixels `Options::label_shift` matched to moved labels and left
+    /// out of `diff_count`. 0 when none were found or the option is off.
+    pub moved_labels: u32,
+    /// Smallest box holding every pixel counted in `diff_count`, as inclusive
+    /// `(min_x, min_y, max_x, max_y)`; anti-aliased pixels are left out. `None` when no pixel
+    /// differs. Approximate with `Options::downscale`, like the counts.
+    pub diff_bounds: Option<(u32, u32, u32, u32)>,
+}
+
+impl MatchResult {
+    /// `diff_count / total_pixels`, for percentage-based limits; 0 for an empty image.
+    pub fn diff_ratio(&self) -> f64 {
+        if self.total_pixels == 0 {
+            0.0
+        } else {
+            self.diff_count as f64 / self.total_pixels as f64
+        }
+    }
+
+    fn with_total_pixels(self, total_pixels: usize) -> Self {
+        MatchResult { total_pixels: total_pixels as u64, ..self }
+    }
+}
+
+/// Counts and differing-pixel bounds of a range of rows, merged across rayon tasks.
+#[derive(Debug, Clone, Copy, Default)]
+struct Tally {
+    diff: u32,
+    aa: u32,
+    bounds: Option<(u32, u32, u32, u32)>,
+}
+
+impl Tally {
+    /// The tally of row `y` from the `(diff, aa, span)` a row processor returns, where `span`
+    /// is the first and last column of a counted pixel.
+    fn row(y: usize, (diff, aa, span): (u32, u32, Option<(u32, u32)>)) -> Self {
+        let y = y as u32;
+        Tally { diff, aa, bounds: span.map(|(x0, x1)| (x0, y, x1, y)) }
+    }
+
+    fn merge(self, other: Self) -> Self {
+        let bounds = match (self.bounds, other.bounds) {
+            (Some(a), Some(b)) => Some((a.0.min(b.0), a.1.min(b.1), a.2.max(b.2), a.3.max(b.3))),
+            (a, b) => a.or(b),
+        };
+        Tally { diff: self.diff + other.diff, aa: self.aa + other.aa, bounds }
+    }
+}
+
+/// Widen the column span of counted pixels in a row to include `x`.
+#[inline]
+fn extend_span(span: &mut Option<(u32, u32)>, x: usize) {
+    let x = x as u32;
+    *span = Some(span.map_or((x, x), |(x0, _)| (x0, x)));
 }
 
 /// Read a u32 from a byte slice without alignment requirements.
+///
+/// Caller must ensure `i + 3 < data.len()` (checked only with the `forbid-unsafe` feature).
 #[inline(always)]
 pub(crate) fn read_u32_ne(data: &[u8], i: usize) -> u32 {
-    // SAFETY: caller must ensure i + 3 < data.len()
-    unsafe {
-        u32::from_ne_bytes([
-            *data.get_unchecked(i),
-            *data.get_unchecked(i + 1),
-            *data.get_unchecked(i + 2),
-            *data.get_unchecked(i + 3),
-        ])
-    }
+    u32::from_ne_bytes(read_rgba(data, i))
 }
 
-/// Process a single row, returning (diff_count, aa_count) (no output).
+/// Process `columns` of a single row, returning (diff_count, aa_count, span of counted
+/// columns) (no output).
 #[inline]
-fn process_row_no_output(
-    img1: &[u8],
-    img2: &[u8],
+#[allow(clippy::too_many_arguments)]
+fn process_row_no_output<T: Component>(
+    img1: &[T],
+    img2: &[T],
+    ignore_row: Option<&[bool]>,
+    refined_row: Option<&[bool]>,
     y: usize,
+    columns: Range<usize>,
     w: usize,
     h: usize,
     max_delta: f64,
-    detect_anti_aliasing: bool,
-) -> (u32, u32) {
+    options: &Options,
+) -> (u32, u32, Option<(u32, u32)>) {
+    let aa_step = options.aa_step();
     let mut diff: u32 = 0;
     let mut aa: u32 = 0;
-    for x in 0..w {
+    let mut span = None;
+    for x in columns {
+        if ignore_row.is_some_and(|m| m[x]) {
+            continue;
+        }
         let pos = (y * w + x) * 4;
 
-        let delta = if read_u32_ne(img1, pos) == read_u32_ne(img2, pos) {
+        let delta = if T::same_pixel(img1, pos, img2, pos) {
             0.0
         } else {
             color_delta(img1, img2, pos, pos, false)
         };
 
-        if delta.abs() > max_delta {
-            if detect_anti_aliasing
-                && (antialiased(img1, x, y, w, h, img1, img2)
-                    || antialiased(img2, x, y, w, h, img2, img1))
+        // Pixels set in the refined mask count even where dilation added them.
+        let forced = refined_row.is_some_and(|r| r[x]);
+        if delta.abs() > max_delta || forced {
+            if !forced
+                && options.detect_anti_aliasing
+                && (antialiased(img1, x, y, w, h, aa_step, img1, img2)
+                    || antialiased(img2, x, y, w, h, aa_step, img2, img1))
             {
                 aa += 1;
-            } else {
+            } else if counted(img1, img2, refined_row, x, y, w, h, max_delta, options) {
                 diff += 1;
+                extend_span(&mut span, x);
             }
         }
     }
-    (diff, aa)
+    (diff, aa, span)
 }
 
-/// Process a single row with output writing, returning (diff_count, aa_count).
+/// Whether a differing, non-anti-aliased pixel counts: it is set in the refined mask when
+/// there is one, and otherwise not [`explained`].
+#[allow(clippy::too_many_arguments)]
 #[inline]
-fn process_row_with_output(
-    img1: &[u8],
-    img2: &[u8],
+fn counted<T: Component>(
+    img1: &[T],
+    img2: &[T],
+    refined_row: Option<&[bool]>,
+    x: usize,
+    y: usize,
+    w: usize,
+    h: usize,
+    max_delta: f64,
+    options: &Options,
+) -> bool {
+    let unexplained = || match (T::bytes(img1), T::bytes(img2)) {
+        (Some(a), Some(b)) => !explained(a, b, x, y, w, h, max_delta, options),
+        // The tolerances work on bytes; `wide::pixelmatch_wide` rejects them for wider components.
+        _ => true,
+    };
+    refined_row.map_or_else(unexplained, |r| r[x])
+}
+
+/// Whether a differing, non-anti-aliased pixel is absorbed by `shift_tolerance`,
+/// `subpixel_tolerance` or `jpeg_tolerance`.
+#[allow(clippy::too_many_arguments)]
+#[inline]
+fn explained(img1: &[u8], img2: &[u8], x: usize, y: usize, w: usize, h: usize, max_delta: f64, o: &Options) -> bool {
+    (o.shift_tolerance > 0 && shifted(img1, img2, x, y, w, h, o.shift_tolerance as usize, max_delta))
+        || (o.subpixel_tolerance && subpixel_shifted(img1, img2, x, y, w, h, max_delta))
+        || o.jpeg_tolerance.is_some_and(|t| jpeg::artifact(img1, img2, x, y, w, h, 35215.0 * t * t))
+}
+
+/// Whether the pixel at (x, y) of either image matches the other image sampled bilinearly
+/// at an offset of up to half a pixel in each direction.
+fn subpixel_shifted(img1: &[u8], img2: &[u8], x: usize, y: usize, w: usize, h: usize, max_delta: f64) -> bool {
+    const OFFSETS: [f64; 3] = [-0.5, 0.0, 0.5];
+    let pos = (y * w + x) * 4;
+    let matches = |a: &[u8], b: &[u8]| {
+        OFFSETS.iter().flat_map(|&dy| OFFSETS.map(|dx| (dx, dy))).filter(|&o| o != (0.0, 0.0)).any(|(dx, dy)| {
+            let sample = bilinear(b, w, h, x as f64 + dx, y as f64 + dy);
+            color_delta(a, &sample, pos, 0, false).abs() <= max_delta
+        })
+    };
+    matches(img1, img2) || matches(img2, img1)
+}
+
+/// Sample `img` at the fractional pixel position (fx, fy), clamping at the edges.
+fn bilinear(img: &[u8], w: usize, h: usize, fx: f64, fy: f64) -> [u8; 4] {
+    let (fx, fy) = (fx.clamp(0.0, (w - 1) as f64), fy.clamp(0.0, (h - 1) as f64));
+    let (x0, y0) = (fx.floor() as usize, fy.floor() as usize);
+    let (x1, y1) = ((x0 + 1).min(w - 1), (y0 + 1).min(h - 1));
+    let (tx, ty) = (fx - x0 as f64, fy - y0 as f64);
+    let px = |x: usize, y: usize, c: usize| img[(y * w + x) * 4 + c] as f64;
+    std::array::from_fn(|c| {
+        let top = px(x0, y0, c) * (1.0 - tx) + px(x1, y0, c) * tx;
+        let bottom = px(x0, y1, c) * (1.0 - tx) + px(x1, y1, c) * tx;
+        (top * (1.0 - ty) + bottom * ty).round() as u8
+    })
+}
+
+/// Whether the differing pixel at (x, y) is explained by content shifted by at most `r`
+/// pixels: each image's pixel matches some pixel within `r` in the other image.
+#[allow(clippy::too_many_arguments)]
+fn shifted(img1: &[u8], img2: &[u8], x: usize, y: usize, w: usize, h: usize, r: usize, max_delta: f64) -> bool {
+    let pos = (y * w + x) * 4;
+    let near = |a: &[u8], b: &[u8]| {
+        (y.saturating_sub(r)..(y + r + 1).min(h)).any(|ny| {
+            (x.saturating_sub(r)..(x + r + 1).min(w))
+                .any(|nx| color_delta(a, b, pos, (ny * w + nx) * 4, false).abs() <= max_delta)
+        })
+    };
+    near(img1, img2) && near(img2, img1)
+}
+
+/// Process `columns` of a single row, drawing them into `out_row` and returning
+/// (diff_count, aa_count, span of counted columns).
+#[inline]
+#[allow(clippy::too_many_arguments)]
+fn process_row_with_output<T: Component>(
+    img1: &[T],
+    img2: &[T],
     out_row: &mut [u8],
+    ignore_row: Option<&[bool]>,
+    refined_row: Option<&[bool]>,
     y: usize,
+    columns: Range<usize>,
     w: usize,
     h: usize,
     max_delta: f64,
@@ -153,29 +804,45 @@ fn process_row_with_output(
     aa_r: u8, aa_g: u8, aa_b: u8,
     diff_r: u8, diff_g: u8, diff_b: u8,
     alt_r: u8, alt_g: u8, alt_b: u8,
-) -> (u32, u32) {
+) -> (u32, u32, Option<(u32, u32)>) {
+    let aa_step = options.aa_step();
     let mut diff: u32 = 0;
     let mut aa: u32 = 0;
-    for x in 0..w {
+    let mut span = None;
+    let first = columns.start;
+    for x in columns {
         let pos = (y * w + x) * 4;
-        let lpos = x * 4;
+        let lpos = (x - first) * 4;
 
-        let delta = if read_u32_ne(img1, pos) == read_u32_ne(img2, pos) {
+        if ignore_row.is_some_and(|m| m[x]) {
+            if !options.diff_mask {
+                draw_gray_pixel_local(img1, pos, options.alpha, out_row, lpos);
+            }
+            continue;
+        }
+
+        let delta = if T::same_pixel(img1, pos, img2, pos) {
             0.0
         } else {
             color_delta(img1, img2, pos, pos, false)
         };
 
-        if delta.abs() > max_delta {
-            let is_aa = options.detect_anti_aliasing
-                && (antialiased(img1, x, y, w, h, img1, img2)
-                    || antialiased(img2, x, y, w, h, img2, img1));
+        let forced = refined_row.is_some_and(|r| r[x]);
+        if delta.abs() > max_delta || forced {
+            let is_aa = !forced
+                && options.detect_anti_aliasing
+                && (antialiased(img1, x, y, w, h, aa_step, img1, img2)
+                    || antialiased(img2, x, y, w, h, aa_step, img2, img1));
 
             if is_aa {
                 aa += 1;
                 if !options.diff_mask {
                     draw_pixel(out_row, lpos, aa_r, aa_g, aa_b);
                 }
+            } else if !counted(img1, img2, refined_row, x, y, w, h, max_delta, options) {
+                if !options.diff_mask {
+                    draw_gray_pixel_local(img1, pos, options.alpha, out_row, lpos);
+                }
             } else {
                 if delta < 0.0 {
                     draw_pixel(out_row, lpos, alt_r, alt_g, alt_b);
@@ -183,26 +850,173 @@ fn process_row_with_output(
                     draw_pixel(out_row, lpos, diff_r, diff_g, diff_b);
                 }
                 diff += 1;
+                extend_span(&mut span, x);
             }
         } else if !options.diff_mask {
             draw_gray_pixel_local(img1, pos, options.alpha, out_row, lpos);
         }
     }
-    (diff, aa)
+    (diff, aa, span)
 }
 
-/// Compare two equally sized images, pixel by pixel.
-///
-/// Returns a `MatchResult` containing the diff count, anti-aliased pixel count,
-/// and whether the images are byte-identical.
-pub fn pixelmatch(
-    img1: &[u8],
-    img2: &[u8],
-    output: Option<&mut [u8]>,
+/// Filtered working copies of both images, if `options.quantize`, `options.median_filter`,
+/// `options.pre_blur` or `options.edges` asks for them.
+fn prefilter(img1: &[u8], img2: &[u8], width: u32, height: u32, options: &Options) -> Option<(Vec<u8>, Vec<u8>)> {
+    let sigma = options.pre_blur.filter(|&s| s > 0.0);
+    if sigma.is_none() && options.quantize.is_none() && !options.median_filter && options.edges.is_none() {
+        return None;
+    }
+    let _span = trace_span!(
+        "prefilter",
+        quantize = ?options.quantize,
+        median = options.median_filter,
+        blur = ?sigma,
+        edges = ?options.edges
+    );
+    let quantized = options.quantize.map(|colors| quantize::quantize_pair(img1, img2, colors));
+    let (img1, img2) = quantized.as_ref().map_or((img1, img2), |(a, b)| (a, b));
+    if sigma.is_none() && !options.median_filter && options.edges.is_none() {
+        return quantized;
+    }
+    let filter = |img: &[u8]| {
+        let median = options.median_filter.then(|| blur::median_3x3(img, width, height));
+        let blurred = match sigma {
+            Some(sigma) => blur::gaussian_blur(median.as_deref().unwrap_or(img), width, height, sigma),
+            None => median.unwrap_or_else(|| img.to_vec()),
+        };
+        match options.edges {
+            Some(detector) => edges::edge_image(&blurred, width, height, detector),
+            None => blurred,
+        }
+    };
+    Some(rayon::join(|| filter(img1), || filter(img2)))
+}
+
+/// Both images, reduced to `options.downscale` of their size, with the options to compare them by.
+struct Downscaled {
+    img1: Vec<u8>,
+    img2: Vec<u8>,
+    width: u32,
+    height: u32,
+    options: Options,
+}
+
+impl Downscaled {
+    /// Box-filter both images and sample `ignore` at the reduced pixel centres, or `None` if
+    /// no reduction was asked for or the size would not change.
+    fn new(
+        img1: &[u8],
+        img2: &[u8],
+        width: u32,
+        height: u32,
+        options: &Options,
+        ignore: Option<&[bool]>,
+    ) -> Option<Self> {
+        let scale = options.downscale?;
+        let (w, h) = (width as usize, height as usize);
+        let sw = ((w as f32 * scale).round() as usize).clamp(1, w.max(1));
+        let sh = ((h as f32 * scale).round() as usize).clamp(1, h.max(1));
+        if (sw, sh) == (w, h) {
+            return None;
+        }
+        let ignore_mask = ignore.map(|ignore| {
+            (0..sw * sh)
+                .map(|i| {
+                    let x = (2 * (i % sw) + 1) * w / (2 * sw);
+                    let y = (2 * (i / sw) + 1) * h / (2 * sh);
+                    if ignore[y * w + x] { 255 } else { 0 }
+                })
+                .collect()
+        });
+        let (width, height) = (sw as u32, sh as u32);
+        let (img1, img2) = rayon::join(
+            || resize::resize_box(img1, w as u32, h as u32, width, height),
+            || resize::resize_box(img2, w as u32, h as u32, width, height),
+        );
+        let options = Options {
+            shift_tolerance: (options.shift_tolerance as f32 * scale).ceil() as u32,
+            isolation_radius: (options.isolation_radius as f32 * scale).ceil() as u32,
+            morphology: options.morphology.iter().map(|op| op.scaled(scale)).collect(),
+            label_shift: (options.label_shift as f32 * scale).ceil() as u32,
+            pre_blur: options.pre_blur.map(|sigma| sigma * scale),
+            downscale: None,
+            device_pixel_ratio: None,
+            jpeg_tolerance: None,
+            scroll_compensation: 0,
+            ignore_regions: Vec::new(),
+            ignore_polygons: Vec::new(),
+            ignore_mask,
+            include_only: false,
+            ignore_hatch: None,
+            ..options.clone()
+        };
+        Some(Self { img1, img2, width, height, options })
+    }
+
+    /// Convert a count of reduced pixels to an estimate in full-size pixels.
+    fn scale_count(&self, count: u32, len: usize) -> u32 {
+        (count as f64 * len as f64 / (self.width as f64 * self.height as f64)).round() as u32
+    }
+
+    /// Convert bounds in reduced pixels to the full-size pixels they cover in a `width` ×
+    /// `height` image.
+    fn scale_bounds(&self, bounds: (u32, u32, u32, u32), width: u32, height: u32) -> (u32, u32, u32, u32) {
+        let (x0, y0, x1, y1) = bounds;
+        let start = |v: u32, from: u32, to: u32| (v as u64 * to as u64 / from as u64) as u32;
+        let end = |v: u32, from: u32, to: u32| {
+            ((v as u64 + 1) * to as u64).div_ceil(from as u64).min(to as u64) as u32 - 1
+        };
+        let (sw, sh) = (self.width, self.height);
+        (start(x0, sw, width), start(y0, sh, height), end(x1, sw, width), end(y1, sh, height))
+    }
+}
+
+/// Box-filter whichever image has the higher [`Options::device_pixel_ratio`] down to
+/// `width` × `height`; the other, or both if the ratios match, is passed through.
+pub(crate) fn normalize_dpr<'a>(
+    img1: &'a [u8],
+    img2: &'a [u8],
     width: u32,
     height: u32,
     options: &Options,
-) -> Result<MatchResult, PixelmatchError> {
+) -> Result<[Cow<'a, [u8]>; 2], PixelmatchError> {
+    let Some([dpr1, dpr2]) = options.device_pixel_ratio.filter(|[a, b]| a != b) else {
+        return Ok([Cow::Borrowed(img1), Cow::Borrowed(img2)]);
+    };
+    let low = dpr1.min(dpr2);
+    let normalize = |img: &'a [u8], dpr: f32| {
+        if dpr == low {
+            return Ok(Cow::Borrowed(img));
+        }
+        let (w, h) = resize::dpr_size(width, height, low, dpr);
+        let expected = (w as usize)
+            .checked_mul(h as usize)
+            .and_then(|len| len.checked_mul(4))
+            .ok_or(PixelmatchError::DimensionOverflow)?;
+        if img.len() != expected {
+            return Err(PixelmatchError::BufferLengthMismatch { expected, actual: img.len() });
+        }
+        Ok(Cow::Owned(resize::resize_box(img, w, h, width, height)))
+    };
+    Ok([normalize(img1, dpr1)?, normalize(img2, dpr2)?])
+}
+
+/// Pass a validation error through, recording it when tracing.
+fn rejected(e: PixelmatchError) -> PixelmatchError {
+    trace_event!(error = %e, "comparison rejected");
+    e
+}
+
+/// Validate image and output buffer sizes against the given dimensions.
+///
+/// Returns the number of pixels (`width * height`).
+pub(crate) fn validate_buffers<T>(
+    img1: &[T],
+    img2: &[T],
+    output_len: Option<usize>,
+    width: u32,
+    height: u32,
+) -> Result<usize, PixelmatchError> {
     let len = (width as usize)
         .checked_mul(height as usize)
         .ok_or(PixelmatchError::DimensionOverflow)?;
@@ -217,11 +1031,11 @@ pub fn pixelmatch(
         });
     }
 
-    if let Some(ref out) = output {
-        if out.len() != img1.len() {
+    if let Some(output_len) = output_len {
+        if output_len != img1.len() {
             return Err(PixelmatchError::OutputSizeMismatch {
                 img1_len: img1.len(),
-                output_len: out.len(),
+                output_len,
             });
         }
     }
@@ -233,70 +1047,354 @@ pub fn pixelmatch(
         });
     }
 
+    Ok(len)
+}
+
+/// Compare two equally sized images, pixel by pixel.
+///
+/// Returns a `MatchResult` containing the diff count, anti-aliased pixel count,
+/// and whether the images are byte-identical. Reports to the [`metrics`] sink, if one is
+/// installed.
+pub fn pixelmatch(
+    img1: &[u8],
+    img2: &[u8],
+    output: Option<&mut [u8]>,
+    width: u32,
+    height: u32,
+    options: &Options,
+) -> Result<MatchResult, PixelmatchError> {
+    let Some(sink) = metrics::sink() else {
+        return compare(img1, img2, output, width, height, options);
+    };
+    let start = std::time::Instant::now();
+    let result = compare(img1, img2, output, width, height, options);
+    sink.on_comparison_complete(&metrics::ComparisonMetrics::new(width, height, start.elapsed(), &result));
+    result
+}
+
+/// [`pixelmatch`] without metrics reporting.
+fn compare(
+    img1: &[u8],
+    img2: &[u8],
+    output: Option<&mut [u8]>,
+    width: u32,
+    height: u32,
+    options: &Options,
+) -> Result<MatchResult, PixelmatchError> {
+    let _span = trace_span!("pixelmatch", width, height);
+    options.validate().map_err(rejected)?;
+    let scaled = options.scaled_for_density();
+    let options = &*scaled;
+    let [img1, img2] = normalize_dpr(img1, img2, width, height, options).map_err(rejected)?;
+    let (img1, img2) = (&*img1, &*img2);
+    let len = validate_buffers(img1, img2, output.as_deref().map(<[u8]>::len), width, height).map_err(rejected)?;
+
     let w = width as usize;
     let h = height as usize;
+    let ignore = ignore_mask(options, w, h).map_err(rejected)?;
 
     // Check if images are identical (memcmp — auto-vectorised by LLVM)
     if img1 == img2 {
+        trace_event!("images are identical");
         if let Some(out) = output {
-            if !options.diff_mask {
-                for i in 0..len {
-                    draw_gray_pixel(img1, i * 4, options.alpha, out);
+            draw_identical(img1, out, w, ignore.as_deref(), options);
+        }
+        return Ok(MatchResult { identical: true, ..Default::default() }.with_total_pixels(len));
+    }
+    let scroll_offset = scroll::detect_offset(img1, img2, width, height, options.scroll_compensation);
+    let scrolled = (scroll_offset != 0).then(|| scroll::shift_rows(img2, width, height, scroll_offset));
+    let img2 = scrolled.as_deref().unwrap_or(img2);
+    if scroll_offset != 0 {
+        trace_event!(scroll_offset, "compensating for scroll");
+    }
+    if let Some(small) = Downscaled::new(img1, img2, width, height, options, ignore.as_deref()) {
+        trace_event!(width = small.width, height = small.height, "comparing downscaled copies");
+        let mut small_out = output.as_ref().map(|_| vec![0u8; small.img1.len()]);
+        let result =
+            compare(&small.img1, &small.img2, small_out.as_deref_mut(), small.width, small.height, &small.options)?;
+        if let (Some(out), Some(small_out)) = (output, small_out) {
+            out.copy_from_slice(&resize::resize_box(&small_out, small.width, small.height, width, height));
+            if let Some(ignore) = &ignore {
+                for (y, out_row) in out.chunks_mut(w * 4).enumerate() {
+                    draw_ignore_hatch(out_row, Some(&ignore[y * w..(y + 1) * w]), y, options.ignore_hatch);
                 }
             }
         }
-        return Ok(MatchResult { diff_count: 0, aa_count: 0, identical: true });
+        return Ok(MatchResult {
+            diff_count: small.scale_count(result.diff_count, len),
+            aa_count: small.scale_count(result.aa_count, len),
+            identical: false,
+            scroll_offset,
+            moved_labels: result.moved_labels,
+            diff_bounds: result.diff_bounds.map(|b| small.scale_bounds(b, width, height)),
+            ..Default::default()
+        }
+        .with_total_pixels(len));
+    }
+    let filtered = prefilter(img1, img2, width, height, options);
+    let (img1, img2) = filtered.as_ref().map_or((img1, img2), |(a, b)| (a, b));
+
+    let max_delta = 35215.0 * options.threshold * options.threshold;
+    let mut moved_labels = 0;
+    let refined = refines_mask(options).then(|| {
+        let _span = trace_span!("refine_mask");
+        let mut mask = raw_diff_mask(img1, img2, w, h, max_delta, options, ignore.as_deref());
+        moved_labels = refine_mask(&mut mask, img1, img2, width, height, options, ignore.as_deref());
+        mask
+    });
+    let area = Rect::new(0, 0, width, height);
+    let tally = compare_rows(img1, img2, output, w, h, area, ignore.as_deref(), refined.as_deref(), options);
+    let (diff_count, aa_count) = (tally.diff, tally.aa);
+    trace_event!(diff_count, aa_count, moved_labels, "comparison complete");
+    let result = MatchResult {
+        diff_count,
+        aa_count,
+        scroll_offset,
+        moved_labels,
+        diff_bounds: tally.bounds,
+        ..Default::default()
+    };
+    Ok(result.with_total_pixels(len))
+}
+
+/// Draw the output of a comparison of identical images: the faded first image (nothing with
+/// `diff_mask`) and the ignore hatching.
+fn draw_identical<T: Component>(img1: &[T], out: &mut [u8], w: usize, ignore: Option<&[bool]>, options: &Options) {
+    if !options.diff_mask {
+        for i in 0..out.len() / 4 {
+            draw_gray_pixel(img1, i * 4, options.alpha, out);
+        }
     }
+    if let Some(ignore) = ignore {
+        for (y, out_row) in out.chunks_mut(w * 4).enumerate() {
+            draw_ignore_hatch(out_row, Some(&ignore[y * w..(y + 1) * w]), y, options.ignore_hatch);
+        }
+    }
+}
 
+/// Count the differing and anti-aliased pixels of `area` of two validated images in
+/// parallel rows, drawing the diff into `output` (the size of `area`) if given. Pixels set
+/// in `refined` count regardless of their delta.
+#[allow(clippy::too_many_arguments)]
+fn compare_rows<T: Component>(
+    img1: &[T],
+    img2: &[T],
+    output: Option<&mut [u8]>,
+    w: usize,
+    h: usize,
+    area: Rect,
+    ignore: Option<&[bool]>,
+    refined: Option<&[bool]>,
+    options: &Options,
+) -> Tally {
     let max_delta = 35215.0 * options.threshold * options.threshold;
     let [aa_r, aa_g, aa_b] = options.aa_color;
     let [diff_r, diff_g, diff_b] = options.diff_color;
     let [alt_r, alt_g, alt_b] = options.diff_color_alt.unwrap_or(options.diff_color);
-
-    let (diff_count, aa_count) = match output {
-        Some(out) => {
-            let row_bytes = w * 4;
-            out
-                .par_chunks_mut(row_bytes)
-                .with_min_len(4)
-                .enumerate()
-                .map(|(y, out_row)| {
-                    process_row_with_output(
-                        img1, img2, out_row, y, w, h, max_delta, options,
-                        aa_r, aa_g, aa_b, diff_r, diff_g, diff_b, alt_r, alt_g, alt_b,
-                    )
-                })
-                .reduce(|| (0, 0), |(d1, a1), (d2, a2)| (d1 + d2, a1 + a2))
-        }
-        None => {
-            (0..h)
-                .into_par_iter()
-                .with_min_len(4)
-                .map(|y| {
-                    process_row_no_output(img1, img2, y, w, h, max_delta, options.detect_anti_aliasing)
-                })
-                .reduce(|| (0, 0), |(d1, a1), (d2, a2)| (d1 + d2, a1 + a2))
+    let ignore_row = |y: usize| ignore.map(|m| &m[y * w..(y + 1) * w]);
+    let refined_row = |y: usize| refined.map(|m| &m[y * w..(y + 1) * w]);
+    let columns = area.x as usize..(area.x + area.width) as usize;
+    let rows = area.y as usize..(area.y + area.height) as usize;
+    {
+        let _span = trace_span!(
+            "compare_rows",
+            rows = rows.len(),
+            min_rows_per_task = 4,
+            threads = rayon::current_num_threads()
+        );
+        match output {
+            Some(out) => {
+                let row_bytes = columns.len() * 4;
+                out
+                    .par_chunks_mut(row_bytes.max(1))
+                    .with_min_len(4)
+                    .zip(rows)
+                    .map(|(out_row, y)| {
+                        let counts = Tally::row(y, process_row_with_output(
+                            img1, img2, out_row, ignore_row(y), refined_row(y), y, columns.clone(), w, h,
+                            max_delta, options, aa_r, aa_g, aa_b, diff_r, diff_g, diff_b, alt_r, alt_g, alt_b,
+                        ));
+                        let area_ignore = ignore_row(y).map(|r| &r[columns.clone()]);
+                        draw_ignore_hatch(out_row, area_ignore, y, options.ignore_hatch);
+                        counts
+                    })
+                    .reduce(Tally::default, Tally::merge)
+            }
+            None => {
+                rows
+                    .into_par_iter()
+                    .with_min_len(4)
+                    .map(|y| {
+                        Tally::row(y, process_row_no_output(
+                            img1, img2, ignore_row(y), refined_row(y), y, columns.clone(), w, h, max_delta, options,
+                        ))
+                    })
+                    .reduce(Tally::default, Tally::merge)
+            }
         }
-    };
+    }
+}
+
+/// Compute a per-pixel mask of mismatched pixels (anti-aliased pixels excluded).
+///
+/// The mask has one entry per pixel in row-major order; `true` marks a pixel that
+/// would be counted in `MatchResult::diff_count`.
+pub fn diff_mask(
+    img1: &[u8],
+    img2: &[u8],
+    width: u32,
+    height: u32,
+    options: &Options,
+) -> Result<Vec<bool>, PixelmatchError> {
+    let _span = trace_span!("diff_mask", width, height);
+    options.validate().map_err(rejected)?;
+    let scaled = options.scaled_for_density();
+    let options = &*scaled;
+    let [img1, img2] = normalize_dpr(img1, img2, width, height, options).map_err(rejected)?;
+    let (img1, img2) = (&*img1, &*img2);
+    let len = validate_buffers(img1, img2, None, width, height).map_err(rejected)?;
+    let w = width as usize;
+    let h = height as usize;
+    let ignore = ignore_mask(options, w, h).map_err(rejected)?;
+    let mut mask = vec![false; len];
+    if img1 == img2 || len == 0 {
+        return Ok(mask);
+    }
+    let scroll_offset = scroll::detect_offset(img1, img2, width, height, options.scroll_compensation);
+    let scrolled = (scroll_offset != 0).then(|| scroll::shift_rows(img2, width, height, scroll_offset));
+    let img2 = scrolled.as_deref().unwrap_or(img2);
+    if let Some(small) = Downscaled::new(img1, img2, width, height, options, ignore.as_deref()) {
+        let small_mask = diff_mask(&small.img1, &small.img2, small.width, small.height, &small.options)?;
+        let (sw, sh) = (small.width as usize, small.height as usize);
+        mask.par_chunks_mut(w).enumerate().for_each(|(y, mask_row)| {
+            let small_row = &small_mask[y * sh / h * sw..][..sw];
+            mask_row.iter_mut().enumerate().for_each(|(x, m)| *m = small_row[x * sw / w]);
+        });
+        return Ok(mask);
+    }
+    let filtered = prefilter(img1, img2, width, height, options);
+    let (img1, img2) = filtered.as_ref().map_or((img1, img2), |(a, b)| (a, b));
+
+    let max_delta = 35215.0 * options.threshold * options.threshold;
+    let mut mask = raw_diff_mask(img1, img2, w, h, max_delta, options, ignore.as_deref());
+    refine_mask(&mut mask, img1, img2, width, height, options, ignore.as_deref());
+    Ok(mask)
+}
+
+/// The `(x, y)` coordinates of the pixels counted in `MatchResult::diff_count`, in
+/// row-major order, for overlaying markers. This builds [`diff_mask`] and then scans it for
+/// the set pixels, so call `diff_mask` directly when the mask itself is also needed.
+pub fn diff_pixels(
+    img1: &[u8],
+    img2: &[u8],
+    width: u32,
+    height: u32,
+    options: &Options,
+) -> Result<Vec<(u32, u32)>, PixelmatchError> {
+    let mask = diff_mask(img1, img2, width, height, options)?;
+    let w = (width as usize).max(1);
+    Ok(mask
+        .par_chunks(w)
+        .enumerate()
+        .flat_map_iter(|(y, row)| {
+            row.iter().enumerate().filter(|&(_, &m)| m).map(move |(x, _)| (x as u32, y as u32))
+        })
+        .collect())
+}
+
+/// How similar two images are, from 0.0 (as different as black and white everywhere) to
+/// 1.0 (identical).
+///
+/// Unlike the binary diff count, every pixel contributes in proportion to how far apart its
+/// colours are: the score is one minus the mean over compared pixels of the smallest
+/// `threshold` at which the pixel would match. Suited to ranking candidates, e.g. picking
+/// the closest of several baselines. `threshold` and anti-aliasing detection do not
+/// affect it; ignored pixels and `device_pixel_ratio` are honoured.
+pub fn similarity(
+    img1: &[u8],
+    img2: &[u8],
+    width: u32,
+    height: u32,
+    options: &Options,
+) -> Result<f64, PixelmatchError> {
+    options.validate()?;
+    let [img1, img2] = normalize_dpr(img1, img2, width, height, options)?;
+    let (img1, img2) = (&*img1, &*img2);
+    let len = validate_buffers(img1, img2, None, width, height)?;
+    let ignore = ignore_mask(options, width as usize, height as usize)?;
+    let (total, compared) = (0..len)
+        .into_par_iter()
+        .with_min_len(1024)
+        .filter(|&i| !ignore.as_ref().is_some_and(|m| m[i]))
+        .map(|i| ((color_delta(img1, img2, i * 4, i * 4, false).abs() / 35215.0).sqrt().min(1.0), 1usize))
+        .reduce(|| (0.0, 0), |(t1, c1), (t2, c2)| (t1 + t2, c1 + c2));
+    Ok(if compared == 0 { 1.0 } else { 1.0 - total / compared as f64 })
+}
 
-    Ok(MatchResult { diff_count, aa_count, identical: false })
+/// Whether `options` ask for the diff mask to be post-processed by [`refine_mask`].
+fn refines_mask(options: &Options) -> bool {
+    options.isolation_radius > 0 || !options.morphology.is_empty() || options.label_shift > 0
+}
+
+/// Apply the mask post-processing steps from `options`, keeping ignored pixels clear, and
+/// return the number of moved label clusters `label_shift` cleared.
+fn refine_mask(
+    mask: &mut [bool],
+    img1: &[u8],
+    img2: &[u8],
+    width: u32,
+    height: u32,
+    options: &Options,
+    ignore: Option<&[bool]>,
+) -> u32 {
+    morph::remove_isolated(mask, width, height, options.isolation_radius);
+    morph::apply(mask, width, height, &options.morphology);
+    if let Some(ignore) = ignore {
+        mask.iter_mut().zip(ignore).for_each(|(m, &ignored)| *m &= !ignored);
+    }
+    if options.label_shift == 0 {
+        return 0;
+    }
+    labels::remove_moved_labels(img1, img2, width, height, mask, options.label_shift, options)
+}
+
+/// Per-pixel mask of differing pixels that are neither anti-aliased nor [`explained`].
+fn raw_diff_mask(
+    img1: &[u8],
+    img2: &[u8],
+    w: usize,
+    h: usize,
+    max_delta: f64,
+    options: &Options,
+    ignore: Option<&[bool]>,
+) -> Vec<bool> {
+    let aa_step = options.aa_step();
+    let mut mask = vec![false; w * h];
+    mask.par_chunks_mut(w).with_min_len(4).enumerate().for_each(|(y, mask_row)| {
+        for (x, m) in mask_row.iter_mut().enumerate() {
+            let pos = (y * w + x) * 4;
+            let ignored = ignore.is_some_and(|i| i[y * w + x]);
+            if ignored || read_u32_ne(img1, pos) == read_u32_ne(img2, pos) {
+                continue;
+            }
+            if color_delta(img1, img2, pos, pos, false).abs() > max_delta {
+                *m = !(options.detect_anti_aliasing
+                    && (antialiased(img1, x, y, w, h, aa_step, img1, img2)
+                        || antialiased(img2, x, y, w, h, aa_step, img2, img1)))
+                    && !explained(img1, img2, x, y, w, h, max_delta, options);
+            }
+        }
+    });
+    mask
 }
 
 /// Draw a grayscale pixel into a row-local output slice.
 /// Reads from `img` at global `src_pos`, writes to `out` at local `dst_pos`.
 #[inline(always)]
-fn draw_gray_pixel_local(img: &[u8], src_pos: usize, alpha: f64, out: &mut [u8], dst_pos: usize) {
-    unsafe {
-        let r = *img.get_unchecked(src_pos) as f64;
-        let g = *img.get_unchecked(src_pos + 1) as f64;
-        let b = *img.get_unchecked(src_pos + 2) as f64;
-        let a = *img.get_unchecked(src_pos + 3) as f64;
-        let val = (255.0 + (r * 0.29889531 + g * 0.58662247 + b * 0.11448223 - 255.0) * alpha * a / 255.0) as u8;
-        *out.get_unchecked_mut(dst_pos) = val;
-        *out.get_unchecked_mut(dst_pos + 1) = val;
-        *out.get_unchecked_mut(dst_pos + 2) = val;
-        *out.get_unchecked_mut(dst_pos + 3) = 255;
-    }
+fn draw_gray_pixel_local<T: Component>(img: &[T], src_pos: usize, alpha: f64, out: &mut [u8], dst_pos: usize) {
+    let (r, g, b, a) = rgba_at(img, src_pos);
+    let val = (255.0 + (r * 0.29889531 + g * 0.58662247 + b * 0.11448223 - 255.0) * alpha * a / 255.0) as u8;
+    write_rgba(out, dst_pos, [val, val, val, 255]);
 }
 
 #[cfg(feature = "napi")]
@@ -304,3 +1402,15 @@ mod napi_bindings;
 
 #[cfg(feature = "wasm")]
 mod wasm_bindings;
+
+#[cfg(feature = "pyo3")]
+mod python_bindings;
+
+#[cfg(feature = "uniffi")]
+uniffi::setup_scaffolding!();
+
+#[cfg(feature = "uniffi")]
+mod uniffi_bindings;
+
+#[cfg(feature = "ruby")]
+mod ruby_bindings;
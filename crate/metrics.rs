@@ -0,0 +1,70 @@
+//! Hooks for exporting comparison metrics without depending on a metrics library.
+//!
+//! Install a [`MetricsSink`] with [`set_sink`] and every call to
+//! [`pixelmatch`](crate::pixelmatch) reports a [`ComparisonMetrics`] to it once done, e.g. to
+//! feed Prometheus counters and histograms. With no sink installed nothing is measured.
+//! Timing uses [`std::time::Instant`], which is unavailable on `wasm32-unknown-unknown`, so
+//! do not install a sink there.
+
+use std::sync::{Arc, PoisonError, RwLock};
+use std::time::Duration;
+
+use crate::{MatchResult, PixelmatchError};
+
+static SINK: RwLock<Option<Arc<dyn MetricsSink>>> = RwLock::new(None);
+
+/// Why a comparison finished without comparing pixel by pixel.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum EarlyExit {
+    /// The images were byte-identical.
+    Identical,
+    /// The arguments or options were rejected with an error.
+    Rejected,
+}
+
+/// What one comparison did.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ComparisonMetrics {
+    pub width: u32,
+    pub height: u32,
+    /// Wall-clock time spent in the call.
+    pub duration: Duration,
+    /// Differing pixels; 0 when the comparison was rejected.
+    pub diff_count: u32,
+    /// Anti-aliased pixels; 0 when the comparison was rejected.
+    pub aa_count: u32,
+    /// Why the comparison stopped early, if it did.
+    pub early_exit: Option<EarlyExit>,
+}
+
+impl ComparisonMetrics {
+    pub(crate) fn new(
+        width: u32,
+        height: u32,
+        duration: Duration,
+        result: &Result<MatchResult, PixelmatchError>,
+    ) -> Self {
+        let (diff_count, aa_count, early_exit) = match result {
+            Ok(r) => (r.diff_count, r.aa_count, r.identical.then_some(EarlyExit::Identical)),
+            Err(_) => (0, 0, Some(EarlyExit::Rejected)),
+        };
+        Self { width, height, duration, diff_count, aa_count, early_exit }
+    }
+}
+
+/// Receives the metrics of each comparison. Called on the thread that made the comparison,
+/// after it finished, so implementations should be quick (e.g. increment atomics).
+pub trait MetricsSink: Send + Sync {
+    fn on_comparison_complete(&self, metrics: &ComparisonMetrics);
+}
+
+/// Install `sink` for every later comparison in the process, replacing any previous one;
+/// `None` removes it.
+pub fn set_sink(sink: Option<Arc<dyn MetricsSink>>) {
+    *SINK.write().unwrap_or_else(PoisonError::into_inner) = sink;
+}
+
+/// The installed sink, if any.
+pub(crate) fn sink() -> Option<Arc<dyn MetricsSink>> {
+    SINK.read().unwrap_or_else(PoisonError::into_inner).clone()
+}
@@ -0,0 +1,115 @@
+//! Post-processing of the diff mask, applied before pixels are counted and drawn.
+
+/// A morphological operation with a square structuring element of `2 * radius + 1` pixels.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MorphOp {
+    /// Set every pixel within the radius of a set pixel, closing small gaps.
+    Dilate(u32),
+    /// Keep only pixels whose whole neighbourhood within the radius is set, stripping thin
+    /// noise.
+    Erode(u32),
+}
+
+impl MorphOp {
+    /// The same operation with its radius scaled for an image resized by `scale`.
+    pub fn scaled(self, scale: f32) -> Self {
+        let scale = |r: u32| (r as f32 * scale).ceil() as u32;
+        match self {
+            Self::Dilate(r) => Self::Dilate(scale(r)),
+            Self::Erode(r) => Self::Erode(scale(r)),
+        }
+    }
+}
+
+impl std::fmt::Display for MorphOp {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Dilate(r) => write!(f, "dilate:{r}"),
+            Self::Erode(r) => write!(f, "erode:{r}"),
+        }
+    }
+}
+
+impl std::str::FromStr for MorphOp {
+    type Err = String;
+
+    /// Parse `dilate:<radius>` or `erode:<radius>`; the radius defaults to 1.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("invalid morphology operation \"{s}\", expected dilate:<radius> or erode:<radius>");
+        let (op, radius) = s.split_once(':').unwrap_or((s, "1"));
+        let radius = radius.trim().parse().map_err(|_| invalid())?;
+        match op.trim() {
+            "dilate" => Ok(Self::Dilate(radius)),
+            "erode" => Ok(Self::Erode(radius)),
+            _ => Err(invalid()),
+        }
+    }
+}
+
+/// Prefix sums of `mask` with a zero first row and column, so the number of set pixels in
+/// any rectangle is four lookups.
+fn summed_area(mask: &[bool], w: usize, h: usize) -> Vec<u32> {
+    let mut sums = vec![0u32; (w + 1) * (h + 1)];
+    for y in 0..h {
+        let mut row = 0;
+        for x in 0..w {
+            row += u32::from(mask[y * w + x]);
+            sums[(y + 1) * (w + 1) + x + 1] = sums[y * (w + 1) + x + 1] + row;
+        }
+    }
+    sums
+}
+
+/// Replace each pixel with `keep(set, count, area)`, where `count` is the number of set
+/// pixels within `r` of it and `area` the size of that window after clipping to the image.
+fn window_filter(mask: &mut [bool], w: usize, h: usize, r: usize, keep: impl Fn(bool, u32, u32) -> bool) {
+    let sums = summed_area(mask, w, h);
+    for y in 0..h {
+        let (y0, y1) = (y.saturating_sub(r), (y + r + 1).min(h));
+        for (x, m) in mask[y * w..(y + 1) * w].iter_mut().enumerate() {
+            let (x0, x1) = (x.saturating_sub(r), (x + r + 1).min(w));
+            let count = sums[y1 * (w + 1) + x1] + sums[y0 * (w + 1) + x0]
+                - sums[y0 * (w + 1) + x1]
+                - sums[y1 * (w + 1) + x0];
+            *m = keep(*m, count, ((x1 - x0) * (y1 - y0)) as u32);
+        }
+    }
+}
+
+/// Grow the set areas by `radius` pixels in every direction.
+pub fn dilate(mask: &mut [bool], width: u32, height: u32, radius: u32) {
+    debug_assert_eq!(mask.len(), width as usize * height as usize);
+    if radius > 0 {
+        window_filter(mask, width as usize, height as usize, radius as usize, |_, count, _| count > 0);
+    }
+}
+
+/// Shrink the set areas by `radius` pixels; the image border does not erode them.
+pub fn erode(mask: &mut [bool], width: u32, height: u32, radius: u32) {
+    debug_assert_eq!(mask.len(), width as usize * height as usize);
+    if radius > 0 {
+        window_filter(mask, width as usize, height as usize, radius as usize, |_, count, area| count == area);
+    }
+}
+
+/// Apply `ops` in order.
+pub fn apply(mask: &mut [bool], width: u32, height: u32, ops: &[MorphOp]) {
+    for op in ops {
+        match *op {
+            MorphOp::Dilate(r) => dilate(mask, width, height, r),
+            MorphOp::Erode(r) => erode(mask, width, height, r),
+        }
+    }
+}
+
+/// Clear set pixels that have no other set pixel within `radius` pixels in any direction
+/// (a `(2 * radius + 1)`-pixel square), and return how many were cleared.
+pub fn remove_isolated(mask: &mut [bool], width: u32, height: u32, radius: u32) -> usize {
+    debug_assert_eq!(mask.len(), width as usize * height as usize);
+    if radius == 0 {
+        return 0;
+    }
+    let before = mask.iter().filter(|&&m| m).count();
+    window_filter(mask, width as usize, height as usize, radius as usize, |set, count, _| set && count > 1);
+    before - mask.iter().filter(|&&m| m).count()
+}
@@ -0,0 +1,111 @@
+//! Classification of diff regions into moved and changed content.
+//!
+//! When a button moves a few pixels, its old and new positions both show up as
+//! differences, and a report cannot tell that from a redesigned button. [`classify_regions`]
+//! searches, for each region from [`find_regions`](crate::regions::find_regions), the
+//! surrounding window for an offset at which the region's content in each image reappears
+//! in the other.
+
+use rayon::prelude::*;
+
+use crate::color::color_delta;
+use crate::regions::DiffRegion;
+use crate::Options;
+
+/// What happened to the content of a diff region.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RegionChange {
+    /// The content is the same in both images, moved by `dx` and `dy` pixels (positive is
+    /// right and down).
+    Moved { dx: i32, dy: i32 },
+    /// No offset within the search window accounts for the difference.
+    Changed,
+}
+
+/// Classify each of `regions` (diff regions of `img1` against `img2`) as moved or changed,
+/// in order.
+///
+/// Offsets up to `max_offset` pixels in each direction are tried. A region counts as moved
+/// when, at some offset, its bounding box in each image matches the other image displaced by
+/// that offset (per `options.threshold`), with no more mismatching pixels than a tenth of
+/// the region's differing pixels; among such offsets the one with the fewest mismatches,
+/// then the shortest, wins.
+pub fn classify_regions(
+    img1: &[u8],
+    img2: &[u8],
+    width: u32,
+    height: u32,
+    regions: &[DiffRegion],
+    max_offset: u32,
+    options: &Options,
+) -> Vec<RegionChange> {
+    let (w, h) = (width as usize, height as usize);
+    debug_assert_eq!(img1.len(), w * h * 4);
+    debug_assert_eq!(img2.len(), w * h * 4);
+    let max_delta = 35215.0 * options.threshold * options.threshold;
+    let r = max_offset as i64;
+    let mut offsets: Vec<(i64, i64)> =
+        (-r..=r).flat_map(|dy| (-r..=r).map(move |dx| (dx, dy))).filter(|&o| o != (0, 0)).collect();
+    offsets.sort_by_key(|&(dx, dy)| dx * dx + dy * dy);
+
+    regions
+        .par_iter()
+        .map(|region| {
+            let budget = (region.pixel_count / 10) as usize;
+            let mut best: Option<((i64, i64), usize)> = None;
+            for &offset in &offsets {
+                let limit = best.map_or(budget, |(_, mismatches)| mismatches.saturating_sub(1));
+                if let Some(mismatches) = mismatches(img1, img2, w, h, region, offset, max_delta, limit) {
+                    best = Some((offset, mismatches));
+                    if mismatches == 0 {
+                        break;
+                    }
+                }
+            }
+            match best {
+                Some(((dx, dy), _)) => RegionChange::Moved { dx: dx as i32, dy: dy as i32 },
+                None => RegionChange::Changed,
+            }
+        })
+        .collect()
+}
+
+/// Pixels of `region` that do not match the other image displaced by `(dx, dy)`, checking
+/// both images, or `None` once there are more than `limit`. A displaced position outside
+/// the image is skipped, unless the opposite one is outside too.
+#[allow(clippy::too_many_arguments)]
+fn mismatches(
+    img1: &[u8],
+    img2: &[u8],
+    w: usize,
+    h: usize,
+    region: &DiffRegion,
+    (dx, dy): (i64, i64),
+    max_delta: f64,
+    limit: usize,
+) -> Option<usize> {
+    let mut count = 0;
+    for y in region.y as usize..(region.y + region.height) as usize {
+        for x in region.x as usize..(region.x + region.width) as usize {
+            let pos = (y * w + x) * 4;
+            let displaced = |sign: i64| {
+                let (nx, ny) = (x as i64 + sign * dx, y as i64 + sign * dy);
+                let inside = (0..w as i64).contains(&nx) && (0..h as i64).contains(&ny);
+                inside.then(|| (ny as usize * w + nx as usize) * 4)
+            };
+            let (forward, backward) = (displaced(1), displaced(-1));
+            let differs = |a: &[u8], b: &[u8], other: usize| color_delta(a, b, pos, other, false).abs() > max_delta;
+            count += match (forward, backward) {
+                (None, None) => 2,
+                _ => {
+                    usize::from(forward.is_some_and(|other| differs(img1, img2, other)))
+                        + usize::from(backward.is_some_and(|other| differs(img2, img1, other)))
+                }
+            };
+            if count > limit {
+                return None;
+            }
+        }
+    }
+    Some(count)
+}
@@ -1,10 +1,18 @@
+use std::borrow::Cow;
+
 use napi::bindgen_prelude::*;
 use napi_derive::napi;
 
+use crate::batch::BatchItem;
+use crate::profile::DiffProfile;
+use crate::regions::find_regions;
 use crate::{Options, PixelmatchError};
 
-#[napi(object)]
-pub struct PixelmatchOptions {
+/// The options object as declared to TypeScript. Values are read by the shared options parser
+/// (see [`Options::from_json`]), which also takes the other `Options` keys.
+#[napi(object, js_name = "PixelmatchOptions")]
+#[allow(dead_code)] // only generates the TypeScript declaration
+pub struct RawPixelmatchOptions {
     pub threshold: Option<f64>,
     pub detect_anti_aliasing: Option<bool>,
     pub alpha: Option<f64>,
@@ -12,53 +20,246 @@ pub struct PixelmatchOptions {
     pub diff_color: Option<Vec<u32>>,
     pub diff_color_alt: Option<Vec<u32>>,
     pub diff_mask: Option<bool>,
+    /// Pixels to exclude: one byte per pixel, or RGBA (alpha is used). Non-zero pixels are
+    /// treated as equal.
+    pub ignore_mask: Option<Uint8Array>,
+    /// Also report totalPixels, diffRatio, bounds, regions, rowCounts, columnCounts and severity.
+    pub extended_result: Option<bool>,
+    /// Throw a TypeError on unknown option keys (e.g. a misspelt `treshold`).
+    pub strict: Option<bool>,
 }
 
-#[napi(object)]
-pub struct NapiMatchResult {
-    pub diff_count: u32,
-    pub aa_count: u32,
-    pub identical: bool,
+/// JS keys the binding reads itself rather than passing to the options parser.
+const BINDING_KEYS: [&str; 3] = ["ignoreMask", "extendedResult", "strict"];
+
+/// Options as received from JS, validated on conversion: malformed values throw a TypeError
+/// and out-of-range values a RangeError, before any comparison work starts.
+pub struct PixelmatchOptions {
+    options: Options,
+    extended: bool,
 }
 
-fn convert_options(opts: Option<PixelmatchOptions>) -> Options {
-    let mut options = Options::default();
-    if let Some(o) = opts {
-        if let Some(t) = o.threshold {
-            options.threshold = t;
-        }
-        if let Some(aa) = o.detect_anti_aliasing {
-            options.detect_anti_aliasing = aa;
+enum OptionError {
+    Type(String),
+    Range(String),
+}
+
+impl OptionError {
+    /// Throw the matching JS error and return the pending-exception marker for napi.
+    unsafe fn throw(self, env: sys::napi_env) -> napi::Error {
+        let (message, range) = match self {
+            Self::Type(m) => (m, false),
+            Self::Range(m) => (m, true),
+        };
+        let error = napi::Error::new(Status::InvalidArg, message.clone());
+        if range {
+            JsRangeError::from(error).throw_into(env);
+        } else {
+            JsTypeError::from(error).throw_into(env);
         }
-        if let Some(a) = o.alpha {
-            options.alpha = a;
+        napi::Error::new(Status::PendingException, message)
+    }
+}
+
+impl From<PixelmatchError> for OptionError {
+    fn from(e: PixelmatchError) -> Self {
+        match e {
+            // Integers out of a field's range are caught first by `out_of_range`, so what
+            // is left of the parse errors is the wrong shape; validation errors are ranges.
+            PixelmatchError::InvalidJson(_) => Self::Type(e.to_string()),
+            _ => Self::Range(e.to_string()),
         }
-        if let Some(ref c) = o.aa_color {
-            if c.len() >= 3 {
-                options.aa_color = [c[0] as u8, c[1] as u8, c[2] as u8];
+    }
+}
+
+/// The first option whose value only fails because of an integer outside the field's range,
+/// such as a colour component over 255 or a negative tolerance.
+fn out_of_range(fields: &serde_json::Map<String, serde_json::Value>) -> Option<OptionError> {
+    fn zero_integers(value: &serde_json::Value) -> serde_json::Value {
+        match value {
+            serde_json::Value::Number(n) if n.is_i64() || n.is_u64() => 0.into(),
+            serde_json::Value::Array(items) => items.iter().map(zero_integers).collect(),
+            serde_json::Value::Object(fields) => {
+                fields.iter().map(|(key, value)| (key.clone(), zero_integers(value))).collect()
             }
+            value => value.clone(),
         }
-        if let Some(ref c) = o.diff_color {
-            if c.len() >= 3 {
-                options.diff_color = [c[0] as u8, c[1] as u8, c[2] as u8];
-            }
+    }
+    fields.iter().find_map(|(key, value)| {
+        let reason = crate::json::check_key(key, value).err()?;
+        let integers_only = crate::json::check_key(key, &zero_integers(value)).is_ok();
+        integers_only.then(|| OptionError::Range(PixelmatchError::InvalidJson(reason).to_string()))
+    })
+}
+
+fn check_unit(name: &str, value: Option<f64>) -> std::result::Result<Option<f64>, OptionError> {
+    match value {
+        Some(v) if !(0.0..=1.0).contains(&v) => {
+            Err(OptionError::Range(format!("{name} must be between 0 and 1, got {v}")))
         }
-        if let Some(ref c) = o.diff_color_alt {
-            if c.len() >= 3 {
-                options.diff_color_alt = Some([c[0] as u8, c[1] as u8, c[2] as u8]);
+        v => Ok(v),
+    }
+}
+
+impl FromNapiValue for PixelmatchOptions {
+    unsafe fn from_napi_value(env: sys::napi_env, napi_val: sys::napi_value) -> Result<Self> {
+        let invalid = |e: napi::Error| OptionError::Type(format!("Invalid options: {}", e.reason)).throw(env);
+        let object = Object::from_napi_value(env, napi_val).map_err(invalid)?;
+        let flag = |key| object.get::<_, Option<bool>>(key).map(Option::flatten).map_err(invalid);
+        let strict = flag("strict")?.unwrap_or(false);
+        let extended = flag("extendedResult")?.unwrap_or(false);
+        let ignore_mask = object.get::<_, Option<Uint8Array>>("ignoreMask").map_err(invalid)?.flatten();
+
+        let keys = crate::json::keys();
+        let mut fields = serde_json::Map::new();
+        for key in Object::keys(&object)?.into_iter().filter(|k| !BINDING_KEYS.contains(&k.as_str())) {
+            if strict && !keys.contains(&key) {
+                return Err(OptionError::Type(format!("Unknown option `{key}`")).throw(env));
+            }
+            if let Some(value) = object.get::<_, serde_json::Value>(&key).map_err(invalid)? {
+                fields.insert(key, value);
             }
         }
-        if let Some(m) = o.diff_mask {
-            options.diff_mask = m;
+        if let Some(error) = out_of_range(&fields) {
+            return Err(error.throw(env));
         }
+        let options = crate::json::from_js_object(fields).map_err(|e| OptionError::from(e).throw(env))?;
+        Ok(Self { options: Options { ignore_mask: ignore_mask.map(|m| m.to_vec()), ..options }, extended })
     }
-    options
+}
+
+/// A rectangle of differing pixels.
+#[napi(object)]
+pub struct NapiDiffRegion {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+    /// Number of differing pixels inside the rectangle.
+    pub pixel_count: u32,
+}
+
+#[napi(object)]
+#[derive(Default)]
+pub struct NapiMatchResult {
+    pub diff_count: u32,
+    pub aa_count: u32,
+    pub identical: bool,
+    /// width * height, as a JS number so that images over 2^32 pixels are counted exactly.
+    pub total_pixels: Option<f64>,
+    /// diffCount / totalPixels.
+    pub diff_ratio: Option<f64>,
+    /// Bounding box of all differing pixels, if any. Only set with `extendedResult`.
+    pub bounds: Option<NapiDiffRegion>,
+    /// 8-connected clusters of differing pixels, in scan order. Only set with `extendedResult`.
+    pub regions: Option<Vec<NapiDiffRegion>>,
+    /// Differing pixels in each row, top to bottom. Only set with `extendedResult`.
+    pub row_counts: Option<Vec<u32>>,
+    /// Differing pixels in each column, left to right; a band of busy columns points to a
+    /// horizontal layout shift. Only set with `extendedResult`.
+    pub column_counts: Option<Vec<u32>>,
+    /// Only set with `extendedResult`.
+    #[napi(ts_type = "'none' | 'minor' | 'moderate' | 'major'")]
+    pub severity: Option<String>,
+}
+
+#[napi(object)]
+pub struct NapiDiffResult {
+    pub diff_count: u32,
+    pub aa_count: u32,
+    pub identical: bool,
+    pub total_pixels: Option<f64>,
+    pub diff_ratio: Option<f64>,
+    pub bounds: Option<NapiDiffRegion>,
+    pub regions: Option<Vec<NapiDiffRegion>>,
+    pub row_counts: Option<Vec<u32>>,
+    pub column_counts: Option<Vec<u32>>,
+    #[napi(ts_type = "'none' | 'minor' | 'moderate' | 'major'")]
+    pub severity: Option<String>,
+    /// RGBA diff image (width * height * 4 bytes).
+    pub diff: Buffer,
+}
+
+#[napi(object)]
+pub struct NapiImagePair {
+    pub img1: Buffer,
+    pub img2: Buffer,
+    pub width: u32,
+    pub height: u32,
+    /// Optional buffer receiving the diff image.
+    pub output: Option<Buffer>,
+}
+
+fn convert_options(opts: Option<PixelmatchOptions>) -> Options {
+    opts.map(|o| o.options).unwrap_or_default()
 }
 
 fn map_error(e: PixelmatchError) -> napi::Error {
     napi::Error::from_reason(e.to_string())
 }
 
+fn wants_extended(opts: &Option<PixelmatchOptions>) -> bool {
+    opts.as_ref().is_some_and(|o| o.extended)
+}
+
+impl From<crate::MatchResult> for NapiMatchResult {
+    fn from(result: crate::MatchResult) -> Self {
+        Self {
+            diff_count: result.diff_count,
+            aa_count: result.aa_count,
+            identical: result.identical,
+            total_pixels: Some(result.total_pixels as f64),
+            diff_ratio: Some(result.diff_ratio()),
+            ..Default::default()
+        }
+    }
+}
+
+/// Classify a diff ratio: any difference is at least minor, 0.1% moderate, 1% major.
+fn severity(diff_count: u32, ratio: f64) -> &'static str {
+    match ratio {
+        _ if diff_count == 0 => "none",
+        r if r < 0.001 => "minor",
+        r if r < 0.01 => "moderate",
+        _ => "major",
+    }
+}
+
+/// Fill in the `extendedResult` fields from the diff mask of the pair.
+fn extend(
+    result: &mut NapiMatchResult,
+    img1: &[u8],
+    img2: &[u8],
+    width: u32,
+    height: u32,
+    options: &Options,
+) -> Result<()> {
+    let mask = crate::diff_mask(img1, img2, width, height, options).map_err(map_error)?;
+    let regions = find_regions(&mask, width, height);
+    let profile = DiffProfile::from_mask(&mask, width, height);
+    result.bounds = regions
+        .iter()
+        .map(|r| (r.x, r.y, r.x + r.width, r.y + r.height))
+        .reduce(|a, b| (a.0.min(b.0), a.1.min(b.1), a.2.max(b.2), a.3.max(b.3)))
+        .map(|(x0, y0, x1, y1)| NapiDiffRegion {
+            x: x0,
+            y: y0,
+            width: x1 - x0,
+            height: y1 - y0,
+            pixel_count: result.diff_count,
+        });
+    result.regions = Some(
+        regions
+            .into_iter()
+            .map(|r| NapiDiffRegion { x: r.x, y: r.y, width: r.width, height: r.height, pixel_count: r.pixel_count })
+            .collect(),
+    );
+    result.row_counts = Some(profile.rows);
+    result.column_counts = Some(profile.columns);
+    result.severity = Some(severity(result.diff_count, result.diff_ratio.unwrap_or(0.0)).to_string());
+    Ok(())
+}
+
 /// Compare two images pixel by pixel, writing the diff to the output buffer.
 /// Returns a NapiMatchResult with diff_count, aa_count, and identical fields.
 #[napi]
@@ -70,12 +271,46 @@ pub fn pixelmatch(
     height: u32,
     options: Option<PixelmatchOptions>,
 ) -> Result<NapiMatchResult> {
+    let extended = wants_extended(&options);
     let opts = convert_options(options);
-    let result = crate::pixelmatch(img1, img2, Some(output.as_mut()), width, height, &opts).map_err(map_error)?;
-    Ok(NapiMatchResult {
+    let mut result: NapiMatchResult =
+        crate::pixelmatch(img1, img2, Some(output.as_mut()), width, height, &opts).map_err(map_error)?.into();
+    if extended {
+        extend(&mut result, img1, img2, width, height, &opts)?;
+    }
+    Ok(result)
+}
+
+/// Compare two images pixel by pixel, allocating the diff image.
+/// Returns a NapiDiffResult whose `diff` field holds the RGBA diff buffer.
+#[napi]
+pub fn pixelmatch_diff(
+    img1: &[u8],
+    img2: &[u8],
+    width: u32,
+    height: u32,
+    options: Option<PixelmatchOptions>,
+) -> Result<NapiDiffResult> {
+    let extended = wants_extended(&options);
+    let opts = convert_options(options);
+    let mut diff = vec![0u8; img1.len()];
+    let mut result: NapiMatchResult =
+        crate::pixelmatch(img1, img2, Some(&mut diff), width, height, &opts).map_err(map_error)?.into();
+    if extended {
+        extend(&mut result, img1, img2, width, height, &opts)?;
+    }
+    Ok(NapiDiffResult {
         diff_count: result.diff_count,
         aa_count: result.aa_count,
         identical: result.identical,
+        total_pixels: result.total_pixels,
+        diff_ratio: result.diff_ratio,
+        bounds: result.bounds,
+        regions: result.regions,
+        row_counts: result.row_counts,
+        column_counts: result.column_counts,
+        severity: result.severity,
+        diff: diff.into(),
     })
 }
 
@@ -88,11 +323,389 @@ pub fn pixelmatch_count(
     height: u32,
     options: Option<PixelmatchOptions>,
 ) -> Result<NapiMatchResult> {
+    let extended = wants_extended(&options);
     let opts = convert_options(options);
-    let result = crate::pixelmatch(img1, img2, None, width, height, &opts).map_err(map_error)?;
-    Ok(NapiMatchResult {
+    let mut result: NapiMatchResult =
+        crate::pixelmatch(img1, img2, None, width, height, &opts).map_err(map_error)?.into();
+    if extended {
+        extend(&mut result, img1, img2, width, height, &opts)?;
+    }
+    Ok(result)
+}
+
+/// Comparison run on the libuv thread pool by `pixelmatchAsync`.
+pub struct PixelmatchTask {
+    img1: Buffer,
+    img2: Buffer,
+    output: Option<Buffer>,
+    width: u32,
+    height: u32,
+    options: Options,
+    extended: bool,
+}
+
+impl Task for PixelmatchTask {
+    type Output = NapiMatchResult;
+    type JsValue = NapiMatchResult;
+
+    fn compute(&mut self) -> Result<Self::Output> {
+        let output = self.output.as_mut().map(|o| o.as_mut());
+        let mut result: NapiMatchResult =
+            crate::pixelmatch(&self.img1, &self.img2, output, self.width, self.height, &self.options)
+                .map_err(map_error)?
+                .into();
+        if self.extended {
+            extend(&mut result, &self.img1, &self.img2, self.width, self.height, &self.options)?;
+        }
+        Ok(result)
+    }
+
+    fn resolve(&mut self, _env: Env, output: Self::Output) -> Result<Self::JsValue> {
+        Ok(output)
+    }
+}
+
+/// Compare two images off the main thread, resolving to a NapiMatchResult.
+/// The diff is written to `output` if given. The buffers must not be modified until the
+/// returned Promise settles.
+#[napi(ts_return_type = "Promise<NapiMatchResult>")]
+pub fn pixelmatch_async(
+    img1: Buffer,
+    img2: Buffer,
+    output: Option<Buffer>,
+    width: u32,
+    height: u32,
+    options: Option<PixelmatchOptions>,
+) -> AsyncTask<PixelmatchTask> {
+    let extended = wants_extended(&options);
+    AsyncTask::new(PixelmatchTask { img1, img2, output, width, height, options: convert_options(options), extended })
+}
+
+/// Compare many image pairs in one call, in parallel across all cores.
+/// Returns one NapiMatchResult per pair, in order; fails if any pair is invalid.
+#[napi]
+pub fn pixelmatch_batch(
+    mut pairs: Vec<NapiImagePair>,
+    options: Option<PixelmatchOptions>,
+) -> Result<Vec<NapiMatchResult>> {
+    let extended = wants_extended(&options);
+    let opts = convert_options(options);
+    let mut items: Vec<BatchItem> = pairs
+        .iter_mut()
+        .map(|p| BatchItem {
+            img1: &p.img1,
+            img2: &p.img2,
+            output: p.output.as_mut().map(|o| o.as_mut()),
+            width: p.width,
+            height: p.height,
+        })
+        .collect();
+    let results = crate::batch::pixelmatch_batch(&mut items, &opts);
+    drop(items);
+    results
+        .into_iter()
+        .zip(&pairs)
+        .enumerate()
+        .map(|(i, (r, p))| {
+            let pair_error = |e: napi::Error| napi::Error::from_reason(format!("pair {i}: {}", e.reason));
+            let mut result: NapiMatchResult = r.map_err(map_error).map_err(pair_error)?.into();
+            if extended {
+                extend(&mut result, &p.img1, &p.img2, p.width, p.height, &opts).map_err(pair_error)?;
+            }
+            Ok(result)
+        })
+        .collect()
+}
+
+/// A zero-copy view of pixel bytes: a Uint8Array, Uint8ClampedArray or Buffer (backed by an
+/// ArrayBuffer or a SharedArrayBuffer), or a whole ArrayBuffer. Views of the same memory may
+/// be read together, but one that is written must not overlap any other.
+pub struct PixelView {
+    data: *mut u8,
+    len: usize,
+}
+
+impl PixelView {
+    fn as_slice(&self) -> &[u8] {
+        if self.len == 0 {
+            &[]
+        } else {
+            // SAFETY: `data` points at `len` bytes kept alive by the JS caller for the duration
+            // of the synchronous call, which must not write them from another thread meanwhile.
+            unsafe { std::slice::from_raw_parts(self.data, self.len) }
+        }
+    }
+
+    fn as_mut_slice(&mut self) -> &mut [u8] {
+        if self.len == 0 {
+            &mut []
+        } else {
+            // SAFETY: as for `as_slice`; callers check that no other view borrowed alongside
+            // this one overlaps it.
+            unsafe { std::slice::from_raw_parts_mut(self.data, self.len) }
+        }
+    }
+
+    /// Whether the two views share any byte.
+    fn overlaps(&self, other: &Self) -> bool {
+        let (a, b) = (self.data as usize, other.data as usize);
+        self.len > 0 && other.len > 0 && a < b + other.len && b < a + self.len
+    }
+}
+
+impl FromNapiValue for PixelView {
+    unsafe fn from_napi_value(env: sys::napi_env, napi_val: sys::napi_value) -> Result<Self> {
+        let invalid =
+            || napi::Error::new(Status::InvalidArg, "Expected a Uint8Array, Uint8ClampedArray or ArrayBuffer");
+        let mut data = std::ptr::null_mut();
+        let mut len = 0;
+        let mut is_typed_array = false;
+        if sys::napi_is_typedarray(env, napi_val, &mut is_typed_array) != sys::Status::napi_ok {
+            return Err(invalid());
+        }
+        if is_typed_array {
+            let mut kind = 0;
+            let mut buffer = std::ptr::null_mut();
+            let mut offset = 0;
+            let status =
+                sys::napi_get_typedarray_info(env, napi_val, &mut kind, &mut len, &mut data, &mut buffer, &mut offset);
+            let bytes = kind == sys::TypedarrayType::uint8_array || kind == sys::TypedarrayType::uint8_clamped_array;
+            if status != sys::Status::napi_ok || !bytes {
+                return Err(invalid());
+            }
+        } else if sys::napi_get_arraybuffer_info(env, napi_val, &mut data, &mut len) != sys::Status::napi_ok {
+            return Err(invalid());
+        }
+        Ok(Self { data: data.cast(), len })
+    }
+}
+
+/// Number of `Uint32Array` slots written by `pixelmatchShared`.
+#[napi]
+pub const SHARED_RESULT_LENGTH: u32 = 3;
+
+/// Compare two images in place, for worker_threads over SharedArrayBuffer.
+///
+/// Inputs and output are read and written where they live, never copied, so screenshots in a
+/// SharedArrayBuffer need no double-buffering. Instead of returning an object, the result is
+/// written to `result` as `[diffCount, aaCount, identical ? 1 : 0]`, so a worker can report
+/// back through shared memory (or transfer a small buffer) without allocating per call.
+/// `extendedResult` is not supported here.
+///
+/// `img1` and `img2` may be views of the same memory, but `output` must not overlap either
+/// (a RangeError is thrown). No other thread may write any of the buffers until the call
+/// returns: the comparison reads them in place, so such writes race with it and make the
+/// result and diff meaningless. Synchronise with `Atomics` before and after the call.
+#[napi(
+    ts_args_type = "img1: Uint8Array | Uint8ClampedArray | ArrayBuffer, \
+                    img2: Uint8Array | Uint8ClampedArray | ArrayBuffer, \
+                    output: Uint8Array | Uint8ClampedArray | ArrayBuffer | undefined | null, \
+                    width: number, height: number, result: Uint32Array, options?: PixelmatchOptions"
+)]
+#[allow(clippy::too_many_arguments)] // `env` is not a JS argument
+pub fn pixelmatch_shared(
+    env: Env,
+    img1: PixelView,
+    img2: PixelView,
+    mut output: Option<PixelView>,
+    width: u32,
+    height: u32,
+    mut result: Uint32Array,
+    options: Option<PixelmatchOptions>,
+) -> Result<()> {
+    if result.len() < SHARED_RESULT_LENGTH as usize {
+        return Err(napi::Error::from_reason(format!(
+            "Result array too small. Expecting at least {SHARED_RESULT_LENGTH}. Got {}",
+            result.len()
+        )));
+    }
+    if output.as_ref().is_some_and(|o| o.overlaps(&img1) || o.overlaps(&img2)) {
+        let error = OptionError::Range("output must not overlap img1 or img2".to_string());
+        return Err(unsafe { error.throw(env.raw()) });
+    }
+    let opts = convert_options(options);
+    let output = output.as_mut().map(PixelView::as_mut_slice);
+    let r = crate::pixelmatch(img1.as_slice(), img2.as_slice(), output, width, height, &opts).map_err(map_error)?;
+    result[..3].copy_from_slice(&[r.diff_count, r.aa_count, u32::from(r.identical)]);
+    Ok(())
+}
+
+/// The `info` part of sharp's `{ resolveWithObject: true }` raw output.
+#[napi(object)]
+pub struct SharpRawInfo {
+    pub width: u32,
+    pub height: u32,
+    /// 3 (RGB) or 4 (RGBA).
+    pub channels: u32,
+}
+
+/// sharp's raw output: `await sharp(input).raw().toBuffer({ resolveWithObject: true })`.
+#[napi(object)]
+pub struct SharpRawImage {
+    pub data: Buffer,
+    pub info: SharpRawInfo,
+}
+
+/// The image's pixels as RGBA, borrowing 4-channel data and expanding RGB with opaque alpha.
+fn sharp_rgba<'a>(image: &'a SharpRawImage, name: &str) -> Result<Cow<'a, [u8]>> {
+    let info = &image.info;
+    let expected = info.width as usize * info.height as usize * info.channels as usize;
+    if !matches!(info.channels, 3 | 4) {
+        return Err(napi::Error::from_reason(format!(
+            "{name}: unsupported channel count {}; use 3 (RGB) or 4 (RGBA)",
+            info.channels
+        )));
+    }
+    if image.data.len() != expected {
+        return Err(napi::Error::from_reason(format!(
+            "{name}: data size does not match info. Expecting {expected}. Got {}",
+            image.data.len()
+        )));
+    }
+    if info.channels == 4 {
+        return Ok(Cow::Borrowed(&image.data));
+    }
+    let rgba = image.data.chunks_exact(3).flat_map(|px| [px[0], px[1], px[2], 255]).collect();
+    Ok(Cow::Owned(rgba))
+}
+
+/// Compare two images straight from sharp's raw output, with 3 or 4 channels each.
+/// Returns a NapiDiffResult whose `diff` is RGBA, ready for
+/// `sharp(diff, { raw: { width, height, channels: 4 } })`.
+#[napi]
+pub fn pixelmatch_sharp(
+    img1: SharpRawImage,
+    img2: SharpRawImage,
+    options: Option<PixelmatchOptions>,
+) -> Result<NapiDiffResult> {
+    let (w1, h1) = (img1.info.width, img1.info.height);
+    let (w2, h2) = (img2.info.width, img2.info.height);
+    if (w1, h1) != (w2, h2) {
+        return Err(napi::Error::from_reason(format!("Image dimensions do not match: {w1}x{h1} vs {w2}x{h2}")));
+    }
+    let data1 = sharp_rgba(&img1, "img1")?;
+    let data2 = sharp_rgba(&img2, "img2")?;
+    pixelmatch_diff(&data1, &data2, w1, h1, options)
+}
+
+/// A rectangle in CSS pixels, e.g. from Playwright's `locator.boundingBox()`.
+#[napi(object)]
+pub struct NapiRect {
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+}
+
+#[napi(object)]
+#[derive(Default)]
+pub struct ScreenshotOptions {
+    /// Regions to ignore, in CSS pixels; scaled by `devicePixelRatio`.
+    pub mask_selectors_rects: Option<Vec<NapiRect>>,
+    /// Ratio of screenshot pixels to CSS pixels. Default: 1
+    pub device_pixel_ratio: Option<f64>,
+    /// Largest diffRatio that still passes. Default: 0
+    pub max_diff_ratio: Option<f64>,
+    /// Matching threshold (0 to 1). Default: 0.1
+    pub threshold: Option<f64>,
+    /// Default: true
+    pub detect_anti_aliasing: Option<bool>,
+}
+
+#[napi(object)]
+pub struct ScreenshotComparison {
+    /// Whether diffRatio is within maxDiffRatio (false if the sizes differ).
+    pub pass: bool,
+    pub diff_count: u32,
+    pub total_pixels: f64,
+    pub diff_ratio: f64,
+    pub width: u32,
+    pub height: u32,
+    /// Why the comparison failed, if it did.
+    pub message: Option<String>,
+    /// PNG-encoded diff image, only set when the comparison fails on pixels.
+    pub diff: Option<Buffer>,
+}
+
+/// Scale a CSS-pixel rectangle to device pixels, growing it to whole pixels and clipping it
+/// to the image.
+fn device_rect(r: &NapiRect, dpr: f64, width: u32, height: u32) -> crate::Rect {
+    let x0 = (r.x * dpr).floor().clamp(0.0, width as f64) as u32;
+    let y0 = (r.y * dpr).floor().clamp(0.0, height as f64) as u32;
+    let x1 = ((r.x + r.width) * dpr).ceil().clamp(0.0, width as f64) as u32;
+    let y1 = ((r.y + r.height) * dpr).ceil().clamp(0.0, height as f64) as u32;
+    crate::Rect::new(x0, y0, x1.saturating_sub(x0), y1.saturating_sub(y0))
+}
+
+/// Compare two PNG screenshots (as returned by Playwright's or Puppeteer's `screenshot()`),
+/// masking `maskSelectorsRects` and passing when at most `maxDiffRatio` of the pixels differ.
+/// Decoding, masking and gating all happen in native code.
+#[napi]
+pub fn compare_screenshots(
+    env: Env,
+    page1: &[u8],
+    page2: &[u8],
+    options: Option<ScreenshotOptions>,
+) -> Result<ScreenshotComparison> {
+    let options = options.unwrap_or_default();
+    let throw = |e: OptionError| unsafe { e.throw(env.raw()) };
+    let dpr = options.device_pixel_ratio.unwrap_or(1.0);
+    if !(dpr > 0.0 && dpr.is_finite()) {
+        return Err(throw(OptionError::Range(format!("devicePixelRatio must be positive, got {dpr}"))));
+    }
+    let max_diff_ratio = check_unit("maxDiffRatio", options.max_diff_ratio).map_err(throw)?.unwrap_or(0.0);
+    let threshold = check_unit("threshold", options.threshold).map_err(throw)?;
+
+    let decode = |data: &[u8], name: &str| {
+        crate::io::decode_png(data).map_err(|e| napi::Error::from_reason(format!("{name}: invalid PNG: {e}")))
+    };
+    let (img1, img2) = (decode(page1, "page1")?, decode(page2, "page2")?);
+    let (width, height) = (img1.width, img1.height);
+    if (width, height) != (img2.width, img2.height) {
+        return Ok(ScreenshotComparison {
+            pass: false,
+            diff_count: 0,
+            total_pixels: width as f64 * height as f64,
+            diff_ratio: 0.0,
+            width,
+            height,
+            message: Some(format!("Screenshot sizes differ: {width}x{height} vs {}x{}", img2.width, img2.height)),
+            diff: None,
+        });
+    }
+
+    let defaults = Options::default();
+    let opts = Options {
+        threshold: threshold.unwrap_or(defaults.threshold),
+        detect_anti_aliasing: options.detect_anti_aliasing.unwrap_or(defaults.detect_anti_aliasing),
+        ignore_regions: options
+            .mask_selectors_rects
+            .iter()
+            .flatten()
+            .map(|r| device_rect(r, dpr, width, height))
+            .collect(),
+        ..defaults
+    };
+    let mut diff = vec![0u8; img1.data.len()];
+    let result = crate::pixelmatch(&img1.data, &img2.data, Some(&mut diff), width, height, &opts).map_err(map_error)?;
+    let diff_ratio = result.diff_ratio();
+    let pass = diff_ratio <= max_diff_ratio;
+    let (message, diff) = if pass {
+        (None, None)
+    } else {
+        let mut png = Vec::new();
+        crate::io::encode_png(&mut png, &diff, width, height).map_err(|e| napi::Error::from_reason(e.to_string()))?;
+        let (actual, allowed) = (diff_ratio * 100.0, max_diff_ratio * 100.0);
+        (Some(format!("{} pixels differ ({actual:.4}% > {allowed:.4}%)", result.diff_count)), Some(png.into()))
+    };
+    Ok(ScreenshotComparison {
+        pass,
         diff_count: result.diff_count,
-        aa_count: result.aa_count,
-        identical: result.identical,
+        total_pixels: result.total_pixels as f64,
+        diff_ratio,
+        width,
+        height,
+        message,
+        diff,
     })
 }
@@ -0,0 +1,103 @@
+//! Comparison of tangent-space normal maps by the angle between normals.
+//!
+//! A normal map stores a unit vector per pixel with each component mapped from -1..1 to
+//! 0..255. How far shading changes depends on the angle between the old and new normal,
+//! which colour distance tracks poorly: a few levels of blue barely move a normal pointing
+//! out of the surface, while the same change in red can tilt a grazing one by degrees.
+//! [`compare_normals`] decodes both images to unit vectors and counts the pixels whose
+//! normals differ by more than a given angle. Alpha is ignored; vectors too short to
+//! normalise are read as pointing straight out of the surface.
+
+use rayon::prelude::*;
+
+use crate::color::draw_pixel;
+use crate::{draw_gray_pixel_local, draw_ignore_hatch, ignore_mask, validate_buffers, Options, PixelmatchError};
+
+/// Result of [`compare_normals`].
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct NormalDiff {
+    /// Pixels whose normals differ by more than the allowed angle.
+    pub diff_count: u32,
+    /// Largest angle between two normals, in degrees.
+    pub max_angle: f64,
+    /// Mean angle over the compared pixels, in degrees.
+    pub mean_angle: f64,
+    /// Whether the two images are byte-identical.
+    pub identical: bool,
+}
+
+/// The unit normal encoded in an RGB pixel.
+fn decode(p: &[u8]) -> [f32; 3] {
+    let v = [p[0], p[1], p[2]].map(|c| c as f32 / 127.5 - 1.0);
+    let len = (v[0] * v[0] + v[1] * v[1] + v[2] * v[2]).sqrt();
+    if len < 1e-3 { [0.0, 0.0, 1.0] } else { v.map(|c| c / len) }
+}
+
+/// Angle in degrees between the normals of two RGBA pixels.
+fn angle(p1: &[u8], p2: &[u8]) -> f32 {
+    if p1[..3] == p2[..3] {
+        return 0.0;
+    }
+    let (n1, n2) = (decode(p1), decode(p2));
+    let dot = n1[0] * n2[0] + n1[1] * n2[1] + n1[2] * n2[2];
+    dot.clamp(-1.0, 1.0).acos().to_degrees()
+}
+
+/// Per-pixel angle in degrees between the normals of two equally sized normal maps, in
+/// row-major order; render it with [`crate::ssim::heatmap`] to see where shading changes.
+pub fn angle_map(img1: &[u8], img2: &[u8], width: u32, height: u32) -> Result<Vec<f32>, PixelmatchError> {
+    validate_buffers(img1, img2, None, width, height)?;
+    Ok(img1.par_chunks_exact(4).zip(img2.par_chunks_exact(4)).map(|(p1, p2)| angle(p1, p2)).collect())
+}
+
+/// Compare two normal maps, counting the pixels whose normals differ by more than
+/// `max_angle` degrees (0 to 180).
+///
+/// Of `options`, only the ignore options and those for drawing `output` (`alpha`,
+/// `diff_color`, `diff_mask`, `ignore_hatch`) apply; ignored pixels are left out of the
+/// angle statistics too.
+pub fn compare_normals(
+    img1: &[u8],
+    img2: &[u8],
+    output: Option<&mut [u8]>,
+    width: u32,
+    height: u32,
+    max_angle: f64,
+    options: &Options,
+) -> Result<NormalDiff, PixelmatchError> {
+    if !(0.0..=180.0).contains(&max_angle) {
+        return Err(PixelmatchError::InvalidAngle(max_angle));
+    }
+    options.validate()?;
+    validate_buffers(img1, img2, output.as_deref().map(<[u8]>::len), width, height)?;
+    let (w, h) = (width as usize, height as usize);
+    let ignore = ignore_mask(options, w, h)?;
+    let angles: Vec<Option<f32>> = (0..w * h)
+        .into_par_iter()
+        .map(|i| (!ignore.as_ref().is_some_and(|m| m[i])).then(|| angle(&img1[i * 4..][..4], &img2[i * 4..][..4])))
+        .collect();
+
+    let differs = |a: f32| a as f64 > max_angle;
+    if let Some(out) = output.filter(|_| w > 0) {
+        let [r, g, b] = options.diff_color;
+        out.par_chunks_mut(w * 4).enumerate().for_each(|(y, out_row)| {
+            for x in 0..w {
+                let i = y * w + x;
+                if angles[i].is_some_and(differs) {
+                    draw_pixel(out_row, x * 4, r, g, b);
+                } else if !options.diff_mask {
+                    draw_gray_pixel_local(img1, i * 4, options.alpha, out_row, x * 4);
+                }
+            }
+            draw_ignore_hatch(out_row, ignore.as_deref().map(|m| &m[y * w..(y + 1) * w]), y, options.ignore_hatch);
+        });
+    }
+
+    let compared: Vec<f32> = angles.into_iter().flatten().collect();
+    Ok(NormalDiff {
+        diff_count: compared.iter().filter(|&&a| differs(a)).count() as u32,
+        max_angle: compared.iter().copied().fold(0.0, f32::max) as f64,
+        mean_angle: compared.iter().map(|&a| a as f64).sum::<f64>() / compared.len().max(1) as f64,
+        identical: img1 == img2,
+    })
+}
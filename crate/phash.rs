@@ -0,0 +1,112 @@
+//! Perceptual image hashes, for deduplication and "roughly the same image" pre-checks.
+//!
+//! Each hash condenses an image into 64 bits that change little under resizing, compression
+//! or small edits; the Hamming distance between two hashes ([`ImageHash::distance`]) says
+//! how alike the images look. [`HashKind::Average`] is the fastest, [`HashKind::Difference`]
+//! tracks gradients, and [`HashKind::Perceptual`] (DCT-based) is the most robust.
+
+use crate::resize::resize_box;
+use crate::ssim::luma;
+
+/// Side of the DCT input for [`HashKind::Perceptual`].
+const DCT_SIZE: usize = 32;
+
+/// A hashing algorithm.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum HashKind {
+    /// aHash: which pixels of an 8×8 thumbnail are brighter than its mean.
+    Average,
+    /// dHash: which pixels of a 9×8 thumbnail are brighter than their right neighbour.
+    Difference,
+    /// pHash: which of the lowest 8×8 DCT frequencies of a 32×32 thumbnail are above their
+    /// median.
+    Perceptual,
+}
+
+/// A 64-bit perceptual hash, shown as 16 hex digits.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ImageHash(pub u64);
+
+impl ImageHash {
+    /// Hash an RGBA image; translucent pixels are blended over white.
+    pub fn of(kind: HashKind, img: &[u8], width: u32, height: u32) -> Self {
+        debug_assert_eq!(img.len(), width as usize * height as usize * 4);
+        match kind {
+            HashKind::Average => {
+                let gray = thumbnail(img, width, height, 8, 8);
+                let mean = gray.iter().sum::<f32>() / gray.len() as f32;
+                Self::from_bits(gray.iter().map(|&v| v > mean))
+            }
+            HashKind::Difference => {
+                let gray = thumbnail(img, width, height, 9, 8);
+                Self::from_bits(gray.chunks_exact(9).flat_map(|row| row.windows(2).map(|p| p[0] > p[1])))
+            }
+            HashKind::Perceptual => {
+                let gray = thumbnail(img, width, height, DCT_SIZE as u32, DCT_SIZE as u32);
+                let coefficients = low_frequencies(&gray);
+                // The DC term only reflects overall brightness, so it is left out of the median.
+                let mut sorted = coefficients[1..].to_vec();
+                sorted.sort_by(f64::total_cmp);
+                let median = (sorted[31] + sorted[32]) / 2.0;
+                Self::from_bits(coefficients.iter().map(|&c| c > median))
+            }
+        }
+    }
+
+    /// Read a PNG and hash it.
+    #[cfg(feature = "png")]
+    pub fn of_png(kind: HashKind, path: impl AsRef<std::path::Path>) -> std::io::Result<Self> {
+        let image = crate::io::read_png(path)?;
+        Ok(Self::of(kind, &image.data, image.width, image.height))
+    }
+
+    /// Number of differing bits, from 0 (same hash) to 64. Images whose hashes are within a
+    /// handful of bits (around 5 to 10) usually look the same.
+    pub fn distance(self, other: Self) -> u32 {
+        (self.0 ^ other.0).count_ones()
+    }
+
+    fn from_bits(bits: impl Iterator<Item = bool>) -> Self {
+        Self(bits.take(64).fold(0, |hash, bit| hash << 1 | u64::from(bit)))
+    }
+}
+
+impl std::fmt::Display for ImageHash {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:016x}", self.0)
+    }
+}
+
+impl std::str::FromStr for ImageHash {
+    type Err = std::num::ParseIntError;
+
+    /// Parse the 16 hex digits written by `Display`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        u64::from_str_radix(s, 16).map(Self)
+    }
+}
+
+/// Luma of `img` box-filtered to `w` × `h`.
+fn thumbnail(img: &[u8], width: u32, height: u32, w: u32, h: u32) -> Vec<f32> {
+    luma(&resize_box(img, width, height, w, h))
+}
+
+/// The 8×8 lowest-frequency coefficients of the 2-D DCT-II of a 32×32 plane, row-major.
+fn low_frequencies(plane: &[f32]) -> [f64; 64] {
+    let basis: Vec<[f64; DCT_SIZE]> = (0..8)
+        .map(|u| {
+            std::array::from_fn(|x| {
+                (std::f64::consts::PI * (2 * x + 1) as f64 * u as f64 / (2 * DCT_SIZE) as f64).cos()
+            })
+        })
+        .collect();
+    // Transform the rows, then the columns of the result.
+    let rows: Vec<[f64; 8]> = plane
+        .chunks_exact(DCT_SIZE)
+        .map(|row| std::array::from_fn(|u| row.iter().zip(&basis[u]).map(|(&p, b)| p as f64 * b).sum()))
+        .collect();
+    std::array::from_fn(|i| {
+        let (v, u) = (i / 8, i % 8);
+        rows.iter().zip(&basis[v]).map(|(row, b)| row[u] * b).sum()
+    })
+}
@@ -0,0 +1,109 @@
+//! Images prepared once for comparison against many others.
+//!
+//! A suite that checks one baseline against many candidates, or one candidate against many
+//! baselines, would otherwise hash the same rows and convert the same pixels to luma in
+//! every call. A [`PreparedImage`] computes its row hashes and content hash up front and its
+//! luma plane on first use, and the comparisons between prepared images reuse them.
+
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::sync::OnceLock;
+
+use crate::align::{align_hashes, row_hashes, RowBand};
+use crate::ssim::{dssim_luma, luma, ssim_luma};
+use crate::{pixelmatch, validate_buffers, MatchResult, Options, PixelmatchError};
+
+/// An RGBA image with the structures its comparisons need computed once.
+#[derive(Debug)]
+pub struct PreparedImage<'a> {
+    data: &'a [u8],
+    width: u32,
+    height: u32,
+    row_hashes: Vec<u64>,
+    content_hash: u64,
+    luma: OnceLock<Vec<f32>>,
+}
+
+impl<'a> PreparedImage<'a> {
+    /// Prepare `data`, which must be `width * height * 4` bytes.
+    pub fn new(data: &'a [u8], width: u32, height: u32) -> Result<Self, PixelmatchError> {
+        validate_buffers(data, data, None, width, height)?;
+        let row_hashes = row_hashes(data, width, height);
+        let mut hasher = DefaultHasher::new();
+        (width, height, &row_hashes).hash(&mut hasher);
+        Ok(Self { data, width, height, row_hashes, content_hash: hasher.finish(), luma: OnceLock::new() })
+    }
+
+    pub fn data(&self) -> &'a [u8] {
+        self.data
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// Hash of each row, top to bottom.
+    pub fn row_hashes(&self) -> &[u64] {
+        &self.row_hashes
+    }
+
+    /// Hash of the size and pixels. Images with different hashes differ; equal hashes are
+    /// only very likely to mean equal images.
+    pub fn content_hash(&self) -> u64 {
+        self.content_hash
+    }
+
+    /// Luma of every pixel, blended over white, as used by [`crate::ssim`].
+    pub fn luma(&self) -> &[f32] {
+        self.luma.get_or_init(|| luma(self.data))
+    }
+
+    /// Whether `other` has the same size and pixels. Differing content hashes answer without
+    /// looking at the pixels.
+    pub fn same_as(&self, other: &PreparedImage) -> bool {
+        self.content_hash == other.content_hash
+            && (self.width, self.height) == (other.width, other.height)
+            && self.data == other.data
+    }
+
+    /// Compare with `other` like [`pixelmatch`], with this image as the first one.
+    pub fn compare(
+        &self,
+        other: &PreparedImage,
+        output: Option<&mut [u8]>,
+        options: &Options,
+    ) -> Result<MatchResult, PixelmatchError> {
+        pixelmatch(self.data, other.data, output, self.width, self.height, options)
+    }
+
+    /// [`align_rows`](crate::align::align_rows) from this image to `other`, using the cached
+    /// row hashes.
+    ///
+    /// # Panics
+    /// Panics if the images differ in width.
+    pub fn align_rows(&self, other: &PreparedImage) -> Vec<RowBand> {
+        assert_eq!(self.width, other.width, "images differ in width");
+        align_hashes(&self.row_hashes, &other.row_hashes)
+    }
+
+    /// [`ssim`](crate::ssim::ssim) of this image and `other`, using the cached luma planes.
+    pub fn ssim(&self, other: &PreparedImage) -> Result<f64, PixelmatchError> {
+        self.check_size(other)?;
+        Ok(ssim_luma(self.luma(), other.luma(), self.width as usize, self.height as usize))
+    }
+
+    /// [`dssim_map`](crate::ssim::dssim_map) of this image and `other`, using the cached luma
+    /// planes.
+    pub fn dssim_map(&self, other: &PreparedImage) -> Result<Vec<f32>, PixelmatchError> {
+        self.check_size(other)?;
+        Ok(dssim_luma(self.luma(), other.luma(), self.width as usize, self.height as usize))
+    }
+
+    fn check_size(&self, other: &PreparedImage) -> Result<(), PixelmatchError> {
+        validate_buffers(self.data, other.data, None, self.width, self.height).map(drop)
+    }
+}
@@ -0,0 +1,79 @@
+//! Per-row and per-column counts of differing pixels.
+//!
+//! Projecting the diff mask onto each axis shows where changes line up. A horizontal layout
+//! shift (a sidebar that grew, a right-to-left regression) changes a band of columns across
+//! most rows, which stands out in [`DiffProfile::columns`] even when the total count looks
+//! like scattered noise; a vertical shift does the same in [`DiffProfile::rows`].
+
+use std::ops::Range;
+
+use crate::{diff_mask, Options, PixelmatchError};
+
+/// Differing pixels per row and per column.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct DiffProfile {
+    /// Differing pixels in each row, top to bottom (`height` entries).
+    pub rows: Vec<u32>,
+    /// Differing pixels in each column, left to right (`width` entries).
+    pub columns: Vec<u32>,
+}
+
+impl DiffProfile {
+    /// Count the set pixels of a row-major mask per row and per column.
+    ///
+    /// # Panics
+    /// Panics if `mask.len() != width * height`.
+    pub fn from_mask(mask: &[bool], width: u32, height: u32) -> Self {
+        let (w, h) = (width as usize, height as usize);
+        assert_eq!(mask.len(), w * h, "mask length does not match width * height");
+        let mut rows = vec![0u32; h];
+        let mut columns = vec![0u32; w];
+        if w == 0 {
+            return Self { rows, columns };
+        }
+        for (row, count) in mask.chunks_exact(w).zip(&mut rows) {
+            for (&set, column) in row.iter().zip(&mut columns) {
+                *count += u32::from(set);
+                *column += u32::from(set);
+            }
+        }
+        Self { rows, columns }
+    }
+
+    /// Runs of consecutive rows with differing pixels, at least `min_count` in each.
+    pub fn row_bands(&self, min_count: u32) -> Vec<Range<u32>> {
+        bands(&self.rows, min_count)
+    }
+
+    /// Runs of consecutive columns with differing pixels, at least `min_count` in each, e.g.
+    /// the strip a sidebar moved into. A `min_count` near the image height picks out columns that
+    /// changed top to bottom.
+    pub fn column_bands(&self, min_count: u32) -> Vec<Range<u32>> {
+        bands(&self.columns, min_count)
+    }
+}
+
+fn bands(counts: &[u32], min_count: u32) -> Vec<Range<u32>> {
+    let mut bands: Vec<Range<u32>> = Vec::new();
+    for (i, _) in counts.iter().enumerate().filter(|&(_, &c)| c > 0 && c >= min_count) {
+        let i = i as u32;
+        match bands.last_mut() {
+            Some(band) if band.end == i => band.end += 1,
+            _ => bands.push(i..i + 1),
+        }
+    }
+    bands
+}
+
+/// The [`DiffProfile`] of the pixels [`pixelmatch`](crate::pixelmatch) would count as
+/// different, from the same [`diff_mask`].
+pub fn diff_profile(
+    img1: &[u8],
+    img2: &[u8],
+    width: u32,
+    height: u32,
+    options: &Options,
+) -> Result<DiffProfile, PixelmatchError> {
+    let mask = diff_mask(img1, img2, width, height, options)?;
+    Ok(DiffProfile::from_mask(&mask, width, height))
+}
@@ -0,0 +1,41 @@
+//! Mean squared error (MSE) and peak signal-to-noise ratio (PSNR).
+//!
+//! Neither says where two images differ, but PSNR is the figure codec and rendering
+//! pipelines are usually benchmarked by, so it is handy to report next to `diff_count`.
+//! Both are taken over the red, green and blue channels of each pixel blended over white,
+//! as SSIM blends them, so a fully transparent pixel matches white whatever colour
+//! it hides.
+
+use rayon::prelude::*;
+
+use crate::{validate_buffers, PixelmatchError};
+
+/// Mean squared difference per colour channel of two equally sized RGBA images, from 0.0
+/// (identical) to 65025.0 (black against white).
+pub fn mse(img1: &[u8], img2: &[u8], width: u32, height: u32) -> Result<f64, PixelmatchError> {
+    let len = validate_buffers(img1, img2, None, width, height)?;
+    if len == 0 {
+        return Ok(0.0);
+    }
+    let sum: f64 = img1
+        .par_chunks_exact(4)
+        .zip(img2.par_chunks_exact(4))
+        .with_min_len(1024)
+        .filter(|(p1, p2)| p1 != p2)
+        .map(|(p1, p2)| (0..3).map(|c| (over_white(p1, c) - over_white(p2, c)).powi(2)).sum::<f64>())
+        .sum();
+    Ok(sum / (len * 3) as f64)
+}
+
+/// PSNR in decibels of two equally sized RGBA images, `10 log10(255² / MSE)`: around 30 to
+/// 50 for lossy copies of the same picture, higher the closer they are, and
+/// [`f64::INFINITY`] when [`mse`] is 0.
+pub fn psnr(img1: &[u8], img2: &[u8], width: u32, height: u32) -> Result<f64, PixelmatchError> {
+    let mse = mse(img1, img2, width, height)?;
+    Ok(if mse == 0.0 { f64::INFINITY } else { 10.0 * (255.0 * 255.0 / mse).log10() })
+}
+
+/// Channel `c` of an RGBA pixel blended over white.
+fn over_white(p: &[u8], c: usize) -> f64 {
+    255.0 + (p[c] as f64 - 255.0) * p[3] as f64 / 255.0
+}
@@ -0,0 +1,113 @@
+use std::borrow::Cow;
+
+use numpy::{PyArray1, PyArray3, PyArrayMethods, PyReadonlyArray3, PyUntypedArrayMethods};
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+
+use crate::Options;
+
+/// Result of a comparison.
+#[pyclass(frozen, get_all, name = "MatchResult", module = "pixelmatch")]
+pub struct PyMatchResult {
+    /// Number of mismatched pixels.
+    diff_count: u32,
+    /// Number of anti-aliased pixels detected.
+    aa_count: u32,
+    /// Whether the two images are byte-identical.
+    identical: bool,
+    /// H×W×4 uint8 diff image, if requested with `diff=True`.
+    diff: Option<Py<PyArray3<u8>>>,
+}
+
+#[pymethods]
+impl PyMatchResult {
+    fn __repr__(&self) -> String {
+        format!(
+            "MatchResult(diff_count={}, aa_count={}, identical={}, diff={})",
+            self.diff_count,
+            self.aa_count,
+            if self.identical { "True" } else { "False" },
+            if self.diff.is_some() { "<array>" } else { "None" }
+        )
+    }
+}
+
+/// The pixels of an H×W×4 uint8 array, borrowed when C-contiguous.
+fn rgba<'a>(array: &'a PyReadonlyArray3<'_, u8>, name: &str) -> PyResult<(Cow<'a, [u8]>, u32, u32)> {
+    let shape = array.shape();
+    if shape[2] != 4 {
+        return Err(PyValueError::new_err(format!("{name}: expected an H×W×4 uint8 array, got shape {shape:?}")));
+    }
+    let (height, width) = (shape[0] as u32, shape[1] as u32);
+    let data = match array.as_slice() {
+        Ok(slice) => Cow::Borrowed(slice),
+        Err(_) => Cow::Owned(array.as_array().iter().copied().collect()),
+    };
+    Ok((data, width, height))
+}
+
+/// Compare two H×W×4 uint8 RGBA arrays. With `diff=True`, the result's `diff` holds the diff
+/// image as a new array. The GIL is released while comparing.
+#[pyfunction(name = "pixelmatch")]
+#[pyo3(signature = (
+    img1,
+    img2,
+    *,
+    threshold = 0.1,
+    detect_anti_aliasing = true,
+    alpha = 0.1,
+    aa_color = [255, 255, 0],
+    diff_color = [255, 0, 0],
+    diff_color_alt = None,
+    diff_mask = false,
+    diff = false,
+))]
+#[allow(clippy::too_many_arguments)]
+fn compare(
+    py: Python<'_>,
+    img1: PyReadonlyArray3<'_, u8>,
+    img2: PyReadonlyArray3<'_, u8>,
+    threshold: f64,
+    detect_anti_aliasing: bool,
+    alpha: f64,
+    aa_color: [u8; 3],
+    diff_color: [u8; 3],
+    diff_color_alt: Option<[u8; 3]>,
+    diff_mask: bool,
+    diff: bool,
+) -> PyResult<PyMatchResult> {
+    let (data1, width, height) = rgba(&img1, "img1")?;
+    let (data2, w2, h2) = rgba(&img2, "img2")?;
+    if (width, height) != (w2, h2) {
+        return Err(PyValueError::new_err(format!(
+            "Image dimensions do not match: {width}x{height} vs {w2}x{h2}"
+        )));
+    }
+    let options = Options {
+        threshold,
+        detect_anti_aliasing,
+        alpha,
+        aa_color,
+        diff_color,
+        diff_color_alt,
+        diff_mask,
+        ..Default::default()
+    };
+    let mut output = diff.then(|| vec![0u8; data1.len()]);
+    let result = py
+        .detach(|| crate::pixelmatch(&data1, &data2, output.as_deref_mut(), width, height, &options))
+        .map_err(|e| PyValueError::new_err(e.to_string()))?;
+    let diff = match output {
+        Some(out) => Some(PyArray1::from_vec(py, out).reshape([height as usize, width as usize, 4])?.unbind()),
+        None => None,
+    };
+    Ok(PyMatchResult { diff_count: result.diff_count, aa_count: result.aa_count, identical: result.identical, diff })
+}
+
+#[pymodule]
+#[pyo3(name = "pixelmatch")]
+fn pixelmatch_module(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_function(wrap_pyfunction!(compare, m)?)?;
+    m.add_class::<PyMatchResult>()?;
+    Ok(())
+}
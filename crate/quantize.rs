@@ -0,0 +1,101 @@
+//! Colour quantisation pre-pass used by [`Options::quantize`](crate::Options::quantize).
+//!
+//! Palette-based renderers (GIF encoders, indexed PNG exporters, retro-style UIs) often
+//! disagree by a unit or two in a channel from one version to the next. Mapping both images
+//! to one palette built from their combined colours with median cut makes those truncation
+//! differences vanish, while colours far enough apart to land in different palette entries
+//! still differ.
+
+use std::collections::HashMap;
+
+use rayon::prelude::*;
+
+/// Build a palette of at most `colors` RGBA colours shared by both images, by median cut
+/// over their combined colour histogram. Returns the palette and the entry each distinct
+/// input colour (as a native-endian `u32`) maps to.
+fn median_cut(img1: &[u8], img2: &[u8], colors: usize) -> (Vec<[u8; 4]>, HashMap<u32, usize>) {
+    let histogram = [img1, img2]
+        .par_iter()
+        .flat_map(|img| img.par_chunks_exact(4))
+        .fold(HashMap::new, |mut counts: HashMap<u32, u64>, p| {
+            *counts.entry(u32::from_ne_bytes([p[0], p[1], p[2], p[3]])).or_default() += 1;
+            counts
+        })
+        .reduce(HashMap::new, |mut a, b| {
+            for (colour, count) in b {
+                *a.entry(colour).or_default() += count;
+            }
+            a
+        });
+    let mut entries: Vec<([u8; 4], u64)> = histogram.into_iter().map(|(c, n)| (c.to_ne_bytes(), n)).collect();
+    // Sort for a result that does not depend on hash order.
+    entries.sort_unstable();
+
+    // Each box is a range of `entries`; split the one with the widest channel at its
+    // weighted median until there are enough boxes or none can be split.
+    let mut boxes: Vec<_> = (!entries.is_empty()).then_some(0..entries.len()).into_iter().collect();
+    while boxes.len() < colors {
+        let widest = boxes
+            .iter()
+            .enumerate()
+            .filter(|(_, b)| b.len() > 1)
+            .map(|(i, b)| (i, widest_channel(&entries[b.clone()])))
+            .max_by_key(|&(i, (_, spread))| (spread, std::cmp::Reverse(i)));
+        let Some((index, (channel, _))) = widest else { break };
+        let range = boxes[index].clone();
+        let slice = &mut entries[range.clone()];
+        slice.sort_unstable_by_key(|(c, _)| c[channel]);
+        let half = slice.iter().map(|(_, n)| n).sum::<u64>() / 2;
+        let mut seen = 0;
+        let split = slice.iter().position(|(_, n)| {
+            seen += n;
+            seen > half
+        });
+        let split = split.unwrap_or(0).clamp(1, slice.len() - 1);
+        boxes[index] = range.start..range.start + split;
+        boxes.push(range.start + split..range.end);
+    }
+
+    let mut lookup = HashMap::with_capacity(entries.len());
+    let palette = boxes
+        .iter()
+        .enumerate()
+        .map(|(index, b)| {
+            let mut sum = [0u64; 4];
+            let mut total = 0;
+            for &(colour, n) in &entries[b.clone()] {
+                lookup.insert(u32::from_ne_bytes(colour), index);
+                sum.iter_mut().zip(colour).for_each(|(s, c)| *s += c as u64 * n);
+                total += n;
+            }
+            sum.map(|s| ((s + total / 2) / total) as u8)
+        })
+        .collect();
+    (palette, lookup)
+}
+
+/// The channel with the largest spread among `entries`, and that spread.
+fn widest_channel(entries: &[([u8; 4], u64)]) -> (usize, u8) {
+    (0..4)
+        .map(|c| {
+            let (min, max) = entries.iter().fold((u8::MAX, 0), |(lo, hi), (p, _)| (lo.min(p[c]), hi.max(p[c])));
+            (c, max - min)
+        })
+        .max_by_key(|&(c, spread)| (spread, std::cmp::Reverse(c)))
+        .unwrap_or((0, 0))
+}
+
+/// Copies of two RGBA images with every pixel replaced by its entry in a palette of at most
+/// `colors` colours built from both. Images with no more distinct colours than that come
+/// back unchanged.
+pub fn quantize_pair(img1: &[u8], img2: &[u8], colors: u32) -> (Vec<u8>, Vec<u8>) {
+    let (palette, lookup) = median_cut(img1, img2, colors.max(1) as usize);
+    let map = |img: &[u8]| {
+        let mut out = vec![0u8; img.len()];
+        out.par_chunks_exact_mut(4).zip(img.par_chunks_exact(4)).for_each(|(o, p)| {
+            o.copy_from_slice(&palette[lookup[&u32::from_ne_bytes([p[0], p[1], p[2], p[3]])]]);
+        });
+        out
+    };
+    rayon::join(|| map(img1), || map(img2))
+}
@@ -0,0 +1,97 @@
+use crate::{diff_mask, Options, PixelmatchError};
+
+/// A connected group of mismatched pixels.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DiffRegion {
+    /// Left edge of the bounding box.
+    pub x: u32,
+    /// Top edge of the bounding box.
+    pub y: u32,
+    /// Width of the bounding box.
+    pub width: u32,
+    /// Height of the bounding box.
+    pub height: u32,
+    /// Number of mismatched pixels in the region.
+    pub pixel_count: u32,
+}
+
+/// Group the set pixels of a row-major mask into 8-connected components.
+///
+/// Regions are returned in scan order of their first (top-left-most) pixel.
+///
+/// # Panics
+/// Panics if `mask.len() != width * height`.
+pub fn find_regions(mask: &[bool], width: u32, height: u32) -> Vec<DiffRegion> {
+    label_regions(mask, width, height).0
+}
+
+/// Compare two images like [`pixelmatch`](crate::pixelmatch) and group the pixels counted
+/// in `diff_count` into 8-connected regions, leaving out those of fewer than `min_pixels`
+/// pixels. Regions are returned in the order of [`find_regions`]; their `pixel_count`s add
+/// up to `diff_count` when `min_pixels` is at most 1.
+pub fn pixelmatch_regions(
+    img1: &[u8],
+    img2: &[u8],
+    width: u32,
+    height: u32,
+    options: &Options,
+    min_pixels: u32,
+) -> Result<Vec<DiffRegion>, PixelmatchError> {
+    let mask = diff_mask(img1, img2, width, height, options)?;
+    let mut regions = find_regions(&mask, width, height);
+    regions.retain(|r| r.pixel_count >= min_pixels);
+    Ok(regions)
+}
+
+/// Like [`find_regions`], also returning for each pixel the index of its region plus one,
+/// or 0 for pixels outside every region.
+pub(crate) fn label_regions(mask: &[bool], width: u32, height: u32) -> (Vec<DiffRegion>, Vec<u32>) {
+    let w = width as usize;
+    let h = height as usize;
+    assert_eq!(mask.len(), w * h, "mask length does not match width * height");
+
+    let mut labels = vec![0u32; mask.len()];
+    let mut stack = Vec::new();
+    let mut regions = Vec::new();
+
+    for start in 0..mask.len() {
+        if !mask[start] || labels[start] != 0 {
+            continue;
+        }
+        let label = regions.len() as u32 + 1;
+        labels[start] = label;
+        stack.push(start);
+
+        let (mut min_x, mut min_y) = (start % w, start / w);
+        let (mut max_x, mut max_y) = (min_x, min_y);
+        let mut count: u32 = 0;
+
+        while let Some(i) = stack.pop() {
+            let (x, y) = (i % w, i / w);
+            count += 1;
+            min_x = min_x.min(x);
+            max_x = max_x.max(x);
+            min_y = min_y.min(y);
+            max_y = max_y.max(y);
+
+            for ny in y.saturating_sub(1)..=(y + 1).min(h - 1) {
+                for nx in x.saturating_sub(1)..=(x + 1).min(w - 1) {
+                    let j = ny * w + nx;
+                    if mask[j] && labels[j] == 0 {
+                        labels[j] = label;
+                        stack.push(j);
+                    }
+                }
+            }
+        }
+
+        regions.push(DiffRegion {
+            x: min_x as u32,
+            y: min_y as u32,
+            width: (max_x - min_x + 1) as u32,
+            height: (max_y - min_y + 1) as u32,
+            pixel_count: count,
+        });
+    }
+    (regions, labels)
+}
@@ -0,0 +1,317 @@
+use std::fmt::Write;
+
+use crate::MatchResult;
+
+/// Maximum body length (in characters) GitHub accepts for a pull request comment.
+pub const GITHUB_COMMENT_LIMIT: usize = 65536;
+
+/// A single named comparison to include in a report.
+#[derive(Debug, Clone)]
+pub struct ReportEntry {
+    /// Display name of the comparison (usually the image path relative to the baseline root).
+    pub name: String,
+    /// Result of the comparison.
+    pub result: MatchResult,
+    /// Whether the comparison passed. Defaults to `diff_count == 0`.
+    pub passed: bool,
+    /// Explanation shown instead of pixel statistics when the images could not be compared
+    /// (e.g. missing baseline or mismatched dimensions).
+    pub message: Option<String>,
+    /// Link to the baseline image, if published.
+    pub baseline_url: Option<String>,
+    /// Link to the candidate image, if published.
+    pub candidate_url: Option<String>,
+    /// Link to the diff image, if published.
+    pub diff_url: Option<String>,
+    /// Link to a downscaled baseline image for inline display.
+    pub baseline_thumbnail_url: Option<String>,
+    /// Link to a downscaled candidate image for inline display.
+    pub candidate_thumbnail_url: Option<String>,
+    /// Link to a downscaled diff image for inline display.
+    pub diff_thumbnail_url: Option<String>,
+}
+
+impl ReportEntry {
+    /// Create an entry that passes when no pixels differ.
+    pub fn new(name: impl Into<String>, result: MatchResult) -> Self {
+        Self {
+            name: name.into(),
+            result,
+            passed: result.diff_count == 0,
+            message: None,
+            baseline_url: None,
+            candidate_url: None,
+            diff_url: None,
+            baseline_thumbnail_url: None,
+            candidate_thumbnail_url: None,
+            diff_thumbnail_url: None,
+        }
+    }
+}
+
+/// Compact Markdown formatter for posting visual diff summaries as pull request comments.
+///
+/// Failures are listed first, followed by passing comparisons (if enabled). Rows that
+/// would push the report past `max_len` are dropped and summarised in a trailing note,
+/// so the output always fits within the comment limit (measured in bytes, which is a
+/// conservative bound on GitHub's character limit).
+#[derive(Debug, Clone)]
+pub struct MarkdownReport {
+    /// Heading of the report. Default: "Visual diff report"
+    pub title: String,
+    /// Maximum length of the rendered report in bytes. Default: [`GITHUB_COMMENT_LIMIT`]
+    pub max_len: usize,
+    /// Include passing comparisons in the table. Default: false
+    pub show_passing: bool,
+    /// Embed diff images of failing comparisons inline in a collapsible section. Default: true
+    pub inline_images: bool,
+}
+
+impl Default for MarkdownReport {
+    fn default() -> Self {
+        Self {
+            title: "Visual diff report".to_string(),
+            max_len: GITHUB_COMMENT_LIMIT,
+            show_passing: false,
+            inline_images: true,
+        }
+    }
+}
+
+impl MarkdownReport {
+    /// Render the entries into a Markdown document no longer than `max_len` bytes.
+    pub fn render(&self, entries: &[ReportEntry]) -> String {
+        let failed: Vec<&ReportEntry> = entries.iter().filter(|e| !e.passed).collect();
+        let passed = entries.len() - failed.len();
+
+        let mut out = String::new();
+        let badge = if failed.is_empty() {
+            badge("visual diff", "passed", "brightgreen")
+        } else {
+            badge("visual diff", &format!("{} failed", failed.len()), "red")
+        };
+        let _ = writeln!(out, "## {} {badge}\n", escape(&self.title));
+        let _ = writeln!(
+            out,
+            "**{}** compared · **{passed}** passed · **{}** failed\n",
+            entries.len(),
+            failed.len()
+        );
+
+        let rows: Vec<&ReportEntry> = if self.show_passing {
+            failed.iter().copied().chain(entries.iter().filter(|e| e.passed)).collect()
+        } else {
+            failed.clone()
+        };
+        if rows.is_empty() {
+            return truncate(out, self.max_len);
+        }
+
+        // Reserve room for the trailing "not shown" note so it always fits.
+        let reserve = 64;
+        let budget = self.max_len.saturating_sub(reserve);
+
+        out.push_str("| | Name | Diff pixels | Diff | Images |\n|---|---|---:|---:|---|\n");
+        let mut shown = 0;
+        for entry in &rows {
+            let row = table_row(entry);
+            if out.len() + row.len() > budget {
+                break;
+            }
+            out.push_str(&row);
+            shown += 1;
+        }
+
+        if self.inline_images && shown > 0 {
+            let mut details = String::from("\n<details><summary>Diff images</summary>\n\n");
+            let mut images = 0;
+            for entry in rows.iter().take(shown).filter(|e| !e.passed) {
+                if let Some(url) = &entry.diff_url {
+                    let name = escape(&entry.name);
+                    let _ = writeln!(details, "**{name}**\n\n![{name}]({})\n", escape_url(url));
+                    images += 1;
+                }
+            }
+            details.push_str("</details>\n");
+            // Images are optional detail: skip the section entirely rather than truncate it.
+            if images > 0 && out.len() + details.len() <= budget {
+                out.push_str(&details);
+            }
+        }
+
+        if shown < rows.len() {
+            let _ = writeln!(out, "\n_…and {} more not shown._", rows.len() - shown);
+        }
+        truncate(out, self.max_len)
+    }
+}
+
+/// Self-contained HTML visual report with side-by-side baseline, candidate and diff images.
+///
+/// Images are referenced by URL (typically relative paths next to the report); thumbnails
+/// are displayed when present and link to the full-size image.
+#[derive(Debug, Clone)]
+pub struct HtmlReport {
+    /// Page title and heading. Default: "Visual diff report"
+    pub title: String,
+    /// Include passing comparisons. Default: false
+    pub show_passing: bool,
+}
+
+impl Default for HtmlReport {
+    fn default() -> Self {
+        Self { title: "Visual diff report".to_string(), show_passing: false }
+    }
+}
+
+impl HtmlReport {
+    /// Render the entries into an HTML document. Failures are listed first.
+    pub fn render(&self, entries: &[ReportEntry]) -> String {
+        let failed = entries.iter().filter(|e| !e.passed).count();
+        let title = escape_html(&self.title);
+        let mut out = String::new();
+        let _ = write!(out, "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n");
+        let _ = write!(out, "<title>{title}</title>\n<style>{HTML_STYLE}</style>\n</head>\n<body>\n<h1>{title}</h1>\n");
+        let _ = writeln!(
+            out,
+            "<p><b>{}</b> compared · <b>{}</b> passed · <b class=\"{}\">{failed}</b> failed</p>",
+            entries.len(),
+            entries.len() - failed,
+            if failed > 0 { "fail" } else { "pass" }
+        );
+
+        let rows = entries
+            .iter()
+            .filter(|e| !e.passed)
+            .chain(entries.iter().filter(|e| e.passed && self.show_passing));
+        for entry in rows {
+            let (class, label) = if entry.passed { ("pass", "passed") } else { ("fail", "failed") };
+            let detail = match &entry.message {
+                Some(message) => escape_html(message),
+                None => format!(
+                    "{} different pixels ({:.2}%), {} anti-aliased",
+                    entry.result.diff_count,
+                    entry.result.diff_ratio() * 100.0,
+                    entry.result.aa_count
+                ),
+            };
+            let _ = writeln!(
+                out,
+                "<section class=\"{class}\">\n<h2><span class=\"badge\">{label}</span> {}</h2>\n<p>{detail}</p>",
+                escape_html(&entry.name)
+            );
+            out.push_str("<div class=\"images\">\n");
+            for (caption, url, thumb) in [
+                ("baseline", &entry.baseline_url, &entry.baseline_thumbnail_url),
+                ("candidate", &entry.candidate_url, &entry.candidate_thumbnail_url),
+                ("diff", &entry.diff_url, &entry.diff_thumbnail_url),
+            ] {
+                let Some(src) = thumb.as_ref().or(url.as_ref()) else { continue };
+                let img = format!("<img src=\"{}\" alt=\"{caption}\" loading=\"lazy\">", escape_html(src));
+                let img = match url {
+                    Some(url) => format!("<a href=\"{}\">{img}</a>", escape_html(url)),
+                    None => img,
+                };
+                let _ = writeln!(out, "<figure>{img}<figcaption>{caption}</figcaption></figure>");
+            }
+            out.push_str("</div>\n</section>\n");
+        }
+        out.push_str("</body>\n</html>\n");
+        out
+    }
+}
+
+const HTML_STYLE: &str = "body{font-family:system-ui,sans-serif;margin:2em;color:#222}\
+section{border-left:4px solid #ccc;padding:0 1em;margin:1.5em 0}\
+section.fail{border-color:#d33}section.pass{border-color:#2a2}\
+.badge{font-size:.7em;padding:.2em .5em;border-radius:3px;color:#fff;background:#2a2;vertical-align:middle}\
+.fail .badge{background:#d33}b.fail{color:#d33}\
+.images{display:flex;gap:1em;flex-wrap:wrap}figure{margin:0}\
+img{max-width:320px;border:1px solid #ddd;image-rendering:pixelated}figcaption{text-align:center;color:#666}";
+
+fn escape_html(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+fn table_row(entry: &ReportEntry) -> String {
+    let status = if entry.passed { "✅" } else { "❌" };
+    let mut links = Vec::new();
+    for (label, url) in [
+        ("baseline", &entry.baseline_url),
+        ("candidate", &entry.candidate_url),
+        ("diff", &entry.diff_url),
+    ] {
+        if let Some(url) = url {
+            links.push(format!("[{label}]({})", escape_url(url)));
+        }
+    }
+    let name = entry.name.replace('`', "'").replace('|', "\\|");
+    match &entry.message {
+        Some(message) => format!("| {status} | `{name}` | {} | | {} |\n", escape(message), links.join(" · ")),
+        None => format!(
+            "| {status} | `{name}` | {} | {:.2}% | {} |\n",
+            entry.result.diff_count,
+            entry.result.diff_ratio() * 100.0,
+            links.join(" · ")
+        ),
+    }
+}
+
+fn badge(label: &str, message: &str, color: &str) -> String {
+    let enc = |s: &str| s.replace('-', "--").replace('_', "__").replace(' ', "%20");
+    format!("![{message}](https://img.shields.io/badge/{}-{}-{color})", enc(label), enc(message))
+}
+
+/// Percent-encode the characters that would end a Markdown link destination or table cell,
+/// leaving the rest of the URL (including existing `%` escapes) as given.
+fn escape_url(url: &str) -> String {
+    let mut out = String::with_capacity(url.len());
+    for c in url.chars() {
+        match c {
+            ' ' | '(' | ')' | '<' | '>' | '|' | '"' | '`' => {
+                let _ = write!(out, "%{:02X}", c as u32);
+            }
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+/// Escape characters that would otherwise be interpreted as Markdown or HTML.
+fn escape(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '\\' | '*' | '_' | '[' | ']' | '|' | '`' | '#' => {
+                out.push('\\');
+                out.push(c);
+            }
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+fn truncate(mut s: String, max_len: usize) -> String {
+    if s.len() > max_len {
+        let mut end = max_len;
+        while !s.is_char_boundary(end) {
+            end -= 1;
+        }
+        s.truncate(end);
+    }
+    s
+}
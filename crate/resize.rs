@@ -0,0 +1,84 @@
+/// Source pixel indices and coverage weights contributing to each output pixel along one axis.
+fn axis_weights(src: u32, dst: u32) -> Vec<Vec<(usize, f64)>> {
+    let scale = src as f64 / dst as f64;
+    (0..dst)
+        .map(|o| {
+            let start = o as f64 * scale;
+            let end = ((o + 1) as f64 * scale).min(src as f64);
+            let first = start.floor() as usize;
+            let last = (end.ceil() as usize).clamp(first + 1, src as usize);
+            (first..last)
+                .map(|i| (i, end.min(i as f64 + 1.0) - start.max(i as f64)))
+                .filter(|&(_, w)| w > 0.0)
+                .collect()
+        })
+        .collect()
+}
+
+/// Resize an RGBA image using a box (area-averaging) filter.
+///
+/// Each output pixel is the coverage-weighted average of the source pixels it overlaps.
+/// Colour channels are averaged with alpha weighting so transparent pixels do not darken
+/// the result.
+pub fn resize_box(img: &[u8], width: u32, height: u32, new_width: u32, new_height: u32) -> Vec<u8> {
+    debug_assert_eq!(img.len(), width as usize * height as usize * 4);
+    let mut out = vec![0u8; new_width as usize * new_height as usize * 4];
+    if width == 0 || height == 0 || new_width == 0 || new_height == 0 {
+        return out;
+    }
+    let xw = axis_weights(width, new_width);
+    let yw = axis_weights(height, new_height);
+    let w = width as usize;
+
+    for (oy, ys) in yw.iter().enumerate() {
+        for (ox, xs) in xw.iter().enumerate() {
+            let (mut r, mut g, mut b, mut a, mut total) = (0.0, 0.0, 0.0, 0.0, 0.0);
+            for &(sy, wy) in ys {
+                for &(sx, wx) in xs {
+                    let weight = wx * wy;
+                    let i = (sy * w + sx) * 4;
+                    let pa = img[i + 3] as f64 * weight;
+                    r += img[i] as f64 * pa;
+                    g += img[i + 1] as f64 * pa;
+                    b += img[i + 2] as f64 * pa;
+                    a += pa;
+                    total += weight;
+                }
+            }
+            let o = (oy * new_width as usize + ox) * 4;
+            if a > 0.0 {
+                out[o] = (r / a).round() as u8;
+                out[o + 1] = (g / a).round() as u8;
+                out[o + 2] = (b / a).round() as u8;
+            }
+            out[o + 3] = (a / total).round() as u8;
+        }
+    }
+    out
+}
+
+/// Dimensions that fit within `max_size` × `max_size` while preserving aspect ratio.
+///
+/// Images already within bounds are returned unchanged (never upscaled).
+pub fn fit_within(width: u32, height: u32, max_size: u32) -> (u32, u32) {
+    if width <= max_size && height <= max_size {
+        return (width, height);
+    }
+    let scale = max_size as f64 / width.max(height) as f64;
+    (((width as f64 * scale).round() as u32).max(1), ((height as f64 * scale).round() as u32).max(1))
+}
+
+/// Downscale an RGBA image to fit within `max_size` × `max_size` for previews.
+///
+/// Returns the thumbnail data and its dimensions.
+pub fn thumbnail(img: &[u8], width: u32, height: u32, max_size: u32) -> (Vec<u8>, u32, u32) {
+    let (tw, th) = fit_within(width, height, max_size);
+    (resize_box(img, width, height, tw, th), tw, th)
+}
+
+/// Size at device-pixel ratio `to` of an image that is `width` × `height` at ratio `from`,
+/// e.g. 200 × 100 at ratio 1 is 400 × 200 at ratio 2.
+pub fn dpr_size(width: u32, height: u32, from: f32, to: f32) -> (u32, u32) {
+    let scale = to as f64 / from as f64;
+    ((width as f64 * scale).round() as u32, (height as f64 * scale).round() as u32)
+}
@@ -0,0 +1,212 @@
+//! Shared review state for failing comparisons.
+//!
+//! A test run records its failures into a JSON manifest with [`Review::record`]; a reviewer
+//! (the `pixelmatch review` command, a GUI, a bot) then approves or rejects them. Approving
+//! copies the candidate over the baseline. Both the baseline and the manifest are replaced
+//! atomically, so a crash or a concurrent reader never sees a half-written file.
+//!
+//! ```json
+//! {
+//!   "version": 1,
+//!   "entries": [
+//!     {
+//!       "name": "login.png",
+//!       "baseline": "baselines/login.png",
+//!       "candidate": "candidates/login.png",
+//!       "diff": "diffs/login.png",
+//!       "artifacts": null,
+//!       "diff_count": 120,
+//!       "total_pixels": 480000,
+//!       "message": null,
+//!       "status": "pending"
+//!     }
+//!   ]
+//! }
+//! ```
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+/// Manifest format version written by this library.
+pub const MANIFEST_VERSION: u32 = 1;
+
+/// Review state of one entry.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ReviewStatus {
+    /// Awaiting a decision.
+    Pending,
+    /// The candidate was copied over the baseline.
+    Approved,
+    /// The candidate was rejected; the baseline is unchanged.
+    Rejected,
+}
+
+/// A failing comparison awaiting review.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ReviewEntry {
+    /// Unique name, usually the image path relative to the baseline root.
+    pub name: String,
+    pub baseline: PathBuf,
+    pub candidate: PathBuf,
+    /// Diff image, if one was written.
+    #[serde(default)]
+    pub diff: Option<PathBuf>,
+    /// Directory of `DiffArtifacts`, if written.
+    #[serde(default)]
+    pub artifacts: Option<PathBuf>,
+    #[serde(default)]
+    pub diff_count: Option<u32>,
+    #[serde(default)]
+    pub total_pixels: Option<u64>,
+    /// Why the comparison failed when there is no pixel count (e.g. a missing baseline).
+    #[serde(default)]
+    pub message: Option<String>,
+    pub status: ReviewStatus,
+}
+
+impl ReviewEntry {
+    /// A pending entry with no diff or statistics.
+    pub fn new(name: impl Into<String>, baseline: impl Into<PathBuf>, candidate: impl Into<PathBuf>) -> Self {
+        Self {
+            name: name.into(),
+            baseline: baseline.into(),
+            candidate: candidate.into(),
+            diff: None,
+            artifacts: None,
+            diff_count: None,
+            total_pixels: None,
+            message: None,
+            status: ReviewStatus::Pending,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct Manifest {
+    version: u32,
+    entries: Vec<ReviewEntry>,
+}
+
+/// A review manifest on disk.
+#[derive(Debug, Clone)]
+pub struct Review {
+    path: PathBuf,
+    entries: Vec<ReviewEntry>,
+}
+
+impl Review {
+    /// Load the manifest at `path`, or start an empty one if it does not exist.
+    pub fn open(path: impl Into<PathBuf>) -> io::Result<Self> {
+        let path = path.into();
+        let entries = match fs::read(&path) {
+            Ok(bytes) => {
+                let manifest: Manifest = serde_json::from_slice(&bytes).map_err(|e| invalid_data(&path, e))?;
+                if manifest.version > MANIFEST_VERSION {
+                    let e = format!("unsupported manifest version {}", manifest.version);
+                    return Err(invalid_data(&path, e));
+                }
+                manifest.entries
+            }
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Vec::new(),
+            Err(e) => return Err(e),
+        };
+        Ok(Self { path, entries })
+    }
+
+    /// Path of the manifest.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// All entries, in the order they were recorded.
+    pub fn entries(&self) -> &[ReviewEntry] {
+        &self.entries
+    }
+
+    /// Entries awaiting a decision.
+    pub fn pending(&self) -> impl Iterator<Item = &ReviewEntry> {
+        self.entries.iter().filter(|e| e.status == ReviewStatus::Pending)
+    }
+
+    /// The entry called `name`.
+    pub fn get(&self, name: &str) -> Option<&ReviewEntry> {
+        self.entries.iter().find(|e| e.name == name)
+    }
+
+    /// Add `entry` as pending, replacing any entry with the same name. Call
+    /// [`save`](Self::save) to persist it.
+    pub fn record(&mut self, mut entry: ReviewEntry) {
+        entry.status = ReviewStatus::Pending;
+        match self.entries.iter_mut().find(|e| e.name == entry.name) {
+            Some(existing) => *existing = entry,
+            None => self.entries.push(entry),
+        }
+    }
+
+    /// Remove the entry called `name`, e.g. because it passes again. Call
+    /// [`save`](Self::save) to persist it.
+    pub fn remove(&mut self, name: &str) -> Option<ReviewEntry> {
+        let i = self.entries.iter().position(|e| e.name == name)?;
+        Some(self.entries.remove(i))
+    }
+
+    /// Drop every entry that is no longer pending. Call [`save`](Self::save) to persist it.
+    pub fn prune(&mut self) {
+        self.entries.retain(|e| e.status == ReviewStatus::Pending);
+    }
+
+    /// Copy the candidate of `name` over its baseline and mark it approved, saving the
+    /// manifest.
+    pub fn approve(&mut self, name: &str) -> io::Result<()> {
+        let entry = self.entry_mut(name)?;
+        let candidate = fs::read(&entry.candidate).map_err(|e| with_path(&entry.candidate, e))?;
+        atomic_write(&entry.baseline, &candidate).map_err(|e| with_path(&entry.baseline, e))?;
+        entry.status = ReviewStatus::Approved;
+        self.save()
+    }
+
+    /// Mark `name` rejected, leaving its baseline untouched, and save the manifest.
+    pub fn reject(&mut self, name: &str) -> io::Result<()> {
+        self.entry_mut(name)?.status = ReviewStatus::Rejected;
+        self.save()
+    }
+
+    /// Write the manifest.
+    pub fn save(&self) -> io::Result<()> {
+        let manifest = Manifest { version: MANIFEST_VERSION, entries: self.entries.clone() };
+        let mut json = serde_json::to_vec_pretty(&manifest).map_err(io::Error::other)?;
+        json.push(b'\n');
+        atomic_write(&self.path, &json).map_err(|e| with_path(&self.path, e))
+    }
+
+    fn entry_mut(&mut self, name: &str) -> io::Result<&mut ReviewEntry> {
+        self.entries
+            .iter_mut()
+            .find(|e| e.name == name)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, format!("no review entry named \"{name}\"")))
+    }
+}
+
+/// Write `data` to a temporary file next to `path` and rename it into place.
+fn atomic_write(path: &Path, data: &[u8]) -> io::Result<()> {
+    let dir = path.parent().filter(|p| !p.as_os_str().is_empty()).unwrap_or(Path::new("."));
+    fs::create_dir_all(dir)?;
+    let file_name = path.file_name().ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "not a file path"))?;
+    let tmp = dir.join(format!(".{}.{}.tmp", file_name.to_string_lossy(), std::process::id()));
+    fs::write(&tmp, data)?;
+    fs::rename(&tmp, path).inspect_err(|_| {
+        let _ = fs::remove_file(&tmp);
+    })
+}
+
+fn with_path(path: &Path, e: io::Error) -> io::Error {
+    io::Error::new(e.kind(), format!("{}: {e}", path.display()))
+}
+
+fn invalid_data(path: &Path, e: impl std::fmt::Display) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, format!("{}: {e}", path.display()))
+}
@@ -0,0 +1,75 @@
+//! Comparison of one rectangle of two images, in place.
+//!
+//! Checking one widget of a full-page screenshot by cropping both images first copies every
+//! row of the widget twice. [`pixelmatch_roi`] reads the rectangle straight out of the full
+//! buffers and writes a diff only the size of the rectangle.
+
+use crate::{
+    compare_rows, ignore_mask, rejected, validate_buffers, MatchResult, Options, PixelmatchError, Rect,
+};
+
+/// Compare the `roi` rectangle of two equally sized RGBA images `width` × `height` pixels.
+///
+/// `output`, if given, receives the diff of the rectangle alone, `roi.width * roi.height * 4`
+/// bytes. Pixels at the edge of `roi` see their neighbours outside it, as in a full
+/// comparison, so the counts are those [`pixelmatch`](crate::pixelmatch) finds inside the
+/// rectangle. Ignore regions, polygons and masks and [`MatchResult::diff_bounds`] are in
+/// image coordinates; `total_pixels` is the area of `roi`. Options that work on whole-image
+/// copies (filters, scroll compensation, mask post-processing, `downscale` and differing
+/// `device_pixel_ratio`s) are rejected with [`PixelmatchError::ConflictingOptions`]. Fails
+/// with [`PixelmatchError::RegionOutOfBounds`] if `roi` extends past the images.
+pub fn pixelmatch_roi(
+    img1: &[u8],
+    img2: &[u8],
+    output: Option<&mut [u8]>,
+    width: u32,
+    height: u32,
+    roi: Rect,
+    options: &Options,
+) -> Result<MatchResult, PixelmatchError> {
+    let _span = trace_span!("pixelmatch_roi", width, height);
+    options.validate().map_err(rejected)?;
+    if needs_whole_image(options) {
+        return Err(rejected(PixelmatchError::ConflictingOptions("option is not supported for a region of interest")));
+    }
+    let scaled = options.scaled_for_density();
+    let options = &*scaled;
+    validate_buffers(img1, img2, None, width, height).map_err(rejected)?;
+    let fits = |start: u32, len: u32, size: u32| start.checked_add(len).is_some_and(|end| end <= size);
+    if !(fits(roi.x, roi.width, width) && fits(roi.y, roi.height, height)) {
+        return Err(rejected(PixelmatchError::RegionOutOfBounds { region: roi, width, height }));
+    }
+    let area = roi.width as usize * roi.height as usize;
+    if let Some(output_len) = output.as_deref().map(<[u8]>::len).filter(|&n| n != area * 4) {
+        return Err(rejected(PixelmatchError::OutputSizeMismatch { img1_len: area * 4, output_len }));
+    }
+    let (w, h) = (width as usize, height as usize);
+    let ignore = ignore_mask(options, w, h).map_err(rejected)?;
+
+    let (x, row_bytes) = (roi.x as usize * 4, roi.width as usize * 4);
+    let identical = (roi.y as usize..(roi.y + roi.height) as usize)
+        .all(|y| img1[y * w * 4 + x..][..row_bytes] == img2[y * w * 4 + x..][..row_bytes]);
+    let tally = compare_rows(img1, img2, output, w, h, roi, ignore.as_deref(), None, options);
+    let result = MatchResult {
+        diff_count: tally.diff,
+        aa_count: tally.aa,
+        identical,
+        diff_bounds: tally.bounds,
+        ..Default::default()
+    };
+    Ok(result.with_total_pixels(area))
+}
+
+/// Whether `options` asks for a step that works on copies of the whole images.
+fn needs_whole_image(o: &Options) -> bool {
+    o.scroll_compensation > 0
+        || o.isolation_radius > 0
+        || !o.morphology.is_empty()
+        || o.label_shift > 0
+        || o.pre_blur.is_some()
+        || o.quantize.is_some()
+        || o.median_filter
+        || o.edges.is_some()
+        || o.downscale.is_some()
+        || o.device_pixel_ratio.is_some_and(|[a, b]| a != b)
+}
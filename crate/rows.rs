@@ -0,0 +1,127 @@
+//! Compact diff artifacts that keep only the rows that changed.
+//!
+//! Most comparisons in a large suite are clean or nearly so, and a full diff image spends
+//! almost all of its bytes on untouched rows. [`changed_rows`] keeps the diff image rows
+//! holding a counted pixel, each with its row index, and [`ChangedRows::write`] stores them
+//! in a small binary format for archiving.
+
+use std::io::{self, Read, Write};
+
+use crate::{diff_mask, pixelmatch, MatchResult, Options, PixelmatchError};
+
+/// Identifies the [`ChangedRows::write`] format, including its version.
+const MAGIC: &[u8; 8] = b"PMROWS1\n";
+
+/// One row of a diff image.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChangedRow {
+    /// Row index, from the top.
+    pub y: u32,
+    /// The row's RGBA diff pixels (`width * 4` bytes).
+    pub pixels: Vec<u8>,
+}
+
+/// The changed rows of a diff image, in top-to-bottom order.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct ChangedRows {
+    pub width: u32,
+    pub height: u32,
+    pub rows: Vec<ChangedRow>,
+}
+
+impl ChangedRows {
+    /// Keep the rows of the RGBA image `diff` in which `mask` (one entry per pixel, as from
+    /// [`diff_mask`]) marks a pixel.
+    ///
+    /// # Panics
+    /// Panics if `diff` is not `width * height * 4` bytes or `mask` not `width * height`
+    /// entries.
+    pub fn from_diff(diff: &[u8], mask: &[bool], width: u32, height: u32) -> Self {
+        let (w, h) = (width as usize, height as usize);
+        assert_eq!(diff.len(), w * h * 4, "diff length does not match width * height * 4");
+        assert_eq!(mask.len(), w * h, "mask length does not match width * height");
+        let rows = (0..h)
+            .filter(|&y| mask[y * w..][..w].contains(&true))
+            .map(|y| ChangedRow { y: y as u32, pixels: diff[y * w * 4..][..w * 4].to_vec() })
+            .collect();
+        Self { width, height, rows }
+    }
+
+    /// The full diff image, with the rows that were left out fully transparent. That is
+    /// exactly the original image when it was drawn with `diff_mask`.
+    pub fn to_image(&self) -> Vec<u8> {
+        let row_len = self.width as usize * 4;
+        let mut image = vec![0u8; row_len * self.height as usize];
+        for row in &self.rows {
+            image[row.y as usize * row_len..][..row_len].copy_from_slice(&row.pixels);
+        }
+        image
+    }
+
+    /// Write the rows as an 8-byte magic number, then the width, height and number of rows,
+    /// then each row's index followed by its pixels; numbers are little-endian `u32`s.
+    pub fn write(&self, mut writer: impl Write) -> io::Result<()> {
+        writer.write_all(MAGIC)?;
+        for n in [self.width, self.height, self.rows.len() as u32] {
+            writer.write_all(&n.to_le_bytes())?;
+        }
+        for row in &self.rows {
+            writer.write_all(&row.y.to_le_bytes())?;
+            writer.write_all(&row.pixels)?;
+        }
+        Ok(())
+    }
+
+    /// Read rows written by [`write`](Self::write).
+    pub fn read(mut reader: impl Read) -> io::Result<Self> {
+        let invalid = |message: &str| io::Error::new(io::ErrorKind::InvalidData, message.to_string());
+        let mut magic = [0u8; 8];
+        reader.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            return Err(invalid("not a changed-rows file"));
+        }
+        let (width, height, count) = (read_u32(&mut reader)?, read_u32(&mut reader)?, read_u32(&mut reader)?);
+        let row_len = (width as usize).checked_mul(4).ok_or_else(|| invalid("width too large"))?;
+        if count > height {
+            return Err(invalid("more rows than the image height"));
+        }
+        let mut rows: Vec<ChangedRow> = Vec::with_capacity(count.min(1024) as usize);
+        for _ in 0..count {
+            let y = read_u32(&mut reader)?;
+            if y >= height || rows.last().is_some_and(|r| r.y >= y) {
+                return Err(invalid("row indices out of order or past the image height"));
+            }
+            let mut pixels = vec![0u8; row_len];
+            reader.read_exact(&mut pixels)?;
+            rows.push(ChangedRow { y, pixels });
+        }
+        Ok(Self { width, height, rows })
+    }
+}
+
+fn read_u32(reader: &mut impl Read) -> io::Result<u32> {
+    let mut bytes = [0u8; 4];
+    reader.read_exact(&mut bytes)?;
+    Ok(u32::from_le_bytes(bytes))
+}
+
+/// Compare the images like [`pixelmatch`] and return the result with the rows of the diff
+/// image that hold a pixel counted in `diff_count`. Rows with only anti-aliased or ignored
+/// pixels are skipped along with untouched ones.
+pub fn changed_rows(
+    img1: &[u8],
+    img2: &[u8],
+    width: u32,
+    height: u32,
+    options: &Options,
+) -> Result<(MatchResult, ChangedRows), PixelmatchError> {
+    // The compared size, which is smaller than the first image if `device_pixel_ratio` scales it.
+    let len = (width as usize).checked_mul(height as usize).and_then(|n| n.checked_mul(4));
+    let mut diff = vec![0u8; len.ok_or(PixelmatchError::DimensionOverflow)?.min(img1.len())];
+    let result = pixelmatch(img1, img2, Some(&mut diff), width, height, options)?;
+    if result.diff_count == 0 {
+        return Ok((result, ChangedRows { width, height, rows: Vec::new() }));
+    }
+    let mask = diff_mask(img1, img2, width, height, options)?;
+    Ok((result, ChangedRows::from_diff(&diff, &mask, width, height)))
+}
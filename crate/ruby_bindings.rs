@@ -0,0 +1,84 @@
+use magnus::scan_args::{get_kwargs, scan_args};
+use magnus::{function, prelude::*, Error, RHash, RString, Ruby, Value};
+
+use crate::Options;
+
+fn color(ruby: &Ruby, name: &str, value: Option<Vec<u8>>) -> Result<Option<[u8; 3]>, Error> {
+    match value {
+        None => Ok(None),
+        Some(c) if c.len() == 3 => Ok(Some([c[0], c[1], c[2]])),
+        Some(c) => Err(Error::new(
+            ruby.exception_arg_error(),
+            format!("{name} must be an [r, g, b] array, got {} components", c.len()),
+        )),
+    }
+}
+
+/// `Pixelmatch.compare(img1, img2, width, height, threshold: 0.1, detect_anti_aliasing: true,
+/// alpha: 0.1, aa_color: [255, 255, 0], diff_color: [255, 0, 0], diff_color_alt: nil,
+/// diff_mask: false, diff: false)`
+///
+/// Images are binary strings of RGBA pixels. Returns a hash with `:diff_count`, `:aa_count`,
+/// `:identical` and `:diff` (the RGBA diff string with `diff: true`, otherwise nil).
+fn compare(ruby: &Ruby, args: &[Value]) -> Result<RHash, Error> {
+    let args = scan_args::<(RString, RString, u32, u32), (), (), (), RHash, ()>(args)?;
+    let (img1, img2, width, height) = args.required;
+    type Kw = (
+        Option<f64>,
+        Option<bool>,
+        Option<f64>,
+        Option<Vec<u8>>,
+        Option<Vec<u8>>,
+        Option<Vec<u8>>,
+        Option<bool>,
+        Option<bool>,
+    );
+    let kw = get_kwargs::<_, (), Kw, ()>(
+        args.keywords,
+        &[],
+        &[
+            "threshold",
+            "detect_anti_aliasing",
+            "alpha",
+            "aa_color",
+            "diff_color",
+            "diff_color_alt",
+            "diff_mask",
+            "diff",
+        ],
+    )?;
+    let (threshold, detect_anti_aliasing, alpha, aa_color, diff_color, diff_color_alt, diff_mask, diff) = kw.optional;
+
+    let defaults = Options::default();
+    let options = Options {
+        threshold: threshold.unwrap_or(defaults.threshold),
+        detect_anti_aliasing: detect_anti_aliasing.unwrap_or(defaults.detect_anti_aliasing),
+        alpha: alpha.unwrap_or(defaults.alpha),
+        aa_color: color(ruby, "aa_color", aa_color)?.unwrap_or(defaults.aa_color),
+        diff_color: color(ruby, "diff_color", diff_color)?.unwrap_or(defaults.diff_color),
+        diff_color_alt: color(ruby, "diff_color_alt", diff_color_alt)?,
+        diff_mask: diff_mask.unwrap_or(defaults.diff_mask),
+        ..defaults
+    };
+
+    // SAFETY: the string contents are only borrowed for the comparison, during which no Ruby
+    // code runs and nothing is allocated on the Ruby heap.
+    let (data1, data2) = unsafe { (img1.as_slice(), img2.as_slice()) };
+    let mut output = diff.unwrap_or(false).then(|| vec![0u8; data1.len()]);
+    let result = crate::pixelmatch(data1, data2, output.as_deref_mut(), width, height, &options)
+        .map_err(|e| Error::new(ruby.exception_arg_error(), e.to_string()))?;
+
+    let hash = ruby.hash_new();
+    hash.aset(ruby.to_symbol("diff_count"), result.diff_count)?;
+    hash.aset(ruby.to_symbol("aa_count"), result.aa_count)?;
+    hash.aset(ruby.to_symbol("identical"), result.identical)?;
+    hash.aset(ruby.to_symbol("diff"), output.map(|o| ruby.str_from_slice(&o)))?;
+    Ok(hash)
+}
+
+#[magnus::init(name = "pixelmatch")]
+fn init(ruby: &Ruby) -> Result<(), Error> {
+    let module = ruby.define_module("Pixelmatch")?;
+    module.define_module_function("compare", function!(compare, -1))?;
+    Ok(())
+}
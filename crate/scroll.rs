@@ -0,0 +1,89 @@
+//! Detection of a vertical scroll offset between two page screenshots.
+//!
+//! Full-page captures often differ only because the page ended up scrolled by a few rows,
+//! which makes every edge on the page a difference. [`detect_offset`] finds such an offset
+//! by correlating per-row brightness profiles, and [`shift_rows`] undoes it.
+
+/// Horizontal segments per row in the brightness profile, so rows with the same overall
+/// brightness but different layouts still tell apart.
+const SEGMENTS: usize = 8;
+
+/// Mean brightness (blended over white) of each of `SEGMENTS` horizontal slices of every row.
+fn row_profiles(img: &[u8], w: usize, h: usize) -> Vec<[f64; SEGMENTS]> {
+    let segments = SEGMENTS.min(w).max(1);
+    (0..h)
+        .map(|y| {
+            let mut profile = [0.0; SEGMENTS];
+            for (s, slot) in profile.iter_mut().enumerate().take(segments) {
+                let (x0, x1) = (s * w / segments, (s + 1) * w / segments);
+                let sum: f64 = img[(y * w + x0) * 4..(y * w + x1) * 4]
+                    .chunks_exact(4)
+                    .map(|p| {
+                        let luma = p[0] as f64 * 0.29889531 + p[1] as f64 * 0.58662247 + p[2] as f64 * 0.11448223;
+                        255.0 + (luma - 255.0) * p[3] as f64 / 255.0
+                    })
+                    .sum();
+                *slot = sum / (x1 - x0).max(1) as f64;
+            }
+            profile
+        })
+        .collect()
+}
+
+/// Vertical offset of `img2`'s content relative to `img1`, in rows: row `y` of `img1`
+/// best matches row `y + offset` of `img2`, so the offset is positive when the content sits
+/// lower in `img2` (the page was scrolled up).
+///
+/// Offsets up to `max_offset` rows in either direction are tried, keeping at least half of
+/// the rows overlapping. Returns 0 unless some offset at least halves the mismatch of not
+/// shifting, so pages that genuinely changed are left alone; among equally good offsets the
+/// smallest wins.
+pub fn detect_offset(img1: &[u8], img2: &[u8], width: u32, height: u32, max_offset: u32) -> i32 {
+    let (w, h) = (width as usize, height as usize);
+    debug_assert_eq!(img1.len(), w * h * 4);
+    debug_assert_eq!(img2.len(), w * h * 4);
+    let max_offset = (max_offset as usize).min(h / 2);
+    if max_offset == 0 || w == 0 {
+        return 0;
+    }
+    let (p1, p2) = rayon::join(|| row_profiles(img1, w, h), || row_profiles(img2, w, h));
+    let cost = |offset: isize| {
+        let overlap = h - offset.unsigned_abs();
+        let total: f64 = (0..overlap)
+            .map(|i| {
+                let (a, b) = if offset >= 0 { (i, i + offset as usize) } else { (i + offset.unsigned_abs(), i) };
+                p1[a].iter().zip(&p2[b]).map(|(x, y)| (x - y).abs()).sum::<f64>()
+            })
+            .sum();
+        total / overlap as f64
+    };
+
+    let unshifted = cost(0);
+    let (mut best, mut best_cost) = (0, unshifted);
+    for distance in 1..=max_offset as isize {
+        for offset in [distance, -distance] {
+            let c = cost(offset);
+            if c < best_cost {
+                (best, best_cost) = (offset, c);
+            }
+        }
+    }
+    if best_cost <= unshifted * 0.5 { best as i32 } else { 0 }
+}
+
+/// Undo an `offset` found by [`detect_offset`] on `img` (the second image): row `y` of the
+/// result is row `y + offset` of `img`. Rows scrolled in from outside have no counterpart
+/// and keep their own content.
+pub fn shift_rows(img: &[u8], width: u32, height: u32, offset: i32) -> Vec<u8> {
+    let (row, h) = (width as usize * 4, height as usize);
+    debug_assert_eq!(img.len(), row * h);
+    let mut out = img.to_vec();
+    for y in 0..h {
+        let source = y as isize + offset as isize;
+        if (0..h as isize).contains(&source) {
+            let source = source as usize;
+            out[y * row..(y + 1) * row].copy_from_slice(&img[source * row..(source + 1) * row]);
+        }
+    }
+    out
+}
@@ -0,0 +1,216 @@
+//! Snapshot testing for images, in the style of `insta`.
+//!
+//! ```no_run
+//! # use pixelmatch::{io::Image, snapshot::Snapshot};
+//! # let image = Image::new(1, 1);
+//! Snapshot::new("login/form").assert(&image);
+//! ```
+//!
+//! The first run writes the baseline `tests/snapshots/login/form.png`; later runs compare
+//! against it. A failing comparison writes the candidate next to the baseline as
+//! `form.new.png`, and `assert` also writes [`DiffArtifacts`] for it. Run with
+//! `UPDATE_SNAPSHOTS=1` to accept changed images instead.
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::artifacts::DiffArtifacts;
+use crate::io::{read_png, write_png, Image};
+use crate::{pixelmatch, MatchResult, Options, PixelmatchError};
+
+/// Environment variable that switches snapshots into approve mode when set to anything
+/// other than empty or `0`.
+pub const UPDATE_ENV: &str = "UPDATE_SNAPSHOTS";
+
+/// Environment variable overriding the default snapshot root.
+pub const DIR_ENV: &str = "PIXELMATCH_SNAPSHOT_DIR";
+
+/// What a successful snapshot check did.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SnapshotOutcome {
+    /// No baseline existed; the image was written as the new baseline.
+    Created,
+    /// The image matched the baseline.
+    Matched(MatchResult),
+    /// The image differed and approve mode was on; the baseline was overwritten.
+    Updated(MatchResult),
+}
+
+/// Why a snapshot check failed.
+#[derive(Debug)]
+pub enum SnapshotError {
+    /// The baseline or candidate could not be read or written.
+    Io { path: PathBuf, source: io::Error },
+    /// The comparison itself failed, e.g. because the options are invalid.
+    Compare(PixelmatchError),
+    /// The image and the baseline have different dimensions.
+    DimensionMismatch { path: PathBuf, expected: (u32, u32), actual: (u32, u32) },
+    /// More pixels differ than allowed.
+    Mismatch { path: PathBuf, candidate: PathBuf, result: MatchResult, max_diff_pixels: u32 },
+}
+
+impl std::fmt::Display for SnapshotError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Io { path, source } => write!(f, "{}: {source}", path.display()),
+            Self::Compare(e) => write!(f, "{e}"),
+            Self::DimensionMismatch { path, expected: (ew, eh), actual: (aw, ah) } => {
+                write!(f, "{}: baseline is {ew}x{eh}, image is {aw}x{ah}", path.display())
+            }
+            Self::Mismatch { path, candidate, result, max_diff_pixels } => write!(
+                f,
+                "{}: {} pixels differ (allowed {max_diff_pixels}); candidate written to {}",
+                path.display(),
+                result.diff_count,
+                candidate.display()
+            ),
+        }
+    }
+}
+
+impl std::error::Error for SnapshotError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io { source, .. } => Some(source),
+            Self::Compare(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+/// A named image snapshot.
+#[derive(Debug, Clone)]
+pub struct Snapshot {
+    name: String,
+    root: PathBuf,
+    options: Options,
+    max_diff_pixels: u32,
+    update: bool,
+}
+
+impl Snapshot {
+    /// A snapshot stored as `<root>/<name>.png`. `name` may contain `/` to group snapshots
+    /// in subdirectories.
+    ///
+    /// The root is `$PIXELMATCH_SNAPSHOT_DIR` if set, otherwise `tests/snapshots` under
+    /// `$CARGO_MANIFEST_DIR` (or the working directory outside Cargo).
+    pub fn new(name: impl Into<String>) -> Self {
+        let root = match std::env::var_os(DIR_ENV) {
+            Some(dir) => PathBuf::from(dir),
+            None => std::env::var_os("CARGO_MANIFEST_DIR")
+                .map_or_else(PathBuf::new, PathBuf::from)
+                .join("tests")
+                .join("snapshots"),
+        };
+        let update = std::env::var(UPDATE_ENV).is_ok_and(|v| !v.is_empty() && v != "0");
+        Self { name: name.into(), root, options: Options::default(), max_diff_pixels: 0, update }
+    }
+
+    /// Store snapshots under `root` instead.
+    pub fn root(mut self, root: impl Into<PathBuf>) -> Self {
+        self.root = root.into();
+        self
+    }
+
+    /// Comparison options. Default: `Options::default()`.
+    pub fn options(mut self, options: Options) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Number of differing pixels tolerated. Default: 0
+    pub fn max_diff_pixels(mut self, max_diff_pixels: u32) -> Self {
+        self.max_diff_pixels = max_diff_pixels;
+        self
+    }
+
+    /// Force approve mode on or off, overriding `UPDATE_SNAPSHOTS`.
+    pub fn update(mut self, update: bool) -> Self {
+        self.update = update;
+        self
+    }
+
+    /// Path of the baseline image.
+    pub fn path(&self) -> PathBuf {
+        self.root.join(format!("{}.png", self.name))
+    }
+
+    /// Path a rejected candidate is written to.
+    pub fn candidate_path(&self) -> PathBuf {
+        self.root.join(format!("{}.new.png", self.name))
+    }
+
+    /// Compare `image` with the baseline, creating or (in approve mode) updating it.
+    pub fn check(&self, image: &Image) -> Result<SnapshotOutcome, SnapshotError> {
+        let path = self.path();
+        let candidate = self.candidate_path();
+        if !path.exists() {
+            save(&path, image)?;
+            remove_stale(&candidate)?;
+            return Ok(SnapshotOutcome::Created);
+        }
+        let baseline = read_png(&path).map_err(|source| SnapshotError::Io { path: path.clone(), source })?;
+
+        let result = if (baseline.width, baseline.height) == (image.width, image.height) {
+            let r = pixelmatch(&baseline.data, &image.data, None, image.width, image.height, &self.options)
+                .map_err(SnapshotError::Compare)?;
+            Some(r)
+        } else {
+            None
+        };
+        if let Some(r) = result.filter(|r| r.diff_count <= self.max_diff_pixels) {
+            remove_stale(&candidate)?;
+            return Ok(SnapshotOutcome::Matched(r));
+        }
+        if self.update {
+            save(&path, image)?;
+            remove_stale(&candidate)?;
+            return Ok(SnapshotOutcome::Updated(result.unwrap_or_default()));
+        }
+
+        save(&candidate, image)?;
+        Err(match result {
+            Some(result) => SnapshotError::Mismatch { path, candidate, result, max_diff_pixels: self.max_diff_pixels },
+            None => SnapshotError::DimensionMismatch {
+                path,
+                expected: (baseline.width, baseline.height),
+                actual: (image.width, image.height),
+            },
+        })
+    }
+
+    /// Like [`check`](Self::check), but panics on failure after writing
+    /// [`DiffArtifacts`] for the snapshot.
+    #[track_caller]
+    pub fn assert(&self, image: &Image) -> SnapshotOutcome {
+        let e = match self.check(image) {
+            Ok(outcome) => return outcome,
+            Err(e) => e,
+        };
+        let artifacts = match e {
+            SnapshotError::Io { .. } | SnapshotError::Compare(_) => None,
+            _ => read_png(self.path()).ok().and_then(|baseline| {
+                let artifacts = DiffArtifacts::new(&self.name);
+                artifacts.write(&baseline, image, &self.options).ok().map(|_| artifacts)
+            }),
+        };
+        let note = artifacts.map(|a| format!("\nartifacts written to {}", a.dir().display())).unwrap_or_default();
+        panic!("snapshot '{}' failed: {e}{note}\nrerun with {UPDATE_ENV}=1 to accept", self.name)
+    }
+}
+
+fn save(path: &Path, image: &Image) -> Result<(), SnapshotError> {
+    let io_err = |source| SnapshotError::Io { path: path.to_path_buf(), source };
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(io_err)?;
+    }
+    write_png(path, &image.data, image.width, image.height).map_err(io_err)
+}
+
+fn remove_stale(path: &Path) -> Result<(), SnapshotError> {
+    match fs::remove_file(path) {
+        Err(e) if e.kind() != io::ErrorKind::NotFound => Err(SnapshotError::Io { path: path.to_path_buf(), source: e }),
+        _ => Ok(()),
+    }
+}
@@ -0,0 +1,218 @@
+//! Structural similarity (SSIM) and its multi-scale variant (MS-SSIM).
+//!
+//! SSIM compares local brightness, contrast and structure in Gaussian windows rather than
+//! individual pixels, so it tracks perceived quality much better than a count of differing
+//! pixels. [`ms_ssim`] evaluates it over an image pyramid, which matches human judgement of
+//! photographic content better still. Both work on luma, with translucent pixels blended
+//! over white. [`dssim_map`] and [`heatmap`] show where the structure changed, and
+//! [`pixelmatch_ssim`] scores a pair and draws that heatmap into a diff buffer in one pass,
+//! for gating compressed screenshots on structural change instead of a pixel count.
+
+use rayon::prelude::*;
+
+use crate::{validate_buffers, PixelmatchError};
+
+/// Standard deviation of the Gaussian window, in pixels.
+const SIGMA: f32 = 1.5;
+/// Radius of the Gaussian window (an 11×11 window).
+const RADIUS: usize = 5;
+/// Stabilising constants for the luminance and contrast terms, for a dynamic range of 255.
+const C1: f32 = (0.01 * 255.0) * (0.01 * 255.0);
+const C2: f32 = (0.03 * 255.0) * (0.03 * 255.0);
+
+/// Dissimilarity [`pixelmatch_ssim`] draws at full intensity. Most changes a viewer notices
+/// are well below 1.0, so they would barely show against the full range.
+pub const DSSIM_MAP_MAX: f32 = 0.25;
+
+/// Colour stops of [`heatmap`], evenly spaced from 0 to the maximum (the "inferno" palette).
+const HEATMAP: [[f32; 3]; 5] =
+    [[0.0, 0.0, 4.0], [87.0, 16.0, 110.0], [188.0, 55.0, 84.0], [249.0, 142.0, 9.0], [252.0, 255.0, 164.0]];
+
+/// Per-scale weights from Wang, Simoncelli and Bovik (2003), finest scale first.
+pub const MS_SSIM_WEIGHTS: [f64; 5] = [0.0448, 0.2856, 0.3001, 0.2363, 0.1333];
+
+/// Luma of every pixel, blended over white.
+pub(crate) fn luma(img: &[u8]) -> Vec<f32> {
+    img.par_chunks_exact(4)
+        .map(|p| {
+            let y = p[0] as f64 * 0.29889531 + p[1] as f64 * 0.58662247 + p[2] as f64 * 0.11448223;
+            (255.0 + (y - 255.0) * p[3] as f64 / 255.0) as f32
+        })
+        .collect()
+}
+
+/// Blur a plane with a normalised Gaussian window of standard deviation `sigma` covering
+/// ±3 sigma, clamping at the edges. With [`SIGMA`] this is the 11×11 SSIM window.
+pub(crate) fn gaussian(plane: &[f32], w: usize, h: usize, sigma: f32) -> Vec<f32> {
+    let radius = (3.0 * sigma).ceil() as usize;
+    let weights: Vec<f32> = (0..=2 * radius)
+        .map(|i| {
+            let d = i as f32 - radius as f32;
+            (-d * d / (2.0 * sigma * sigma)).exp()
+        })
+        .collect();
+    let total: f32 = weights.iter().sum();
+    let tap = |i: usize, k: usize, len: usize| (i + k).saturating_sub(radius).min(len - 1);
+
+    let mut rows = vec![0.0f32; w * h];
+    rows.par_chunks_mut(w).enumerate().for_each(|(y, row)| {
+        let src = &plane[y * w..(y + 1) * w];
+        for (x, out) in row.iter_mut().enumerate() {
+            *out = weights.iter().enumerate().map(|(k, &wt)| src[tap(x, k, w)] * wt).sum::<f32>() / total;
+        }
+    });
+    let mut out = vec![0.0f32; w * h];
+    out.par_chunks_mut(w).enumerate().for_each(|(y, row)| {
+        for (x, o) in row.iter_mut().enumerate() {
+            *o = weights.iter().enumerate().map(|(k, &wt)| rows[tap(y, k, h) * w + x] * wt).sum::<f32>() / total;
+        }
+    });
+    out
+}
+
+/// Per-pixel SSIM and contrast-structure terms of two luma planes.
+fn maps(l1: &[f32], l2: &[f32], w: usize, h: usize) -> (Vec<f32>, Vec<f32>) {
+    let product = |a: &[f32], b: &[f32]| a.iter().zip(b).map(|(x, y)| x * y).collect::<Vec<f32>>();
+    let (mu1, mu2) = rayon::join(|| gaussian(l1, w, h, SIGMA), || gaussian(l2, w, h, SIGMA));
+    let (s11, (s22, s12)) = rayon::join(
+        || gaussian(&product(l1, l1), w, h, SIGMA),
+        || rayon::join(|| gaussian(&product(l2, l2), w, h, SIGMA), || gaussian(&product(l1, l2), w, h, SIGMA)),
+    );
+    (0..w * h)
+        .into_par_iter()
+        .map(|i| {
+            let (m1, m2) = (mu1[i], mu2[i]);
+            let var1 = s11[i] - m1 * m1;
+            let var2 = s22[i] - m2 * m2;
+            let cov = s12[i] - m1 * m2;
+            let cs = (2.0 * cov + C2) / (var1 + var2 + C2);
+            let l = (2.0 * m1 * m2 + C1) / (m1 * m1 + m2 * m2 + C1);
+            (l * cs, cs)
+        })
+        .unzip()
+}
+
+fn mean(values: &[f32]) -> f64 {
+    if values.is_empty() {
+        return 1.0;
+    }
+    values.par_iter().map(|&v| v as f64).sum::<f64>() / values.len() as f64
+}
+
+/// Halve a plane by averaging 2×2 blocks, dropping an odd last row or column.
+fn half(plane: &[f32], w: usize, h: usize) -> (Vec<f32>, usize, usize) {
+    let (hw, hh) = (w / 2, h / 2);
+    let out = (0..hw * hh)
+        .into_par_iter()
+        .map(|i| {
+            let (x, y) = (i % hw * 2, i / hw * 2);
+            (plane[y * w + x] + plane[y * w + x + 1] + plane[(y + 1) * w + x] + plane[(y + 1) * w + x + 1]) / 4.0
+        })
+        .collect();
+    (out, hw, hh)
+}
+
+/// Mean SSIM of two equally sized RGBA images: 1.0 for identical images, lower (down to
+/// -1.0 in theory, around 0 in practice) the less alike they are.
+pub fn ssim(img1: &[u8], img2: &[u8], width: u32, height: u32) -> Result<f64, PixelmatchError> {
+    validate_buffers(img1, img2, None, width, height)?;
+    let (w, h) = (width as usize, height as usize);
+    let (l1, l2) = rayon::join(|| luma(img1), || luma(img2));
+    Ok(ssim_luma(&l1, &l2, w, h))
+}
+
+/// [`ssim`] of two precomputed [`luma`] planes.
+pub(crate) fn ssim_luma(l1: &[f32], l2: &[f32], w: usize, h: usize) -> f64 {
+    mean(&maps(l1, l2, w, h).0)
+}
+
+/// Per-pixel structural dissimilarity, `(1 - SSIM) / 2` over the window around each pixel:
+/// 0.0 where the images agree, rising to 1.0 where they are opposite. One entry per pixel in
+/// row-major order. Unlike the colour delta, it responds to changed texture and structure
+/// even when the average colour of an area is unchanged.
+pub fn dssim_map(img1: &[u8], img2: &[u8], width: u32, height: u32) -> Result<Vec<f32>, PixelmatchError> {
+    validate_buffers(img1, img2, None, width, height)?;
+    let (l1, l2) = rayon::join(|| luma(img1), || luma(img2));
+    Ok(dssim_luma(&l1, &l2, width as usize, height as usize))
+}
+
+/// [`dssim_map`] of two precomputed [`luma`] planes.
+pub(crate) fn dssim_luma(l1: &[f32], l2: &[f32], w: usize, h: usize) -> Vec<f32> {
+    let (ssim_map, _) = maps(l1, l2, w, h);
+    ssim_map.into_par_iter().map(|s| ((1.0 - s) / 2.0).clamp(0.0, 1.0)).collect()
+}
+
+/// Render per-pixel `values` as an opaque RGBA image through a perceptually uniform colour
+/// map, from black at 0 through purple, red and orange to pale yellow at `max` and above.
+/// DSSIM values are usually small; a `max` of 0.1 to 0.25 makes changes stand out.
+pub fn heatmap(values: &[f32], max: f32) -> Vec<u8> {
+    let max = if max > 0.0 { max } else { 1.0 };
+    values.par_iter().flat_map_iter(|&v| heat(v, max)).collect()
+}
+
+/// The [`heatmap`] colour of `v` on a scale up to `max`, which must be positive.
+fn heat(v: f32, max: f32) -> [u8; 4] {
+    let t = (v / max).clamp(0.0, 1.0) * (HEATMAP.len() - 1) as f32;
+    let i = (t as usize).min(HEATMAP.len() - 2);
+    let f = t - i as f32;
+    let channel = |c: usize| (HEATMAP[i][c] + (HEATMAP[i + 1][c] - HEATMAP[i][c]) * f).round() as u8;
+    [channel(0), channel(1), channel(2), 255]
+}
+
+/// Compare two equally sized RGBA images by SSIM instead of by pixel, returning [`ssim`].
+///
+/// `output`, if given, must be as long as the images and receives the [`dssim_map`] drawn
+/// by [`heatmap`] up to [`DSSIM_MAP_MAX`], computed from the same windows as the score.
+/// Compression noise and one-pixel anti-aliasing shifts barely lower the score, while
+/// changed text, borders or layout do, so a minimum score is a steadier gate than
+/// `diff_count` for lossy screenshots.
+pub fn pixelmatch_ssim(
+    img1: &[u8],
+    img2: &[u8],
+    output: Option<&mut [u8]>,
+    width: u32,
+    height: u32,
+) -> Result<f64, PixelmatchError> {
+    validate_buffers(img1, img2, output.as_deref().map(<[u8]>::len), width, height)?;
+    let (w, h) = (width as usize, height as usize);
+    let (l1, l2) = rayon::join(|| luma(img1), || luma(img2));
+    let (ssim_map, _) = maps(&l1, &l2, w, h);
+    if let Some(output) = output {
+        output.par_chunks_exact_mut(4).zip(&ssim_map).for_each(|(px, &s)| {
+            px.copy_from_slice(&heat(((1.0 - s) / 2.0).clamp(0.0, 1.0), DSSIM_MAP_MAX));
+        });
+    }
+    Ok(mean(&ssim_map))
+}
+
+/// MS-SSIM of two equally sized RGBA images, from 0.0 to 1.0 (identical), over a pyramid
+/// with one level per entry of `weights` (finest first; see [`MS_SSIM_WEIGHTS`]).
+///
+/// Each level halves the previous one. The contrast-structure term is taken at every level
+/// and the luminance term only at the coarsest; terms below 0 are clamped to 0. Levels that
+/// would be smaller than the 11×11 window are dropped and the remaining weights
+/// renormalised, so small images are compared at fewer scales.
+pub fn ms_ssim(img1: &[u8], img2: &[u8], width: u32, height: u32, weights: &[f64]) -> Result<f64, PixelmatchError> {
+    validate_buffers(img1, img2, None, width, height)?;
+    let (mut w, mut h) = (width as usize, height as usize);
+    let (mut l1, mut l2) = rayon::join(|| luma(img1), || luma(img2));
+    let window = 2 * RADIUS + 1;
+    let levels = (1..weights.len()).take_while(|&i| (w >> i).min(h >> i) >= window).count() + 1;
+    let weights = &weights[..levels.min(weights.len())];
+    let total: f64 = weights.iter().sum();
+    if weights.is_empty() || total <= 0.0 || w == 0 || h == 0 {
+        return Ok(1.0);
+    }
+
+    let mut score = 1.0;
+    for (level, &weight) in weights.iter().enumerate() {
+        let (ssim_map, cs_map) = maps(&l1, &l2, w, h);
+        let term = if level + 1 == weights.len() { mean(&ssim_map) } else { mean(&cs_map) };
+        score *= term.max(0.0).powf(weight / total);
+        if level + 1 < weights.len() {
+            let ((n1, hw, hh), (n2, _, _)) = rayon::join(|| half(&l1, w, h), || half(&l2, w, h));
+            (l1, l2, w, h) = (n1, n2, hw, hh);
+        }
+    }
+    Ok(score)
+}
@@ -0,0 +1,174 @@
+//! Suite-level statistics across a test run.
+//!
+//! Feed every comparison into one [`SuiteStats`] (it is `Sync`, so tests running in
+//! parallel can share it through a `static` or an `Arc`), then call
+//! [`summary`](SuiteStats::summary) at the end of the run. With the `serde` feature the
+//! summary serialises to JSON for reports and dashboards.
+
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+
+use crate::report::ReportEntry;
+use crate::MatchResult;
+
+/// Upper bounds (inclusive) of the diff-ratio histogram buckets. The first bucket holds
+/// identical-looking comparisons (no differing pixels).
+pub const RATIO_BUCKETS: [f64; 6] = [0.0, 0.0001, 0.001, 0.01, 0.1, 1.0];
+
+#[derive(Debug, Clone)]
+struct Sample {
+    name: String,
+    diff_count: u32,
+    total_pixels: u64,
+    passed: bool,
+}
+
+impl Sample {
+    fn ratio(&self) -> f64 {
+        if self.total_pixels == 0 {
+            0.0
+        } else {
+            self.diff_count as f64 / self.total_pixels as f64
+        }
+    }
+}
+
+/// Thread-safe collector of comparison results.
+#[derive(Debug, Default)]
+pub struct SuiteStats {
+    samples: Mutex<Vec<Sample>>,
+}
+
+/// One of the comparisons with the largest diff ratio.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Offender {
+    pub name: String,
+    pub diff_count: u32,
+    pub diff_ratio: f64,
+}
+
+/// Number of comparisons whose diff ratio is at most `max_ratio` (and above the previous
+/// bucket's bound).
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct RatioBucket {
+    pub max_ratio: f64,
+    pub count: usize,
+}
+
+/// An image recorded more than once (e.g. on retries) whose results disagreed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct FlakyImage {
+    pub name: String,
+    pub runs: usize,
+    pub failures: usize,
+    pub min_diff_count: u32,
+    pub max_diff_count: u32,
+}
+
+/// Aggregated statistics of a run.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct SuiteSummary {
+    /// Number of comparisons recorded, including repeats.
+    pub comparisons: usize,
+    /// Number of distinct names.
+    pub images: usize,
+    pub passed: usize,
+    pub failed: usize,
+    pub total_pixels: u64,
+    pub diff_pixels: u64,
+    /// The worst comparisons by diff ratio, one per name, worst first.
+    pub worst: Vec<Offender>,
+    /// Distribution of diff ratios over [`RATIO_BUCKETS`].
+    pub ratio_histogram: Vec<RatioBucket>,
+    /// Images whose repeated comparisons both passed and failed, or found different
+    /// numbers of differing pixels.
+    pub flaky: Vec<FlakyImage>,
+}
+
+impl SuiteStats {
+    pub const fn new() -> Self {
+        Self { samples: Mutex::new(Vec::new()) }
+    }
+
+    /// Record one comparison of `total_pixels` pixels.
+    pub fn record(&self, name: impl Into<String>, result: &MatchResult, total_pixels: u64, passed: bool) {
+        let sample = Sample { name: name.into(), diff_count: result.diff_count, total_pixels, passed };
+        self.samples.lock().unwrap_or_else(|e| e.into_inner()).push(sample);
+    }
+
+    /// Record a report entry.
+    pub fn record_entry(&self, entry: &ReportEntry) {
+        self.record(entry.name.clone(), &entry.result, entry.result.total_pixels, entry.passed);
+    }
+
+    /// Number of comparisons recorded so far.
+    pub fn len(&self) -> usize {
+        self.samples.lock().unwrap_or_else(|e| e.into_inner()).len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Aggregate everything recorded so far, listing at most `worst` offenders.
+    pub fn summary(&self, worst: usize) -> SuiteSummary {
+        let samples = self.samples.lock().unwrap_or_else(|e| e.into_inner()).clone();
+
+        let mut by_name: BTreeMap<&str, Vec<&Sample>> = BTreeMap::new();
+        for s in &samples {
+            by_name.entry(&s.name).or_default().push(s);
+        }
+
+        let mut offenders: Vec<Offender> = by_name
+            .values()
+            .filter_map(|runs| runs.iter().max_by(|a, b| a.ratio().total_cmp(&b.ratio())))
+            .filter(|s| s.diff_count > 0)
+            .map(|s| Offender { name: s.name.clone(), diff_count: s.diff_count, diff_ratio: s.ratio() })
+            .collect();
+        offenders.sort_by(|a, b| b.diff_ratio.total_cmp(&a.diff_ratio).then_with(|| a.name.cmp(&b.name)));
+        offenders.truncate(worst);
+
+        let mut ratio_histogram: Vec<RatioBucket> =
+            RATIO_BUCKETS.iter().map(|&max_ratio| RatioBucket { max_ratio, count: 0 }).collect();
+        for s in &samples {
+            let ratio = s.ratio();
+            let i = RATIO_BUCKETS.iter().position(|&max| ratio <= max).unwrap_or(RATIO_BUCKETS.len() - 1);
+            ratio_histogram[i].count += 1;
+        }
+
+        let flaky = by_name
+            .iter()
+            .filter(|(_, runs)| runs.len() > 1)
+            .filter_map(|(&name, runs)| {
+                let failures = runs.iter().filter(|s| !s.passed).count();
+                let min_diff_count = runs.iter().map(|s| s.diff_count).min()?;
+                let max_diff_count = runs.iter().map(|s| s.diff_count).max()?;
+                let mixed = failures > 0 && failures < runs.len();
+                (mixed || min_diff_count != max_diff_count).then(|| FlakyImage {
+                    name: name.to_string(),
+                    runs: runs.len(),
+                    failures,
+                    min_diff_count,
+                    max_diff_count,
+                })
+            })
+            .collect();
+
+        let passed = samples.iter().filter(|s| s.passed).count();
+        SuiteSummary {
+            comparisons: samples.len(),
+            images: by_name.len(),
+            passed,
+            failed: samples.len() - passed,
+            total_pixels: samples.iter().map(|s| s.total_pixels).sum(),
+            diff_pixels: samples.iter().map(|s| u64::from(s.diff_count)).sum(),
+            worst: offenders,
+            ratio_histogram,
+            flaky,
+        }
+    }
+}
@@ -0,0 +1,221 @@
+use std::ops::Range;
+
+use crate::{
+    draw_ignore_hatch, ignore_mask, process_row_no_output, process_row_with_output, MatchResult, Options,
+    PixelmatchError, Tally,
+};
+
+/// Compares two images fed in horizontal bands of rows, so neither image has to be held in
+/// memory at once.
+///
+/// Anti-aliasing detection looks up to two rows away (shift tolerance up to
+/// `shift_tolerance` rows), so a row is only finalised once that many rows below it have
+/// arrived (or the image is complete); the matcher keeps just those rows plus as many above
+/// the next pending row. Results are identical to [`crate::pixelmatch`].
+///
+/// Options that need the whole image before any row can be compared are not supported, and
+/// [`StreamMatcher::new`] rejects them with [`PixelmatchError::ConflictingOptions`]:
+/// `jpeg_tolerance`, `scroll_compensation`, `isolation_radius`, `morphology`, `label_shift`,
+/// `pre_blur`, `quantize`, `median_filter`, `edges`, `downscale` and differing
+/// `device_pixel_ratio` values.
+#[derive(Debug)]
+pub struct StreamMatcher {
+    width: usize,
+    height: usize,
+    options: Options,
+    ignore: Option<Vec<bool>>,
+    /// Rows of context needed on each side of a row before it can be finalised.
+    margin: usize,
+    /// Buffered rows `window_start..received` of each image.
+    window1: Vec<u8>,
+    window2: Vec<u8>,
+    window_start: usize,
+    received: usize,
+    next_row: usize,
+    tally: Tally,
+    identical: bool,
+}
+
+impl StreamMatcher {
+    pub fn new(width: u32, height: u32, options: Options) -> Result<Self, PixelmatchError> {
+        options.validate()?;
+        let prefiltered = options.pre_blur.is_some_and(|s| s > 0.0) || options.median_filter || options.edges.is_some();
+        if prefiltered || options.downscale.is_some_and(|s| s < 1.0) {
+            return Err(PixelmatchError::ConflictingOptions(
+                "pre_blur, median_filter, edges and downscale are not supported when streaming",
+            ));
+        }
+        if options.quantize.is_some() {
+            return Err(PixelmatchError::ConflictingOptions("quantize is not supported when streaming"));
+        }
+        if options.device_pixel_ratio.is_some_and(|[a, b]| a != b) {
+            return Err(PixelmatchError::ConflictingOptions(
+                "differing device_pixel_ratio values are not supported when streaming",
+            ));
+        }
+        if options.scroll_compensation > 0 || options.jpeg_tolerance.is_some() {
+            return Err(PixelmatchError::ConflictingOptions(
+                "scroll_compensation and jpeg_tolerance are not supported when streaming",
+            ));
+        }
+        if options.isolation_radius > 0 || !options.morphology.is_empty() || options.label_shift > 0 {
+            return Err(PixelmatchError::ConflictingOptions(
+                "isolation_radius, morphology and label_shift are not supported when streaming",
+            ));
+        }
+        let (w, h) = (width as usize, height as usize);
+        w.checked_mul(h).and_then(|n| n.checked_mul(4)).ok_or(PixelmatchError::DimensionOverflow)?;
+        let ignore = ignore_mask(&options, w, h)?;
+        let options = options.scaled_for_density().into_owned();
+        let margin = (options.shift_tolerance as usize).max(2 * options.aa_step());
+        Ok(Self {
+            width: w,
+            height: h,
+            options,
+            ignore,
+            margin,
+            window1: Vec::new(),
+            window2: Vec::new(),
+            window_start: 0,
+            received: 0,
+            next_row: 0,
+            tally: Tally::default(),
+            identical: true,
+        })
+    }
+
+    /// Number of rows received so far.
+    pub fn rows_received(&self) -> u32 {
+        self.received as u32
+    }
+
+    /// Add the next band of rows of both images (RGBA, whole rows, the same number in each).
+    ///
+    /// Returns the rows finalised by this band. If `output` is given, their diff pixels are
+    /// appended to it.
+    pub fn push(
+        &mut self,
+        band1: &[u8],
+        band2: &[u8],
+        output: Option<&mut Vec<u8>>,
+    ) -> Result<Range<u32>, PixelmatchError> {
+        if band1.len() != band2.len() {
+            return Err(PixelmatchError::ImageSizeMismatch { img1_len: band1.len(), img2_len: band2.len() });
+        }
+        let row_bytes = self.width * 4;
+        let remaining_rows = self.height - self.received;
+        if row_bytes == 0 || !band1.len().is_multiple_of(row_bytes) || band1.len() / row_bytes > remaining_rows {
+            return Err(PixelmatchError::BandSizeMismatch { row_bytes, remaining_rows, actual: band1.len() });
+        }
+        self.identical &= band1 == band2;
+        self.window1.extend_from_slice(band1);
+        self.window2.extend_from_slice(band2);
+        self.received += band1.len() / row_bytes;
+
+        let ready = if self.received == self.height { self.height } else { self.received.saturating_sub(self.margin) };
+        let start = self.next_row;
+        if ready > start {
+            self.process(start..ready, output);
+            self.next_row = ready;
+        }
+
+        let keep_from = self.next_row.saturating_sub(self.margin);
+        if keep_from > self.window_start {
+            let drop = (keep_from - self.window_start) * row_bytes;
+            self.window1.drain(..drop);
+            self.window2.drain(..drop);
+            self.window_start = keep_from;
+        }
+        Ok(start as u32..self.next_row as u32)
+    }
+
+    /// The result once every row has been pushed.
+    pub fn finish(self) -> Result<MatchResult, PixelmatchError> {
+        if self.received < self.height {
+            return Err(PixelmatchError::IncompleteImage { expected_rows: self.height, received_rows: self.received });
+        }
+        Ok(MatchResult {
+            diff_count: self.tally.diff,
+            aa_count: self.tally.aa,
+            identical: self.identical,
+            diff_bounds: self.tally.bounds,
+            ..Default::default()
+        }
+        .with_total_pixels(self.width * self.height))
+    }
+
+    fn process(&mut self, rows: Range<usize>, mut output: Option<&mut Vec<u8>>) {
+        let w = self.width;
+        let h = self.received - self.window_start;
+        let options = &self.options;
+        let max_delta = 35215.0 * options.threshold * options.threshold;
+        let [aa_r, aa_g, aa_b] = options.aa_color;
+        let [diff_r, diff_g, diff_b] = options.diff_color;
+        let [alt_r, alt_g, alt_b] = options.diff_color_alt.unwrap_or(options.diff_color);
+
+        for y in rows {
+            let ignore_row = self.ignore.as_deref().map(|m| &m[y * w..(y + 1) * w]);
+            let local_y = y - self.window_start;
+            let counts = match output.as_mut() {
+                Some(out) => {
+                    let at = out.len();
+                    out.resize(at + w * 4, 0);
+                    let counts = process_row_with_output(
+                        &self.window1, &self.window2, &mut out[at..], ignore_row, None, local_y, 0..w, w, h,
+                        max_delta, options, aa_r, aa_g, aa_b, diff_r, diff_g, diff_b, alt_r, alt_g, alt_b,
+                    );
+                    draw_ignore_hatch(&mut out[at..], ignore_row, y, options.ignore_hatch);
+                    counts
+                }
+                None => process_row_no_output(
+                    &self.window1, &self.window2, ignore_row, None, local_y, 0..w, w, h, max_delta, options,
+                ),
+            };
+            self.tally = self.tally.merge(Tally::row(y, counts));
+        }
+    }
+}
+
+/// Compare two images given as iterators over their RGBA rows, top to bottom, e.g. the
+/// scanlines of two decoders. Only the rows anti-aliasing detection needs around the current
+/// one are buffered, so neither image is assembled in memory. Takes the options
+/// [`StreamMatcher`] supports; `output`, if given, is filled with the diff image as with
+/// [`crate::pixelmatch`].
+pub fn pixelmatch_rows<'a>(
+    rows1: impl IntoIterator<Item = &'a [u8]>,
+    rows2: impl IntoIterator<Item = &'a [u8]>,
+    mut output: Option<&mut [u8]>,
+    width: u32,
+    height: u32,
+    options: &Options,
+) -> Result<MatchResult, PixelmatchError> {
+    let mut matcher = StreamMatcher::new(width, height, options.clone())?;
+    let row_bytes = width as usize * 4;
+    let img1_len = row_bytes * height as usize;
+    if let Some(output_len) = output.as_deref().map(<[u8]>::len).filter(|&n| n != img1_len) {
+        return Err(PixelmatchError::OutputSizeMismatch { img1_len, output_len });
+    }
+    let (mut rows1, mut rows2) = (rows1.into_iter(), rows2.into_iter());
+    let mut finished = Vec::new();
+    loop {
+        let remaining_rows = height as usize - matcher.rows_received() as usize;
+        let (row1, row2) = match (rows1.next(), rows2.next()) {
+            (Some(row1), Some(row2)) => (row1, row2),
+            (None, None) => break,
+            // One image has more rows than the other: too many, or `finish` reports too few.
+            (Some(extra), None) | (None, Some(extra)) if remaining_rows == 0 => {
+                return Err(PixelmatchError::BandSizeMismatch { row_bytes, remaining_rows, actual: extra.len() });
+            }
+            _ => break,
+        };
+        if row1.len() != row_bytes {
+            return Err(PixelmatchError::BandSizeMismatch { row_bytes, remaining_rows, actual: row1.len() });
+        }
+        finished.clear();
+        let rows = matcher.push(row1, row2, output.is_some().then_some(&mut finished))?;
+        if let Some(out) = output.as_deref_mut() {
+            out[rows.start as usize * row_bytes..rows.end as usize * row_bytes].copy_from_slice(&finished);
+        }
+    }
+    matcher.finish()
+}
@@ -0,0 +1,215 @@
+//! Comparison of slippy-map tile trees laid out as `{z}/{x}/{y}.png` (`png` feature).
+//!
+//! [`compare_tile_trees`] walks a baseline and a candidate tree, compares the tiles present
+//! in both in parallel, and lists the tiles only one tree has instead of failing on them.
+//! [`TileReport::zoom_stats`] aggregates the results per zoom level, since a style change
+//! usually affects some zoom levels and not others. Files that do not follow the layout are
+//! ignored.
+
+use std::collections::BTreeMap;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use rayon::prelude::*;
+
+use crate::io::read_png;
+use crate::{pixelmatch, MatchResult, Options};
+
+/// Address of a tile: zoom level, column and row.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct TileId {
+    pub z: u32,
+    pub x: u32,
+    pub y: u32,
+}
+
+impl TileId {
+    /// Parse a path relative to the tree root, `{z}/{x}/{y}.png`.
+    pub fn from_path(relative: &Path) -> Option<Self> {
+        let parts: Vec<&str> = relative.iter().map(|c| c.to_str()).collect::<Option<_>>()?;
+        let [z, x, file] = parts[..] else {
+            return None;
+        };
+        let (y, extension) = file.rsplit_once('.')?;
+        if !extension.eq_ignore_ascii_case("png") {
+            return None;
+        }
+        Some(Self { z: z.parse().ok()?, x: x.parse().ok()?, y: y.parse().ok()? })
+    }
+}
+
+impl std::fmt::Display for TileId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}/{}/{}", self.z, self.x, self.y)
+    }
+}
+
+/// Outcome of comparing one tile present in both trees.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TileOutcome {
+    /// The tiles were compared; `width` × `height` is their size.
+    Compared { result: MatchResult, width: u32, height: u32 },
+    /// The tiles have different sizes.
+    DimensionMismatch { baseline: (u32, u32), candidate: (u32, u32) },
+    /// A tile could not be read or compared.
+    Error(String),
+}
+
+/// A compared tile.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TileComparison {
+    pub id: TileId,
+    pub outcome: TileOutcome,
+}
+
+impl TileComparison {
+    /// Number of differing pixels, if the tile was compared.
+    pub fn diff_count(&self) -> Option<u32> {
+        match &self.outcome {
+            TileOutcome::Compared { result, .. } => Some(result.diff_count),
+            _ => None,
+        }
+    }
+}
+
+/// Totals for one zoom level.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ZoomStats {
+    pub zoom: u32,
+    /// Tiles present in both trees and compared.
+    pub compared: usize,
+    /// Compared tiles with at least one differing pixel.
+    pub differing: usize,
+    /// Tiles present in both trees that could not be compared (unreadable or of
+    /// different sizes).
+    pub errors: usize,
+    /// Tiles only the candidate tree has.
+    pub missing_baseline: usize,
+    /// Tiles only the baseline tree has.
+    pub missing_candidate: usize,
+    /// Differing pixels over all compared tiles.
+    pub diff_pixels: u64,
+    /// Pixels over all compared tiles.
+    pub total_pixels: u64,
+}
+
+impl ZoomStats {
+    /// Share of compared pixels that differ (0 to 1).
+    pub fn diff_ratio(&self) -> f64 {
+        if self.total_pixels == 0 { 0.0 } else { self.diff_pixels as f64 / self.total_pixels as f64 }
+    }
+}
+
+/// Result of [`compare_tile_trees`].
+#[derive(Debug, Clone, Default)]
+pub struct TileReport {
+    /// Tiles present in both trees, in `z`, `x`, `y` order.
+    pub comparisons: Vec<TileComparison>,
+    /// Tiles only the candidate tree has, in order.
+    pub missing_baseline: Vec<TileId>,
+    /// Tiles only the baseline tree has, in order.
+    pub missing_candidate: Vec<TileId>,
+}
+
+impl TileReport {
+    /// Totals per zoom level, lowest zoom first; every zoom level with a tile in either tree
+    /// is listed.
+    pub fn zoom_stats(&self) -> Vec<ZoomStats> {
+        fn entry(zooms: &mut BTreeMap<u32, ZoomStats>, z: u32) -> &mut ZoomStats {
+            zooms.entry(z).or_insert(ZoomStats { zoom: z, ..Default::default() })
+        }
+        let mut zooms = BTreeMap::new();
+        for c in &self.comparisons {
+            let stats = entry(&mut zooms, c.id.z);
+            match &c.outcome {
+                TileOutcome::Compared { result, width, height } => {
+                    stats.compared += 1;
+                    stats.differing += usize::from(result.diff_count > 0);
+                    stats.diff_pixels += u64::from(result.diff_count);
+                    stats.total_pixels += u64::from(*width) * u64::from(*height);
+                }
+                TileOutcome::DimensionMismatch { .. } | TileOutcome::Error(_) => stats.errors += 1,
+            }
+        }
+        for id in &self.missing_baseline {
+            entry(&mut zooms, id.z).missing_baseline += 1;
+        }
+        for id in &self.missing_candidate {
+            entry(&mut zooms, id.z).missing_candidate += 1;
+        }
+        zooms.into_values().collect()
+    }
+
+    /// Whether every tile present in both trees was compared without a differing pixel.
+    /// Missing tiles are skipped, not failed.
+    pub fn passed(&self) -> bool {
+        self.comparisons.iter().all(|c| c.diff_count() == Some(0))
+    }
+}
+
+/// All tiles under `root`, by address.
+pub fn collect_tiles(root: &Path) -> io::Result<BTreeMap<TileId, PathBuf>> {
+    let mut tiles = BTreeMap::new();
+    walk(root, root, &mut tiles)?;
+    Ok(tiles)
+}
+
+fn walk(root: &Path, dir: &Path, tiles: &mut BTreeMap<TileId, PathBuf>) -> io::Result<()> {
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            walk(root, &path, tiles)?;
+        } else if let Some(id) = path.strip_prefix(root).ok().and_then(TileId::from_path) {
+            tiles.insert(id, path);
+        }
+    }
+    Ok(())
+}
+
+/// Compare two tile trees with the same options for every tile.
+pub fn compare_tile_trees(baseline_root: &Path, candidate_root: &Path, options: &Options) -> io::Result<TileReport> {
+    compare_tile_trees_with(baseline_root, candidate_root, |_| options.clone())
+}
+
+/// Compare two tile trees, choosing the options for each tile with `options_for`, e.g. a
+/// higher threshold for the zoom levels with most labels.
+pub fn compare_tile_trees_with(
+    baseline_root: &Path,
+    candidate_root: &Path,
+    options_for: impl Fn(&TileId) -> Options + Sync,
+) -> io::Result<TileReport> {
+    let (baseline, candidate) = (collect_tiles(baseline_root)?, collect_tiles(candidate_root)?);
+    let missing = |a: &BTreeMap<TileId, PathBuf>, b: &BTreeMap<TileId, PathBuf>| {
+        a.keys().filter(|id| !b.contains_key(id)).copied().collect()
+    };
+    let pairs: Vec<(TileId, &PathBuf, &PathBuf)> =
+        baseline.iter().filter_map(|(id, path)| Some((*id, path, candidate.get(id)?))).collect();
+    let comparisons = pairs
+        .into_par_iter()
+        .map(|(id, baseline, candidate)| {
+            TileComparison { id, outcome: compare_tile(baseline, candidate, &options_for(&id)) }
+        })
+        .collect();
+    Ok(TileReport {
+        comparisons,
+        missing_baseline: missing(&candidate, &baseline),
+        missing_candidate: missing(&baseline, &candidate),
+    })
+}
+
+fn compare_tile(baseline: &Path, candidate: &Path, options: &Options) -> TileOutcome {
+    let load = |path: &Path| read_png(path).map_err(|e| format!("{}: {e}", path.display()));
+    let (baseline, candidate) = match (load(baseline), load(candidate)) {
+        (Ok(b), Ok(c)) => (b, c),
+        (Err(e), _) | (_, Err(e)) => return TileOutcome::Error(e),
+    };
+    let (width, height) = (baseline.width, baseline.height);
+    if (candidate.width, candidate.height) != (width, height) {
+        let candidate = (candidate.width, candidate.height);
+        return TileOutcome::DimensionMismatch { baseline: (width, height), candidate };
+    }
+    match pixelmatch(&baseline.data, &candidate.data, None, width, height, options) {
+        Ok(result) => TileOutcome::Compared { result, width, height },
+        Err(e) => TileOutcome::Error(e.to_string()),
+    }
+}
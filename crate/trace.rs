@@ -0,0 +1,30 @@
+//! Instrumentation through the `tracing` crate (`tracing` feature).
+//!
+//! Without the feature the macros expand to nothing, so call sites need no `cfg`. Spans
+//! and events are emitted at debug level under the `pixelmatch` target; a subscriber that
+//! records span close times (e.g. `tracing_subscriber`'s `FmtSpan::CLOSE`) gives a timing
+//! breakdown of each comparison phase.
+
+/// Enter a debug-level span for the rest of the enclosing scope: bind the result, as in
+/// `let _span = trace_span!("prefilter");`.
+macro_rules! trace_span {
+    ($($arg:tt)*) => {{
+        #[cfg(feature = "tracing")]
+        let span = tracing::debug_span!(target: "pixelmatch", $($arg)*).entered();
+        #[cfg(not(feature = "tracing"))]
+        let span = $crate::trace::NoSpan;
+        span
+    }};
+}
+
+/// Stand-in for an entered span without the `tracing` feature.
+#[cfg(not(feature = "tracing"))]
+pub(crate) struct NoSpan;
+
+/// Emit a debug-level event.
+macro_rules! trace_event {
+    ($($arg:tt)*) => {
+        #[cfg(feature = "tracing")]
+        tracing::debug!(target: "pixelmatch", $($arg)*);
+    };
+}
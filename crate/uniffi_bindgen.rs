@@ -0,0 +1,7 @@
+//! `uniffi-bindgen`, pinned to the crate's UniFFI version. Generate Kotlin and Swift bindings
+//! from the built library with
+//! `cargo run --features uniffi-bindgen --bin uniffi-bindgen generate --library <lib> --language kotlin --out-dir <dir>`.
+
+fn main() {
+    uniffi::uniffi_bindgen_main()
+}
@@ -0,0 +1,120 @@
+use crate::Options;
+
+/// An RGB colour.
+#[derive(uniffi::Record)]
+pub struct Rgb {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+}
+
+impl From<Rgb> for [u8; 3] {
+    fn from(c: Rgb) -> Self {
+        [c.r, c.g, c.b]
+    }
+}
+
+/// Comparison options; every field defaults to the library default.
+#[derive(uniffi::Record)]
+pub struct MatchOptions {
+    /// Matching threshold (0 to 1); smaller is more sensitive.
+    #[uniffi(default = 0.1)]
+    pub threshold: f64,
+    /// Whether to detect and exclude anti-aliased pixels from the diff count.
+    #[uniffi(default = true)]
+    pub detect_anti_aliasing: bool,
+    /// Opacity of the original image in the diff output.
+    #[uniffi(default = 0.1)]
+    pub alpha: f64,
+    /// Colour of anti-aliased pixels in the diff output. Default: yellow.
+    #[uniffi(default = None)]
+    pub aa_color: Option<Rgb>,
+    /// Colour of different pixels in the diff output. Default: red.
+    #[uniffi(default = None)]
+    pub diff_color: Option<Rgb>,
+    /// Alternative diff colour for dark-on-light differences. Default: `diff_color`.
+    #[uniffi(default = None)]
+    pub diff_color_alt: Option<Rgb>,
+    /// Draw the diff over a transparent background (a mask).
+    #[uniffi(default = false)]
+    pub diff_mask: bool,
+}
+
+impl From<MatchOptions> for Options {
+    fn from(o: MatchOptions) -> Self {
+        let defaults = Options::default();
+        Options {
+            threshold: o.threshold,
+            detect_anti_aliasing: o.detect_anti_aliasing,
+            alpha: o.alpha,
+            aa_color: o.aa_color.map_or(defaults.aa_color, Into::into),
+            diff_color: o.diff_color.map_or(defaults.diff_color, Into::into),
+            diff_color_alt: o.diff_color_alt.map(Into::into),
+            diff_mask: o.diff_mask,
+            ..defaults
+        }
+    }
+}
+
+/// Result of a comparison.
+#[derive(uniffi::Record)]
+pub struct ComparisonResult {
+    pub diff_count: u32,
+    pub aa_count: u32,
+    pub identical: bool,
+}
+
+/// Result of a comparison with the RGBA diff image.
+#[derive(uniffi::Record)]
+pub struct DiffResult {
+    pub diff_count: u32,
+    pub aa_count: u32,
+    pub identical: bool,
+    /// width * height * 4 bytes.
+    pub diff: Vec<u8>,
+}
+
+/// A comparison could not run, e.g. because a buffer does not match the dimensions.
+#[derive(Debug, uniffi::Error)]
+#[uniffi(flat_error)]
+pub enum CompareError {
+    Invalid(crate::PixelmatchError),
+}
+
+impl std::fmt::Display for CompareError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Invalid(e) => e.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for CompareError {}
+
+/// Compare two RGBA images of `width * height * 4` bytes.
+#[uniffi::export]
+pub fn compare(
+    img1: Vec<u8>,
+    img2: Vec<u8>,
+    width: u32,
+    height: u32,
+    options: MatchOptions,
+) -> Result<ComparisonResult, CompareError> {
+    let r = crate::pixelmatch(&img1, &img2, None, width, height, &options.into()).map_err(CompareError::Invalid)?;
+    Ok(ComparisonResult { diff_count: r.diff_count, aa_count: r.aa_count, identical: r.identical })
+}
+
+/// Compare two RGBA images, also returning the diff image.
+#[uniffi::export]
+pub fn compare_with_diff(
+    img1: Vec<u8>,
+    img2: Vec<u8>,
+    width: u32,
+    height: u32,
+    options: MatchOptions,
+) -> Result<DiffResult, CompareError> {
+    let mut diff = vec![0u8; img1.len()];
+    let r = crate::pixelmatch(&img1, &img2, Some(&mut diff), width, height, &options.into())
+        .map_err(CompareError::Invalid)?;
+    Ok(DiffResult { diff_count: r.diff_count, aa_count: r.aa_count, identical: r.identical, diff })
+}
@@ -1,13 +1,51 @@
+use js_sys::Uint8ClampedArray;
 use wasm_bindgen::prelude::*;
+use wasm_bindgen::Clamped;
+use web_sys::{
+    CanvasRenderingContext2d, HtmlCanvasElement, ImageData, OffscreenCanvas, OffscreenCanvasRenderingContext2d,
+};
 
+use crate::stream::StreamMatcher;
 use crate::Options;
 
+/// The JS options object accepted by the `*_with_options` entry points. It takes the keys
+/// of [`Options::from_json`]; missing keys keep their defaults and unknown keys are ignored,
+/// so new options can be added without changing the exported signatures.
+fn parse_options(options: JsValue) -> Result<Options, JsError> {
+    if options.is_undefined() || options.is_null() {
+        return Ok(Options::default());
+    }
+    let json = js_sys::JSON::stringify(&options).map_err(|_| JsError::new("Invalid options: not serializable"))?;
+    let Ok(serde_json::Value::Object(fields)) = serde_json::from_str(&String::from(json)) else {
+        return Err(JsError::new("Invalid options: expected an object"));
+    };
+    crate::json::from_js_object(fields).map_err(|e| JsError::new(&e.to_string()))
+}
+
 /// Result of a WASM pixel comparison.
 #[wasm_bindgen]
 pub struct WasmMatchResult {
     diff_count: u32,
     aa_count: u32,
     identical: bool,
+    total_pixels: f64,
+    diff_ratio: f64,
+    diff: Option<ImageData>,
+    output: Option<Vec<u8>>,
+}
+
+impl From<crate::MatchResult> for WasmMatchResult {
+    fn from(result: crate::MatchResult) -> Self {
+        Self {
+            diff_count: result.diff_count,
+            aa_count: result.aa_count,
+            identical: result.identical,
+            total_pixels: result.total_pixels as f64,
+            diff_ratio: result.diff_ratio(),
+            diff: None,
+            output: None,
+        }
+    }
 }
 
 #[wasm_bindgen]
@@ -26,13 +64,39 @@ impl WasmMatchResult {
     pub fn identical(&self) -> bool {
         self.identical
     }
+
+    /// width * height, as a JS number so that images over 2^32 pixels are counted exactly.
+    #[wasm_bindgen(getter)]
+    pub fn total_pixels(&self) -> f64 {
+        self.total_pixels
+    }
+
+    /// diff_count / total_pixels, for percentage-based limits.
+    #[wasm_bindgen(getter)]
+    pub fn diff_ratio(&self) -> f64 {
+        self.diff_ratio
+    }
+
+    /// The diff image, from `pixelmatch_image_data_with_diff` only.
+    #[wasm_bindgen(getter)]
+    pub fn diff(&self) -> Option<ImageData> {
+        self.diff.clone()
+    }
+
+    /// The RGBA diff pixels, from `pixelmatch_wasm_alloc` only. Each access returns a
+    /// fresh copy.
+    #[wasm_bindgen(getter)]
+    pub fn output(&self) -> Option<Uint8ClampedArray> {
+        self.output.as_deref().map(Uint8ClampedArray::from)
+    }
 }
 
 /// Compare two images pixel by pixel (WASM entry point).
 ///
-/// Uses flattened parameters instead of an options object — the thin JS wrapper
-/// in `wasm/index.js` destructures the ergonomic options-object API into these.
+/// Uses flattened parameters instead of an options object. Kept for compatibility;
+/// new code should use `pixelmatch_wasm_with_options`.
 #[wasm_bindgen]
+#[allow(clippy::too_many_arguments)] // the flattened signature is kept only for compatibility
 pub fn pixelmatch_wasm(
     img1: &[u8],
     img2: &[u8],
@@ -62,18 +126,18 @@ pub fn pixelmatch_wasm(
         diff_color: [diff_r, diff_g, diff_b],
         diff_color_alt: if has_alt { Some([alt_r, alt_g, alt_b]) } else { None },
         diff_mask,
+        ..Default::default()
     };
     let result = crate::pixelmatch(img1, img2, Some(output), width, height, &options)
         .map_err(|e| JsError::new(&e.to_string()))?;
-    Ok(WasmMatchResult {
-        diff_count: result.diff_count,
-        aa_count: result.aa_count,
-        identical: result.identical,
-    })
+    Ok(result.into())
 }
 
 /// Compare two images pixel by pixel without diff output (WASM entry point).
+///
+/// Kept for compatibility; new code should use `pixelmatch_wasm_count_with_options`.
 #[wasm_bindgen]
+#[allow(clippy::too_many_arguments)] // the flattened signature is kept only for compatibility
 pub fn pixelmatch_wasm_count(
     img1: &[u8],
     img2: &[u8],
@@ -102,12 +166,256 @@ pub fn pixelmatch_wasm_count(
         diff_color: [diff_r, diff_g, diff_b],
         diff_color_alt: if has_alt { Some([alt_r, alt_g, alt_b]) } else { None },
         diff_mask,
+        ..Default::default()
     };
     let result = crate::pixelmatch(img1, img2, None, width, height, &options)
         .map_err(|e| JsError::new(&e.to_string()))?;
-    Ok(WasmMatchResult {
-        diff_count: result.diff_count,
-        aa_count: result.aa_count,
-        identical: result.identical,
-    })
+    Ok(result.into())
+}
+
+/// Compare two images pixel by pixel, taking a JS options object
+/// (`{ threshold, detectAntiAliasing, alpha, aaColor, diffColor, diffColorAlt, diffMask }`).
+/// `ignore_mask` is [`Options::ignore_mask`], passed apart from the options as bytes.
+#[wasm_bindgen]
+pub fn pixelmatch_wasm_with_options(
+    img1: &[u8],
+    img2: &[u8],
+    output: &mut [u8],
+    width: u32,
+    height: u32,
+    options: JsValue,
+    ignore_mask: Option<Vec<u8>>,
+) -> Result<WasmMatchResult, JsError> {
+    let options = Options { ignore_mask, ..parse_options(options)? };
+    let result = crate::pixelmatch(img1, img2, Some(output), width, height, &options)
+        .map_err(|e| JsError::new(&e.to_string()))?;
+    Ok(result.into())
+}
+
+/// Compare two images without diff output, taking a JS options object and an optional
+/// ignore mask like [`pixelmatch_wasm_with_options`].
+#[wasm_bindgen]
+pub fn pixelmatch_wasm_count_with_options(
+    img1: &[u8],
+    img2: &[u8],
+    width: u32,
+    height: u32,
+    options: JsValue,
+    ignore_mask: Option<Vec<u8>>,
+) -> Result<WasmMatchResult, JsError> {
+    let options = Options { ignore_mask, ..parse_options(options)? };
+    let result = crate::pixelmatch(img1, img2, None, width, height, &options)
+        .map_err(|e| JsError::new(&e.to_string()))?;
+    Ok(result.into())
+}
+
+/// Compare two images, allocating the diff buffer inside the module. The diff is returned
+/// as a `Uint8ClampedArray` in the result's `output`, so no pre-sized output array is needed.
+#[wasm_bindgen]
+pub fn pixelmatch_wasm_alloc(
+    img1: &[u8],
+    img2: &[u8],
+    width: u32,
+    height: u32,
+    options: JsValue,
+) -> Result<WasmMatchResult, JsError> {
+    let options = parse_options(options)?;
+    let mut output = vec![0u8; img1.len()];
+    let result = crate::pixelmatch(img1, img2, Some(&mut output), width, height, &options)
+        .map_err(|e| JsError::new(&e.to_string()))?;
+    Ok(WasmMatchResult { output: Some(output), ..result.into() })
+}
+
+fn image_data_size(img1: &ImageData, img2: &ImageData) -> Result<(u32, u32), JsError> {
+    let (w1, h1) = (img1.width(), img1.height());
+    let (w2, h2) = (img2.width(), img2.height());
+    if (w1, h1) != (w2, h2) {
+        return Err(JsError::new(&format!("Image dimensions do not match: {w1}x{h1} vs {w2}x{h2}")));
+    }
+    Ok((w1, h1))
+}
+
+/// Compare two `ImageData`s (e.g. from `ctx.getImageData()`) without diff output,
+/// taking a JS options object.
+#[wasm_bindgen]
+pub fn pixelmatch_image_data(img1: &ImageData, img2: &ImageData, options: JsValue) -> Result<WasmMatchResult, JsError> {
+    let (width, height) = image_data_size(img1, img2)?;
+    let options = parse_options(options)?;
+    let result = crate::pixelmatch(&img1.data(), &img2.data(), None, width, height, &options)
+        .map_err(|e| JsError::new(&e.to_string()))?;
+    Ok(result.into())
+}
+
+/// Compare two `ImageData`s, returning the diff as a new `ImageData` in the result's `diff`,
+/// ready for `ctx.putImageData()`.
+#[wasm_bindgen]
+pub fn pixelmatch_image_data_with_diff(
+    img1: &ImageData,
+    img2: &ImageData,
+    options: JsValue,
+) -> Result<WasmMatchResult, JsError> {
+    let (width, height) = image_data_size(img1, img2)?;
+    let options = parse_options(options)?;
+    let data1 = img1.data();
+    let mut output = vec![0u8; data1.len()];
+    let result = crate::pixelmatch(&data1, &img2.data(), Some(&mut output), width, height, &options)
+        .map_err(|e| JsError::new(&e.to_string()))?;
+    let diff = ImageData::new_with_u8_clamped_array_and_sh(Clamped(&output), width, height)
+        .map_err(|_| JsError::new("Failed to create diff ImageData"))?;
+    Ok(WasmMatchResult { diff: Some(diff), ..result.into() })
+}
+
+/// The 2D context of an `HTMLCanvasElement` or `OffscreenCanvas`.
+enum Canvas2d {
+    Html(HtmlCanvasElement, CanvasRenderingContext2d),
+    Offscreen(OffscreenCanvas, OffscreenCanvasRenderingContext2d),
+}
+
+impl Canvas2d {
+    fn new(canvas: &JsValue, name: &str) -> Result<Self, JsError> {
+        let no_context = || JsError::new(&format!("{name}: no 2d context available"));
+        if let Some(c) = canvas.dyn_ref::<HtmlCanvasElement>() {
+            let ctx = c.get_context("2d").ok().flatten().ok_or_else(no_context)?;
+            Ok(Self::Html(c.clone(), ctx.dyn_into().map_err(|_| no_context())?))
+        } else if let Some(c) = canvas.dyn_ref::<OffscreenCanvas>() {
+            let ctx = c.get_context("2d").ok().flatten().ok_or_else(no_context)?;
+            Ok(Self::Offscreen(c.clone(), ctx.dyn_into().map_err(|_| no_context())?))
+        } else {
+            Err(JsError::new(&format!("{name}: expected an HTMLCanvasElement or OffscreenCanvas")))
+        }
+    }
+
+    fn image_data(&self) -> Result<ImageData, JsError> {
+        let data = match self {
+            Self::Html(c, ctx) => ctx.get_image_data(0.0, 0.0, c.width() as f64, c.height() as f64),
+            Self::Offscreen(c, ctx) => ctx.get_image_data(0.0, 0.0, c.width() as f64, c.height() as f64),
+        };
+        data.map_err(|_| JsError::new("Failed to read canvas pixels (is it tainted by cross-origin content?)"))
+    }
+
+    /// Resize the canvas to the image and draw it.
+    fn paint(&self, image: &ImageData) -> Result<(), JsError> {
+        let drawn = match self {
+            Self::Html(c, ctx) => {
+                c.set_width(image.width());
+                c.set_height(image.height());
+                ctx.put_image_data(image, 0.0, 0.0)
+            }
+            Self::Offscreen(c, ctx) => {
+                c.set_width(image.width());
+                c.set_height(image.height());
+                ctx.put_image_data(image, 0.0, 0.0)
+            }
+        };
+        drawn.map_err(|_| JsError::new("Failed to draw the diff onto the target canvas"))
+    }
+}
+
+/// Compare the contents of two canvases (`HTMLCanvasElement`, or `OffscreenCanvas` in a
+/// worker). If `target` is a canvas, it is resized to the images and the diff painted onto it.
+#[wasm_bindgen]
+pub fn pixelmatch_canvas(
+    canvas1: JsValue,
+    canvas2: JsValue,
+    target: JsValue,
+    options: JsValue,
+) -> Result<WasmMatchResult, JsError> {
+    let img1 = Canvas2d::new(&canvas1, "canvas1")?.image_data()?;
+    let img2 = Canvas2d::new(&canvas2, "canvas2")?.image_data()?;
+    if target.is_undefined() || target.is_null() {
+        return pixelmatch_image_data(&img1, &img2, options);
+    }
+    let target = Canvas2d::new(&target, "target")?;
+    let result = pixelmatch_image_data_with_diff(&img1, &img2, options)?;
+    if let Some(diff) = &result.diff {
+        target.paint(diff)?;
+    }
+    Ok(result)
+}
+
+/// Decode both PNGs, checking that their dimensions match.
+#[cfg(feature = "png")]
+fn decode_pair(png1: &[u8], png2: &[u8]) -> Result<(crate::io::Image, crate::io::Image), JsError> {
+    let decode = |name: &str, bytes: &[u8]| {
+        crate::io::decode_png(bytes).map_err(|e| JsError::new(&format!("{name}: invalid PNG: {e}")))
+    };
+    let (img1, img2) = (decode("img1", png1)?, decode("img2", png2)?);
+    if (img1.width, img1.height) != (img2.width, img2.height) {
+        return Err(JsError::new(&format!(
+            "Image dimensions do not match: {}x{} vs {}x{}",
+            img1.width, img1.height, img2.width, img2.height
+        )));
+    }
+    Ok((img1, img2))
+}
+
+/// Compare two PNG files (e.g. fetched blobs) decoded inside the module, avoiding the canvas
+/// round-trip that premultiplies alpha. Requires the `wasm-png` feature.
+#[cfg(feature = "png")]
+#[wasm_bindgen]
+pub fn pixelmatch_png(png1: &[u8], png2: &[u8], options: JsValue) -> Result<WasmMatchResult, JsError> {
+    let options = parse_options(options)?;
+    let (img1, img2) = decode_pair(png1, png2)?;
+    let result = crate::pixelmatch(&img1.data, &img2.data, None, img1.width, img1.height, &options)
+        .map_err(|e| JsError::new(&e.to_string()))?;
+    Ok(result.into())
+}
+
+/// Like `pixelmatch_png`, also returning the diff as an `ImageData` in the result's `diff`.
+#[cfg(feature = "png")]
+#[wasm_bindgen]
+pub fn pixelmatch_png_with_diff(png1: &[u8], png2: &[u8], options: JsValue) -> Result<WasmMatchResult, JsError> {
+    let options = parse_options(options)?;
+    let (img1, img2) = decode_pair(png1, png2)?;
+    let (width, height) = (img1.width, img1.height);
+    let mut output = vec![0u8; img1.data.len()];
+    let result = crate::pixelmatch(&img1.data, &img2.data, Some(&mut output), width, height, &options)
+        .map_err(|e| JsError::new(&e.to_string()))?;
+    let diff = ImageData::new_with_u8_clamped_array_and_sh(Clamped(&output), width, height)
+        .map_err(|_| JsError::new("Failed to create diff ImageData"))?;
+    Ok(WasmMatchResult { diff: Some(diff), ..result.into() })
+}
+
+/// Stateful matcher for images too large to pass in one piece: push row bands of both
+/// images in order, then call `finish`.
+#[wasm_bindgen]
+pub struct WasmStreamMatcher {
+    inner: Option<StreamMatcher>,
+    with_diff: bool,
+}
+
+#[wasm_bindgen]
+impl WasmStreamMatcher {
+    /// Start a comparison of `width` x `height` images. With `with_diff`, each `push` returns
+    /// the diff pixels of the rows it finalised.
+    #[wasm_bindgen(constructor)]
+    pub fn new(width: u32, height: u32, options: JsValue, with_diff: bool) -> Result<WasmStreamMatcher, JsError> {
+        let options = parse_options(options)?;
+        let inner = StreamMatcher::new(width, height, options).map_err(|e| JsError::new(&e.to_string()))?;
+        Ok(Self { inner: Some(inner), with_diff })
+    }
+
+    /// Add the next band of whole rows of both images. Returns the RGBA diff of the rows
+    /// finalised by this band (empty without `with_diff`); rows lag up to two behind the
+    /// input, because anti-aliasing detection needs the rows below.
+    pub fn push(&mut self, band1: &[u8], band2: &[u8]) -> Result<Uint8ClampedArray, JsError> {
+        let inner = self.inner.as_mut().ok_or_else(|| JsError::new("Matcher already finished"))?;
+        let mut diff = Vec::new();
+        inner
+            .push(band1, band2, self.with_diff.then_some(&mut diff))
+            .map_err(|e| JsError::new(&e.to_string()))?;
+        Ok(Uint8ClampedArray::from(&diff[..]))
+    }
+
+    /// Number of rows pushed so far.
+    #[wasm_bindgen(getter)]
+    pub fn rows_received(&self) -> u32 {
+        self.inner.as_ref().map_or(0, StreamMatcher::rows_received)
+    }
+
+    /// The result once every row has been pushed. The matcher cannot be used afterwards.
+    pub fn finish(&mut self) -> Result<WasmMatchResult, JsError> {
+        let inner = self.inner.take().ok_or_else(|| JsError::new("Matcher already finished"))?;
+        Ok(inner.finish().map_err(|e| JsError::new(&e.to_string()))?.into())
+    }
 }
@@ -0,0 +1,65 @@
+//! Comparison of 16-bit and floating-point images.
+//!
+//! HDR renders, scientific plots and 16-bit PNGs lose detail when stripped to 8 bits before
+//! comparing. [`pixelmatch_wide`] runs the same delta, anti-aliasing and underlay code as
+//! [`pixelmatch`](crate::pixelmatch) over any [`Component`] type, with channels scaled to
+//! 0..=255 so a threshold means the same at every depth. The diff image is always 8-bit.
+
+use crate::{
+    compare_rows, draw_identical, ignore_mask, rejected, validate_buffers, Component, MatchResult, Options,
+    PixelmatchError, Rect,
+};
+
+/// Compare two equally sized RGBA images of `u8`, `u16` or `f32` (0.0 to 1.0) components.
+///
+/// `output`, if given, is an 8-bit RGBA diff image with one byte per input component. Only
+/// the options that work pixel by pixel apply: thresholds, anti-aliasing, colours and
+/// ignored areas. Filtering, tolerances, mask post-processing, scaling and scroll
+/// compensation work on 8-bit copies and are rejected with
+/// [`PixelmatchError::ConflictingOptions`]; use [`pixelmatch`](crate::pixelmatch) for those.
+pub fn pixelmatch_wide<T: Component>(
+    img1: &[T],
+    img2: &[T],
+    output: Option<&mut [u8]>,
+    width: u32,
+    height: u32,
+    options: &Options,
+) -> Result<MatchResult, PixelmatchError> {
+    let _span = trace_span!("pixelmatch_wide", width, height);
+    options.validate().map_err(rejected)?;
+    if needs_bytes(options) {
+        return Err(rejected(PixelmatchError::ConflictingOptions("option requires 8-bit images")));
+    }
+    let len = validate_buffers(img1, img2, output.as_deref().map(<[u8]>::len), width, height).map_err(rejected)?;
+    let (w, h) = (width as usize, height as usize);
+    let ignore = ignore_mask(options, w, h).map_err(rejected)?;
+
+    if img1 == img2 {
+        if let Some(out) = output {
+            draw_identical(img1, out, w, ignore.as_deref(), options);
+        }
+        return Ok(MatchResult { identical: true, ..Default::default() }.with_total_pixels(len));
+    }
+    let area = Rect::new(0, 0, width, height);
+    let tally = compare_rows(img1, img2, output, w, h, area, ignore.as_deref(), None, options);
+    let result =
+        MatchResult { diff_count: tally.diff, aa_count: tally.aa, diff_bounds: tally.bounds, ..Default::default() };
+    Ok(result.with_total_pixels(len))
+}
+
+/// Whether `options` asks for a step that only works on 8-bit images.
+fn needs_bytes(o: &Options) -> bool {
+    o.shift_tolerance > 0
+        || o.subpixel_tolerance
+        || o.jpeg_tolerance.is_some()
+        || o.scroll_compensation > 0
+        || o.isolation_radius > 0
+        || !o.morphology.is_empty()
+        || o.label_shift > 0
+        || o.pre_blur.is_some()
+        || o.quantize.is_some()
+        || o.median_filter
+        || o.edges.is_some()
+        || o.downscale.is_some()
+        || o.device_pixel_ratio.is_some()
+}
@@ -0,0 +1,137 @@
+/* Generated by cbindgen from crate/capi.rs. Do not edit by hand. */
+
+#ifndef PIXELMATCH_H
+#define PIXELMATCH_H
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+// Number of `double`s read from the `options` array of `pixelmatch_compare_flat`.
+#define PIXELMATCH_FLAT_OPTIONS_LEN 14
+
+// Number of `uint32_t`s written to the `result` array of `pixelmatch_compare_flat`.
+#define PIXELMATCH_FLAT_RESULT_LEN 3
+
+// Status code returned by `pixelmatch_compare`.
+typedef enum PixelmatchStatus {
+  PIXELMATCH_STATUS_OK = 0,
+  // A required pointer argument was null.
+  PIXELMATCH_STATUS_NULL_POINTER = 1,
+  // Width * height overflows addressable memory.
+  PIXELMATCH_STATUS_DIMENSION_OVERFLOW = 2,
+  // `len` does not match width * height * 4.
+  PIXELMATCH_STATUS_BUFFER_LENGTH_MISMATCH = 3,
+  // An option value was rejected.
+  PIXELMATCH_STATUS_INVALID_ARGUMENT = 4,
+  // An internal error; no result was produced.
+  PIXELMATCH_STATUS_INTERNAL = 5,
+} PixelmatchStatus;
+
+// Comparison options. Start from `pixelmatch_default_options()` and override fields.
+typedef struct PixelmatchOptions {
+  // Matching threshold (0 to 1); smaller is more sensitive.
+  double threshold;
+  // Whether to detect and exclude anti-aliased pixels from the diff count.
+  bool detect_anti_aliasing;
+  // Opacity of the original image in the diff output.
+  double alpha;
+  // Colour of anti-aliased pixels in the diff output.
+  uint8_t aa_color[3];
+  // Colour of different pixels in the diff output.
+  uint8_t diff_color[3];
+  // Whether `diff_color_alt` is used for dark-on-light differences.
+  bool has_diff_color_alt;
+  // Alternative diff colour for dark-on-light differences.
+  uint8_t diff_color_alt[3];
+  // Draw the diff over a transparent background (a mask).
+  bool diff_mask;
+} PixelmatchOptions;
+
+// Result of a comparison.
+typedef struct PixelmatchResult {
+  // Number of mismatched pixels.
+  uint32_t diff_count;
+  // Number of anti-aliased pixels detected.
+  uint32_t aa_count;
+  // Whether the two images are byte-identical.
+  bool identical;
+} PixelmatchResult;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+// The default options.
+struct PixelmatchOptions pixelmatch_default_options(void);
+
+// Compare two RGBA images of `len` bytes each (`width * height * 4`).
+//
+// `output` may be null; otherwise it receives the `len`-byte diff image. `options` may be
+// null for the defaults. On success `*result` is filled in and `PIXELMATCH_STATUS_OK` is
+// returned; on failure `*result` is left untouched.
+//
+// # Safety
+// `img1` and `img2` must point to `len` readable bytes, `output` (if not null) to `len`
+// writable bytes not overlapping the inputs, and `options` (if not null) and `result` to
+// valid structs.
+enum PixelmatchStatus pixelmatch_compare(const uint8_t *img1,
+                                         const uint8_t *img2,
+                                         uint8_t *output,
+                                         size_t len,
+                                         uint32_t width,
+                                         uint32_t height,
+                                         const struct PixelmatchOptions *options,
+                                         struct PixelmatchResult *result);
+
+// `pixelmatch_compare` with the options given as a NUL-terminated UTF-8 JSON object, which
+// reaches every option rather than only those in `PixelmatchOptions`. Keys are camelCase
+// (`{"threshold": 0.2, "ignoreRegions": [{"x": 0, "y": 0, "width": 10, "height": 10}]}`);
+// missing keys keep their defaults. `options_json` may be null for the defaults.
+// Malformed JSON, unknown keys and invalid values give `PIXELMATCH_STATUS_INVALID_ARGUMENT`.
+//
+// # Safety
+// As for `pixelmatch_compare`; `options_json` (if not null) must point to a NUL-terminated
+// string.
+enum PixelmatchStatus pixelmatch_compare_json(const uint8_t *img1,
+                                              const uint8_t *img2,
+                                              uint8_t *output,
+                                              size_t len,
+                                              uint32_t width,
+                                              uint32_t height,
+                                              const char *options_json,
+                                              struct PixelmatchResult *result);
+
+// A static, NUL-terminated description of a status code.
+const char *pixelmatch_status_message(enum PixelmatchStatus status);
+
+// The library version, as a static NUL-terminated string.
+const char *pixelmatch_version(void);
+
+// `pixelmatch_compare` for FFIs without struct support (Deno, Bun).
+//
+// `options` is null for the defaults, or `PIXELMATCH_FLAT_OPTIONS_LEN` doubles:
+// `[threshold, detect_anti_aliasing, alpha, aa_r, aa_g, aa_b, diff_r, diff_g, diff_b,
+// has_diff_color_alt, alt_r, alt_g, alt_b, diff_mask]`, with booleans as 0 or 1. `result`
+// receives `[diff_count, aa_count, identical]`. Returns a `PixelmatchStatus` value.
+//
+// # Safety
+// As for `pixelmatch_compare`; `options` (if not null) must point to
+// `PIXELMATCH_FLAT_OPTIONS_LEN` doubles and `result` to `PIXELMATCH_FLAT_RESULT_LEN`
+// writable `uint32_t`s.
+int32_t pixelmatch_compare_flat(const uint8_t *img1,
+                                const uint8_t *img2,
+                                uint8_t *output,
+                                size_t len,
+                                uint32_t width,
+                                uint32_t height,
+                                const double *options,
+                                uint32_t *result);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* PIXELMATCH_H */
@@ -1,6 +1,6 @@
 import { createRequire } from 'node:module';
 import type { ImageLike, PixelData, PixelmatchOptions, PixelmatchResult } from './types.js';
-import { buildResult, validateInput } from './validate.js';
+import { buildResult, normalizeIgnoreMask, validateInput } from './validate.js';
 import jsFallback from './pixelmatch.js';
 
 export type { ImageLike, PixelData, PixelmatchOptions, PixelmatchResult } from './types.js';
@@ -19,6 +19,7 @@ interface NativeOptions {
   diffColor?: number[];
   diffColorAlt?: number[];
   diffMask?: boolean;
+  ignoreMask?: Uint8Array;
 }
 
 interface NativeBinding {
@@ -90,18 +91,19 @@ if (native) {
   const { pixelmatch: nativeMatch, pixelmatchCount } = native;
 
   const fn = (img1: ImageLike, img2: ImageLike, options: PixelmatchOptions = {}): PixelmatchResult => {
-    const { output, ...rest } = options;
+    const { output, ignoreMask, ...rest } = options;
     validateInput(img1, img2, output);
 
     const { data: data1, width, height } = img1;
     const { data: data2 } = img2;
     const totalPixels = width * height;
+    const nativeOptions: NativeOptions = { ...rest, ignoreMask: normalizeIgnoreMask(ignoreMask, totalPixels) };
 
     let raw: NativeMatchResult;
     if (output) {
-      raw = nativeMatch(data1, data2, output, width, height, rest);
+      raw = nativeMatch(data1, data2, output, width, height, nativeOptions);
     } else {
-      raw = pixelmatchCount(data1, data2, width, height, rest);
+      raw = pixelmatchCount(data1, data2, width, height, nativeOptions);
     }
 
     return buildResult(raw.diffCount, raw.aaCount, totalPixels, raw.identical);
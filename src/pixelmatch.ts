@@ -1,5 +1,5 @@
 import type { ImageLike, PixelData, PixelmatchOptions, PixelmatchResult } from './types.js';
-import { buildResult, validateInput } from './validate.js';
+import { buildResult, normalizeIgnoreMask, validateInput } from './validate.js';
 
 export type { ImageLike, PixelData, PixelmatchOptions, PixelmatchResult } from './types.js';
 
@@ -27,6 +27,7 @@ export default function pixelmatch(img1: ImageLike, img2: ImageLike, options: Pi
 
   // check if images are identical
   const len = width * height;
+  const ignored = normalizeIgnoreMask(options.ignoreMask, len);
   const a32 = new Uint32Array(data1.buffer, data1.byteOffset, len);
   const b32 = new Uint32Array(data2.buffer, data2.byteOffset, len);
   let identical = true;
@@ -60,6 +61,12 @@ export default function pixelmatch(img1: ImageLike, img2: ImageLike, options: Pi
       const i = y * width + x;
       const pos = i * 4;
 
+      // ignored pixels are never counted; draw them as background
+      if (ignored && ignored[i]) {
+        if (output && !diffMask) drawGrayPixel(data1, pos, alpha, output);
+        continue;
+      }
+
       // squared YUV distance between colours at this pixel position, negative if the img2 pixel is darker
       const delta = a32[i] === b32[i] ? 0 : colorDelta(data1, data2, pos, pos, false);
 
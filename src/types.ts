@@ -23,6 +23,8 @@ export interface PixelmatchOptions {
   diffColor?: [number, number, number];
   /** Alternative diff colour for pixels that are darker in img2 [R, G, B]. Default: same as diffColor */
   diffColorAlt?: [number, number, number];
+  /** Pixels to exclude from the comparison: one byte per pixel, or RGBA (alpha is used). Non-zero pixels are treated as equal and drawn as the grayscale background. Default: none */
+  ignoreMask?: PixelData;
 }
 
 export interface PixelmatchResult {
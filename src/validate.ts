@@ -24,6 +24,21 @@ export function validateInput(img1: ImageLike, img2: ImageLike, output?: PixelDa
     throw new Error(`Output buffer size does not match. Expecting ${expected}. Got ${output.length}`);
 }
 
+/** Reduce an ignore mask (one byte per pixel, or RGBA) to one byte per pixel, non-zero where ignored. */
+export function normalizeIgnoreMask(mask: PixelData | undefined, totalPixels: number): Uint8Array | undefined {
+  if (!mask) return undefined;
+  if (!isPixelData(mask)) throw new Error('Ignore mask: Uint8Array, Uint8ClampedArray or Buffer expected.');
+  if (mask.length === totalPixels) return new Uint8Array(mask.buffer, mask.byteOffset, mask.length);
+  if (mask.length === totalPixels * 4) {
+    const ignored = new Uint8Array(totalPixels);
+    for (let i = 0; i < totalPixels; i++) ignored[i] = mask[i * 4 + 3];
+    return ignored;
+  }
+  throw new Error(
+    `Ignore mask size does not match image size. Expecting ${totalPixels} or ${totalPixels * 4}. Got ${mask.length}`,
+  );
+}
+
 export function buildResult(
   diffCount: number,
   aaCount: number,
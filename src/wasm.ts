@@ -1,5 +1,5 @@
 import type { ImageLike, PixelData, PixelmatchOptions, PixelmatchResult } from './types.js';
-import { buildResult, validateInput } from './validate.js';
+import { buildResult, normalizeIgnoreMask, validateInput } from './validate.js';
 
 export type { ImageLike, PixelData, PixelmatchOptions, PixelmatchResult } from './types.js';
 
@@ -12,46 +12,22 @@ interface WasmMatchResult {
 
 interface WasmBindings {
   default: (wasmUrl?: string | URL) => Promise<void>;
-  pixelmatch_wasm: (
+  pixelmatch_wasm_with_options: (
     img1: PixelData,
     img2: PixelData,
     output: PixelData,
     width: number,
     height: number,
-    threshold: number,
-    detectAntiAliasing: boolean,
-    alpha: number,
-    aaR: number,
-    aaG: number,
-    aaB: number,
-    diffR: number,
-    diffG: number,
-    diffB: number,
-    hasAlt: boolean,
-    altR: number,
-    altG: number,
-    altB: number,
-    diffMask: boolean,
+    options: Omit<PixelmatchOptions, 'output' | 'ignoreMask'>,
+    ignoreMask: Uint8Array | undefined,
   ) => WasmMatchResult;
-  pixelmatch_wasm_count: (
+  pixelmatch_wasm_count_with_options: (
     img1: PixelData,
     img2: PixelData,
     width: number,
     height: number,
-    threshold: number,
-    detectAntiAliasing: boolean,
-    alpha: number,
-    aaR: number,
-    aaG: number,
-    aaB: number,
-    diffR: number,
-    diffG: number,
-    diffB: number,
-    hasAlt: boolean,
-    altR: number,
-    altG: number,
-    altB: number,
-    diffMask: boolean,
+    options: Omit<PixelmatchOptions, 'output' | 'ignoreMask'>,
+    ignoreMask: Uint8Array | undefined,
   ) => WasmMatchResult;
 }
 
@@ -71,70 +47,18 @@ export default function pixelmatch(
 ): PixelmatchResult {
   if (!bg) throw new Error('WASM not initialised. Call initialize() first.');
 
-  const {
-    threshold = 0.1,
-    detectAntiAliasing = true,
-    alpha = 0.1,
-    aaColor = [255, 255, 0],
-    diffColor = [255, 0, 0],
-    diffColorAlt,
-    diffMask = false,
-    output,
-  } = options;
+  const { output, ignoreMask, ...rest } = options;
 
   validateInput(img1, img2, output);
 
   const { data: data1, width, height } = img1;
   const { data: data2 } = img2;
   const totalPixels = width * height;
-
-  const [aaR, aaG, aaB] = aaColor;
-  const [diffR, diffG, diffB] = diffColor;
-  const hasAlt = !!diffColorAlt;
-  const [altR, altG, altB] = diffColorAlt || diffColor;
+  const ignored = normalizeIgnoreMask(ignoreMask, totalPixels);
 
   using raw = output
-    ? bg.pixelmatch_wasm(
-        data1,
-        data2,
-        output,
-        width,
-        height,
-        threshold,
-        detectAntiAliasing,
-        alpha,
-        aaR,
-        aaG,
-        aaB,
-        diffR,
-        diffG,
-        diffB,
-        hasAlt,
-        altR,
-        altG,
-        altB,
-        diffMask,
-      )
-    : bg.pixelmatch_wasm_count(
-        data1,
-        data2,
-        width,
-        height,
-        threshold,
-        detectAntiAliasing,
-        alpha,
-        aaR,
-        aaG,
-        aaB,
-        diffR,
-        diffG,
-        diffB,
-        hasAlt,
-        altR,
-        altG,
-        altB,
-        diffMask,
-      );
+    ? bg.pixelmatch_wasm_with_options(data1, data2, output, width, height, rest, ignored)
+    : bg.pixelmatch_wasm_count_with_options(data1, data2, width, height, rest, ignored);
 
   return buildResult(raw.diff_count, raw.aa_count, totalPixels, raw.identical);
 }
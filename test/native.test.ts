@@ -0,0 +1,338 @@
+import { describe, it, expect } from 'vitest';
+import fs from 'node:fs';
+import { createRequire } from 'node:module';
+import { fileURLToPath } from 'node:url';
+import { Worker } from 'node:worker_threads';
+import { PNG } from 'pngjs';
+
+// Exercises the napi exports directly. Runs against the addon left in the repository root by
+// `pnpm build:native` and is skipped when it has not been built.
+
+interface NativeRegion {
+  x: number;
+  y: number;
+  width: number;
+  height: number;
+  pixelCount: number;
+}
+
+interface NativeResult {
+  diffCount: number;
+  aaCount: number;
+  identical: boolean;
+  totalPixels?: number;
+  diffRatio?: number;
+  bounds?: NativeRegion;
+  regions?: NativeRegion[];
+  rowCounts?: number[];
+  columnCounts?: number[];
+  severity?: string;
+}
+
+interface NativeBinding {
+  pixelmatch: (
+    img1: Uint8Array,
+    img2: Uint8Array,
+    output: Uint8Array,
+    width: number,
+    height: number,
+    options?: object,
+  ) => NativeResult;
+  pixelmatchCount: (img1: Uint8Array, img2: Uint8Array, width: number, height: number, options?: object) => NativeResult;
+  pixelmatchAsync: (
+    img1: Uint8Array,
+    img2: Uint8Array,
+    output: Uint8Array | null,
+    width: number,
+    height: number,
+    options?: object,
+  ) => Promise<NativeResult>;
+  pixelmatchDiff: (
+    img1: Uint8Array,
+    img2: Uint8Array,
+    width: number,
+    height: number,
+    options?: object,
+  ) => NativeResult & { diff: Buffer };
+  pixelmatchBatch: (pairs: ImagePair[], options?: object) => NativeResult[];
+  pixelmatchShared: (
+    img1: Uint8Array | ArrayBuffer,
+    img2: Uint8Array | ArrayBuffer,
+    output: Uint8Array | ArrayBuffer | null,
+    width: number,
+    height: number,
+    result: Uint32Array,
+    options?: object,
+  ) => void;
+  pixelmatchSharp: (img1: SharpRaw, img2: SharpRaw, options?: object) => NativeResult & { diff: Buffer };
+  compareScreenshots: (page1: Buffer, page2: Buffer, options?: object) => ScreenshotComparison;
+}
+
+interface ScreenshotComparison {
+  pass: boolean;
+  diffCount: number;
+  totalPixels: number;
+  diffRatio: number;
+  width: number;
+  height: number;
+  message?: string;
+  diff?: Buffer;
+}
+
+interface SharpRaw {
+  data: Buffer;
+  info: { width: number; height: number; channels: number };
+}
+
+interface ImagePair {
+  img1: Buffer;
+  img2: Buffer;
+  width: number;
+  height: number;
+  output?: Buffer;
+}
+
+const root = new URL('../', import.meta.url);
+const addon = fs.readdirSync(root).find((name) => /^pixelmatch\..+\.node$/.test(name));
+const addonPath = addon ? fileURLToPath(new URL(addon, root)) : '';
+const native = addon ? (createRequire(import.meta.url)(addonPath) as NativeBinding) : null;
+
+describe.skipIf(!native)('native binding', () => {
+  const binding = native as NativeBinding;
+  const img1 = readImage('1a');
+  const img2 = readImage('1b');
+  const { width, height } = img1;
+
+  it('pixelmatchAsync resolves to the synchronous result and writes the diff', async () => {
+    const output = Buffer.alloc(img1.data.length);
+    const expected = Buffer.alloc(img1.data.length);
+    const sync = binding.pixelmatch(img1.data, img2.data, expected, width, height, { threshold: 0.05 });
+
+    const pending = binding.pixelmatchAsync(img1.data, img2.data, output, width, height, { threshold: 0.05 });
+    expect(pending).toBeInstanceOf(Promise);
+    const result = await pending;
+    expect(result.diffCount).toBe(109);
+    expect(result.diffCount).toBe(sync.diffCount);
+    expect(result.aaCount).toBe(sync.aaCount);
+    expect(output.equals(expected)).toBe(true);
+
+    const count = await binding.pixelmatchAsync(img1.data, img2.data, null, width, height, { threshold: 0.05 });
+    expect(count.diffCount).toBe(109);
+    await expect(binding.pixelmatchAsync(img1.data, img2.data.subarray(4), null, width, height)).rejects.toThrow();
+  });
+
+  it('pixelmatchDiff allocates and returns the diff', () => {
+    const expected = Buffer.alloc(img1.data.length);
+    binding.pixelmatch(img1.data, img2.data, expected, width, height, { threshold: 0.05 });
+
+    const result = binding.pixelmatchDiff(img1.data, img2.data, width, height, { threshold: 0.05 });
+    expect(result.diffCount).toBe(109);
+    expect(result.diff).toBeInstanceOf(Buffer);
+    expect(result.diff.length).toBe(width * height * 4);
+    expect(result.diff.equals(expected)).toBe(true);
+    expect(result.diff.equals(readImage('1diff').data)).toBe(true);
+  });
+
+  it('reports the extended result fields only with extendedResult', () => {
+    const plain = binding.pixelmatchCount(img1.data, img2.data, width, height, { threshold: 0.05 });
+    expect(plain.totalPixels).toBe(width * height);
+    expect(plain.diffRatio).toBeCloseTo(109 / (width * height), 12);
+    expect(plain.regions).toBeUndefined();
+    expect(plain.severity).toBeUndefined();
+
+    const result = binding.pixelmatchCount(img1.data, img2.data, width, height, {
+      threshold: 0.05,
+      extendedResult: true,
+    });
+    expect(result.diffCount).toBe(109);
+    const sum = (values: number[]) => values.reduce((a, b) => a + b, 0);
+    expect(sum(result.rowCounts ?? [])).toBe(109);
+    expect(sum(result.columnCounts ?? [])).toBe(109);
+    expect(result.rowCounts).toHaveLength(height);
+    expect(result.columnCounts).toHaveLength(width);
+    expect(sum((result.regions ?? []).map((r) => r.pixelCount))).toBe(109);
+    const bounds = result.bounds as NativeRegion;
+    expect(bounds.pixelCount).toBe(109);
+    for (const r of result.regions ?? []) {
+      expect(r.x).toBeGreaterThanOrEqual(bounds.x);
+      expect(r.y).toBeGreaterThanOrEqual(bounds.y);
+      expect(r.x + r.width).toBeLessThanOrEqual(bounds.x + bounds.width);
+      expect(r.y + r.height).toBeLessThanOrEqual(bounds.y + bounds.height);
+    }
+    // 109 of 131072 pixels is under 0.1%.
+    expect(result.severity).toBe('minor');
+
+    const same = binding.pixelmatchCount(img1.data, img1.data, width, height, { extendedResult: true });
+    expect(same.severity).toBe('none');
+    expect(same.bounds).toBeUndefined();
+    expect(same.regions).toEqual([]);
+  });
+
+  it('throws TypeError for malformed options and RangeError for out-of-range ones', () => {
+    const count = (options: object) => () => binding.pixelmatchCount(img1.data, img2.data, width, height, options);
+    expect(count({ threshold: 'high' })).toThrow(TypeError);
+    expect(count({ aaColor: [255, 0] })).toThrow(TypeError);
+    expect(count({ diffMask: 1 })).toThrow(TypeError);
+    expect(count({ threshold: 2 })).toThrow(RangeError);
+    expect(count({ threshold: 2 })).toThrow(/threshold/i);
+    expect(count({ alpha: -0.5 })).toThrow(RangeError);
+    expect(count({ diffColor: [0, 0, 256] })).toThrow(RangeError);
+    expect(count({ diffColor: [0, 0, 256] })).toThrow('diffColor');
+    expect(count({ shiftTolerance: -1 })).toThrow(RangeError);
+    expect(count({ ignoreRegions: [{ x: -1, y: 0, width: 1, height: 1 }] })).toThrow(RangeError);
+    expect(count({ ignoreRegions: [{ x: '0', y: 0, width: 1, height: 1 }] })).toThrow(TypeError);
+  });
+
+  it('reads every Options key through the shared parser', () => {
+    const count = (options: object) => binding.pixelmatchCount(img1.data, img2.data, width, height, options);
+    const everything = [{ x: 0, y: 0, width, height }];
+    expect(count({ threshold: 0.05, ignoreRegions: everything }).diffCount).toBe(0);
+    expect(count({ threshold: 0.05, diffColorAlt: null, ignoreHatch: undefined }).diffCount).toBe(109);
+    expect(() => count({ ignoreRegions: [{ x: 0, y: 0 }] })).toThrow(TypeError);
+    expect(() => count({ morphology: ['grow:1'] })).toThrow('morphology');
+    expect(() => count({ shiftTolerance: 1.5 })).toThrow(TypeError);
+  });
+
+  it('rejects unknown option keys only in strict mode', () => {
+    const count = (options: object) => binding.pixelmatchCount(img1.data, img2.data, width, height, options);
+    expect(count({ treshold: 0.5 }).diffCount).toBe(count({}).diffCount);
+    expect(() => count({ treshold: 0.5, strict: true })).toThrow(TypeError);
+    expect(() => count({ treshold: 0.5, strict: true })).toThrow('treshold');
+    expect(count({ threshold: 0.05, extendedResult: false, strict: true }).diffCount).toBe(109);
+  });
+
+  it('pixelmatchShared works on SharedArrayBuffer memory in place from a worker', async () => {
+    // img1, img2 and the diff live side by side in one SharedArrayBuffer, as a worker pool
+    // would share them. The worker gets the buffer itself, not a copy of its contents.
+    const size = img1.data.length;
+    const shared = new SharedArrayBuffer(size * 3);
+    new Uint8Array(shared, 0, size).set(img1.data);
+    new Uint8Array(shared, size, size).set(img2.data);
+    const result = new Uint32Array(new SharedArrayBuffer(4 * 3));
+
+    const worker = new Worker(
+      `const { workerData: d, parentPort } = require('node:worker_threads');
+       const native = require(d.addonPath);
+       const view = (i) => new Uint8Array(d.shared, i * d.size, d.size);
+       native.pixelmatchShared(view(0), view(1), view(2), d.width, d.height, d.result, { threshold: 0.05 });
+       parentPort.postMessage('done');`,
+      { eval: true, workerData: { addonPath, shared, size, width, height, result } },
+    );
+    await new Promise((resolve, reject) => worker.once('message', resolve).once('error', reject));
+    await worker.terminate();
+
+    expect(result[0]).toBe(109);
+    expect(result[2]).toBe(0);
+    expect(Buffer.from(shared, size * 2, size).equals(readImage('1diff').data)).toBe(true);
+
+    // The same memory may be read as both images.
+    const same = new Uint8Array(shared, 0, size);
+    binding.pixelmatchShared(same, same, null, width, height, result);
+    expect(Array.from(result)).toEqual([0, 0, 1]);
+  });
+
+  it('pixelmatchShared rejects an output overlapping an input', () => {
+    const size = img1.data.length;
+    const shared = new SharedArrayBuffer(size * 2);
+    const img = new Uint8Array(shared, 0, size);
+    const result = new Uint32Array(3);
+    const overlapping = new Uint8Array(shared, size / 2, size);
+    expect(() => binding.pixelmatchShared(img, img2.data, overlapping, width, height, result)).toThrow(RangeError);
+    expect(() => binding.pixelmatchShared(img2.data, img, img, width, height, result)).toThrow(RangeError);
+
+    const separate = new Uint8Array(shared, size, size);
+    binding.pixelmatchShared(img, img, separate, width, height, result);
+    expect(result[2]).toBe(1);
+  });
+
+  it('pixelmatchSharp accepts sharp raw output with 3 or 4 channels', () => {
+    const rgb = (rgba: Buffer) => Buffer.from(rgba.filter((_, i) => i % 4 !== 3));
+    const opaque = (rgba: Buffer) => Buffer.from(rgba.map((v, i) => (i % 4 === 3 ? 255 : v)));
+    const raw = (data: Buffer, channels: number): SharpRaw => ({ data, info: { width, height, channels } });
+
+    const rgba = binding.pixelmatchSharp(raw(img1.data, 4), raw(img2.data, 4), { threshold: 0.05 });
+    expect(rgba.diffCount).toBe(109);
+    expect(rgba.diff.equals(readImage('1diff').data)).toBe(true);
+
+    // RGB input is compared as opaque RGBA, and may be mixed with RGBA.
+    const expected = binding.pixelmatchDiff(opaque(img1.data), opaque(img2.data), width, height, { threshold: 0.05 });
+    const three = binding.pixelmatchSharp(raw(rgb(img1.data), 3), raw(rgb(img2.data), 3), { threshold: 0.05 });
+    expect(three.diffCount).toBe(expected.diffCount);
+    expect(three.diff.length).toBe(width * height * 4);
+    expect(three.diff.equals(expected.diff)).toBe(true);
+    const mixed = binding.pixelmatchSharp(raw(rgb(img1.data), 3), raw(opaque(img2.data), 4), { threshold: 0.05 });
+    expect(mixed.diffCount).toBe(expected.diffCount);
+
+    expect(() => binding.pixelmatchSharp(raw(img1.data, 2), raw(img2.data, 2))).toThrow('unsupported channel count');
+    expect(() => binding.pixelmatchSharp(raw(img1.data, 3), raw(img2.data, 4))).toThrow(
+      'img1: data size does not match info',
+    );
+    const small = { data: img2.data, info: { width: height, height: width, channels: 4 } };
+    expect(() => binding.pixelmatchSharp(raw(img1.data, 4), small)).toThrow('Image dimensions do not match');
+  });
+
+  it('compareScreenshots scales masks by devicePixelRatio and gates on the diff ratio', () => {
+    // An 8x4 screenshot at devicePixelRatio 2, with a 2x2 device-pixel block changed at (4, 0):
+    // one CSS pixel at (2, 0).
+    const screenshot = (changed: boolean) => {
+      const png = new PNG({ width: 8, height: 4 });
+      png.data.fill(255);
+      if (changed) {
+        for (const [x, y] of [[4, 0], [5, 0], [4, 1], [5, 1]]) png.data.fill(0, (y * 8 + x) * 4, (y * 8 + x) * 4 + 3);
+      }
+      return PNG.sync.write(png);
+    };
+    const [before, after] = [screenshot(false), screenshot(true)];
+    const compare = (options: object) => binding.compareScreenshots(before, after, { detectAntiAliasing: false, ...options });
+
+    const failed = compare({ maxDiffRatio: 0.1 });
+    expect(failed.pass).toBe(false);
+    expect([failed.diffCount, failed.totalPixels, failed.width, failed.height]).toEqual([4, 32, 8, 4]);
+    expect(failed.diffRatio).toBe(4 / 32);
+    expect(failed.message).toContain('4 pixels differ');
+    expect(PNG.sync.read(failed.diff as Buffer).width).toBe(8);
+
+    const within = compare({ maxDiffRatio: 0.125 });
+    expect(within.pass).toBe(true);
+    expect(within.diff).toBeUndefined();
+
+    const rect = { x: 2, y: 0, width: 1, height: 1 };
+    expect(compare({ maskSelectorsRects: [rect], devicePixelRatio: 2 })).toMatchObject({ pass: true, diffCount: 0 });
+    // At ratio 1 the same CSS rectangle covers only one of the changed pixels.
+    expect(compare({ maskSelectorsRects: [rect], devicePixelRatio: 1 }).diffCount).toBe(4);
+    const half = { x: 2, y: 0, width: 0.5, height: 0.5 };
+    expect(compare({ maskSelectorsRects: [half], devicePixelRatio: 2 }).diffCount).toBe(3);
+
+    expect(() => compare({ devicePixelRatio: 0 })).toThrow(RangeError);
+    expect(() => compare({ maxDiffRatio: 2 })).toThrow(RangeError);
+
+    const wider = binding.compareScreenshots(before, PNG.sync.write(new PNG({ width: 9, height: 4 })));
+    expect(wider).toMatchObject({ pass: false, diffCount: 0, totalPixels: 32 });
+    expect(wider.message).toContain('8x4 vs 9x4');
+  });
+
+  it('pixelmatchBatch compares every pair in order', () => {
+    const [img3, img4] = [readImage('3a'), readImage('3b')];
+    const output = Buffer.alloc(img3.data.length);
+    const results = binding.pixelmatchBatch(
+      [
+        { img1: img1.data, img2: img2.data, width, height },
+        { img1: img3.data, img2: img4.data, width: img3.width, height: img3.height, output },
+        { img1: img1.data, img2: img1.data, width, height },
+      ],
+      { threshold: 0.05 },
+    );
+    expect(results.map((r) => r.diffCount)).toEqual([109, 112, 0]);
+    expect(results[2].identical).toBe(true);
+    expect(output.equals(readImage('3diff').data)).toBe(true);
+
+    const bad = { img1: img1.data, img2: img2.data.subarray(4), width, height };
+    expect(() => binding.pixelmatchBatch([{ img1: img1.data, img2: img2.data, width, height }, bad])).toThrow(
+      /^pair 1: /,
+    );
+  });
+});
+
+function readImage(name: string): PNG {
+  return PNG.sync.read(fs.readFileSync(new URL(`fixtures/${name}.png`, import.meta.url)));
+}
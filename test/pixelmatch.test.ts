@@ -119,6 +119,25 @@ describe('pixelmatch', () => {
       'Output data: Uint8Array, Uint8ClampedArray or Buffer expected',
     );
   });
+
+  it('skips pixels in ignoreMask', () => {
+    const white = { data: new Uint8Array(4 * 3).fill(255), width: 3, height: 1 };
+    const black = { data: new Uint8Array([0, 0, 0, 255, 0, 0, 0, 255, 0, 0, 0, 255]), width: 3, height: 1 };
+    expect(match(white, black, { ignoreMask: new Uint8Array([1, 0, 1]) }).diffCount).toBe(1);
+
+    const rgba = new Uint8ClampedArray(4 * 3);
+    rgba[3] = 255;
+    const output = new Uint8Array(4 * 3);
+    expect(match(white, black, { ignoreMask: rgba, output }).diffCount).toBe(2);
+    expect(Array.from(output.subarray(0, 4))).toEqual([255, 255, 255, 255]);
+  });
+
+  it('throws error if ignoreMask has wrong size', () => {
+    const img = { data: new Uint8Array(4 * 20 * 20), width: 20, height: 20 };
+    expect(() => match(img, img, { ignoreMask: new Uint8Array(10) })).toThrow(
+      'Ignore mask size does not match image size. Expecting 400 or 1600. Got 10',
+    );
+  });
 });
 
 describe('compat', () => {
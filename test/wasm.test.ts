@@ -0,0 +1,63 @@
+import { describe, it, expect } from 'vitest';
+import fs from 'node:fs';
+import { PNG } from 'pngjs';
+import match, { initialize } from '../src/wasm.js';
+import type { PixelmatchOptions } from '../src/types.js';
+
+// Runs the WASM entry against the module left in wasm/ by `pnpm build:wasm` and is skipped when
+// it has not been built. Node's fetch cannot load file URLs, so the module is instantiated from
+// its bytes first; initialize() then finds it ready.
+
+const wasmPath = new URL('../wasm/pixelmatch_bg.wasm', import.meta.url);
+const built = fs.existsSync(wasmPath);
+if (built) {
+  const bindings = (await import('../wasm/pixelmatch_bg.js' as string)) as {
+    initSync: (module: { module: Buffer }) => void;
+  };
+  bindings.initSync({ module: fs.readFileSync(wasmPath) });
+  await initialize();
+}
+
+describe.skipIf(!built)('wasm binding', () => {
+  const img1 = readImage('1a');
+  const img2 = readImage('1b');
+  const { width, height } = img1;
+
+  it('matches the expected diff', () => {
+    const output = new Uint8Array(width * height * 4);
+    expect(match(img1, img2, { output, threshold: 0.05 }).diffCount).toBe(109);
+    expect(Buffer.from(output).equals(readImage('1diff').data)).toBe(true);
+  });
+
+  it('reads the shared option keys and ignores unknown ones', () => {
+    const options = { threshold: 0.05, thresold: 1 } as PixelmatchOptions;
+    expect(match(img1, img2, options).diffCount).toBe(109);
+    const everything = { threshold: 0.05, ignoreRegions: [{ x: 0, y: 0, width, height }] } as PixelmatchOptions;
+    expect(match(img1, img2, everything).diffCount).toBe(0);
+    expect(() => match(img1, img2, { threshold: 2 })).toThrow(/threshold/i);
+  });
+
+  it('does not count pixels under ignoreMask', () => {
+    const output = new Uint8Array(width * height * 4);
+    match(img1, img2, { output, threshold: 0.05 });
+    // Ignore every pixel drawn in the default diff colour, one byte per pixel and as RGBA.
+    const ignoreMask = new Uint8Array(width * height);
+    for (let i = 0; i < ignoreMask.length; i++) {
+      ignoreMask[i] = output[i * 4] === 255 && output[i * 4 + 1] === 0 && output[i * 4 + 2] === 0 ? 1 : 0;
+    }
+    const rgba = new Uint8Array(width * height * 4);
+    ignoreMask.forEach((ignored, i) => (rgba[i * 4 + 3] = ignored * 255));
+
+    expect(match(img1, img2, { threshold: 0.05, ignoreMask }).diffCount).toBe(0);
+    expect(match(img1, img2, { threshold: 0.05, ignoreMask: rgba }).diffCount).toBe(0);
+    expect(match(img1, img2, { output, threshold: 0.05, ignoreMask }).diffCount).toBe(0);
+
+    // Leaving one of them unmasked counts exactly that pixel.
+    ignoreMask[ignoreMask.indexOf(1)] = 0;
+    expect(match(img1, img2, { threshold: 0.05, ignoreMask }).diffCount).toBe(1);
+  });
+});
+
+function readImage(name: string): PNG {
+  return PNG.sync.read(fs.readFileSync(new URL(`fixtures/${name}.png`, import.meta.url)));
+}
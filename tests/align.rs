@@ -0,0 +1,66 @@
+use pixelmatch::align::{align_rows, BandKind, RowBand};
+
+const WIDTH: u32 = 4;
+
+/// An image whose rows are filled with the given values.
+fn rows(values: &[u8]) -> Vec<u8> {
+    values.iter().flat_map(|&v| [v, v, v, 255].repeat(WIDTH as usize)).collect()
+}
+
+fn band(kind: BandKind, rows1: std::ops::Range<u32>, rows2: std::ops::Range<u32>) -> RowBand {
+    RowBand { kind, rows1, rows2 }
+}
+
+fn align(a: &[u8], b: &[u8]) -> Vec<RowBand> {
+    align_rows(&rows(a), a.len() as u32, &rows(b), b.len() as u32, WIDTH)
+}
+
+#[test]
+fn test_align_rows_inserted_banner() {
+    let page: Vec<u8> = (0..30).collect();
+    let mut shifted = page[..10].to_vec();
+    shifted.extend([200; 5]);
+    shifted.extend(&page[10..]);
+
+    let bands = align(&page, &shifted);
+    assert_eq!(
+        bands,
+        [
+            band(BandKind::Unchanged, 0..10, 0..10),
+            band(BandKind::Inserted, 10..10, 10..15),
+            band(BandKind::Shifted, 10..30, 15..35),
+        ]
+    );
+    assert_eq!(bands[2].offset(), 5);
+    assert_eq!(bands[0].offset(), 0);
+
+    // The reverse comparison sees the banner removed and the content moving up.
+    let bands = align(&shifted, &page);
+    assert_eq!(bands[1], band(BandKind::Removed, 10..15, 10..10));
+    assert_eq!(bands[2].offset(), -5);
+}
+
+#[test]
+fn test_align_rows_separates_changes_from_shifts() {
+    let page: Vec<u8> = (0..30).collect();
+    // Two rows removed near the top, and one row further down genuinely changed.
+    let mut candidate: Vec<u8> = page[..5].iter().chain(&page[7..]).copied().collect();
+    candidate[18] = 250;
+
+    let bands = align(&page, &candidate);
+    assert_eq!(
+        bands,
+        [
+            band(BandKind::Unchanged, 0..5, 0..5),
+            band(BandKind::Removed, 5..7, 5..5),
+            band(BandKind::Shifted, 7..20, 5..18),
+            band(BandKind::Changed, 20..21, 18..19),
+            band(BandKind::Shifted, 21..30, 19..28),
+        ]
+    );
+
+    // Identical and empty images.
+    assert_eq!(align(&page, &page), [band(BandKind::Unchanged, 0..30, 0..30)]);
+    assert!(align(&[], &[]).is_empty());
+    assert_eq!(align(&[], &[1, 2]), [band(BandKind::Inserted, 0..0, 0..2)]);
+}
@@ -0,0 +1,52 @@
+use pixelmatch::artifacts::DiffArtifacts;
+use pixelmatch::io::read_png;
+use pixelmatch::Options;
+
+mod common;
+
+use common::{solid, temp_dir};
+
+#[test]
+fn test_artifacts_written_for_failed_comparison() {
+    let root = temp_dir("artifacts-failed");
+    let artifacts = DiffArtifacts::with_root(&root, "ui::login \"form\"");
+    assert_eq!(artifacts.dir(), root.join("ui__login__form_"));
+
+    let baseline = solid(3, 2, [255, 255, 255, 255]);
+    let mut candidate = baseline.clone();
+    candidate.data[..4].copy_from_slice(&[0, 0, 0, 255]);
+    std::fs::create_dir_all(artifacts.dir()).unwrap();
+    std::fs::write(artifacts.dir().join("stale.png"), b"").unwrap();
+
+    let result = artifacts.write(&baseline, &candidate, &Options::default()).unwrap().unwrap();
+    assert_eq!(result.diff_count, 1);
+    assert!(!artifacts.dir().join("stale.png").exists(), "previous artifacts are cleared");
+    assert_eq!(read_png(artifacts.dir().join("candidate.png")).unwrap(), candidate);
+    assert_eq!(&read_png(artifacts.dir().join("diff.png")).unwrap().data[..4], &[255, 0, 0, 255]);
+    let mask = read_png(artifacts.dir().join("diff-mask.png")).unwrap();
+    assert_eq!(&mask.data[..8], &[255, 0, 0, 255, 0, 0, 0, 0]);
+
+    let metadata: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(artifacts.dir().join("metadata.json")).unwrap()).unwrap();
+    assert_eq!(metadata["name"], "ui::login \"form\"");
+    assert_eq!(metadata["diff_count"], 1);
+    assert_eq!(metadata["total_pixels"], 6);
+    assert_eq!(metadata["files"].as_array().unwrap().len(), 4);
+
+    let _ = std::fs::remove_dir_all(&root);
+}
+
+#[test]
+fn test_artifacts_dimension_mismatch() {
+    let root = temp_dir("artifacts-dimensions");
+    let artifacts = DiffArtifacts::with_root(&root, "../escape");
+    assert_eq!(artifacts.dir(), root.join("escape"));
+
+    let result = artifacts.write(&solid(2, 2, [0; 4]), &solid(3, 2, [0; 4]), &Options::default()).unwrap();
+    assert!(result.is_none());
+    assert!(!artifacts.dir().join("diff.png").exists());
+    let metadata = std::fs::read_to_string(artifacts.dir().join("metadata.json")).unwrap();
+    assert!(metadata.contains(r#""error": "dimension mismatch""#));
+
+    let _ = std::fs::remove_dir_all(&root);
+}
@@ -0,0 +1,54 @@
+use pixelmatch::atlas::{compare_frames, Frame};
+use pixelmatch::{Options, PixelmatchError, Rect};
+
+/// A 64×32 atlas of two 32×32 sprites: a grey square on the left, a white one on the right.
+fn atlas() -> Vec<u8> {
+    (0..32 * 64).flat_map(|i| if i % 64 < 32 { [128, 128, 128, 255] } else { [255; 4] }).collect()
+}
+
+fn frames() -> Vec<Frame> {
+    vec![Frame::new("player", Rect::new(0, 0, 32, 32)), Frame::new("coin", Rect::new(32, 0, 32, 32))]
+}
+
+#[test]
+fn test_compare_frames_reports_each_frame() {
+    let baseline = atlas();
+    let mut candidate = baseline.clone();
+    for (x, y) in [(40, 10), (41, 10), (50, 20)] {
+        candidate[(y * 64 + x) * 4..][..3].fill(0);
+    }
+    let options = Options { detect_anti_aliasing: false, ..Default::default() };
+    let results = compare_frames(&baseline, &candidate, 64, 32, &frames(), &options).unwrap();
+    let counts: Vec<(&str, u32)> = results.iter().map(|r| (r.name.as_str(), r.result.diff_count)).collect();
+    assert_eq!(counts, [("player", 0), ("coin", 3)]);
+    assert!(results[0].result.identical);
+    assert_eq!(results[1].rect, Rect::new(32, 0, 32, 32));
+
+    // Ignore regions are given in atlas coordinates.
+    let ignored = Options { ignore_regions: vec![Rect::new(38, 8, 6, 6)], ..options.clone() };
+    let results = compare_frames(&baseline, &candidate, 64, 32, &frames(), &ignored).unwrap();
+    assert_eq!(results[1].result.diff_count, 1);
+    let only = Options { include_only: true, ..ignored };
+    let results = compare_frames(&baseline, &candidate, 64, 32, &frames(), &only).unwrap();
+    assert_eq!(results[1].result.diff_count, 2);
+}
+
+#[test]
+fn test_compare_frames_isolates_neighbours() {
+    // The edge between the two sprites is not treated as anti-aliasing of either.
+    let baseline = atlas();
+    let mut candidate = baseline.clone();
+    candidate[(5 * 64 + 31) * 4..][..3].fill(255);
+    let results = compare_frames(&baseline, &candidate, 64, 32, &frames(), &Options::default()).unwrap();
+    assert_eq!(results[0].result.diff_count + results[0].result.aa_count, 1);
+    assert_eq!(results[1].result.diff_count, 0);
+}
+
+#[test]
+fn test_compare_frames_rejects_frames_out_of_bounds() {
+    let img = atlas();
+    let frames = [Frame::new("wide", Rect::new(40, 0, 32, 8))];
+    let err = compare_frames(&img, &img, 64, 32, &frames, &Options::default()).unwrap_err();
+    assert!(matches!(err, PixelmatchError::RegionOutOfBounds { region, .. } if region.x == 40));
+    assert!(compare_frames(&img, &img, 64, 32, &[], &Options::default()).unwrap().is_empty());
+}
@@ -0,0 +1,96 @@
+use pixelmatch::baseline::{BaselineComparison, BaselineStore, FsBaselineStore};
+use pixelmatch::edges::EdgeDetector;
+use pixelmatch::morph::MorphOp;
+use pixelmatch::{Options, Rect};
+
+mod common;
+
+use common::{solid, temp_dir};
+
+#[test]
+fn test_fs_baseline_store_record_compare_approve() {
+    let root = temp_dir("baseline-lifecycle");
+    let store = FsBaselineStore::new(&root);
+    let white = solid(4, 4, [255, 255, 255, 255]);
+    let mut changed = white.clone();
+    changed.data[..4].copy_from_slice(&[0, 0, 0, 255]);
+    let defaults = Options::default();
+
+    assert_eq!(store.compare("home/hero", &white, &defaults).unwrap(), BaselineComparison::Missing);
+    assert!(store.record_new("home/hero", &white).unwrap());
+    assert!(!store.record_new("home/hero", &changed).unwrap(), "existing baselines are kept");
+    assert!(root.join("home/hero.png").exists());
+
+    match store.compare("home/hero", &changed, &defaults).unwrap() {
+        BaselineComparison::Compared(r) => assert_eq!(r.diff_count, 1),
+        other => panic!("expected a comparison, got {other:?}"),
+    }
+    assert_eq!(
+        store.compare("home/hero", &solid(2, 2, [0; 4]), &defaults).unwrap(),
+        BaselineComparison::DimensionMismatch { baseline: (4, 4), candidate: (2, 2) }
+    );
+
+    store.approve("home/hero", &changed).unwrap();
+    assert_eq!(store.load("home/hero").unwrap(), Some(changed.clone()));
+    let approved = store.compare("home/hero", &changed, &defaults).unwrap();
+    assert!(matches!(approved, BaselineComparison::Compared(r) if r.identical));
+
+    assert!(store.load("../outside").is_err());
+    let _ = std::fs::remove_dir_all(&root);
+}
+
+#[test]
+fn test_fs_baseline_store_per_baseline_options() {
+    let root = temp_dir("baseline-options");
+    let store = FsBaselineStore::new(&root);
+    let white = solid(4, 4, [255, 255, 255, 255]);
+    let mut changed = white.clone();
+    changed.data[..4].copy_from_slice(&[0, 0, 0, 255]);
+    store.record_new("banner", &white).unwrap();
+    assert!(store.load_options("banner").unwrap().is_none());
+
+    let options = Options {
+        threshold: 0.25,
+        diff_color_alt: Some([0, 128, 255]),
+        ignore_regions: vec![Rect::new(0, 0, 1, 1), Rect::new(2, 2, 2, 2)],
+        ignore_hatch: Some([0, 0, 255]),
+        shift_tolerance: 2,
+        subpixel_tolerance: true,
+        morphology: vec![MorphOp::Dilate(1), MorphOp::Erode(1)],
+        device_pixel_ratio: Some([2.0, 2.0]),
+        quantize: Some(16),
+        ..Default::default()
+    };
+    store.save_options("banner", &options).unwrap();
+    let loaded = store.load_options("banner").unwrap().unwrap();
+    assert_eq!(loaded.threshold, 0.25);
+    assert_eq!(loaded.diff_color_alt, Some([0, 128, 255]));
+    assert_eq!(loaded.ignore_regions, options.ignore_regions);
+    assert_eq!(loaded.ignore_hatch, Some([0, 0, 255]));
+    assert!(!loaded.include_only);
+    assert_eq!(loaded.shift_tolerance, 2);
+    assert!(loaded.subpixel_tolerance);
+    assert_eq!(loaded.morphology, options.morphology);
+    assert_eq!(loaded.device_pixel_ratio, Some([2.0, 2.0]));
+    assert_eq!(loaded.quantize, Some(16));
+    let edges = Options { edges: Some(EdgeDetector::Canny { low: 0.1, high: 0.3 }), ..Default::default() };
+    store.save_options("edges", &edges).unwrap();
+    assert_eq!(store.load_options("edges").unwrap().unwrap().edges, edges.edges);
+    store.save_options("labels", &Options { label_shift: 3, ..Default::default() }).unwrap();
+    assert_eq!(store.load_options("labels").unwrap().unwrap().label_shift, 3);
+    assert_eq!(loaded.aa_color, options.aa_color);
+
+    let result = store.compare("banner", &changed, &Options::default()).unwrap();
+    assert!(matches!(result, BaselineComparison::Compared(r) if r.diff_count == 0), "stored ignore region applies");
+
+    // Comparing only inside the regions catches the change again.
+    store.save_options("banner", &Options { include_only: true, subpixel_tolerance: false, ..options }).unwrap();
+    assert!(store.load_options("banner").unwrap().unwrap().include_only);
+    let result = store.compare("banner", &changed, &Options::default()).unwrap();
+    assert!(matches!(result, BaselineComparison::Compared(r) if r.diff_count == 1));
+
+    std::fs::write(root.join("banner.toml"), "threshold = high\n").unwrap();
+    let err = store.load_options("banner").unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+    let _ = std::fs::remove_dir_all(&root);
+}
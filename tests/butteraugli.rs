@@ -0,0 +1,56 @@
+use pixelmatch::butteraugli::butteraugli;
+use pixelmatch::PixelmatchError;
+
+/// An opaque RGBA image of `len` pixels of one colour.
+fn flat(len: usize, rgb: [u8; 3]) -> Vec<u8> {
+    (0..len).flat_map(|_| [rgb[0], rgb[1], rgb[2], 255]).collect()
+}
+
+/// `img` with pixel `i` set to grey `v`.
+fn with_pixel(img: &[u8], i: usize, v: u8) -> Vec<u8> {
+    let mut out = img.to_vec();
+    out[i * 4..i * 4 + 3].fill(v);
+    out
+}
+
+#[test]
+fn test_butteraugli_jnd_scale() {
+    let grey = flat(32 * 32, [128; 3]);
+    let same = butteraugli(&grey, &grey, 32, 32).unwrap();
+    assert_eq!((same.score, same.norm, same.map.len()), (0.0, 0.0, 32 * 32));
+
+    // A flat 1% brightness step is about one JND, and a larger one scores higher.
+    let step = butteraugli(&grey, &flat(32 * 32, [130; 3]), 32, 32).unwrap();
+    assert!((0.8..1.2).contains(&step.score), "{}", step.score);
+    let larger = butteraugli(&grey, &flat(32 * 32, [140; 3]), 32, 32).unwrap();
+    assert!(larger.score > 4.0 * step.score);
+    // The eye is less sensitive to blue than to red-green changes of the same size.
+    let red = butteraugli(&grey, &flat(32 * 32, [134, 128, 128]), 32, 32).unwrap();
+    let blue = butteraugli(&grey, &flat(32 * 32, [128, 128, 134]), 32, 32).unwrap();
+    assert!(blue.score < red.score);
+}
+
+#[test]
+fn test_butteraugli_contrast_masking() {
+    // The same one-pixel change is plain on a flat area and hidden in busy texture.
+    let grey = flat(32 * 32, [128; 3]);
+    let texture: Vec<u8> = (0..32 * 32u32)
+        .flat_map(|i| if i.wrapping_mul(2654435761) >> 31 == 1 { [40, 40, 40, 255] } else { [220, 220, 220, 255] })
+        .collect();
+    let on_flat = butteraugli(&grey, &with_pixel(&grey, 500, 148), 32, 32).unwrap();
+    let on_texture = butteraugli(&texture, &with_pixel(&texture, 500, texture[2000] + 20), 32, 32).unwrap();
+    assert!(on_flat.score > 2.0, "{}", on_flat.score);
+    assert!(on_texture.score < on_flat.score / 4.0, "{}", on_texture.score);
+    // The distance peaks at the changed pixel, and the norm rates the whole image.
+    let peak = on_flat.map.iter().copied().fold(0.0, f32::max);
+    assert_eq!(on_flat.map[500], peak);
+    assert!(on_flat.norm < on_flat.score);
+}
+
+#[test]
+fn test_butteraugli_validates_buffers() {
+    let img = flat(16, [0; 3]);
+    let mismatch = butteraugli(&img, &img[..60], 4, 4);
+    assert!(matches!(mismatch, Err(PixelmatchError::ImageSizeMismatch { .. })));
+    assert_eq!(butteraugli(&[], &[], 0, 0).unwrap().map, Vec::<f32>::new());
+}
@@ -0,0 +1,88 @@
+use std::ffi::CStr;
+
+use pixelmatch::capi::{
+    pixelmatch_compare, pixelmatch_compare_flat, pixelmatch_compare_json, pixelmatch_default_options,
+    pixelmatch_status_message, pixelmatch_version, PixelmatchResult, PixelmatchStatus, PIXELMATCH_FLAT_OPTIONS_LEN,
+};
+
+#[test]
+fn test_capi_compare_with_and_without_output() {
+    let img1 = [255u8, 255, 255, 255, 0, 0, 0, 255];
+    let img2 = [255u8, 255, 255, 255, 255, 255, 255, 255];
+    let mut output = [0u8; 8];
+    let mut result = PixelmatchResult::default();
+
+    let options = pixelmatch_default_options();
+    let status = unsafe {
+        pixelmatch_compare(img1.as_ptr(), img2.as_ptr(), output.as_mut_ptr(), 8, 2, 1, &options, &mut result)
+    };
+    assert_eq!(status, PixelmatchStatus::Ok);
+    assert_eq!((result.diff_count, result.aa_count, result.identical), (1, 0, false));
+    assert_eq!(&output[4..], &[255, 0, 0, 255]);
+
+    let status = unsafe {
+        pixelmatch_compare(img1.as_ptr(), img1.as_ptr(), std::ptr::null_mut(), 8, 2, 1, std::ptr::null(), &mut result)
+    };
+    assert_eq!(status, PixelmatchStatus::Ok);
+    assert!(result.identical);
+}
+
+#[test]
+fn test_capi_compare_json() {
+    let img1 = [255u8, 255, 255, 255, 0, 0, 0, 255];
+    let img2 = [255u8, 255, 255, 255, 255, 255, 255, 255];
+    let mut result = PixelmatchResult::default();
+    let compare = |json: &CStr, result| unsafe {
+        pixelmatch_compare_json(img1.as_ptr(), img2.as_ptr(), std::ptr::null_mut(), 8, 2, 1, json.as_ptr(), result)
+    };
+
+    assert_eq!(compare(c"{}", &mut result), PixelmatchStatus::Ok);
+    assert_eq!(result.diff_count, 1);
+    let ignored = cr#"{"ignoreRegions": [{"x": 1, "y": 0, "width": 1, "height": 1}]}"#;
+    assert_eq!(compare(ignored, &mut result), PixelmatchStatus::Ok);
+    assert_eq!(result.diff_count, 0);
+    assert_eq!(compare(cr#"{"treshold": 0.2}"#, &mut result), PixelmatchStatus::InvalidArgument);
+    assert_eq!(compare(cr#"{"threshold": 2}"#, &mut result), PixelmatchStatus::InvalidArgument);
+}
+
+#[test]
+fn test_capi_errors() {
+    let img = [0u8; 8];
+    let mut result = PixelmatchResult { diff_count: 7, ..Default::default() };
+    let compare = |img2: *const u8, len, result| unsafe {
+        pixelmatch_compare(img.as_ptr(), img2, std::ptr::null_mut(), len, 2, 1, std::ptr::null(), result)
+    };
+    assert_eq!(compare(std::ptr::null(), 8, &mut result), PixelmatchStatus::NullPointer);
+    assert_eq!(compare(img.as_ptr(), 4, &mut result), PixelmatchStatus::BufferLengthMismatch);
+    assert_eq!(compare(img.as_ptr(), 8, std::ptr::null_mut()), PixelmatchStatus::NullPointer);
+    assert_eq!(result.diff_count, 7);
+
+    let message = unsafe { CStr::from_ptr(pixelmatch_status_message(PixelmatchStatus::BufferLengthMismatch)) };
+    assert_eq!(message.to_str().unwrap(), "buffer length does not match width * height * 4");
+}
+
+#[test]
+fn test_capi_flat_entry_point_and_version() {
+    let img1 = [255u8, 255, 255, 255, 255, 255, 255, 255];
+    let img2 = [255u8, 255, 255, 255, 0, 0, 0, 255];
+    let mut output = [0u8; 8];
+    let mut result = [9u32; 3];
+
+    let mut options = [0.1, 1.0, 0.1, 255.0, 255.0, 0.0, 255.0, 0.0, 0.0, 1.0, 0.0, 0.0, 255.0, 0.0];
+    assert_eq!(options.len(), PIXELMATCH_FLAT_OPTIONS_LEN);
+    let run = |options: *const f64, output: &mut [u8; 8], result: &mut [u32; 3]| unsafe {
+        let (img1, img2, output) = (img1.as_ptr(), img2.as_ptr(), output.as_mut_ptr());
+        pixelmatch_compare_flat(img1, img2, output, 8, 2, 1, options, result.as_mut_ptr())
+    };
+    assert_eq!(run(options.as_ptr(), &mut output, &mut result), PixelmatchStatus::Ok as i32);
+    assert_eq!(result, [1, 0, 0]);
+    assert_eq!(&output[4..], &[0, 0, 255, 255], "dark-on-light uses the alternative colour");
+
+    options[9] = 0.0;
+    assert_eq!(run(options.as_ptr(), &mut output, &mut result), 0);
+    assert_eq!(&output[4..], &[255, 0, 0, 255]);
+    assert_eq!(run(std::ptr::null(), &mut output, &mut result), 0);
+
+    let version = unsafe { CStr::from_ptr(pixelmatch_version()) };
+    assert_eq!(version.to_str().unwrap(), env!("CARGO_PKG_VERSION"));
+}
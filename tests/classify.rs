@@ -0,0 +1,90 @@
+use pixelmatch::classify::{classification_image, classify_pixels, PixelClass};
+use pixelmatch::{pixelmatch, Options, PixelmatchError, Rect};
+
+mod common;
+
+use common::read_image;
+
+fn count(classes: &[PixelClass], wanted: &[PixelClass]) -> u32 {
+    classes.iter().filter(|c| wanted.contains(c)).count() as u32
+}
+
+#[test]
+fn test_classes_agree_with_counts() {
+    let (img1, w, h) = read_image("1a");
+    let (img2, _, _) = read_image("1b");
+    for options in [
+        Options::default(),
+        Options { threshold: 0.05, ..Default::default() },
+        Options { shift_tolerance: 1, ..Default::default() },
+        Options { isolation_radius: 1, ..Default::default() },
+    ] {
+        let result = pixelmatch(&img1, &img2, None, w, h, &options).unwrap();
+        let classes = classify_pixels(&img1, &img2, w, h, &options).unwrap();
+        assert_eq!(classes.len(), (w * h) as usize);
+        assert_eq!(count(&classes, &[PixelClass::DiffDark, PixelClass::DiffLight]), result.diff_count);
+        let aa = count(&classes, &[PixelClass::AntialiasedFirst, PixelClass::AntialiasedSecond]);
+        assert_eq!(aa, result.aa_count);
+        assert!(count(&classes, &[PixelClass::BelowThreshold]) > 0);
+    }
+    let tolerant = Options { shift_tolerance: 1, ..Default::default() };
+    assert!(count(&classify_pixels(&img1, &img2, w, h, &tolerant).unwrap(), &[PixelClass::Tolerated]) > 0);
+}
+
+#[test]
+fn test_classes_of_simple_changes() {
+    // Left pixel darker, right pixel lighter, on an otherwise equal 3x1 image.
+    let img1 = [128, 128, 128, 255].repeat(3);
+    let mut img2 = img1.clone();
+    img2[..3].fill(0);
+    img2[8..11].fill(255);
+    let options = Options { detect_anti_aliasing: false, ..Default::default() };
+    let classes = classify_pixels(&img1, &img2, 3, 1, &options).unwrap();
+    assert_eq!(classes, [PixelClass::DiffDark, PixelClass::Equal, PixelClass::DiffLight]);
+
+    let ignoring = Options { ignore_regions: vec![Rect { x: 0, y: 0, width: 1, height: 1 }], ..options };
+    let classes = classify_pixels(&img1, &img2, 3, 1, &ignoring).unwrap();
+    assert_eq!(classes[0], PixelClass::Ignored);
+
+    let image = classification_image(&classes);
+    assert_eq!(image.len(), 12);
+    assert_eq!(image[4..8], PixelClass::Equal.color());
+    assert!(classes[2].is_diff() && !classes[1].is_diff());
+}
+
+#[test]
+fn test_classify_rejects_downscale() {
+    let img = [0u8; 16];
+    let options = Options { downscale: Some(0.5), ..Default::default() };
+    assert!(matches!(classify_pixels(&img, &img, 2, 2, &options), Err(PixelmatchError::ConflictingOptions(_))));
+    assert!(matches!(
+        classify_pixels(&img, &img[..12], 2, 2, &Options::default()),
+        Err(PixelmatchError::BufferLengthMismatch { .. } | PixelmatchError::ImageSizeMismatch { .. })
+    ));
+}
+
+#[test]
+fn test_callback_reports_diff_and_aa_pixels() {
+    use pixelmatch::classify::pixelmatch_with_callback;
+    let (img1, w, h) = read_image("1a");
+    let (img2, _, _) = read_image("1b");
+    let options = Options::default();
+    let expected = pixelmatch(&img1, &img2, None, w, h, &options).unwrap();
+    let classes = classify_pixels(&img1, &img2, w, h, &options).unwrap();
+
+    let mut seen = Vec::new();
+    let result = pixelmatch_with_callback(&img1, &img2, w, h, &options, |x, y, delta, class| {
+        assert_eq!(classes[(y * w + x) as usize], class);
+        assert!(delta != 0.0);
+        seen.push((y, x));
+    })
+    .unwrap();
+    assert_eq!(result, expected);
+    assert_eq!(seen.len() as u32, expected.diff_count + expected.aa_count);
+    assert!(seen.windows(2).all(|p| p[0] < p[1]), "pixels are reported in row-major order");
+
+    let mut calls = 0;
+    let same = pixelmatch_with_callback(&img1, &img1, w, h, &options, |_, _, _, _| calls += 1).unwrap();
+    assert!(same.identical);
+    assert_eq!(calls, 0);
+}
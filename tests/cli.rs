@@ -0,0 +1,757 @@
+use std::path::PathBuf;
+use std::process::Command;
+
+fn fixture(name: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("test").join("fixtures").join(format!("{name}.png"))
+}
+
+fn pixelmatch() -> Command {
+    Command::new(env!("CARGO_BIN_EXE_pixelmatch"))
+}
+
+fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("pixelmatch-cli-{name}-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+#[test]
+fn test_cli_identical_exits_zero() {
+    let out = pixelmatch().arg(fixture("1a")).arg(fixture("1a")).output().unwrap();
+    assert_eq!(out.status.code(), Some(0));
+    assert!(String::from_utf8_lossy(&out.stdout).contains("different pixels: 0"));
+}
+
+#[test]
+fn test_cli_writes_diff_and_reports_count() {
+    let dir = temp_dir("diff");
+    let diff = dir.join("diff.png");
+    let out = pixelmatch()
+        .args([fixture("1a"), fixture("1b"), diff.clone()])
+        .args(["--threshold", "0.05"])
+        .output()
+        .unwrap();
+    assert_eq!(out.status.code(), Some(66));
+    assert!(String::from_utf8_lossy(&out.stdout).contains("different pixels: 109"));
+
+    let written = pixelmatch::io::read_png(&diff).unwrap();
+    let expected = pixelmatch::io::read_png(fixture("1diff")).unwrap();
+    assert_eq!(written, expected);
+}
+
+#[test]
+fn test_cli_writes_dssim_map() {
+    let dir = temp_dir("dssim");
+    let map = dir.join("dssim.png");
+    let out = pixelmatch().args([fixture("1a"), fixture("1b")]).arg("--dssim-map").arg(&map).output().unwrap();
+    assert_eq!(out.status.code(), Some(66));
+    let written = pixelmatch::io::read_png(&map).unwrap();
+    let baseline = pixelmatch::io::read_png(fixture("1a")).unwrap();
+    assert_eq!((written.width, written.height), (baseline.width, baseline.height));
+    // Unchanged areas are black; the changes show up brighter.
+    assert_eq!(written.data[..4], [0, 0, 4, 255]);
+    assert!(written.data.chunks_exact(4).any(|px| px[0] > 100));
+
+    let out = pixelmatch().args([fixture("1a"), fixture("2a")]).arg("--dssim-map").arg(&map).output().unwrap();
+    assert_eq!(out.status.code(), Some(64));
+}
+
+#[test]
+fn test_cli_writes_classify_map() {
+    let dir = temp_dir("classify");
+    let map = dir.join("classes.png");
+    let out = pixelmatch().args([fixture("1a"), fixture("1b")]).arg("--classify-map").arg(&map).output().unwrap();
+    assert_eq!(out.status.code(), Some(66));
+    let written = pixelmatch::io::read_png(&map).unwrap();
+    let baseline = pixelmatch::io::read_png(fixture("1a")).unwrap();
+    assert_eq!((written.width, written.height), (baseline.width, baseline.height));
+    assert!(written.data.chunks_exact(4).any(|px| px == [0, 0, 0, 255]));
+    assert!(written.data.chunks_exact(4).any(|px| px == [255, 0, 0, 255] || px == [255, 0, 255, 255]));
+
+    let out = pixelmatch().args([fixture("1a"), fixture("2a")]).arg("--classify-map").arg(&map).output().unwrap();
+    assert_eq!(out.status.code(), Some(64));
+}
+
+#[test]
+fn test_cli_prints_gmsd() {
+    let out = pixelmatch().args([fixture("1a"), fixture("1a"), "--gmsd".into()]).output().unwrap();
+    assert!(String::from_utf8_lossy(&out.stdout).contains("gmsd: 0.0000"));
+    let out = pixelmatch().args([fixture("1a"), fixture("1b"), "--gmsd".into()]).output().unwrap();
+    let stdout = String::from_utf8_lossy(&out.stdout);
+    let score: f64 = stdout.lines().find_map(|l| l.strip_prefix("gmsd: ")).unwrap().parse().unwrap();
+    assert!(score > 0.0, "{stdout}");
+}
+
+#[test]
+fn test_cli_prints_psnr() {
+    let out = pixelmatch().args([fixture("1a"), fixture("1a"), "--psnr".into()]).output().unwrap();
+    assert!(String::from_utf8_lossy(&out.stdout).contains("psnr: inf dB"));
+    let out = pixelmatch().args([fixture("1a"), fixture("1b"), "--psnr".into()]).output().unwrap();
+    let stdout = String::from_utf8_lossy(&out.stdout);
+    let db: f64 = stdout.lines().find_map(|l| l.strip_prefix("psnr: ")?.strip_suffix(" dB")).unwrap().parse().unwrap();
+    assert!(db > 10.0 && db.is_finite(), "{stdout}");
+}
+
+#[test]
+fn test_cli_prints_delta_stats() {
+    let out = pixelmatch().args([fixture("1a"), fixture("1b"), "--delta-stats".into()]).output().unwrap();
+    let stdout = String::from_utf8_lossy(&out.stdout);
+    let line = stdout.lines().find_map(|l| l.strip_prefix("deltas: max ")).unwrap();
+    let max: f64 = line.split(',').next().unwrap().parse().unwrap();
+    assert!(max > 0.0 && max <= 1.0, "{stdout}");
+
+    let out = pixelmatch().args([fixture("1a"), fixture("1a"), "--delta-stats".into()]).output().unwrap();
+    let stdout = String::from_utf8_lossy(&out.stdout);
+    assert!(stdout.contains("over 0 differing pixels"), "{stdout}");
+}
+
+#[cfg(feature = "butteraugli")]
+#[test]
+fn test_cli_prints_butteraugli() {
+    let out = pixelmatch().args([fixture("1a"), fixture("1a"), "--butteraugli".into()]).output().unwrap();
+    assert!(String::from_utf8_lossy(&out.stdout).contains("butteraugli: 0.0000 (3-norm 0.0000)"));
+    let out = pixelmatch().args([fixture("1a"), fixture("1b"), "--butteraugli".into()]).output().unwrap();
+    let stdout = String::from_utf8_lossy(&out.stdout);
+    let line = stdout.lines().find_map(|l| l.strip_prefix("butteraugli: ")).unwrap();
+    let score: f64 = line.split(' ').next().unwrap().parse().unwrap();
+    assert!(score > 1.0, "{stdout}");
+}
+
+#[test]
+fn test_cli_prints_flip_and_writes_map() {
+    let dir = temp_dir("flip");
+    let map = dir.join("flip.png");
+    let out = pixelmatch().args([fixture("1a"), fixture("1b")]).args(["--flip", "--flip-map"]).arg(&map).output();
+    let out = out.unwrap();
+    let stdout = String::from_utf8_lossy(&out.stdout);
+    let score: f64 = stdout.lines().find_map(|l| l.strip_prefix("flip: ")).unwrap().parse().unwrap();
+    assert!(score > 0.0 && score < 1.0, "{stdout}");
+    let written = pixelmatch::io::read_png(&map).unwrap();
+    assert_eq!(written.data[..4], [0, 0, 4, 255]);
+
+    let out = pixelmatch().args([fixture("1a"), fixture("1b")]).args(["--flip", "--flip-ppd", "0"]).output().unwrap();
+    assert_eq!(out.status.code(), Some(64));
+}
+
+#[test]
+fn test_cli_rejects_printed_metrics_in_machine_formats() {
+    for format in ["json", "ndjson", "github"] {
+        let out = pixelmatch().args([fixture("1a"), fixture("1b")]).args(["--flip", "--format", format]).output();
+        let out = out.unwrap();
+        assert_eq!(out.status.code(), Some(2), "{format}");
+        let stderr = String::from_utf8_lossy(&out.stderr);
+        assert!(stderr.contains("--flip prints to the text output"), "{stderr}");
+    }
+
+    // A map file is still written, without computing anything that is not printed.
+    let dir = temp_dir("flip-json");
+    let map = dir.join("flip.png");
+    let args = ["--format", "json", "--flip-map"];
+    let out = pixelmatch().args([fixture("1a"), fixture("1b")]).args(args).arg(&map).output().unwrap();
+    assert_eq!(out.status.code(), Some(1));
+    assert!(!String::from_utf8_lossy(&out.stdout).contains("flip"));
+    assert!(map.is_file());
+
+    let out = pixelmatch().args([fixture("1a"), fixture("1a"), "--flip-map".into(), map]).output().unwrap();
+    assert!(!String::from_utf8_lossy(&out.stdout).contains("flip:"));
+
+    let out = pixelmatch().args([temp_dir("metrics-a"), temp_dir("metrics-b")]).arg("--psnr").output().unwrap();
+    assert_eq!(out.status.code(), Some(64));
+    assert!(String::from_utf8_lossy(&out.stderr).contains("--psnr needs a single pair of images"));
+}
+
+#[test]
+fn test_cli_prints_normal_map_angles() {
+    let out = pixelmatch().args([fixture("1a"), fixture("1a")]).args(["--normal-angle", "5"]).output().unwrap();
+    assert!(String::from_utf8_lossy(&out.stdout).contains("normals over 5°: 0 (max 0.00°, mean 0.00°)"));
+    let out = pixelmatch().args([fixture("1a"), fixture("1b")]).args(["--normal-angle", "5"]).output().unwrap();
+    let stdout = String::from_utf8_lossy(&out.stdout);
+    let line = stdout.lines().find_map(|l| l.strip_prefix("normals over 5°: ")).unwrap();
+    assert!(line.split(' ').next().unwrap().parse::<u32>().unwrap() > 0, "{stdout}");
+    let out = pixelmatch().args([fixture("1a"), fixture("1b")]).args(["--normal-angle", "200"]).output().unwrap();
+    assert_eq!(out.status.code(), Some(64));
+}
+
+#[test]
+fn test_cli_prints_histogram_distances() {
+    let out = pixelmatch().args([fixture("1a"), fixture("1a"), "--histogram".into()]).output().unwrap();
+    let stdout = String::from_utf8_lossy(&out.stdout);
+    assert!(stdout.contains("luma histogram: chi-square 0.0000, intersection 1.0000, emd 0.0000"), "{stdout}");
+    assert!(stdout.contains("blue histogram:"), "{stdout}");
+}
+
+#[test]
+fn test_cli_prints_changed_channels() {
+    let out = pixelmatch().args([fixture("1a"), fixture("1a"), "--channels".into()]).output().unwrap();
+    let stdout = String::from_utf8_lossy(&out.stdout);
+    assert!(stdout.contains("changed channels: red 0, green 0, blue 0, alpha 0"), "{stdout}");
+    let out = pixelmatch().args([fixture("1a"), fixture("1b"), "--channels".into()]).output().unwrap();
+    let stdout = String::from_utf8_lossy(&out.stdout);
+    let line = stdout.lines().find_map(|l| l.strip_prefix("changed channels: red ")).unwrap();
+    assert!(line.split(',').next().unwrap().parse::<u32>().unwrap() > 0, "{stdout}");
+}
+
+#[test]
+fn test_cli_dimension_mismatch() {
+    let out = pixelmatch().arg(fixture("1a")).arg(fixture("2a")).output().unwrap();
+    assert_eq!(out.status.code(), Some(65));
+}
+
+#[test]
+fn test_cli_invalid_usage() {
+    let out = pixelmatch().arg(fixture("1a")).output().unwrap();
+    assert_eq!(out.status.code(), Some(64));
+}
+
+#[test]
+fn test_cli_approve_updates_failing_baselines() {
+    let dir = temp_dir("approve");
+    let (baselines, candidates) = (dir.join("baseline"), dir.join("candidate"));
+    std::fs::create_dir_all(baselines.join("nested")).unwrap();
+    std::fs::create_dir_all(candidates.join("nested")).unwrap();
+    std::fs::copy(fixture("1a"), baselines.join("same.png")).unwrap();
+    std::fs::copy(fixture("1a"), candidates.join("same.png")).unwrap();
+    std::fs::copy(fixture("1a"), baselines.join("nested/changed.png")).unwrap();
+    std::fs::copy(fixture("1b"), candidates.join("nested/changed.png")).unwrap();
+    std::fs::copy(fixture("2a"), baselines.join("other.png")).unwrap();
+    std::fs::copy(fixture("2b"), candidates.join("other.png")).unwrap();
+    std::fs::copy(fixture("3a"), candidates.join("new.png")).unwrap();
+
+    let out = pixelmatch()
+        .arg("approve")
+        .args([&baselines, &candidates])
+        .args(["--filter", "changed"])
+        .output()
+        .unwrap();
+    assert_eq!(out.status.code(), Some(0), "{}", String::from_utf8_lossy(&out.stderr));
+    let stdout = String::from_utf8_lossy(&out.stdout);
+    assert!(stdout.contains("approved: nested/changed.png"), "{stdout}");
+    assert!(!stdout.contains("other.png"), "filtered pair should not be approved: {stdout}");
+
+    let read = |p: PathBuf| std::fs::read(p).unwrap();
+    assert_eq!(read(baselines.join("nested/changed.png")), read(fixture("1b")));
+    assert_eq!(read(baselines.join("other.png")), read(fixture("2a")));
+    assert!(!baselines.join("new.png").exists());
+
+    let out = pixelmatch().arg("approve").args([&baselines, &candidates]).arg("--new").output().unwrap();
+    assert_eq!(out.status.code(), Some(0));
+    assert_eq!(read(baselines.join("other.png")), read(fixture("2b")));
+    assert_eq!(read(baselines.join("new.png")), read(fixture("3a")));
+}
+
+#[test]
+fn test_cli_approve_interactive_declines() {
+    use std::io::Write;
+    use std::process::Stdio;
+
+    let dir = temp_dir("approve-interactive");
+    let (baselines, candidates) = (dir.join("baseline"), dir.join("candidate"));
+    std::fs::create_dir_all(&baselines).unwrap();
+    std::fs::create_dir_all(&candidates).unwrap();
+    std::fs::copy(fixture("1a"), baselines.join("a.png")).unwrap();
+    std::fs::copy(fixture("1b"), candidates.join("a.png")).unwrap();
+
+    let mut child = pixelmatch()
+        .arg("approve")
+        .args([&baselines, &candidates])
+        .arg("--interactive")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child.stdin.take().unwrap().write_all(b"n\n").unwrap();
+    let out = child.wait_with_output().unwrap();
+    assert_eq!(out.status.code(), Some(0));
+    assert!(String::from_utf8_lossy(&out.stdout).contains("0 baseline(s) updated"));
+    assert_eq!(std::fs::read(baselines.join("a.png")).unwrap(), std::fs::read(fixture("1a")).unwrap());
+}
+
+#[test]
+fn test_cli_json_output_and_exit_codes() {
+    let out = pixelmatch()
+        .args([fixture("1a"), fixture("1b")])
+        .args(["--threshold", "0.05", "--format", "json"])
+        .output()
+        .unwrap();
+    assert_eq!(out.status.code(), Some(1));
+    let doc: serde_json::Value = serde_json::from_slice(&out.stdout).unwrap();
+    assert_eq!(doc["schema_version"], 1);
+    assert_eq!(doc["passed"], false);
+    assert_eq!(doc["summary"]["failed"], 1);
+    let record = &doc["results"][0];
+    assert_eq!(record["status"], "fail");
+    assert_eq!(record["diff_count"], 109);
+    assert_eq!(record["identical"], false);
+    assert_eq!(record["diff_bounds"].as_array().unwrap().len(), 4);
+
+    let out = pixelmatch().args([fixture("1a"), fixture("1a"), "--format=json".into()]).output().unwrap();
+    assert_eq!(out.status.code(), Some(0));
+    let doc: serde_json::Value = serde_json::from_slice(&out.stdout).unwrap();
+    assert!(doc["results"][0]["diff_bounds"].is_null());
+
+    let out = pixelmatch().args([fixture("1a"), "missing.png".into(), "--format=json".into()]).output().unwrap();
+    assert_eq!(out.status.code(), Some(2));
+
+    let out = pixelmatch().args([fixture("1a"), fixture("1b")]).args(["--format", "json", "--bogus"]).output().unwrap();
+    assert_eq!(out.status.code(), Some(2));
+}
+
+#[test]
+fn test_cli_ndjson_directory_mode() {
+    let dir = temp_dir("ndjson");
+    let (baselines, candidates, diffs) = (dir.join("baseline"), dir.join("candidate"), dir.join("diff"));
+    std::fs::create_dir_all(&baselines).unwrap();
+    std::fs::create_dir_all(&candidates).unwrap();
+    std::fs::copy(fixture("1a"), baselines.join("a.png")).unwrap();
+    std::fs::copy(fixture("1a"), candidates.join("a.png")).unwrap();
+    std::fs::copy(fixture("1a"), baselines.join("b.png")).unwrap();
+    std::fs::copy(fixture("1b"), candidates.join("b.png")).unwrap();
+    std::fs::copy(fixture("3a"), candidates.join("c.png")).unwrap();
+
+    let out = pixelmatch().args([&baselines, &candidates, &diffs]).args(["--format", "ndjson"]).output().unwrap();
+    assert_eq!(out.status.code(), Some(1));
+    let records: Vec<serde_json::Value> =
+        String::from_utf8_lossy(&out.stdout).lines().map(|l| serde_json::from_str(l).unwrap()).collect();
+    let statuses: Vec<_> =
+        records.iter().map(|r| (r["name"].as_str().unwrap(), r["status"].as_str().unwrap())).collect();
+    assert_eq!(statuses, [("a.png", "pass"), ("b.png", "fail"), ("c.png", "missing_baseline")]);
+    assert!(records[0]["diff"].is_null());
+    assert!(diffs.join("b.png").exists());
+    assert!(!diffs.join("a.png").exists());
+}
+
+#[test]
+fn test_cli_report_writes_html_with_thumbnails() {
+    let dir = temp_dir("report");
+    let (baselines, candidates, out) = (dir.join("baseline"), dir.join("candidate"), dir.join("report"));
+    std::fs::create_dir_all(&baselines).unwrap();
+    std::fs::create_dir_all(&candidates).unwrap();
+    std::fs::copy(fixture("1a"), baselines.join("same.png")).unwrap();
+    std::fs::copy(fixture("1a"), candidates.join("same.png")).unwrap();
+    std::fs::copy(fixture("4a"), baselines.join("changed.png")).unwrap();
+    std::fs::copy(fixture("4b"), candidates.join("changed.png")).unwrap();
+
+    let status =
+        pixelmatch().arg("report").args([&baselines, &candidates]).arg("--out").arg(&out).status().unwrap();
+    assert_eq!(status.code(), Some(66));
+    let html = std::fs::read_to_string(out.join("index.html")).unwrap();
+    assert!(html.contains("changed.png"));
+    assert!(!html.contains("same.png"));
+    for kind in ["baseline", "candidate", "diff"] {
+        assert!(out.join(kind).join("changed.png").exists(), "{kind} image missing");
+        let thumb = pixelmatch::io::read_png(out.join("thumbnails").join(kind).join("changed.png")).unwrap();
+        assert!(thumb.width <= 320 && thumb.height <= 320);
+    }
+
+    // Consume a prior JSON run instead of comparing again
+    let results = dir.join("results.json");
+    let json = pixelmatch().args([&baselines, &candidates]).args(["--format", "json"]).output().unwrap();
+    std::fs::write(&results, json.stdout).unwrap();
+    let from_out = dir.join("from-report");
+    let status =
+        pixelmatch().args(["report", "--from"]).arg(&results).arg("--out").arg(&from_out).status().unwrap();
+    assert_eq!(status.code(), Some(66));
+    assert!(std::fs::read_to_string(from_out.join("index.html")).unwrap().contains("changed.png"));
+    assert!(from_out.join("candidate").join("changed.png").exists());
+
+    // Names with URL delimiters are linked percent-encoded.
+    std::fs::rename(baselines.join("changed.png"), baselines.join("changed #1.png")).unwrap();
+    std::fs::rename(candidates.join("changed.png"), candidates.join("changed #1.png")).unwrap();
+    let status =
+        pixelmatch().arg("report").args([&baselines, &candidates]).arg("--out").arg(&out).status().unwrap();
+    assert_eq!(status.code(), Some(66));
+    let html = std::fs::read_to_string(out.join("index.html")).unwrap();
+    assert!(html.contains("href=\"diff/changed%20%231.png\""), "{html}");
+    assert!(out.join("diff").join("changed #1.png").exists());
+
+    // Names in a results file cannot write outside --out.
+    let escaping = std::fs::read_to_string(&results).unwrap().replace("\"changed.png\"", "\"../../escaped.png\"");
+    std::fs::write(&results, escaping).unwrap();
+    let out = pixelmatch().args(["report", "--from"]).arg(&results).arg("--out").arg(&from_out).output().unwrap();
+    assert_eq!(out.status.code(), Some(64));
+    assert!(String::from_utf8_lossy(&out.stderr).contains("\"../../escaped.png\" is not a relative path"));
+    assert!(!dir.parent().unwrap().join("escaped.png").exists());
+}
+
+#[test]
+fn test_cli_config_file_overrides() {
+    let dir = temp_dir("config");
+    let (baselines, candidates) = (dir.join("baseline"), dir.join("candidate"));
+    std::fs::create_dir_all(baselines.join("ignored")).unwrap();
+    std::fs::create_dir_all(candidates.join("ignored")).unwrap();
+    let names = ["strict.png", "ignored/a.png", "loose.png", "exact.png", "polygon.png"];
+    for name in names {
+        std::fs::copy(fixture("1a"), baselines.join(name)).unwrap();
+        std::fs::copy(fixture("1b"), candidates.join(name)).unwrap();
+    }
+    let config = dir.join("pixelmatch.toml");
+    std::fs::write(
+        &config,
+        r#"
+threshold = 0.05
+
+[presets.loose]
+threshold = 1.0
+
+[[overrides]]
+files = "ignored/**"
+ignore = [{ x = 0, y = 0, width = 10000, height = 10000 }]
+
+[[overrides]]
+files = ["loose.*"]
+preset = "loose"
+
+[[overrides]]
+files = ["exact.*"]
+preset = "strict"
+
+[[overrides]]
+files = ["polygon.*"]
+ignore_polygons = [[[-1, -1], [10000, -1], [10000, 10000], [-1, 10000]]]
+"#,
+    )
+    .unwrap();
+
+    let out = pixelmatch()
+        .args([&baselines, &candidates])
+        .arg("--config")
+        .arg(&config)
+        .args(["--format", "ndjson"])
+        .output()
+        .unwrap();
+    let records: Vec<serde_json::Value> =
+        String::from_utf8_lossy(&out.stdout).lines().map(|l| serde_json::from_str(l).unwrap()).collect();
+    let counts: Vec<_> =
+        records.iter().map(|r| (r["name"].as_str().unwrap(), r["diff_count"].as_u64().unwrap())).collect();
+    assert_eq!(
+        counts,
+        [("exact.png", 12933), ("ignored/a.png", 0), ("loose.png", 0), ("polygon.png", 0), ("strict.png", 109)]
+    );
+
+    // Flags take precedence over the file
+    let out = pixelmatch()
+        .args([baselines.join("strict.png"), candidates.join("strict.png")])
+        .arg("--config")
+        .arg(&config)
+        .args(["--threshold", "1", "--format", "json"])
+        .output()
+        .unwrap();
+    assert_eq!(out.status.code(), Some(0));
+
+    std::fs::write(&config, "preset = \"missing\"\n").unwrap();
+    let out = pixelmatch().args([&baselines, &candidates]).arg("--config").arg(&config).output().unwrap();
+    assert_eq!(out.status.code(), Some(64));
+    assert!(String::from_utf8_lossy(&out.stderr).contains("unknown preset \"missing\""));
+
+    // Misspelt keys are rejected wherever they appear rather than leaving the default.
+    let tables = [
+        ("treshold = 0.9\n", "unknown key `treshold` in the top level"),
+        ("[presets.loose]\nthreshhold = 1.0\n", "unknown key `threshhold` in [presets.loose]"),
+        ("[[overrides]]\nfiles = \"*\"\nignore_region = []\n", "unknown key `ignore_region` in [[overrides]] #1"),
+    ];
+    for (text, message) in tables {
+        std::fs::write(&config, text).unwrap();
+        let out = pixelmatch().args([&baselines, &candidates]).arg("--config").arg(&config).output().unwrap();
+        assert_eq!(out.status.code(), Some(64));
+        assert!(String::from_utf8_lossy(&out.stderr).contains(message), "{}", String::from_utf8_lossy(&out.stderr));
+    }
+}
+
+#[test]
+fn test_cli_max_diff_thresholds() {
+    let run = |args: &[&str]| {
+        pixelmatch().args([fixture("1a"), fixture("1b")]).args(["--threshold", "0.05"]).args(args).status().unwrap()
+    };
+    // 109 of 512x256 pixels differ (~0.083%)
+    assert_eq!(run(&["--max-diff-pixels", "109"]).code(), Some(0));
+    assert_eq!(run(&["--max-diff-pixels", "108"]).code(), Some(66));
+    assert_eq!(run(&["--max-diff-ratio", "0.001"]).code(), Some(0));
+    assert_eq!(run(&["--max-diff-ratio", "0.0005"]).code(), Some(66));
+    assert_eq!(run(&["--max-diff-ratio", "0.001", "--max-diff-pixels", "100"]).code(), Some(66));
+    assert_eq!(run(&["--max-diff-ratio", "2"]).code(), Some(64));
+}
+
+#[test]
+fn test_cli_progress_log_when_not_a_terminal() {
+    let dir = temp_dir("progress");
+    let (baselines, candidates) = (dir.join("baseline"), dir.join("candidate"));
+    std::fs::create_dir_all(&baselines).unwrap();
+    std::fs::create_dir_all(&candidates).unwrap();
+    for (name, b) in [("a.png", "1a"), ("b.png", "1b"), ("c.png", "1a")] {
+        std::fs::copy(fixture("1a"), baselines.join(name)).unwrap();
+        std::fs::copy(fixture(b), candidates.join(name)).unwrap();
+    }
+
+    let out = pixelmatch().args([&baselines, &candidates]).args(["--jobs", "2"]).output().unwrap();
+    assert_eq!(out.status.code(), Some(66));
+    let stderr = String::from_utf8_lossy(&out.stderr);
+    let mut lines: Vec<_> = stderr.lines().map(|l| l.split_once("] ").unwrap().1).collect();
+    lines.sort();
+    assert_eq!(lines, ["FAIL b.png", "pass a.png", "pass c.png"]);
+    assert!(stderr.contains("[3/3]"), "{stderr}");
+
+    let out = pixelmatch().args([&baselines, &candidates]).arg("--no-progress").output().unwrap();
+    assert!(out.stderr.is_empty());
+}
+
+#[test]
+fn test_cli_ignore_regions_and_mask() {
+    let run = |args: &[&std::ffi::OsStr]| {
+        let out = pixelmatch().args([fixture("1a"), fixture("1b")]).args(["--threshold", "0.05"]).args(args).output();
+        let stdout = String::from_utf8(out.unwrap().stdout).unwrap();
+        stdout.lines().find_map(|l| l.strip_prefix("different pixels: ")).map(str::to_string)
+    };
+    assert_eq!(run(&["--ignore".as_ref(), "0,0,512,256".as_ref()]).as_deref(), Some("0"));
+    let split = ["--ignore".as_ref(), "0,0,256,256".as_ref(), "--ignore".as_ref(), "256,0,256,256".as_ref()];
+    assert_eq!(run(&split).as_deref(), Some("0"));
+
+    // White (opaque) pixels in the mask are ignored, black ones are compared.
+    let dir = temp_dir("ignore-mask");
+    let mask = dir.join("mask.png");
+    let mut data = vec![255u8; 512 * 256 * 4];
+    pixelmatch::io::write_png(&mask, &data, 512, 256).unwrap();
+    assert_eq!(run(&["--ignore-mask".as_ref(), mask.as_os_str()]).as_deref(), Some("0"));
+    data.fill(0);
+    for px in data.chunks_exact_mut(4) {
+        px[3] = 255;
+    }
+    pixelmatch::io::write_png(&mask, &data, 512, 256).unwrap();
+    assert_eq!(run(&["--ignore-mask".as_ref(), mask.as_os_str()]).as_deref(), Some("109"));
+
+    let out = pixelmatch().args([fixture("1a"), fixture("1b")]).args(["--ignore", "1,2,3"]).output().unwrap();
+    assert_eq!(out.status.code(), Some(64));
+}
+
+#[test]
+fn test_cli_stdin_and_stdout() {
+    use std::io::Write;
+    use std::process::Stdio;
+
+    let mut child = pixelmatch()
+        .args([fixture("1a").as_os_str(), "-".as_ref(), "-".as_ref()])
+        .args(["--threshold", "0.05"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child.stdin.take().unwrap().write_all(&std::fs::read(fixture("1b")).unwrap()).unwrap();
+    let out = child.wait_with_output().unwrap();
+    assert_eq!(out.status.code(), Some(66));
+    assert!(String::from_utf8_lossy(&out.stderr).contains("different pixels: 109"));
+    let diff = pixelmatch::io::decode_png(out.stdout.as_slice()).unwrap();
+    assert_eq!(diff, pixelmatch::io::read_png(fixture("1diff")).unwrap());
+
+    let out = pixelmatch().args(["-", "-"]).stdin(Stdio::null()).output().unwrap();
+    assert_eq!(out.status.code(), Some(64));
+}
+
+#[test]
+fn test_cli_github_annotations_and_summary() {
+    let dir = temp_dir("github");
+    let (baselines, candidates) = (dir.join("baseline"), dir.join("candidate"));
+    std::fs::create_dir_all(&baselines).unwrap();
+    std::fs::create_dir_all(&candidates).unwrap();
+    for (name, b) in [("same.png", "1a"), ("changed.png", "1b")] {
+        std::fs::copy(fixture("1a"), baselines.join(name)).unwrap();
+        std::fs::copy(fixture(b), candidates.join(name)).unwrap();
+    }
+    let summary = dir.join("summary.md");
+
+    let out = pixelmatch()
+        .args([&baselines, &candidates])
+        .args(["--threshold", "0.05", "--format", "github", "--no-progress"])
+        .env("GITHUB_STEP_SUMMARY", &summary)
+        .output()
+        .unwrap();
+    assert_eq!(out.status.code(), Some(1));
+    let stdout = String::from_utf8_lossy(&out.stdout);
+    let lines: Vec<_> = stdout.lines().collect();
+    assert_eq!(lines.len(), 1, "{stdout}");
+    assert!(lines[0].starts_with("::error file="), "{stdout}");
+    assert!(lines[0].contains("changed.png,title=Visual diff%3A changed.png::109 different pixels (0.08%25)"));
+
+    let markdown = std::fs::read_to_string(&summary).unwrap();
+    assert!(markdown.contains("changed.png"));
+    assert!(markdown.contains("**1** failed"));
+
+    let out = pixelmatch().args([fixture("1a"), "missing.png".into()]).args(["--format", "github"]).output().unwrap();
+    assert_eq!(out.status.code(), Some(2));
+    assert!(String::from_utf8_lossy(&out.stdout).starts_with("::error title=pixelmatch::"));
+}
+
+#[cfg(feature = "tui")]
+#[test]
+fn test_cli_view_nothing_to_review() {
+    let dir = temp_dir("view");
+    let (baselines, candidates) = (dir.join("baseline"), dir.join("candidate"));
+    std::fs::create_dir_all(&baselines).unwrap();
+    std::fs::create_dir_all(&candidates).unwrap();
+    std::fs::copy(fixture("1a"), baselines.join("a.png")).unwrap();
+    std::fs::copy(fixture("1a"), candidates.join("a.png")).unwrap();
+
+    let out = pixelmatch().arg("view").args([&baselines, &candidates]).arg("--no-progress").output().unwrap();
+    assert_eq!(out.status.code(), Some(0));
+    assert!(String::from_utf8_lossy(&out.stdout).contains("nothing to review"));
+}
+
+#[test]
+fn test_cli_montage_tiles_worst_diffs() {
+    let dir = temp_dir("montage");
+    let (baselines, candidates) = (dir.join("baseline"), dir.join("candidate"));
+    std::fs::create_dir_all(&baselines).unwrap();
+    std::fs::create_dir_all(&candidates).unwrap();
+    for (name, a, b) in [("one.png", "1a", "1b"), ("four.png", "4a", "4b"), ("same.png", "1a", "1a")] {
+        std::fs::copy(fixture(a), baselines.join(name)).unwrap();
+        std::fs::copy(fixture(b), candidates.join(name)).unwrap();
+    }
+    let sheet = dir.join("sheet.png");
+
+    let out = pixelmatch()
+        .arg("montage")
+        .args([&baselines, &candidates])
+        .arg("--out")
+        .arg(&sheet)
+        .args(["--top", "1", "--tile-size", "100", "--no-progress"])
+        .output()
+        .unwrap();
+    assert_eq!(out.status.code(), Some(66));
+    assert!(String::from_utf8_lossy(&out.stdout).contains("montage of 1 of 2 failing comparisons"));
+    let image = pixelmatch::io::read_png(&sheet).unwrap();
+    // One tile: padding + tile + two label lines + padding
+    assert_eq!((image.width, image.height), (116, 140));
+}
+
+#[test]
+fn test_cli_inline_preview_protocols() {
+    let run = |protocol: &str| {
+        let out = pixelmatch().args([fixture("1a"), fixture("1b")]).args(["--preview", protocol]).output().unwrap();
+        assert_eq!(out.status.code(), Some(66));
+        String::from_utf8(out.stdout).unwrap()
+    };
+    assert!(run("kitty").contains("\x1b_Ga=T,f=100,"));
+    assert!(run("iterm").contains("\x1b]1337;File=inline=1;"));
+    let sixel = run("sixel");
+    assert!(sixel.contains("\x1bPq\"1;1;480;240") && sixel.ends_with("\x1b\\\n"), "{sixel:.40}");
+    // Auto-detection never writes escape codes when stdout is not a terminal.
+    assert!(!run("auto").contains('\x1b'));
+    // Identical images have nothing to preview.
+    let out = pixelmatch().args([fixture("1a"), fixture("1a")]).args(["--preview", "kitty"]).output().unwrap();
+    assert!(!String::from_utf8_lossy(&out.stdout).contains('\x1b'));
+}
+
+#[test]
+fn test_cli_review_records_and_approves() {
+    let dir = temp_dir("review");
+    let (baselines, candidates, manifest) = (dir.join("baseline"), dir.join("candidate"), dir.join("review.json"));
+    std::fs::create_dir_all(&baselines).unwrap();
+    std::fs::create_dir_all(&candidates).unwrap();
+    for (name, a, b) in [("same.png", "1a", "1a"), ("changed.png", "1a", "1b"), ("other.png", "2a", "2b")] {
+        std::fs::copy(fixture(a), baselines.join(name)).unwrap();
+        std::fs::copy(fixture(b), candidates.join(name)).unwrap();
+    }
+
+    let out = pixelmatch().args([&baselines, &candidates]).arg("--review").arg(&manifest).output().unwrap();
+    assert_eq!(out.status.code(), Some(66));
+    let out = pixelmatch().arg("review").arg(&manifest).output().unwrap();
+    let stdout = String::from_utf8_lossy(&out.stdout);
+    assert!(stdout.contains("PENDING changed.png: ") && stdout.contains("2 pending"), "{stdout}");
+    assert!(!stdout.contains("same.png"), "{stdout}");
+
+    let out = pixelmatch()
+        .arg("review")
+        .arg(&manifest)
+        .args(["--approve", "changed.png", "--reject", "other.png"])
+        .output()
+        .unwrap();
+    assert_eq!(out.status.code(), Some(0), "{}", String::from_utf8_lossy(&out.stderr));
+    let read = |p: PathBuf| std::fs::read(p).unwrap();
+    assert_eq!(read(baselines.join("changed.png")), read(fixture("1b")));
+    assert_eq!(read(baselines.join("other.png")), read(fixture("2a")));
+
+    // The approved pair passes now and drops out of the manifest on the next run.
+    pixelmatch().args([&baselines, &candidates]).arg("--review").arg(&manifest).output().unwrap();
+    let json: serde_json::Value = serde_json::from_slice(&read(manifest)).unwrap();
+    let entries: Vec<_> = json["entries"].as_array().unwrap().iter().map(|e| (&e["name"], &e["status"])).collect();
+    assert_eq!(entries, [(&"other.png".into(), &"pending".into())]);
+}
+
+#[test]
+fn test_cli_flaky_writes_ignore_mask() {
+    let dir = temp_dir("flaky");
+    let mask = dir.join("mask.png");
+    let out = pixelmatch()
+        .arg("flaky")
+        .args([fixture("1a"), fixture("1b"), fixture("1a")])
+        .arg("--out")
+        .arg(&mask)
+        .output()
+        .unwrap();
+    assert_eq!(out.status.code(), Some(0), "{}", String::from_utf8_lossy(&out.stderr));
+    assert!(String::from_utf8_lossy(&out.stdout).contains("unstable region(s)"));
+
+    let out = pixelmatch().args([fixture("1a"), fixture("1b")]).arg("--ignore-mask").arg(&mask).output().unwrap();
+    assert_eq!(out.status.code(), Some(0), "{}", String::from_utf8_lossy(&out.stdout));
+
+    let out = pixelmatch().args(["flaky", "--out"]).arg(&mask).arg(fixture("1a")).output().unwrap();
+    assert_eq!(out.status.code(), Some(64));
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn test_cli_tiles_reports_per_zoom() {
+    let dir = temp_dir("tiles");
+    let (base, cand) = (dir.join("base"), dir.join("cand"));
+    for (root, image) in [(&base, "1a"), (&cand, "1b")] {
+        std::fs::create_dir_all(root.join("12/5")).unwrap();
+        std::fs::copy(fixture("1a"), root.join("12/5/7.png")).unwrap();
+        std::fs::copy(fixture(image), root.join("12/5/8.png")).unwrap();
+    }
+    std::fs::create_dir_all(cand.join("13/10")).unwrap();
+    std::fs::copy(fixture("1a"), cand.join("13/10/14.png")).unwrap();
+
+    let out = pixelmatch().arg("tiles").args([&base, &cand]).output().unwrap();
+    let stdout = String::from_utf8_lossy(&out.stdout);
+    assert_eq!(out.status.code(), Some(66), "{stdout}");
+    assert!(stdout.lines().any(|l| l.split_whitespace().take(5).eq(["12", "2", "1", "0", "0"])), "{stdout}");
+    assert!(stdout.contains("failed: 12/5/8 ("), "{stdout}");
+    assert!(stdout.contains("missing baseline: 13/10/14"), "{stdout}");
+
+    // Missing tiles alone do not fail the run.
+    std::fs::copy(fixture("1a"), cand.join("12/5/8.png")).unwrap();
+    let out = pixelmatch().arg("tiles").args([&base, &cand]).output().unwrap();
+    assert_eq!(out.status.code(), Some(0), "{}", String::from_utf8_lossy(&out.stdout));
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn test_cli_environment_overrides() {
+    let run = |env: &[(&str, &str)], args: &[&str]| {
+        let out = pixelmatch().args([fixture("1a"), fixture("1b")]).args(args).envs(env.iter().copied()).output();
+        out.unwrap()
+    };
+    // 109 of 512x256 pixels differ at threshold 0.05.
+    let out = run(&[("PIXELMATCH_THRESHOLD", "0.05")], &[]);
+    assert!(String::from_utf8_lossy(&out.stdout).contains("different pixels: 109"));
+    let env = [("PIXELMATCH_THRESHOLD", "0.05"), ("PIXELMATCH_MAX_DIFF_PIXELS", "109")];
+    assert_eq!(run(&env, &[]).status.code(), Some(0));
+    let env = [("PIXELMATCH_THRESHOLD", "0.05"), ("PIXELMATCH_MAX_DIFF_RATIO", "0.001")];
+    assert_eq!(run(&env, &[]).status.code(), Some(0));
+    // Flags win over the environment.
+    assert_eq!(run(&env, &["--max-diff-ratio", "0.0005"]).status.code(), Some(66));
+
+    let out = run(&[("PIXELMATCH_THRESHOLD", "low")], &[]);
+    assert_eq!(out.status.code(), Some(64));
+    assert!(String::from_utf8_lossy(&out.stderr).contains("PIXELMATCH_THRESHOLD: threshold: invalid type"));
+    assert_eq!(run(&[("PIXELMATCH_MAX_DIFF_RATIO", "2")], &[]).status.code(), Some(64));
+
+    // An override leaves a configured value JSON cannot hold to validation, not to a panic.
+    let dir = temp_dir("env-nan");
+    let config = dir.join("pixelmatch.toml");
+    std::fs::write(&config, "threshold = nan\n").unwrap();
+    let out = run(&[("PIXELMATCH_ALPHA", "0.5")], &["--config", config.to_str().unwrap()]);
+    assert_eq!(out.status.code(), Some(64));
+    assert!(String::from_utf8_lossy(&out.stderr).contains("Threshold must be between 0 and 1"));
+    let _ = std::fs::remove_dir_all(&dir);
+}
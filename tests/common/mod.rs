@@ -0,0 +1,47 @@
+//! Fixtures shared by the integration test crates. Each crate uses only some of them.
+#![allow(dead_code)]
+
+use std::path::PathBuf;
+
+/// Directory of the PNG fixtures shared with the JavaScript tests.
+pub fn fixtures_dir() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("test").join("fixtures")
+}
+
+/// Decode the fixture `name` as 8-bit RGBA, returning the pixels, width and height.
+pub fn read_image(name: &str) -> (Vec<u8>, u32, u32) {
+    let path = fixtures_dir().join(format!("{name}.png"));
+    let file = std::fs::File::open(&path).unwrap_or_else(|e| panic!("Failed to open {}: {e}", path.display()));
+    let mut decoder = png::Decoder::new(file);
+    // Expand all colour types to RGBA, matching pngjs behaviour
+    decoder.set_transformations(png::Transformations::EXPAND | png::Transformations::ALPHA);
+    let mut reader = decoder.read_info().unwrap();
+    let mut buf = vec![0u8; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut buf).unwrap();
+    buf.truncate(info.buffer_size());
+
+    // Verify we got RGBA output
+    assert_eq!(
+        info.color_type,
+        png::ColorType::Rgba,
+        "Expected RGBA output for {name}.png, got {:?}",
+        info.color_type
+    );
+    assert_eq!(info.bit_depth, png::BitDepth::Eight, "Expected 8-bit depth for {name}.png");
+
+    (buf, info.width, info.height)
+}
+
+/// An empty scratch directory for the test `name`, unique to this process.
+pub fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("pixelmatch-test-{name}-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+/// A `width` x `height` image filled with `rgba`.
+#[cfg(feature = "png")]
+pub fn solid(width: u32, height: u32, rgba: [u8; 4]) -> pixelmatch::io::Image {
+    pixelmatch::io::Image { data: rgba.repeat(width as usize * height as usize), width, height }
+}
@@ -0,0 +1,44 @@
+use pixelmatch::consensus::Consensus;
+use pixelmatch::Options;
+
+const WIDTH: u32 = 8;
+const HEIGHT: u32 = 6;
+
+/// A white image with black pixels at `points`.
+fn image(points: &[(u32, u32)]) -> Vec<u8> {
+    let mut img = [255u8; 4].repeat((WIDTH * HEIGHT) as usize);
+    for &(x, y) in points {
+        img[((y * WIDTH + x) * 4) as usize..][..3].fill(0);
+    }
+    img
+}
+
+#[test]
+fn test_consensus_counts_pixels_that_differ_in_enough_runs() {
+    let baseline = image(&[]);
+    // A real change at (1, 1) in every run, and noise that moves around.
+    let runs = [image(&[(1, 1), (6, 4)]), image(&[(1, 1), (3, 2)]), image(&[(1, 1), (6, 4), (0, 5)])];
+    let runs: Vec<&[u8]> = runs.iter().map(Vec::as_slice).collect();
+    let options = Options { detect_anti_aliasing: false, ..Default::default() };
+
+    let consensus = Consensus::compare(&baseline, &runs, WIDTH, HEIGHT, &options).unwrap();
+    assert_eq!(consensus.runs, 3);
+    assert_eq!(consensus.votes[(WIDTH + 1) as usize], 3);
+    assert_eq!(consensus.votes[(4 * WIDTH + 6) as usize], 2);
+    assert_eq!([0, 1, 2, 3, 4].map(|k| consensus.count(k)), [4, 4, 2, 1, 0]);
+    let unanimous = consensus.unanimous();
+    assert!(unanimous[(WIDTH + 1) as usize]);
+    assert_eq!(unanimous.iter().filter(|&&m| m).count(), 1);
+    assert_eq!(consensus.regions(2).len(), 2);
+}
+
+#[test]
+fn test_consensus_from_masks() {
+    let a = [true, false, true, false];
+    let b = [true, true, false, false];
+    let consensus = Consensus::from_masks(&[&a, &b], 2, 2).unwrap();
+    assert_eq!(consensus.votes, [2, 1, 1, 0]);
+    assert_eq!(consensus.mask(2), [true, false, false, false]);
+    assert!(Consensus::from_masks(&[&a, &b[..3]], 2, 2).is_err());
+    assert_eq!(Consensus::from_masks(&[], 2, 2).unwrap().count(1), 0);
+}
@@ -0,0 +1,78 @@
+use pixelmatch::deltas::{delta_stats, DeltaStats};
+use pixelmatch::{Options, PixelmatchError, Rect};
+
+fn grey(v: u8, n: usize) -> Vec<u8> {
+    [v, v, v, 255].repeat(n)
+}
+
+#[test]
+fn test_delta_stats_tell_outliers_from_borderline_changes() {
+    let base = grey(128, 100);
+    // One pixel flipped to black.
+    let mut outlier = base.clone();
+    outlier[..3].fill(0);
+    // Every pixel slightly lighter.
+    let borderline = grey(140, 100);
+
+    let options = Options::default();
+    let one = delta_stats(&base, &outlier, 10, 10, &options).unwrap();
+    assert_eq!(one.differing, 1);
+    assert!(one.max > 0.4);
+    assert_eq!((one.mean, one.p95), (one.max, one.max));
+
+    let many = delta_stats(&base, &borderline, 10, 10, &options).unwrap();
+    assert_eq!(many.differing, 100);
+    assert!(many.max < 0.2);
+    assert!((many.mean - many.max).abs() < 1e-9 && many.p95 == many.max);
+
+    assert_eq!(delta_stats(&base, &base, 10, 10, &options).unwrap(), DeltaStats::default());
+}
+
+#[test]
+fn test_delta_stats_percentile_and_ignore() {
+    // 20 pixels: 19 slightly off, one far off; the p95 (19th of 20) is a small delta.
+    let base = grey(128, 20);
+    let mut changed = grey(136, 20);
+    changed[..3].fill(255);
+    let stats = delta_stats(&base, &changed, 20, 1, &Options::default()).unwrap();
+    assert_eq!(stats.differing, 20);
+    assert!(stats.p95 < stats.mean && stats.mean < stats.max);
+
+    let ignoring = Options { ignore_regions: vec![Rect::new(0, 0, 1, 1)], ..Default::default() };
+    let stats = delta_stats(&base, &changed, 20, 1, &ignoring).unwrap();
+    assert_eq!(stats.differing, 19);
+    assert_eq!(stats.max, stats.p95);
+
+    assert!(matches!(
+        delta_stats(&base, &changed[4..], 20, 1, &Options::default()),
+        Err(PixelmatchError::BufferLengthMismatch { .. } | PixelmatchError::ImageSizeMismatch { .. })
+    ));
+}
+
+#[test]
+fn test_delta_histogram_suggests_thresholds() {
+    use pixelmatch::deltas::delta_histogram;
+    // 90 identical pixels, 9 slightly lighter, one black.
+    let base = grey(128, 100);
+    let mut changed = base.clone();
+    changed[..9 * 4].copy_from_slice(&grey(136, 9));
+    changed[99 * 4..][..3].fill(0);
+
+    let histogram = delta_histogram(&base, &changed, 10, 10, &Options::default(), 64).unwrap();
+    assert_eq!(histogram.buckets.len(), 64);
+    assert_eq!(histogram.total(), 100);
+    assert_eq!(histogram.buckets[0], 90);
+    let stats = delta_stats(&base, &changed, 10, 10, &Options::default()).unwrap();
+    let last = histogram.buckets.iter().rposition(|&c| c > 0).unwrap();
+    assert_eq!(last, (stats.max * 64.0) as usize);
+
+    // 90% match at any threshold; 99% need the small deltas; 100% the black pixel too.
+    assert_eq!(histogram.threshold_for(0.9), histogram.bucket_width());
+    let small = histogram.threshold_for(0.99);
+    assert!(small > histogram.bucket_width() && small < stats.max);
+    assert!(histogram.threshold_for(1.0) >= stats.max);
+
+    let ignoring = Options { ignore_regions: vec![Rect::new(0, 0, 10, 1)], ..Default::default() };
+    assert_eq!(delta_histogram(&base, &changed, 10, 10, &ignoring, 8).unwrap().total(), 90);
+    assert_eq!(delta_histogram(&base, &base, 10, 10, &Options::default(), 0).unwrap().buckets, [100]);
+}
@@ -0,0 +1,56 @@
+use pixelmatch::depth::{compare_depth, DepthOptions};
+use pixelmatch::PixelmatchError;
+
+#[test]
+fn test_compare_depth_tolerances() {
+    let before = [0.1f32, 0.5, 0.9, 0.99, 0.25, 0.75];
+    let after = [0.1f32, 0.5005, 0.9, 0.995, 0.35, 0.75];
+    let exact = DepthOptions { absolute: 0.0, ..Default::default() };
+    let diff = compare_depth(&before, &after, 3, 2, &exact).unwrap();
+    assert_eq!(diff.diff_count, 3);
+    assert!((diff.max_error - 0.1).abs() < 1e-6);
+    assert!((diff.errors[1] - 0.0005).abs() < 1e-6 && diff.errors[0] == 0.0);
+
+    let absolute = DepthOptions { absolute: 0.001, ..Default::default() };
+    assert_eq!(compare_depth(&before, &after, 3, 2, &absolute).unwrap().diff_count, 2);
+    // 1% of the depth covers the far change at 0.99 but not the jump at 0.25.
+    let relative = DepthOptions { relative: 0.01, ..absolute };
+    assert_eq!(compare_depth(&before, &after, 3, 2, &relative).unwrap().diff_count, 1);
+
+    let heatmap = diff.heatmap();
+    assert_eq!(heatmap.len(), 6 * 4);
+    assert_ne!(heatmap[4 * 4..][..4], heatmap[..4]);
+}
+
+#[test]
+fn test_compare_depth_reversed_z() {
+    // Reversed Z puts far surfaces near 0, so relative tolerance only covers them once the
+    // values are converted back to conventional depth.
+    let before = [0.01f32, 0.9];
+    let after = [0.015f32, 0.9];
+    let relative = DepthOptions { absolute: 0.0, relative: 0.01, reversed_z: false };
+    assert_eq!(compare_depth(&before, &after, 2, 1, &relative).unwrap().diff_count, 1);
+    let reversed = DepthOptions { reversed_z: true, ..relative };
+    let diff = compare_depth(&before, &after, 2, 1, &reversed).unwrap();
+    assert_eq!(diff.diff_count, 0);
+    assert!((diff.errors[0] - 0.005).abs() < 1e-6);
+}
+
+#[test]
+fn test_compare_depth_special_values_and_errors() {
+    let before = [f32::NAN, f32::INFINITY, 1.0, f32::NAN];
+    let after = [f32::NAN, f32::INFINITY, f32::INFINITY, 1.0];
+    let tolerant = DepthOptions { absolute: 1.0, relative: 1.0, ..Default::default() };
+    let diff = compare_depth(&before, &after, 2, 2, &tolerant).unwrap();
+    assert_eq!(diff.diff_count, 2);
+    assert_eq!(diff.max_error, 0.0);
+
+    let err = compare_depth(&before, &after[..2], 2, 2, &tolerant).unwrap_err();
+    assert!(matches!(err, PixelmatchError::ImageSizeMismatch { .. }));
+    let err = compare_depth(&before, &after, 3, 2, &tolerant).unwrap_err();
+    assert!(matches!(err, PixelmatchError::BufferLengthMismatch { expected: 6, actual: 4 }));
+    for options in [DepthOptions { absolute: -1.0, ..tolerant }, DepthOptions { relative: f32::NAN, ..tolerant }] {
+        let err = compare_depth(&before, &after, 2, 2, &options).unwrap_err();
+        assert!(matches!(err, PixelmatchError::InvalidTolerance(_)));
+    }
+}
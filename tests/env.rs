@@ -0,0 +1,41 @@
+use pixelmatch::edges::EdgeDetector;
+use pixelmatch::{Options, PixelmatchError};
+
+// The environment is process-wide, so everything runs in one test.
+#[test]
+fn test_options_apply_env() {
+    let mut options = Options::default();
+    options.apply_env().unwrap();
+    assert_eq!(options.to_json(), Options::default().to_json());
+
+    std::env::set_var("PIXELMATCH_THRESHOLD", "0.25");
+    std::env::set_var("PIXELMATCH_DETECT_ANTI_ALIASING", "false");
+    std::env::set_var("PIXELMATCH_AA_COLOR", "[0, 0, 255]");
+    std::env::set_var("PIXELMATCH_EDGES", "sobel:0.2");
+    std::env::set_var("PIXELMATCH_ALPHA", "");
+    options.apply_env().unwrap();
+    assert_eq!(options.threshold, 0.25);
+    assert!(!options.detect_anti_aliasing);
+    assert_eq!(options.aa_color, [0, 0, 255]);
+    assert_eq!(options.edges, Some(EdgeDetector::Sobel(0.2)));
+    assert_eq!(options.alpha, Options::default().alpha);
+
+    std::env::set_var("PIXELMATCH_SHIFT_TOLERANCE", "-1");
+    let before = options.to_json();
+    let err = options.apply_env().unwrap_err();
+    assert!(matches!(&err, PixelmatchError::InvalidEnv { name, .. } if name == "PIXELMATCH_SHIFT_TOLERANCE"));
+    assert_eq!(options.to_json(), before);
+
+    for name in ["THRESHOLD", "DETECT_ANTI_ALIASING", "AA_COLOR", "EDGES", "ALPHA", "SHIFT_TOLERANCE"] {
+        std::env::remove_var(format!("PIXELMATCH_{name}"));
+    }
+
+    // Fields without an override are left alone, even where JSON cannot hold their value.
+    let mut options = Options { threshold: f64::NAN, pre_blur: Some(f32::INFINITY), ..Options::default() };
+    std::env::set_var("PIXELMATCH_ALPHA", "0.5");
+    options.apply_env().unwrap();
+    assert!(options.threshold.is_nan());
+    assert_eq!(options.pre_blur, Some(f32::INFINITY));
+    assert_eq!(options.alpha, 0.5);
+    std::env::remove_var("PIXELMATCH_ALPHA");
+}
@@ -0,0 +1,61 @@
+use pixelmatch::flaky::FlakyMask;
+use pixelmatch::{pixelmatch, Options};
+
+fn solid(width: u32, height: u32, rgba: [u8; 4]) -> Vec<u8> {
+    rgba.repeat((width * height) as usize)
+}
+
+fn set(img: &mut [u8], width: u32, x: u32, y: u32, rgba: [u8; 4]) {
+    let i = ((y * width + x) * 4) as usize;
+    img[i..i + 4].copy_from_slice(&rgba);
+}
+
+#[test]
+fn test_flaky_mask_detect() {
+    let (w, h) = (10, 8);
+    let first = solid(w, h, [255; 4]);
+    let mut second = first.clone();
+    let mut third = first.clone();
+    set(&mut second, w, 2, 2, [0, 0, 0, 255]);
+    set(&mut third, w, 7, 5, [0, 0, 0, 255]);
+    let options = Options { detect_anti_aliasing: false, ..Default::default() };
+
+    let exact = FlakyMask::detect(&[&first, &second, &third], w, h, &options, 0).unwrap();
+    assert_eq!(exact.count(), 2);
+    assert!(exact.mask[(2 * w + 2) as usize] && exact.mask[(5 * w + 7) as usize]);
+    assert_eq!(exact.regions().len(), 2);
+
+    // Padding grows each pixel into a 3x3 block, clipped at the border.
+    let padded = FlakyMask::detect(&[&first, &second, &third], w, h, &options, 1).unwrap();
+    assert_eq!(padded.count(), 18);
+    let mut corner = first.clone();
+    set(&mut corner, w, 0, 0, [0, 0, 0, 255]);
+    assert_eq!(FlakyMask::detect(&[&first, &corner], w, h, &options, 2).unwrap().count(), 9);
+
+    assert_eq!(FlakyMask::detect(&[&first], w, h, &options, 3).unwrap().count(), 0);
+    assert!(FlakyMask::detect(&[&first, &first[..4]], w, h, &options, 0).is_err());
+
+    // The mask makes a later comparison of the unstable captures pass.
+    let ignoring = Options { ignore_mask: Some(padded.to_ignore_mask()), ..options.clone() };
+    assert_eq!(pixelmatch(&second, &third, None, w, h, &options).unwrap().diff_count, 2);
+    assert_eq!(pixelmatch(&second, &third, None, w, h, &ignoring).unwrap().diff_count, 0);
+}
+
+#[test]
+fn test_flaky_mask_save_load() {
+    let dir = std::env::temp_dir().join(format!("pixelmatch-flaky-save-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    let (w, h) = (4, 3);
+    let first = solid(w, h, [255; 4]);
+    let mut second = first.clone();
+    set(&mut second, w, 1, 1, [0, 0, 0, 255]);
+    let mask = FlakyMask::detect(&[&first, &second], w, h, &Options::default(), 0).unwrap();
+
+    let path = dir.join("mask.png");
+    mask.save(&path).unwrap();
+    assert_eq!(FlakyMask::load(&path).unwrap(), mask);
+    let rgba = Options { ignore_mask: Some(mask.to_rgba()), ..Default::default() };
+    assert_eq!(pixelmatch(&first, &second, None, w, h, &rgba).unwrap().diff_count, 0);
+    let _ = std::fs::remove_dir_all(&dir);
+}
@@ -0,0 +1,48 @@
+use pixelmatch::flip::{flip, flip_map, pixels_per_degree, DEFAULT_PPD};
+use pixelmatch::PixelmatchError;
+
+/// An opaque RGBA image of `len` pixels of one colour.
+fn flat(len: usize, rgb: [u8; 3]) -> Vec<u8> {
+    (0..len).flat_map(|_| [rgb[0], rgb[1], rgb[2], 255]).collect()
+}
+
+#[test]
+fn test_flip_colour_differences() {
+    let grey = flat(32 * 32, [128; 3]);
+    assert_eq!(flip(&grey, &grey, 32, 32, DEFAULT_PPD).unwrap(), 0.0);
+    let slight = flip(&grey, &flat(32 * 32, [130; 3]), 32, 32, DEFAULT_PPD).unwrap();
+    let clear = flip(&grey, &flat(32 * 32, [160; 3]), 32, 32, DEFAULT_PPD).unwrap();
+    let black_white = flip(&flat(32 * 32, [0; 3]), &flat(32 * 32, [255; 3]), 32, 32, DEFAULT_PPD).unwrap();
+    assert!(0.0 < slight && slight < 0.1, "{slight}");
+    assert!(slight < clear && clear < black_white && black_white <= 1.0, "{clear} {black_white}");
+}
+
+#[test]
+fn test_flip_viewing_distance() {
+    // A one-pixel checkerboard blends into its mean grey from far away, but not up close.
+    let checker: Vec<u8> =
+        (0..64 * 64).flat_map(|i| if (i % 64 + i / 64) % 2 == 0 { [0, 0, 0, 255] } else { [255; 4] }).collect();
+    let mean = flat(64 * 64, [188; 3]);
+    let far = flip(&checker, &mean, 64, 64, DEFAULT_PPD).unwrap();
+    let near = flip(&checker, &mean, 64, 64, 10.0).unwrap();
+    assert!(far < 0.1, "{far}");
+    assert!(near > 0.5, "{near}");
+    assert!((pixels_per_degree(0.7, 0.7, 3840) - DEFAULT_PPD).abs() < 1e-3);
+}
+
+#[test]
+fn test_flip_map() {
+    let grey = flat(32 * 32, [128; 3]);
+    let mut dot = grey.clone();
+    dot[(16 * 32 + 16) * 4..(16 * 32 + 16) * 4 + 3].fill(0);
+    let map = flip_map(&grey, &dot, 32, 32, DEFAULT_PPD).unwrap();
+    assert_eq!(map.len(), 32 * 32);
+    assert_eq!(map[16 * 32 + 16], map.iter().copied().fold(0.0, f32::max));
+    assert!(map[16 * 32 + 16] > 0.0);
+    assert_eq!(map[0], 0.0);
+    // An invalid viewing distance falls back to the default.
+    assert_eq!(flip_map(&grey, &dot, 32, 32, f64::NAN).unwrap(), map);
+
+    let mismatch = flip_map(&grey, &grey[..64], 32, 32, DEFAULT_PPD);
+    assert!(matches!(mismatch, Err(PixelmatchError::ImageSizeMismatch { .. })));
+}
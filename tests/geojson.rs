@@ -0,0 +1,43 @@
+use pixelmatch::geojson::{regions_to_geojson, GeoBounds};
+use pixelmatch::regions::DiffRegion;
+
+fn assert_close(actual: f64, expected: f64) {
+    assert!((actual - expected).abs() < 1e-9, "expected {expected}, got {actual}");
+}
+
+#[test]
+fn test_tile_projection_corners() {
+    let bounds = GeoBounds::Tile { z: 0, x: 0, y: 0 };
+    let (lon, lat) = bounds.project(0.0, 0.0, 256, 256);
+    assert_close(lon, -180.0);
+    assert_close(lat, 85.0511287798066);
+    let (lon, lat) = bounds.project(128.0, 128.0, 256, 256);
+    assert_close(lon, 0.0);
+    assert_close(lat, 0.0);
+}
+
+#[test]
+fn test_bbox_projection_matches_tile() {
+    let tile = GeoBounds::Tile { z: 1, x: 1, y: 0 };
+    let bbox = GeoBounds::BBox { west: 0.0, south: 0.0, east: 180.0, north: 85.0511287798066 };
+    for (px, py) in [(0.0, 0.0), (64.0, 32.0), (256.0, 256.0)] {
+        let (a_lon, a_lat) = tile.project(px, py, 256, 256);
+        let (b_lon, b_lat) = bbox.project(px, py, 256, 256);
+        assert_close(a_lon, b_lon);
+        assert_close(a_lat, b_lat);
+    }
+}
+
+#[test]
+fn test_regions_to_geojson() {
+    let regions = [DiffRegion { x: 0, y: 0, width: 128, height: 128, pixel_count: 42 }];
+    let json = regions_to_geojson(&regions, 256, 256, &GeoBounds::Tile { z: 0, x: 0, y: 0 });
+    assert!(json.starts_with(r#"{"type":"FeatureCollection","features":[{"type":"Feature""#));
+    assert!(json.contains(r#""type":"Polygon""#));
+    assert!(json.contains("[-180,0],[0,0],[0,85.0511287798066"), "{json}");
+    assert!(json.contains(r#""properties":{"x":0,"y":0,"width":128,"height":128,"pixel_count":42}"#));
+    assert_eq!(
+        regions_to_geojson(&[], 256, 256, &GeoBounds::Tile { z: 0, x: 0, y: 0 }),
+        r#"{"type":"FeatureCollection","features":[]}"#
+    );
+}
@@ -0,0 +1,40 @@
+use pixelmatch::gmsd::{gms_map, gmsd};
+
+const WIDTH: u32 = 32;
+const HEIGHT: u32 = 24;
+
+/// A white chart with a black horizontal line at row `line`, broken between `gap` columns,
+/// with every channel lowered by `darken`.
+fn chart(line: u32, gap: std::ops::Range<u32>, darken: u8) -> Vec<u8> {
+    (0..WIDTH * HEIGHT)
+        .flat_map(|i| {
+            let (x, y) = (i % WIDTH, i / WIDTH);
+            let v = if y == line && !gap.contains(&x) { 0 } else { 255 - darken };
+            [v, v, v, 255]
+        })
+        .collect()
+}
+
+#[test]
+fn test_gmsd_ignores_brightness_and_catches_structure() {
+    let original = chart(10, 0..0, 0);
+    assert_eq!(gmsd(&original, &original, WIDTH, HEIGHT).unwrap(), 0.0);
+    // Lowering the background by 40 levels weakens the line's edges only slightly.
+    let dimmed = gmsd(&original, &chart(10, 0..0, 40), WIDTH, HEIGHT).unwrap();
+    let broken = gmsd(&original, &chart(10, 12..20, 0), WIDTH, HEIGHT).unwrap();
+    let moved = gmsd(&original, &chart(13, 0..0, 0), WIDTH, HEIGHT).unwrap();
+    assert!(dimmed < 0.01, "{dimmed}");
+    assert!(broken > 0.05 && moved > broken, "{broken} {moved}");
+    assert!(gmsd(&original, &original[4..], WIDTH, HEIGHT).is_err());
+}
+
+#[test]
+fn test_gms_map_marks_changed_edges() {
+    let map = gms_map(&chart(10, 0..0, 0), &chart(10, 12..20, 0), WIDTH, HEIGHT).unwrap();
+    assert_eq!(map.len(), (WIDTH * HEIGHT) as usize);
+    let at = |x: u32, y: u32| map[(y * WIDTH + x) as usize];
+    // The edges above and below the missing stretch of line.
+    assert!(at(16, 9) < 0.5 && at(16, 11) < 0.5, "{} {}", at(16, 9), at(16, 11));
+    assert_eq!(at(4, 10), 1.0);
+    assert_eq!(at(16, 20), 1.0);
+}
@@ -0,0 +1,35 @@
+use pixelmatch::histogram::{chi_square, compare, emd, intersection, Histogram};
+
+/// An RGBA image of `len` pixels, the first `dark` of them black and the rest `rgb`.
+fn image(len: usize, dark: usize, rgb: [u8; 3]) -> Vec<u8> {
+    (0..len).flat_map(|i| if i < dark { [0, 0, 0, 255] } else { [rgb[0], rgb[1], rgb[2], 255] }).collect()
+}
+
+#[test]
+fn test_histogram_distances() {
+    let white = Histogram::luma(&image(100, 0, [255; 3]));
+    let black = Histogram::luma(&image(100, 100, [255; 3]));
+    let half = Histogram::luma(&image(100, 50, [255; 3]));
+    assert_eq!(white.total(), 100);
+    assert_eq!((white.bins[255], half.bins[0], half.bins[255]), (100, 50, 50));
+
+    assert_eq!((chi_square(&white, &white), intersection(&white, &white), emd(&white, &white)), (0.0, 1.0, 0.0));
+    assert_eq!((chi_square(&white, &black), intersection(&white, &black), emd(&white, &black)), (1.0, 0.0, 1.0));
+    assert!((intersection(&white, &half) - 0.5).abs() < 1e-12);
+    assert!((emd(&white, &half) - 0.5).abs() < 1e-12);
+    // A small brightness shift moves little mass a short way.
+    let dimmed = Histogram::luma(&image(100, 0, [250; 3]));
+    assert!(emd(&white, &dimmed) < 0.05);
+    assert_eq!(chi_square(&white, &dimmed), 1.0, "chi-square compares bins, not distances");
+}
+
+#[test]
+fn test_histogram_compare_detects_colour_cast() {
+    // A warm cast on a small image, compared with a larger neutral one.
+    let neutral = image(64, 16, [200, 200, 200]);
+    let warm = image(16, 4, [230, 200, 170]);
+    let result = compare(&neutral, &warm);
+    assert_eq!(result.green.emd, 0.0);
+    assert!(result.red.emd > 0.05 && result.blue.emd > 0.05);
+    assert!(result.luma.emd < result.red.emd);
+}
@@ -1,33 +1,8 @@
-use std::path::PathBuf;
-
 use pixelmatch::{pixelmatch, Options};
 
-fn fixtures_dir() -> PathBuf {
-    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("test").join("fixtures")
-}
-
-fn read_image(name: &str) -> (Vec<u8>, u32, u32) {
-    let path = fixtures_dir().join(format!("{name}.png"));
-    let file = std::fs::File::open(&path).unwrap_or_else(|e| panic!("Failed to open {}: {e}", path.display()));
-    let mut decoder = png::Decoder::new(file);
-    // Expand all colour types to RGBA, matching pngjs behaviour
-    decoder.set_transformations(png::Transformations::EXPAND | png::Transformations::ALPHA);
-    let mut reader = decoder.read_info().unwrap();
-    let mut buf = vec![0u8; reader.output_buffer_size()];
-    let info = reader.next_frame(&mut buf).unwrap();
-    buf.truncate(info.buffer_size());
+mod common;
 
-    // Verify we got RGBA output
-    assert_eq!(
-        info.color_type,
-        png::ColorType::Rgba,
-        "Expected RGBA output for {name}.png, got {:?}",
-        info.color_type
-    );
-    assert_eq!(info.bit_depth, png::BitDepth::Eight, "Expected 8-bit depth for {name}.png");
-
-    (buf, info.width, info.height)
-}
+use common::read_image;
 
 fn diff_test(img1_name: &str, img2_name: &str, diff_name: &str, options: Options, expected_mismatch: u32) {
     let (img1, width, height) = read_image(img1_name);
@@ -305,3 +280,1054 @@ fn test_semi_transparent_color_delta() {
     // Allow no tolerance — must be bit-exact with JS
     assert_eq!(delta, expected_signed, "FMA canary: semi-transparent colorDelta must match JS exactly");
 }
+
+// --- Diff mask and region tests ---
+
+#[test]
+fn test_diff_mask_matches_diff_count() {
+    let (img1, width, height) = read_image("1a");
+    let (img2, _, _) = read_image("1b");
+    let options = Options { threshold: 0.05, ..Default::default() };
+    let result = pixelmatch(&img1, &img2, None, width, height, &options).unwrap();
+    let mask = pixelmatch::diff_mask(&img1, &img2, width, height, &options).unwrap();
+    assert_eq!(mask.len(), (width * height) as usize);
+    assert_eq!(mask.iter().filter(|&&m| m).count() as u32, result.diff_count);
+}
+
+#[test]
+fn test_find_regions_groups_diagonal_neighbours() {
+    use pixelmatch::regions::{find_regions, DiffRegion};
+    #[rustfmt::skip]
+    let mask = [
+        true,  false, false, false,
+        false, true,  false, false,
+        false, false, false, true,
+    ];
+    let regions = find_regions(&mask, 4, 3);
+    assert_eq!(
+        regions,
+        vec![
+            DiffRegion { x: 0, y: 0, width: 2, height: 2, pixel_count: 2 },
+            DiffRegion { x: 3, y: 2, width: 1, height: 1, pixel_count: 1 },
+        ]
+    );
+}
+
+#[test]
+fn test_pixelmatch_regions() {
+    use pixelmatch::regions::{pixelmatch_regions, DiffRegion};
+    let (w, h) = (16u32, 8u32);
+    let base = vec![230u8; (w * h * 4) as usize];
+    let mut changed = base.clone();
+    let mut set = |x: usize, y: usize| changed[(y * w as usize + x) * 4..][..3].fill(0);
+    // A 3x2 block and a lone speck.
+    for (x, y) in [(2, 2), (3, 2), (4, 2), (2, 3), (3, 3), (4, 3), (12, 6)] {
+        set(x, y);
+    }
+    let options = Options { detect_anti_aliasing: false, ..Default::default() };
+    let result = pixelmatch(&base, &changed, None, w, h, &options).unwrap();
+
+    let regions = pixelmatch_regions(&base, &changed, w, h, &options, 0).unwrap();
+    assert_eq!(
+        regions,
+        vec![
+            DiffRegion { x: 2, y: 2, width: 3, height: 2, pixel_count: 6 },
+            DiffRegion { x: 12, y: 6, width: 1, height: 1, pixel_count: 1 },
+        ]
+    );
+    assert_eq!(regions.iter().map(|r| r.pixel_count).sum::<u32>(), result.diff_count);
+    assert_eq!(pixelmatch_regions(&base, &changed, w, h, &options, 2).unwrap(), regions[..1]);
+    assert!(pixelmatch_regions(&base, &base, w, h, &options, 0).unwrap().is_empty());
+    assert!(pixelmatch_regions(&base, &changed[4..], w, h, &options, 0).is_err());
+}
+
+// --- Resize tests ---
+
+#[test]
+fn test_resize_box_averages_blocks() {
+    use pixelmatch::resize::resize_box;
+    #[rustfmt::skip]
+    let img = [
+        0, 0, 0, 255,    255, 255, 255, 255,
+        255, 255, 255, 255, 0, 0, 0, 255,
+    ];
+    assert_eq!(resize_box(&img, 2, 2, 1, 1), [128, 128, 128, 255]);
+    // Transparent pixels do not darken the average
+    let img = [255, 0, 0, 255, 0, 0, 0, 0];
+    assert_eq!(resize_box(&img, 2, 1, 1, 1), [255, 0, 0, 128]);
+}
+
+#[test]
+fn test_thumbnail_preserves_aspect_ratio() {
+    use pixelmatch::resize::{fit_within, thumbnail};
+    assert_eq!(fit_within(1000, 500, 100), (100, 50));
+    assert_eq!(fit_within(50, 20, 100), (50, 20));
+    let (img, width, height) = read_image("4a");
+    let (thumb, tw, th) = thumbnail(&img, width, height, 32);
+    assert!(tw <= 32 && th <= 32);
+    assert_eq!(thumb.len(), (tw * th * 4) as usize);
+}
+
+// --- Ignore region tests ---
+
+#[test]
+fn test_ignore_regions_skip_pixels() {
+    use pixelmatch::Rect;
+    let (img1, width, height) = read_image("1a");
+    let (img2, _, _) = read_image("1b");
+    let options = Options { threshold: 0.05, ..Default::default() };
+    let mask = pixelmatch::diff_mask(&img1, &img2, width, height, &options).unwrap();
+    let full = pixelmatch(&img1, &img2, None, width, height, &options).unwrap();
+
+    // Ignore the left half of the image
+    let half = Rect::new(0, 0, width / 2, height);
+    let ignored = Options { ignore_regions: vec![half], ..options.clone() };
+    let left = (0..mask.len()).filter(|&i| mask[i] && half.contains(i as u32 % width, i as u32 / width)).count();
+    assert!(left > 0, "fixture should have diffs in the left half");
+
+    let mut diff = vec![0u8; img1.len()];
+    let result = pixelmatch(&img1, &img2, Some(&mut diff), width, height, &ignored).unwrap();
+    assert_eq!(result.diff_count, full.diff_count - left as u32);
+    let no_output = pixelmatch(&img1, &img2, None, width, height, &ignored).unwrap();
+    assert_eq!(no_output, result);
+
+    // Ignored pixels are drawn as the grayscale underlay
+    let (expected, _, _) = read_image("1diff");
+    for i in 0..(width * height) as usize {
+        let (x, y) = (i as u32 % width, i as u32 / width);
+        if half.contains(x, y) && mask[i] {
+            assert_ne!(diff[i * 4..i * 4 + 4], expected[i * 4..i * 4 + 4]);
+            assert_eq!(diff[i * 4], diff[i * 4 + 1], "ignored pixel should be gray");
+        }
+    }
+}
+
+#[test]
+fn test_ignore_region_clipped_to_image() {
+    use pixelmatch::Rect;
+    let img1 = [255u8, 0, 0, 255, 255, 0, 0, 255];
+    let img2 = [0u8, 0, 255, 255, 0, 0, 255, 255];
+    let options = Options { ignore_regions: vec![Rect::new(1, 0, 100, 100)], ..Default::default() };
+    let result = pixelmatch(&img1, &img2, None, 2, 1, &options).unwrap();
+    assert_eq!(result.diff_count, 1);
+}
+
+#[test]
+fn test_ignore_mask_single_channel_and_alpha() {
+    let img1 = [255u8, 0, 0, 255, 255, 0, 0, 255, 255, 0, 0, 255];
+    let img2 = [0u8, 0, 255, 255, 0, 0, 255, 255, 0, 0, 255, 255];
+    let run = |mask: Vec<u8>| {
+        let options = Options { ignore_mask: Some(mask), ..Default::default() };
+        pixelmatch(&img1, &img2, None, 3, 1, &options)
+    };
+    assert_eq!(run(vec![0, 1, 0]).unwrap().diff_count, 2);
+    assert_eq!(run(vec![9, 9, 9, 0, 0, 0, 0, 255, 0, 0, 0, 0]).unwrap().diff_count, 2);
+
+    let err = run(vec![0, 1]).unwrap_err();
+    assert!(matches!(err, pixelmatch::PixelmatchError::IgnoreMaskSizeMismatch { pixels: 3, actual: 2 }));
+}
+
+#[test]
+fn test_ignore_hatch() {
+    use pixelmatch::stream::StreamMatcher;
+    use pixelmatch::Rect;
+    let (w, h) = (8u32, 6u32);
+    let img1 = [255u8, 255, 255, 255].repeat((w * h) as usize);
+    let img2 = [0u8, 0, 0, 255].repeat((w * h) as usize);
+    let region = Rect::new(2, 1, 4, 3);
+    let options = Options { ignore_regions: vec![region], ignore_hatch: Some([0, 0, 255]), ..Default::default() };
+
+    for (name, other) in [("changed", &img2), ("identical", &img1)] {
+        let mut diff = vec![0u8; img1.len()];
+        pixelmatch(&img1, other, Some(&mut diff), w, h, &options).unwrap();
+        for i in 0..(w * h) as usize {
+            let (x, y) = (i as u32 % w, i as u32 / w);
+            let px = &diff[i * 4..i * 4 + 4];
+            if region.contains(x, y) && (x + y).is_multiple_of(4) {
+                assert_eq!(px, [0, 0, 255, 255], "{name}: hatch at {x},{y}");
+            } else {
+                assert_ne!(px, [0, 0, 255, 255], "{name}: no hatch at {x},{y}");
+            }
+        }
+
+        // Streaming draws the same hatch regardless of band boundaries.
+        for band_rows in [1, 4] {
+            let band = band_rows * w as usize * 4;
+            let mut matcher = StreamMatcher::new(w, h, options.clone()).unwrap();
+            let mut streamed = Vec::new();
+            for (c1, c2) in img1.chunks(band).zip(other.chunks(band)) {
+                matcher.push(c1, c2, Some(&mut streamed)).unwrap();
+            }
+            assert_eq!(streamed, diff, "{name} with {band_rows}-row bands");
+        }
+    }
+}
+
+#[test]
+fn test_ignore_polygons() {
+    use pixelmatch::{Polygon, Rect};
+    let (img1, width, height) = read_image("1a");
+    let (img2, _, _) = read_image("1b");
+    let options = Options { threshold: 0.05, ..Default::default() };
+
+    // An axis-aligned polygon ignores exactly what the equivalent rect does.
+    let (w, h) = ((width / 2) as f64, height as f64);
+    let square = Polygon::new(vec![(0.0, 0.0), (w, 0.0), (w, h), (0.0, h)]);
+    let by_rect = Options { ignore_regions: vec![Rect::new(0, 0, width / 2, height)], ..options.clone() };
+    let by_polygon = Options { ignore_polygons: vec![square], ..options.clone() };
+    assert_eq!(
+        pixelmatch(&img1, &img2, None, width, height, &by_polygon).unwrap(),
+        pixelmatch(&img1, &img2, None, width, height, &by_rect).unwrap()
+    );
+
+    // A diamond (a rotated square) plus a triangle partly outside the image.
+    let (w, h) = (10u32, 10u32);
+    let white = [255u8, 255, 255, 255].repeat((w * h) as usize);
+    let black = [0u8, 0, 0, 255].repeat((w * h) as usize);
+    let polygons = vec![
+        Polygon::new(vec![(5.0, 0.0), (10.0, 5.0), (5.0, 10.0), (0.0, 5.0)]),
+        Polygon::new(vec![(-5.0, 8.0), (3.0, 8.0), (3.0, 20.0)]),
+    ];
+    let options = Options { ignore_polygons: polygons.clone(), ..Default::default() };
+    let mask = pixelmatch::diff_mask(&white, &black, w, h, &options).unwrap();
+    for (i, &differs) in mask.iter().enumerate() {
+        let (x, y) = (i as u32 % w, i as u32 / w);
+        assert_eq!(differs, !polygons.iter().any(|p| p.contains(x, y)), "pixel {x},{y}");
+    }
+    let counted = mask.iter().filter(|&&m| m).count() as u32;
+    assert!(counted > 0 && counted < w * h);
+    assert!(polygons[0].contains(5, 5) && !polygons[0].contains(0, 0) && !polygons[0].contains(9, 9));
+    assert!(polygons[1].contains(0, 9) && !polygons[1].contains(4, 9));
+    assert_eq!(pixelmatch(&white, &black, None, w, h, &options).unwrap().diff_count, counted);
+}
+
+#[test]
+fn test_include_only() {
+    use pixelmatch::{PixelmatchError, Polygon, Rect};
+    let (img1, width, height) = read_image("1a");
+    let (img2, _, _) = read_image("1b");
+    let options = Options { threshold: 0.05, ..Default::default() };
+    let mask = pixelmatch::diff_mask(&img1, &img2, width, height, &options).unwrap();
+
+    let half = Rect::new(0, 0, width / 2, height);
+    let inside = (0..mask.len()).filter(|&i| mask[i] && half.contains(i as u32 % width, i as u32 / width)).count();
+    let only = Options { ignore_regions: vec![half], include_only: true, ..options.clone() };
+    let result = pixelmatch(&img1, &img2, None, width, height, &only).unwrap();
+    assert_eq!(result.diff_count, inside as u32);
+    let ignored = Options { include_only: false, ..only.clone() };
+    let rest = pixelmatch(&img1, &img2, None, width, height, &ignored).unwrap();
+    assert_eq!(result.diff_count + rest.diff_count, mask.iter().filter(|&&m| m).count() as u32);
+
+    // Pixels outside the regions are drawn as the underlay.
+    let mut diff = vec![0u8; img1.len()];
+    pixelmatch(&img1, &img2, Some(&mut diff), width, height, &only).unwrap();
+    for i in 0..(width * height) as usize {
+        if !half.contains(i as u32 % width, i as u32 / width) {
+            assert_eq!(diff[i * 4], diff[i * 4 + 1], "pixel {i} should be gray");
+        }
+    }
+
+    // Regions, polygons and masks combine before inverting.
+    let (w, h) = (4u32, 1u32);
+    let white = [255u8; 16];
+    let black = [0u8, 0, 0, 255].repeat(4);
+    let combined = Options {
+        ignore_regions: vec![Rect::new(0, 0, 1, 1)],
+        ignore_polygons: vec![Polygon::new(vec![(1.0, 0.0), (2.0, 0.0), (2.0, 1.0), (1.0, 1.0)])],
+        ignore_mask: Some(vec![0, 0, 1, 0]),
+        include_only: true,
+        ..Default::default()
+    };
+    assert_eq!(pixelmatch(&white, &black, None, w, h, &combined).unwrap().diff_count, 3);
+
+    let nothing = Options { include_only: true, ..Default::default() };
+    assert!(matches!(
+        pixelmatch(&white, &black, None, w, h, &nothing),
+        Err(PixelmatchError::ConflictingOptions(_))
+    ));
+}
+
+#[test]
+fn test_shift_tolerance() {
+    use pixelmatch::stream::StreamMatcher;
+    // Thin vertical strokes, like glyphs, moved one pixel to the right.
+    let (w, h) = (24u32, 12u32);
+    let strokes = |offset: u32, extra: Option<(u32, u32)>| {
+        let mut img = [255u8; 4].repeat((w * h) as usize);
+        for y in 2..10 {
+            for x in [3, 4, 10, 16, 17] {
+                let i = ((y * w + x + offset) * 4) as usize;
+                img[i..i + 3].fill(0);
+            }
+        }
+        if let Some((x, y)) = extra {
+            let i = ((y * w + x) * 4) as usize;
+            img[i..i + 3].fill(0);
+        }
+        img
+    };
+    let img1 = strokes(0, None);
+    let img2 = strokes(1, None);
+    let options = Options { detect_anti_aliasing: false, ..Default::default() };
+    let strict = pixelmatch(&img1, &img2, None, w, h, &options).unwrap();
+    assert!(strict.diff_count >= 40, "{}", strict.diff_count);
+
+    let tolerant = Options { shift_tolerance: 1, ..options.clone() };
+    assert_eq!(pixelmatch(&img1, &img2, None, w, h, &tolerant).unwrap().diff_count, 0);
+    let mut diff = vec![0u8; img1.len()];
+    assert_eq!(pixelmatch(&img1, &img2, Some(&mut diff), w, h, &tolerant).unwrap().diff_count, 0);
+    assert!(diff.chunks_exact(4).all(|px| px[0] == px[1] && px[1] == px[2]), "shifted pixels are drawn gray");
+
+    // A new mark far from any stroke still counts.
+    let changed = strokes(1, Some((22, 0)));
+    assert_eq!(pixelmatch(&img1, &changed, None, w, h, &tolerant).unwrap().diff_count, 1);
+    let mask = pixelmatch::diff_mask(&img1, &changed, w, h, &tolerant).unwrap();
+    assert_eq!(mask.iter().filter(|&&m| m).count(), 1);
+    assert!(mask[22]);
+
+    // Streaming keeps enough rows for the neighbourhood.
+    let wide = Options { shift_tolerance: 3, ..options };
+    let whole = pixelmatch(&img1, &changed, None, w, h, &wide).unwrap();
+    let mut matcher = StreamMatcher::new(w, h, wide).unwrap();
+    for (c1, c2) in img1.chunks((w * 4) as usize).zip(changed.chunks((w * 4) as usize)) {
+        matcher.push(c1, c2, None).unwrap();
+    }
+    assert_eq!(matcher.finish().unwrap(), whole);
+}
+
+#[test]
+fn test_pixelmatch_rows() {
+    use pixelmatch::stream::pixelmatch_rows;
+    use pixelmatch::PixelmatchError;
+    let (img1, w, h) = read_image("1a");
+    let (img2, _, _) = read_image("1b");
+    let options = Options::default();
+    let row = (w * 4) as usize;
+    let mut expected_diff = vec![0u8; img1.len()];
+    let expected = pixelmatch(&img1, &img2, Some(&mut expected_diff), w, h, &options).unwrap();
+
+    let mut diff = vec![0u8; img1.len()];
+    let result = pixelmatch_rows(img1.chunks(row), img2.chunks(row), Some(&mut diff), w, h, &options).unwrap();
+    assert_eq!(result, expected);
+    assert_eq!(diff, expected_diff);
+    assert!(pixelmatch_rows(img1.chunks(row), img1.chunks(row), None, w, h, &options).unwrap().identical);
+
+    let short = pixelmatch_rows(img1.chunks(row), img2.chunks(row).take(3), None, w, h, &options);
+    assert!(matches!(short, Err(PixelmatchError::IncompleteImage { received_rows: 3, .. })));
+    let extra = img2.chunks(row).chain([&img2[..row]]);
+    let long = pixelmatch_rows(img1.chunks(row), extra, None, w, h, &options);
+    assert!(matches!(long, Err(PixelmatchError::BandSizeMismatch { remaining_rows: 0, .. })));
+    let doubled = pixelmatch_rows(img1.chunks(row * 2), img2.chunks(row * 2), None, w, h, &options);
+    assert!(matches!(doubled, Err(PixelmatchError::BandSizeMismatch { .. })));
+}
+
+#[test]
+fn test_subpixel_tolerance() {
+    // Gray levels of one row, repeated vertically; a dark block with a hard edge at x = 5.
+    let (w, h) = (12u32, 6u32);
+    let image = |row: [u8; 12]| row.iter().flat_map(|&v| [v, v, v, 255]).collect::<Vec<u8>>().repeat(h as usize);
+    let edge = image([0, 0, 0, 0, 0, 255, 255, 255, 255, 255, 255, 255]);
+    // The same edge rendered half a pixel to the left, and a whole pixel to the left.
+    let half = image([0, 0, 0, 0, 128, 255, 255, 255, 255, 255, 255, 255]);
+    let whole = image([0, 0, 0, 0, 255, 255, 255, 255, 255, 255, 255, 255]);
+    let options = Options { detect_anti_aliasing: false, ..Default::default() };
+    let tolerant = Options { subpixel_tolerance: true, ..options.clone() };
+
+    assert_eq!(pixelmatch(&edge, &half, None, w, h, &options).unwrap().diff_count, h);
+    assert_eq!(pixelmatch(&edge, &half, None, w, h, &tolerant).unwrap().diff_count, 0);
+    assert_eq!(pixelmatch(&half, &edge, None, w, h, &tolerant).unwrap().diff_count, 0);
+    let mut diff = vec![0u8; edge.len()];
+    assert_eq!(pixelmatch(&edge, &half, Some(&mut diff), w, h, &tolerant).unwrap().diff_count, 0);
+    assert!(pixelmatch::diff_mask(&edge, &half, w, h, &tolerant).unwrap().iter().all(|&m| !m));
+
+    // Whole-pixel shifts are beyond the tolerance.
+    assert_eq!(pixelmatch(&edge, &whole, None, w, h, &tolerant).unwrap().diff_count, h);
+}
+
+#[test]
+fn test_similarity() {
+    use pixelmatch::{similarity, Rect};
+    let (w, h) = (4u32, 4u32);
+    let solid = |v: u8| [v, v, v, 255].repeat((w * h) as usize);
+    let options = Options::default();
+    let white = solid(255);
+
+    assert_eq!(similarity(&white, &white, w, h, &options).unwrap(), 1.0);
+    let black = similarity(&white, &solid(0), w, h, &options).unwrap();
+    assert!(black < 0.05, "{black}");
+    // Closer greys score higher, so candidates can be ranked.
+    let scores = [250, 200, 100].map(|v| similarity(&white, &solid(v), w, h, &options).unwrap());
+    assert!(scores[0] > scores[1] && scores[1] > scores[2] && scores[2] > black, "{scores:?}");
+    // A single changed pixel lowers the score by its share of the image.
+    let mut dot = white.clone();
+    dot[..3].fill(0);
+    let one = similarity(&white, &dot, w, h, &options).unwrap();
+    assert!((one - (1.0 - (1.0 - black) / 16.0)).abs() < 1e-9, "{one}");
+
+    let ignored = Options { ignore_regions: vec![Rect::new(0, 0, 1, 1)], ..Default::default() };
+    assert_eq!(similarity(&white, &dot, w, h, &ignored).unwrap(), 1.0);
+    assert!(similarity(&white, &dot[4..], w, h, &options).is_err());
+}
+
+#[test]
+fn test_diff_causes() {
+    use pixelmatch::causes::{diff_causes, CauseCounts, DiffCause};
+    let before = [[255, 0, 0, 255], [100, 100, 100, 255], [150, 150, 150, 255], [0, 0, 0, 255], [9, 9, 9, 255]];
+    let after = [[255, 0, 0, 128], [160, 160, 160, 255], [255, 100, 150, 255], [255, 0, 0, 255], [9, 9, 9, 255]];
+    let (img1, img2) = (before.concat(), after.concat());
+    let options = Options { detect_anti_aliasing: false, ..Default::default() };
+
+    let causes = diff_causes(&img1, &img2, 5, 1, &options).unwrap();
+    assert_eq!(
+        causes,
+        [
+            Some(DiffCause::AlphaOnly),
+            Some(DiffCause::LuminanceOnly),
+            Some(DiffCause::ChromaOnly),
+            Some(DiffCause::Structural),
+            None,
+        ]
+    );
+    let counts = CauseCounts::tally(&causes);
+    assert_eq!(counts.total(), pixelmatch(&img1, &img2, None, 5, 1, &options).unwrap().diff_count);
+    assert_eq!((counts.get(DiffCause::ChromaOnly), counts.structural), (1, 1));
+}
+
+#[test]
+fn test_channel_counts() {
+    use pixelmatch::causes::{channel_counts, ChannelCounts};
+    let before = [[255, 0, 0, 255], [100, 100, 100, 255], [150, 150, 150, 255], [200, 200, 200, 255]];
+    let after = [[255, 0, 0, 128], [160, 160, 160, 255], [255, 140, 150, 255], [200, 200, 200, 255]];
+    let (img1, img2) = (before.concat(), after.concat());
+    let options = Options { detect_anti_aliasing: false, ..Default::default() };
+    let counts = channel_counts(&img1, &img2, 4, 1, &options).unwrap();
+    // Alpha only; a grey shift in all three colours; mostly red, green too little to count.
+    assert_eq!(counts, ChannelCounts { red: 2, green: 1, blue: 1, alpha: 1 });
+}
+
+#[test]
+fn test_jpeg_tolerance() {
+    // Mid gray, with a dark stripe giving the top-left 8x8 block a sharp edge.
+    let (w, h) = (16u32, 16u32);
+    let set = |img: &mut Vec<u8>, x: usize, y: usize, v: u8| img[(y * w as usize + x) * 4..][..3].fill(v);
+    let mut base = [128, 128, 128, 255].repeat((w * h) as usize);
+    for y in 0..8 {
+        (0..4).for_each(|x| set(&mut base, x, y, 0));
+    }
+    // Mild changes as ringing inside the edge block, blocking on a block boundary, and in the
+    // middle of a flat block; and a strong change inside the edge block.
+    let mut candidate = base.clone();
+    set(&mut candidate, 5, 3, 158);
+    set(&mut candidate, 8, 12, 158);
+    set(&mut candidate, 11, 11, 158);
+    set(&mut candidate, 2, 2, 255);
+    let options = Options { threshold: 0.05, detect_anti_aliasing: false, ..Default::default() };
+    let tolerant = Options { jpeg_tolerance: Some(0.2), ..options.clone() };
+
+    assert_eq!(pixelmatch(&base, &candidate, None, w, h, &options).unwrap().diff_count, 4);
+    assert_eq!(pixelmatch(&base, &candidate, None, w, h, &tolerant).unwrap().diff_count, 2);
+    let mask = pixelmatch::diff_mask(&candidate, &base, w, h, &tolerant).unwrap();
+    assert!(mask[11 * w as usize + 11] && mask[2 * w as usize + 2]);
+    assert_eq!(mask.iter().filter(|&&m| m).count(), 2);
+
+    let invalid = Options { jpeg_tolerance: Some(1.5), ..options };
+    assert!(matches!(
+        pixelmatch(&base, &candidate, None, w, h, &invalid),
+        Err(pixelmatch::PixelmatchError::InvalidThreshold(_))
+    ));
+}
+
+#[test]
+fn test_scroll_compensation() {
+    use pixelmatch::scroll::{detect_offset, shift_rows};
+    let (w, h) = (40u32, 60u32);
+    // A "page" whose rows all differ; the candidate is scrolled down by 5 rows, so its content
+    // sits 5 rows higher and 5 new rows appear at the bottom.
+    let page = |y: usize, x: usize| ((x / 4 * 31 + y * 17) ^ (y * 7)) as u8;
+    let image = |scroll: usize| -> Vec<u8> {
+        (0..h as usize)
+            .flat_map(|y| (0..w as usize).flat_map(move |x| { let v = page(y + scroll, x); [v, v, v, 255] }))
+            .collect()
+    };
+    let (baseline, scrolled) = (image(0), image(5));
+    assert_eq!(detect_offset(&baseline, &scrolled, w, h, 10), -5);
+    assert_eq!(detect_offset(&scrolled, &baseline, w, h, 10), 5);
+    assert_eq!(detect_offset(&baseline, &baseline, w, h, 10), 0);
+    assert_eq!(detect_offset(&baseline, &scrolled, w, h, 3), 0, "offset beyond the search range");
+    let shifted = shift_rows(&scrolled, w, h, -5);
+    assert_eq!(shifted[5 * w as usize * 4..], baseline[5 * w as usize * 4..]);
+
+    let options = Options { detect_anti_aliasing: false, ..Default::default() };
+    let compensated = Options { scroll_compensation: 10, ..options.clone() };
+    let plain = pixelmatch(&baseline, &scrolled, None, w, h, &options).unwrap();
+    assert!(plain.diff_count > w * h / 2);
+    assert_eq!(plain.scroll_offset, 0);
+    let result = pixelmatch(&baseline, &scrolled, None, w, h, &compensated).unwrap();
+    assert_eq!(result.scroll_offset, -5);
+    // Only the rows scrolled in at the top (with no counterpart) can differ.
+    assert!(result.diff_count > 0 && result.diff_count <= 5 * w);
+    let mask = pixelmatch::diff_mask(&baseline, &scrolled, w, h, &compensated).unwrap();
+    assert_eq!(mask.iter().filter(|&&m| m).count(), result.diff_count as usize);
+    assert!(mask[5 * w as usize..].iter().all(|&m| !m));
+}
+
+#[test]
+fn test_isolation_radius() {
+    use pixelmatch::stream::StreamMatcher;
+    use pixelmatch::PixelmatchError;
+    let (w, h) = (16u32, 16u32);
+    let base = vec![255u8; (w * h * 4) as usize];
+    let mut changed = base.clone();
+    // Two lone pixels, and a pair two pixels apart.
+    for (x, y) in [(1, 1), (14, 14), (6, 8), (8, 8)] {
+        changed[(y * w as usize + x) * 4..][..3].fill(0);
+    }
+    let options = Options { detect_anti_aliasing: false, ..Default::default() };
+    let radius1 = Options { isolation_radius: 1, ..options.clone() };
+    let radius2 = Options { isolation_radius: 2, ..options.clone() };
+
+    assert_eq!(pixelmatch(&base, &changed, None, w, h, &options).unwrap().diff_count, 4);
+    assert_eq!(pixelmatch(&base, &changed, None, w, h, &radius1).unwrap().diff_count, 0);
+    assert_eq!(pixelmatch(&base, &changed, None, w, h, &radius2).unwrap().diff_count, 2);
+    let mut diff = vec![0u8; base.len()];
+    assert_eq!(pixelmatch(&base, &changed, Some(&mut diff), w, h, &radius2).unwrap().diff_count, 2);
+    assert_eq!(diff[(8 * w as usize + 8) * 4..][..4], [255, 0, 0, 255]);
+    assert_ne!(diff[(w as usize + 1) * 4..][..4], [255, 0, 0, 255]);
+    let mask = pixelmatch::diff_mask(&base, &changed, w, h, &radius2).unwrap();
+    assert_eq!(mask.iter().filter(|&&m| m).count(), 2);
+    assert!(matches!(StreamMatcher::new(w, h, radius1), Err(PixelmatchError::ConflictingOptions(_))));
+}
+
+#[test]
+fn test_morphology() {
+    use pixelmatch::morph::MorphOp;
+    let (w, h) = (16u32, 16u32);
+    let base = vec![255u8; (w * h * 4) as usize];
+    let set = |img: &mut Vec<u8>, x: usize, y: usize| img[(y * w as usize + x) * 4..][..3].fill(0);
+    // A dashed line with one-pixel gaps, and a lone pixel.
+    let mut changed = base.clone();
+    for x in (2..11).step_by(2) {
+        set(&mut changed, x, 4);
+    }
+    set(&mut changed, 12, 12);
+    let options = Options { detect_anti_aliasing: false, ..Default::default() };
+    let count = |o: &Options| pixelmatch(&base, &changed, None, w, h, o).unwrap().diff_count;
+    assert_eq!(count(&options), 6);
+
+    // Dilation grows each pixel into a 3x3 block; the dashes merge into one 11x3 bar.
+    let dilated = Options { morphology: vec![MorphOp::Dilate(1)], ..options.clone() };
+    assert_eq!(count(&dilated), 33 + 9);
+    // Closing (dilate then erode) fills the gaps and restores the original extent.
+    let closed = Options { morphology: vec![MorphOp::Dilate(1), MorphOp::Erode(1)], ..options.clone() };
+    assert_eq!(count(&closed), 9 + 1);
+    // Opening (erode first) strips everything thinner than the element.
+    let opened = Options { morphology: vec![MorphOp::Erode(1), MorphOp::Dilate(1)], ..options.clone() };
+    assert_eq!(count(&opened), 0);
+
+    let mut diff = vec![0u8; base.len()];
+    assert_eq!(pixelmatch(&base, &changed, Some(&mut diff), w, h, &closed).unwrap().diff_count, 10);
+    assert_eq!(diff[(4 * w as usize + 3) * 4..][..4], [255, 0, 0, 255], "gap drawn as a difference");
+    let mask = pixelmatch::diff_mask(&base, &changed, w, h, &closed).unwrap();
+    assert_eq!(mask.iter().filter(|&&m| m).count(), 10);
+    let ignored = Options { ignore_regions: vec![pixelmatch::Rect::new(0, 0, 16, 4)], ..dilated.clone() };
+    assert_eq!(count(&ignored), 22 + 9, "dilation does not spill into ignored pixels");
+
+    assert_eq!("dilate:2".parse(), Ok(MorphOp::Dilate(2)));
+    assert_eq!("erode".parse(), Ok(MorphOp::Erode(1)));
+    assert!("open:1".parse::<MorphOp>().is_err());
+    assert_eq!(MorphOp::Erode(3).to_string(), "erode:3");
+}
+
+#[test]
+fn test_label_shift() {
+    use pixelmatch::labels::moved_labels;
+    use pixelmatch::stream::StreamMatcher;
+    use pixelmatch::PixelmatchError;
+    let (w, h) = (120u32, 60u32);
+    let white = vec![255u8; (w * h * 4) as usize];
+    let fill = |img: &mut Vec<u8>, (x0, y0): (usize, usize), (bw, bh): (usize, usize)| {
+        for y in y0..y0 + bh {
+            img[(y * w as usize + x0) * 4..][..bw * 4].chunks_exact_mut(4).for_each(|p| p[..3].fill(0));
+        }
+    };
+    // A two-word "label" moved 3 pixels right and 1 down, and a marker added to the second image.
+    let label = |img: &mut Vec<u8>, x: usize, y: usize| {
+        fill(img, (x, y), (12, 6));
+        fill(img, (x + 16, y), (10, 6));
+    };
+    let (mut before, mut after) = (white.clone(), white.clone());
+    label(&mut before, 20, 20);
+    label(&mut after, 23, 21);
+    fill(&mut after, (90, 30), (8, 8));
+
+    let options = Options { detect_anti_aliasing: false, ..Default::default() };
+    let shifted = Options { label_shift: 4, ..options.clone() };
+    let plain = pixelmatch(&before, &after, None, w, h, &options).unwrap();
+    assert!(plain.diff_count > 64);
+    assert_eq!(plain.moved_labels, 0);
+    let result = pixelmatch(&before, &after, None, w, h, &shifted).unwrap();
+    assert_eq!(result.diff_count, 64, "only the added marker counts");
+    assert!(result.moved_labels >= 2, "both words are matched");
+    let too_far = Options { label_shift: 2, ..options.clone() };
+    assert_eq!(pixelmatch(&before, &after, None, w, h, &too_far).unwrap().diff_count, plain.diff_count);
+
+    let mut diff = vec![0u8; white.len()];
+    pixelmatch(&before, &after, Some(&mut diff), w, h, &shifted).unwrap();
+    assert_ne!(diff[(20 * w as usize + 20) * 4..][..4], [255, 0, 0, 255], "moved label drawn as unchanged");
+    assert_eq!(diff[(30 * w as usize + 90) * 4..][..4], [255, 0, 0, 255]);
+    let mask = pixelmatch::diff_mask(&before, &after, w, h, &options).unwrap();
+    let moved = moved_labels(&before, &after, w, h, &mask, 4, &options);
+    assert_eq!(moved.len() as u32, result.moved_labels);
+    assert!(moved.iter().all(|m| (m.dx, m.dy) == (3, 1) && m.region.x < 60));
+    assert!(matches!(StreamMatcher::new(w, h, shifted), Err(PixelmatchError::ConflictingOptions(_))));
+}
+
+#[test]
+fn test_pre_blur() {
+    use pixelmatch::stream::StreamMatcher;
+    use pixelmatch::PixelmatchError;
+    let (w, h) = (16u32, 16u32);
+    let base = vec![200u8; (w * h * 4) as usize];
+    // One stray pixel, and a solid 6x6 block.
+    let mut speck = base.clone();
+    speck[(8 * w as usize + 8) * 4..][..3].fill(150);
+    let mut block = base.clone();
+    for y in 4..10 {
+        block[(y * w as usize + 4) * 4..(y * w as usize + 10) * 4].fill(0);
+    }
+    let options = Options { detect_anti_aliasing: false, ..Default::default() };
+    let blurred = Options { pre_blur: Some(1.0), ..options.clone() };
+
+    assert_eq!(pixelmatch(&base, &speck, None, w, h, &options).unwrap().diff_count, 1);
+    assert_eq!(pixelmatch(&base, &speck, None, w, h, &blurred).unwrap().diff_count, 0);
+    let mut diff = vec![0u8; base.len()];
+    let count = pixelmatch(&base, &block, Some(&mut diff), w, h, &blurred).unwrap().diff_count;
+    assert!(count >= 36, "block still counted, got {count}");
+    let mask = pixelmatch::diff_mask(&base, &block, w, h, &blurred).unwrap();
+    assert_eq!(mask.iter().filter(|&&m| m).count(), count as usize);
+
+    for sigma in [-1.0, f32::NAN, f32::INFINITY] {
+        let invalid = Options { pre_blur: Some(sigma), ..options.clone() };
+        let result = pixelmatch(&base, &speck, None, w, h, &invalid);
+        assert!(matches!(result, Err(PixelmatchError::InvalidBlur(_))), "sigma {sigma}");
+    }
+    assert!(matches!(StreamMatcher::new(w, h, blurred), Err(PixelmatchError::ConflictingOptions(_))));
+}
+
+#[test]
+fn test_median_filter() {
+    let (w, h) = (16u32, 16u32);
+    let base = vec![120u8; (w * h * 4) as usize];
+    // Scattered single-pixel salt and pepper, and a solid 4x4 block.
+    let mut noisy = base.clone();
+    for (i, &(x, y)) in [(1, 1), (5, 2), (9, 9), (14, 6), (3, 12)].iter().enumerate() {
+        noisy[(y * w as usize + x) * 4..][..3].fill(if i % 2 == 0 { 255 } else { 0 });
+    }
+    let mut block = base.clone();
+    for y in 6..10 {
+        block[(y * w as usize + 6) * 4..(y * w as usize + 10) * 4].fill(0);
+    }
+    let options = Options { detect_anti_aliasing: false, ..Default::default() };
+    let median = Options { median_filter: true, ..options.clone() };
+
+    assert_eq!(pixelmatch(&base, &noisy, None, w, h, &options).unwrap().diff_count, 5);
+    assert_eq!(pixelmatch(&base, &noisy, None, w, h, &median).unwrap().diff_count, 0);
+    // The median keeps a block's edges, losing only its corners.
+    assert_eq!(pixelmatch(&base, &block, None, w, h, &median).unwrap().diff_count, 12);
+    let mask = pixelmatch::diff_mask(&base, &block, w, h, &median).unwrap();
+    assert_eq!(mask.iter().filter(|&&m| m).count(), 12);
+}
+
+#[test]
+fn test_quantize() {
+    use pixelmatch::quantize::quantize_pair;
+    use pixelmatch::stream::StreamMatcher;
+    use pixelmatch::PixelmatchError;
+    let (w, h) = (8u32, 8u32);
+    // Two flat colours, and the same with one renderer truncating differently.
+    let render = |dark: u8, light: u8| {
+        (0..w * h).flat_map(|i| if i % w < 4 { [dark, dark, 40, 255] } else { [light, 200, light, 255] }).collect()
+    };
+    let img1: Vec<u8> = render(30, 220);
+    let img2: Vec<u8> = render(31, 219);
+    let options = Options { threshold: 0.0, detect_anti_aliasing: false, ..Default::default() };
+    assert_eq!(pixelmatch(&img1, &img2, None, w, h, &options).unwrap().diff_count, 64);
+
+    let quantized = Options { quantize: Some(2), ..options.clone() };
+    assert_eq!(pixelmatch(&img1, &img2, None, w, h, &quantized).unwrap().diff_count, 0);
+    // A genuinely new colour still gets its own palette entry.
+    let mut changed = img1.clone();
+    changed[..4].copy_from_slice(&[255, 0, 0, 255]);
+    let three = Options { quantize: Some(3), ..options.clone() };
+    assert_eq!(pixelmatch(&img1, &changed, None, w, h, &three).unwrap().diff_count, 1);
+    // Images with no more colours than the palette are left alone.
+    assert_eq!(quantize_pair(&img1, &changed, 3), (img1.clone(), changed.clone()));
+
+    for colors in [0, 1, 257] {
+        let invalid = Options { quantize: Some(colors), ..options.clone() };
+        assert!(matches!(invalid.validate(), Err(PixelmatchError::InvalidColorCount(c)) if c == colors));
+    }
+    assert!(matches!(StreamMatcher::new(w, h, quantized), Err(PixelmatchError::ConflictingOptions(_))));
+}
+
+#[test]
+fn test_edges() {
+    use pixelmatch::edges::EdgeDetector;
+    use pixelmatch::stream::StreamMatcher;
+    use pixelmatch::PixelmatchError;
+    let (w, h) = (24u32, 24u32);
+    // An 8x8 block on white, at an offset and in a colour.
+    let block = |x0: usize, rgb: [u8; 3]| {
+        let mut img = vec![255u8; (w * h * 4) as usize];
+        for (x, y) in (x0..x0 + 8).flat_map(|x| (8..16).map(move |y| (x, y))) {
+            img[(y * w as usize + x) * 4..][..3].copy_from_slice(&rgb);
+        }
+        img
+    };
+    let (blue, red, moved) = (block(8, [0, 0, 255]), block(8, [255, 0, 0]), block(11, [0, 0, 255]));
+    let options = Options { detect_anti_aliasing: false, ..Default::default() };
+    assert_eq!(pixelmatch(&blue, &red, None, w, h, &options).unwrap().diff_count, 64);
+
+    for detector in [EdgeDetector::Sobel(0.1), EdgeDetector::Canny { low: 0.1, high: 0.25 }] {
+        let edges = Options { edges: Some(detector), ..options.clone() };
+        assert_eq!(pixelmatch(&blue, &red, None, w, h, &edges).unwrap().diff_count, 0, "{detector}");
+        let count = pixelmatch(&blue, &moved, None, w, h, &edges).unwrap().diff_count;
+        assert!(count > 0, "{detector}");
+        let mask = pixelmatch::diff_mask(&blue, &moved, w, h, &edges).unwrap();
+        assert_eq!(mask.iter().filter(|&&m| m).count(), count as usize);
+        assert_eq!(detector.to_string().parse(), Ok(detector));
+    }
+    // Canny thins the block outline to a one-pixel line (the corners may round off).
+    let outline = pixelmatch::edges::edge_mask(&blue, w, h, EdgeDetector::Canny { low: 0.1, high: 0.25 });
+    let count = outline.iter().filter(|&&e| e).count();
+    assert!((4 * 6..=4 * 8).contains(&count), "{count}");
+
+    assert_eq!("canny".parse(), Ok(EdgeDetector::Canny { low: 0.1, high: 0.25 }));
+    assert!("laplace:0.1".parse::<EdgeDetector>().is_err());
+    let invalid = Options { edges: Some(EdgeDetector::Sobel(1.5)), ..options.clone() };
+    assert!(matches!(pixelmatch(&blue, &red, None, w, h, &invalid), Err(PixelmatchError::InvalidThreshold(_))));
+    let inverted = Options { edges: Some(EdgeDetector::Canny { low: 0.3, high: 0.1 }), ..options.clone() };
+    let result = pixelmatch(&blue, &red, None, w, h, &inverted);
+    assert!(matches!(result, Err(PixelmatchError::ConflictingOptions(_))));
+    let edges = Options { edges: Some(EdgeDetector::Sobel(0.25)), ..options };
+    assert!(matches!(StreamMatcher::new(w, h, edges), Err(PixelmatchError::ConflictingOptions(_))));
+}
+
+#[test]
+fn test_downscale() {
+    use pixelmatch::{PixelmatchError, Rect};
+    let (w, h) = (32u32, 32u32);
+    let base = vec![230u8; (w * h * 4) as usize];
+    let set = |img: &mut Vec<u8>, x: usize, y: usize, v: u8| img[(y * w as usize + x) * 4..][..3].fill(v);
+    // A faint single pixel that averages away, and a black 8x8 block on the 2x2 grid.
+    let mut faint = base.clone();
+    set(&mut faint, 5, 5, 200);
+    let mut block = base.clone();
+    for (x, y) in (8..16).flat_map(|x| (8..16).map(move |y| (x, y))) {
+        set(&mut block, x, y, 0);
+    }
+    let options = Options { detect_anti_aliasing: false, ..Default::default() };
+    let half = Options { downscale: Some(0.5), ..options.clone() };
+
+    assert_eq!(pixelmatch(&base, &faint, None, w, h, &options).unwrap().diff_count, 1);
+    assert_eq!(pixelmatch(&base, &faint, None, w, h, &half).unwrap().diff_count, 0);
+    // Counts are reported in full-size pixels.
+    let mut diff = vec![0u8; base.len()];
+    let result = pixelmatch(&base, &block, Some(&mut diff), w, h, &half).unwrap();
+    assert_eq!(result.diff_count, 64);
+    assert!(!result.identical);
+    assert_eq!(diff[(12 * w as usize + 12) * 4..][..4], [255, 0, 0, 255]);
+    let mask = pixelmatch::diff_mask(&base, &block, w, h, &half).unwrap();
+    assert_eq!(mask.iter().filter(|&&m| m).count(), 64);
+    assert!(mask[12 * w as usize + 12] && !mask[4 * w as usize + 4]);
+
+    let ignored = Options { ignore_regions: vec![Rect::new(8, 8, 8, 8)], ..half.clone() };
+    assert_eq!(pixelmatch(&base, &block, None, w, h, &ignored).unwrap().diff_count, 0);
+    let full = Options { downscale: Some(1.0), ..options.clone() };
+    assert_eq!(pixelmatch(&base, &faint, None, w, h, &full).unwrap().diff_count, 1);
+    for scale in [0.0, -0.5, 1.5, f32::NAN] {
+        let invalid = Options { downscale: Some(scale), ..options.clone() };
+        let result = pixelmatch(&base, &block, None, w, h, &invalid);
+        assert!(matches!(result, Err(PixelmatchError::InvalidScale(_))), "scale {scale}");
+    }
+}
+
+#[test]
+fn test_device_pixel_ratio() {
+    use pixelmatch::stream::StreamMatcher;
+    use pixelmatch::PixelmatchError;
+
+    let (w, h) = (12u32, 10u32);
+    let shade = |x: usize, y: usize| ((x * 20 + y * 7) % 256) as u8;
+    let standard: Vec<u8> =
+        (0..(w * h) as usize).flat_map(|i| { let v = shade(i % w as usize, i / w as usize); [v, v, v, 255] }).collect();
+    // The same content at twice the density: every pixel becomes a 2x2 block.
+    let retina: Vec<u8> = (0..(w * h * 4) as usize)
+        .flat_map(|i| { let v = shade(i % (2 * w as usize) / 2, i / (2 * w as usize) / 2); [v, v, v, 255] })
+        .collect();
+    let options = Options { device_pixel_ratio: Some([1.0, 2.0]), ..Default::default() };
+    assert_eq!(options.compared_size((w, h), (2 * w, 2 * h)), Some((w, h)));
+    assert_eq!(options.compared_size((w, h), (2 * w + 1, 2 * h)), None);
+
+    let mut diff = vec![0u8; standard.len()];
+    let result = pixelmatch(&standard, &retina, Some(&mut diff), w, h, &options).unwrap();
+    assert_eq!(result.diff_count, 0);
+    assert!(result.identical);
+    let swapped = Options { device_pixel_ratio: Some([2.0, 1.0]), ..Default::default() };
+    assert_eq!(pixelmatch(&retina, &standard, None, w, h, &swapped).unwrap().diff_count, 0);
+
+    let mut changed = retina.clone();
+    changed[..8].fill(0);
+    changed[2 * w as usize * 4..][..8].fill(0);
+    assert_eq!(pixelmatch::diff_mask(&standard, &changed, w, h, &options).unwrap().iter().filter(|&&m| m).count(), 1);
+    let result = pixelmatch(&standard, &retina[4..], None, w, h, &options);
+    assert!(matches!(result, Err(PixelmatchError::BufferLengthMismatch { .. })));
+    for ratio in [0.0, -1.0, f32::INFINITY] {
+        let invalid = Options { device_pixel_ratio: Some([1.0, ratio]), ..Default::default() };
+        let result = pixelmatch(&standard, &standard, None, w, h, &invalid);
+        assert!(matches!(result, Err(PixelmatchError::InvalidPixelRatio(_))), "ratio {ratio}");
+    }
+    assert!(matches!(StreamMatcher::new(w, h, options), Err(PixelmatchError::ConflictingOptions(_))));
+}
+
+#[test]
+fn test_density_scaling() {
+    use pixelmatch::stream::StreamMatcher;
+    // A black/white edge, anti-aliased with a grey column in the second image.
+    let edge = |scale: usize, grey: bool| -> Vec<u8> {
+        let (w, h) = (8 * scale, 8 * scale);
+        (0..w * h)
+            .flat_map(|i| match i % w / scale {
+                4 if grey => [128, 128, 128, 255],
+                x if x < 4 => [255; 4],
+                _ => [0, 0, 0, 255],
+            })
+            .collect()
+    };
+    let options = Options::default();
+    let result = pixelmatch(&edge(1, false), &edge(1, true), None, 8, 8, &options).unwrap();
+    assert_eq!((result.diff_count, result.aa_count), (0, 8));
+
+    // At twice the density the grey column is two pixels wide and no longer looks like
+    // anti-aliasing to a 3x3 neighbourhood.
+    let (img1, img2) = (edge(2, false), edge(2, true));
+    let retina = Options { device_pixel_ratio: Some([2.0, 2.0]), ..Default::default() };
+    assert!(pixelmatch(&img1, &img2, None, 16, 16, &retina).unwrap().diff_count > 0);
+    let scaled = Options { density_scaling: true, ..retina.clone() };
+    let result = pixelmatch(&img1, &img2, None, 16, 16, &scaled).unwrap();
+    assert_eq!((result.diff_count, result.aa_count), (0, 32));
+    assert!(pixelmatch::diff_mask(&img1, &img2, 16, 16, &scaled).unwrap().iter().all(|&m| !m));
+    let mut matcher = StreamMatcher::new(16, 16, scaled.clone()).unwrap();
+    for (r1, r2) in img1.chunks(16 * 4).zip(img2.chunks(16 * 4)) {
+        matcher.push(r1, r2, None).unwrap();
+    }
+    assert_eq!(matcher.finish().unwrap(), result);
+
+    let tuned = Options { threshold: 0.6, shift_tolerance: 2, pre_blur: Some(0.5), ..scaled };
+    let effective = tuned.scaled_for_density();
+    assert_eq!((effective.threshold, effective.shift_tolerance, effective.pre_blur), (1.0, 4, Some(1.0)));
+    assert_eq!(retina.scaled_for_density().threshold, retina.threshold);
+}
+
+// --- Batch tests ---
+
+#[test]
+fn test_batch_matches_individual_comparisons() {
+    use pixelmatch::batch::{pixelmatch_batch, BatchItem};
+    let options = Options { threshold: 0.05, ..Default::default() };
+    let images: Vec<_> = ["1", "2", "3"]
+        .iter()
+        .map(|n| (read_image(&format!("{n}a")), read_image(&format!("{n}b"))))
+        .collect();
+    let mut outputs: Vec<Vec<u8>> = images.iter().map(|((a, _, _), _)| vec![0; a.len()]).collect();
+
+    let mut items: Vec<BatchItem> = images
+        .iter()
+        .zip(outputs.iter_mut())
+        .map(|(((a, w, h), (b, _, _)), out)| BatchItem::new(a, b, *w, *h).with_output(out))
+        .collect();
+    items.push(BatchItem::new(&[0; 4], &[0; 8], 1, 1));
+    let results = pixelmatch_batch(&mut items, &options);
+
+    assert_eq!(results.len(), 4);
+    for (i, ((a, w, h), (b, _, _))) in images.iter().enumerate() {
+        let mut expected = vec![0; a.len()];
+        let single = pixelmatch(a, b, Some(&mut expected), *w, *h, &options).unwrap();
+        assert_eq!(results[i].as_ref().unwrap(), &single);
+        assert_eq!(outputs[i], expected);
+    }
+    assert!(results[3].is_err());
+}
+
+// --- Streaming tests ---
+
+#[test]
+fn test_stream_matches_whole_image_for_any_band_height() {
+    use pixelmatch::stream::StreamMatcher;
+    let options = Options { threshold: 0.05, ..Default::default() };
+    for name in ["1", "4", "6"] {
+        let (a, w, h) = read_image(&format!("{name}a"));
+        let (b, _, _) = read_image(&format!("{name}b"));
+        let mut expected = vec![0; a.len()];
+        let whole = pixelmatch(&a, &b, Some(&mut expected), w, h, &options).unwrap();
+
+        for band_rows in [1, 2, 3, 7, h as usize] {
+            let band = band_rows * w as usize * 4;
+            let mut matcher = StreamMatcher::new(w, h, options.clone()).unwrap();
+            let mut diff = Vec::new();
+            let mut next = 0;
+            for (c1, c2) in a.chunks(band).zip(b.chunks(band)) {
+                let rows = matcher.push(c1, c2, Some(&mut diff)).unwrap();
+                assert_eq!(rows.start, next);
+                next = rows.end;
+            }
+            assert_eq!(next, h, "{name} with {band_rows}-row bands");
+            assert_eq!(matcher.finish().unwrap(), whole, "{name} with {band_rows}-row bands");
+            assert_eq!(diff, expected, "{name} with {band_rows}-row bands");
+        }
+    }
+}
+
+#[test]
+fn test_stream_rejects_partial_rows_and_early_finish() {
+    use pixelmatch::stream::StreamMatcher;
+    let mut matcher = StreamMatcher::new(2, 2, Options::default()).unwrap();
+    assert!(matches!(
+        matcher.push(&[0; 4], &[0; 4], None),
+        Err(pixelmatch::PixelmatchError::BandSizeMismatch { row_bytes: 8, remaining_rows: 2, actual: 4 })
+    ));
+    assert!(matcher.push(&[0; 24], &[0; 24], None).is_err());
+    assert_eq!(matcher.push(&[0; 8], &[0; 8], None).unwrap(), 0..0);
+    assert!(matches!(
+        matcher.finish(),
+        Err(pixelmatch::PixelmatchError::IncompleteImage { expected_rows: 2, received_rows: 1 })
+    ));
+}
+
+#[test]
+fn test_stream_rejects_whole_image_options() {
+    use pixelmatch::edges::EdgeDetector;
+    use pixelmatch::morph::MorphOp;
+    use pixelmatch::stream::StreamMatcher;
+    let unsupported = [
+        Options { jpeg_tolerance: Some(0.3), ..Default::default() },
+        Options { scroll_compensation: 4, ..Default::default() },
+        Options { isolation_radius: 1, ..Default::default() },
+        Options { morphology: vec![MorphOp::Dilate(1)], ..Default::default() },
+        Options { label_shift: 2, ..Default::default() },
+        Options { pre_blur: Some(1.0), ..Default::default() },
+        Options { quantize: Some(16), ..Default::default() },
+        Options { median_filter: true, ..Default::default() },
+        Options { edges: Some(EdgeDetector::Sobel(0.2)), ..Default::default() },
+        Options { downscale: Some(0.5), ..Default::default() },
+        Options { device_pixel_ratio: Some([1.0, 2.0]), ..Default::default() },
+    ];
+    for options in unsupported {
+        let result = StreamMatcher::new(4, 4, options.clone());
+        assert!(matches!(result, Err(pixelmatch::PixelmatchError::ConflictingOptions(_))), "{options:?}");
+    }
+    // Settings that leave the images untouched are accepted.
+    let dpr = Some([2.0, 2.0]);
+    let noop = Options { pre_blur: Some(0.0), downscale: Some(1.0), device_pixel_ratio: dpr, ..Default::default() };
+    assert!(StreamMatcher::new(4, 4, noop).is_ok());
+}
+
+#[test]
+fn test_presets() {
+    use pixelmatch::Preset;
+    let (a, w, h) = read_image("1a");
+    let (b, _, _) = read_image("1b");
+    let count = |options: &Options| pixelmatch(&a, &b, None, w, h, options).unwrap().diff_count;
+
+    let default = count(&Options::default());
+    assert!(count(&Options::preset(Preset::Strict)) > default);
+    assert!(count(&Options::preset(Preset::Lenient)) <= default);
+    for preset in Preset::ALL {
+        assert_eq!(preset.name().parse::<Preset>().unwrap(), preset);
+        assert_eq!(Options::preset(preset).diff_color, Options::default().diff_color);
+    }
+    assert!("fuzzy".parse::<Preset>().is_err());
+}
+
+#[test]
+fn test_options_validate() {
+    use pixelmatch::PixelmatchError;
+    let img = [0u8; 4];
+    let run = |options: Options| pixelmatch(&img, &img, None, 1, 1, &options);
+
+    assert!(Options::default().validate().is_ok());
+    for threshold in [f64::NAN, -0.1, 1.1] {
+        let result = run(Options { threshold, ..Default::default() });
+        assert!(matches!(result, Err(PixelmatchError::InvalidThreshold(_))), "threshold {threshold}");
+    }
+    assert!(matches!(run(Options { alpha: 1.5, ..Default::default() }), Err(PixelmatchError::InvalidAlpha(_))));
+    // alpha has no effect with diff_mask, but the JS fallback accepts the pair, so it is not
+    // an error.
+    assert!(run(Options { alpha: 0.5, diff_mask: true, ..Default::default() }).is_ok());
+    assert!(run(Options { threshold: 1.0, alpha: 0.0, ..Default::default() }).is_ok());
+}
+
+#[test]
+fn test_diff_bounds() {
+    let (img1, w, h) = read_image("1a");
+    let (img2, _, _) = read_image("1b");
+    let options = Options::default();
+    let mask = pixelmatch::diff_mask(&img1, &img2, w, h, &options).unwrap();
+    let (xs, ys): (Vec<u32>, Vec<u32>) = (0..w * h).filter(|&i| mask[i as usize]).map(|i| (i % w, i / w)).unzip();
+    let range = |v: &[u32]| (*v.iter().min().unwrap(), *v.iter().max().unwrap());
+    let ((x0, x1), (y0, y1)) = (range(&xs), range(&ys));
+    let expected = (x0, y0, x1, y1);
+
+    let mut diff = vec![0u8; img1.len()];
+    assert_eq!(pixelmatch(&img1, &img2, Some(&mut diff), w, h, &options).unwrap().diff_bounds, Some(expected));
+    assert_eq!(pixelmatch(&img1, &img2, None, w, h, &options).unwrap().diff_bounds, Some(expected));
+    assert_eq!(pixelmatch(&img1, &img1, None, w, h, &options).unwrap().diff_bounds, None);
+
+    // Below-threshold and anti-aliased changes leave no bounds.
+    let lenient = Options { threshold: 1.0, ..Default::default() };
+    assert_eq!(pixelmatch(&img1, &img2, None, w, h, &lenient).unwrap().diff_bounds, None);
+
+    // A block found on half-size copies maps back to the full-size pixels it covers.
+    let (w, h) = (32u32, 32u32);
+    let base = vec![230u8; (w * h * 4) as usize];
+    let mut block = base.clone();
+    for (x, y) in (8..16).flat_map(|x| (10..14).map(move |y| (x, y))) {
+        block[(y * w as usize + x) * 4..][..3].fill(0);
+    }
+    let options = Options { detect_anti_aliasing: false, ..Default::default() };
+    assert_eq!(pixelmatch(&base, &block, None, w, h, &options).unwrap().diff_bounds, Some((8, 10, 15, 13)));
+    let half = Options { downscale: Some(0.5), ..options };
+    assert_eq!(pixelmatch(&base, &block, None, w, h, &half).unwrap().diff_bounds, Some((8, 10, 15, 13)));
+}
+
+#[test]
+fn test_diff_ratio() {
+    let (img1, w, h) = read_image("1a");
+    let (img2, _, _) = read_image("1b");
+    let result = pixelmatch(&img1, &img2, None, w, h, &Options::default()).unwrap();
+    assert_eq!(result.total_pixels, w as u64 * h as u64);
+    assert_eq!(result.diff_ratio(), result.diff_count as f64 / result.total_pixels as f64);
+    assert!(result.diff_ratio() > 0.0 && result.diff_ratio() < 1.0);
+
+    let same = pixelmatch(&img1, &img1, None, w, h, &Options::default()).unwrap();
+    assert_eq!((same.total_pixels, same.diff_ratio()), (w as u64 * h as u64, 0.0));
+    let empty = pixelmatch(&[], &[], None, 0, 0, &Options::default()).unwrap();
+    assert_eq!((empty.total_pixels, empty.diff_ratio()), (0, 0.0));
+}
+
+#[test]
+fn test_diff_pixels() {
+    let (img1, w, h) = read_image("1a");
+    let (img2, _, _) = read_image("1b");
+    let options = Options::default();
+    let result = pixelmatch(&img1, &img2, None, w, h, &options).unwrap();
+    let mask = pixelmatch::diff_mask(&img1, &img2, w, h, &options).unwrap();
+    let pixels = pixelmatch::diff_pixels(&img1, &img2, w, h, &options).unwrap();
+
+    assert_eq!(pixels.len() as u32, result.diff_count);
+    assert!(pixels.iter().all(|&(x, y)| mask[(y * w + x) as usize]));
+    assert!(pixels.windows(2).all(|p| (p[0].1, p[0].0) < (p[1].1, p[1].0)));
+    let (x0, y0, x1, y1) = result.diff_bounds.unwrap();
+    assert!(pixels.iter().all(|&(x, y)| (x0..=x1).contains(&x) && (y0..=y1).contains(&y)));
+    assert!(pixelmatch::diff_pixels(&img1, &img1, w, h, &options).unwrap().is_empty());
+}
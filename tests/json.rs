@@ -0,0 +1,54 @@
+use pixelmatch::edges::EdgeDetector;
+use pixelmatch::morph::MorphOp;
+use pixelmatch::{Options, PixelmatchError, Polygon, Rect};
+
+#[test]
+fn test_options_from_json() {
+    let options = Options::from_json("{}").unwrap();
+    assert_eq!(options.to_json(), Options::default().to_json());
+
+    let err = Options::from_json(r#"{"threshold": 0.2, "nameé": 1}"#).unwrap_err();
+    assert!(matches!(&err, PixelmatchError::InvalidJson(m) if m == "unknown option nameé"), "{err}");
+
+    let json = r#"{
+        "threshold": 0.2, "detectAntiAliasing": false, "diffColorAlt": [0, 128, 255],
+        "morphology": ["dilate:2"], "edges": "canny:0.1,0.3", "devicePixelRatio": [1, 2],
+        "quantize": 16,
+        "ignoreRegions": [{"x": 1, "y": 2, "width": 3, "height": 4}],
+        "ignorePolygons": [[[0, 0], [4.5, 0], [0, 4]]], "ignoreHatch": null
+    }"#;
+    let options = Options::from_json(json).unwrap();
+    assert_eq!(options.threshold, 0.2);
+    assert!(!options.detect_anti_aliasing);
+    assert_eq!(options.diff_color_alt, Some([0, 128, 255]));
+    assert_eq!(options.morphology, [MorphOp::Dilate(2)]);
+    assert_eq!(options.edges, Some(EdgeDetector::Canny { low: 0.1, high: 0.3 }));
+    assert_eq!(options.device_pixel_ratio, Some([1.0, 2.0]));
+    assert_eq!(options.quantize, Some(16));
+    assert_eq!(options.ignore_regions, [Rect::new(1, 2, 3, 4)]);
+    assert_eq!(options.ignore_polygons, [Polygon::new(vec![(0.0, 0.0), (4.5, 0.0), (0.0, 4.0)])]);
+
+    let round_trip = Options::from_json(&options.to_json()).unwrap();
+    assert_eq!(round_trip.to_json(), options.to_json());
+}
+
+#[test]
+fn test_options_from_json_rejects_invalid_input() {
+    for json in ["", "[]", "{", r#"{"threshold": }"#, r#"{"threshold": 0.1} x"#, r#"{"alpha": "0.1"}"#] {
+        let err = Options::from_json(json).unwrap_err();
+        assert!(matches!(err, PixelmatchError::InvalidJson(_)), "{json}: {err}");
+    }
+    for json in [r#"{"shiftTolerance": 1.5}"#, r#"{"aaColor": [0, 0, 256]}"#, r#"{"morphology": ["grow:1"]}"#] {
+        let err = Options::from_json(json).unwrap_err();
+        assert!(matches!(err, PixelmatchError::InvalidJson(_)), "{json}: {err}");
+    }
+    // Errors name the key at fault.
+    let err = Options::from_json(r#"{"threshold": 0.1, "aaColor": [0, 0, 256]}"#).unwrap_err();
+    assert!(matches!(&err, PixelmatchError::InvalidJson(m) if m.starts_with("aaColor: invalid value")), "{err}");
+    let nested = format!("{{\"ignorePolygons\": {}{}}}", "[".repeat(100), "]".repeat(100));
+    assert!(matches!(Options::from_json(&nested), Err(PixelmatchError::InvalidJson(_))));
+
+    // Well-formed options still go through `validate`.
+    let err = Options::from_json(r#"{"threshold": 1.5}"#).unwrap_err();
+    assert!(matches!(err, PixelmatchError::InvalidThreshold(t) if t == 1.5));
+}
@@ -0,0 +1,40 @@
+use std::sync::{Arc, Mutex};
+
+use pixelmatch::metrics::{set_sink, ComparisonMetrics, EarlyExit, MetricsSink};
+use pixelmatch::{pixelmatch, Options};
+
+#[derive(Default)]
+struct Collector(Mutex<Vec<ComparisonMetrics>>);
+
+impl MetricsSink for Collector {
+    fn on_comparison_complete(&self, metrics: &ComparisonMetrics) {
+        self.0.lock().unwrap().push(*metrics);
+    }
+}
+
+// The sink is process-wide, so everything runs in one test.
+#[test]
+fn test_metrics_sink_receives_each_comparison() {
+    let white = vec![255u8; 8 * 4 * 4];
+    let mut changed = white.clone();
+    changed[..3].fill(0);
+    let collector = Arc::new(Collector::default());
+    set_sink(Some(collector.clone()));
+
+    let options = Options { detect_anti_aliasing: false, ..Default::default() };
+    pixelmatch(&white, &changed, None, 8, 4, &options).unwrap();
+    pixelmatch(&white, &white, None, 8, 4, &options).unwrap();
+    assert!(pixelmatch(&white, &changed, None, 4, 4, &options).is_err());
+    // A downscaled comparison recurses internally but is reported once.
+    pixelmatch(&white, &changed, None, 8, 4, &Options { downscale: Some(0.5), ..options.clone() }).unwrap();
+    set_sink(None);
+    pixelmatch(&white, &changed, None, 8, 4, &options).unwrap();
+
+    let metrics = collector.0.lock().unwrap();
+    assert_eq!(metrics.len(), 4);
+    let summary: Vec<_> = metrics.iter().map(|m| (m.width, m.height, m.diff_count, m.early_exit)).collect();
+    assert_eq!(summary[0], (8, 4, 1, None));
+    assert_eq!(summary[1], (8, 4, 0, Some(EarlyExit::Identical)));
+    assert_eq!(summary[2], (4, 4, 0, Some(EarlyExit::Rejected)));
+    assert_eq!((summary[3].0, summary[3].1, summary[3].3), (8, 4, None));
+}
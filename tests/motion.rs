@@ -0,0 +1,42 @@
+use pixelmatch::motion::{classify_regions, RegionChange};
+use pixelmatch::regions::find_regions;
+use pixelmatch::{diff_mask, Options};
+
+const WIDTH: u32 = 40;
+const HEIGHT: u32 = 30;
+
+/// A white canvas with a textured 6x6 icon at `icon` and a flat square of `square` gray at
+/// (28, 20).
+fn canvas(icon: (usize, usize), square: u8) -> Vec<u8> {
+    let mut img = [255u8; 4].repeat((WIDTH * HEIGHT) as usize);
+    let mut set = |x: usize, y: usize, v: u8| img[(y * WIDTH as usize + x) * 4..][..3].fill(v);
+    for (ix, iy) in (0..6).flat_map(|ix| (0..6).map(move |iy| (ix, iy))) {
+        set(icon.0 + ix, icon.1 + iy, ((ix * 37 + iy * 91 + ix * iy * 13) % 11 * 20) as u8);
+    }
+    for (x, y) in (28..34).flat_map(|x| (20..26).map(move |y| (x, y))) {
+        set(x, y, square);
+    }
+    img
+}
+
+fn classify(img1: &[u8], img2: &[u8], max_offset: u32) -> Vec<RegionChange> {
+    let options = Options { detect_anti_aliasing: false, ..Default::default() };
+    let mask = diff_mask(img1, img2, WIDTH, HEIGHT, &options).unwrap();
+    let regions = find_regions(&mask, WIDTH, HEIGHT);
+    classify_regions(img1, img2, WIDTH, HEIGHT, &regions, max_offset, &options)
+}
+
+#[test]
+fn test_classify_regions_moved_and_changed() {
+    let (before, after) = (canvas((5, 5), 100), canvas((8, 7), 30));
+    assert_eq!(classify(&before, &after, 4), [RegionChange::Moved { dx: 3, dy: 2 }, RegionChange::Changed]);
+    assert_eq!(classify(&after, &before, 4), [RegionChange::Moved { dx: -3, dy: -2 }, RegionChange::Changed]);
+}
+
+#[test]
+fn test_classify_regions_offset_beyond_window() {
+    let (before, after) = (canvas((5, 5), 100), canvas((14, 5), 100));
+    // The old and new positions no longer touch, and neither is found within 4 pixels.
+    assert_eq!(classify(&before, &after, 4), [RegionChange::Changed, RegionChange::Changed]);
+    assert_eq!(classify(&before, &after, 10), [RegionChange::Moved { dx: 9, dy: 0 }; 2]);
+}
@@ -0,0 +1,59 @@
+use pixelmatch::normals::{angle_map, compare_normals};
+use pixelmatch::{Options, PixelmatchError, Rect};
+
+/// The RGBA encoding of a normal tilted `degrees` towards +x.
+fn tilted(degrees: f64) -> [u8; 4] {
+    let (sin, cos) = degrees.to_radians().sin_cos();
+    let encode = |c: f64| ((c + 1.0) * 127.5).round() as u8;
+    [encode(sin), encode(0.0), encode(cos), 255]
+}
+
+fn normal_map(width: usize, height: usize, tilts: &[(usize, f64)]) -> Vec<u8> {
+    let mut img: Vec<u8> = (0..width * height).flat_map(|_| tilted(0.0)).collect();
+    for &(i, degrees) in tilts {
+        img[i * 4..][..4].copy_from_slice(&tilted(degrees));
+    }
+    img
+}
+
+#[test]
+fn test_compare_normals_counts_by_angle() {
+    let flat = normal_map(8, 8, &[]);
+    let bumped = normal_map(8, 8, &[(3, 20.0), (10, 20.0), (40, 45.0), (50, 2.0)]);
+    let options = Options::default();
+    let diff = compare_normals(&flat, &bumped, None, 8, 8, 5.0, &options).unwrap();
+    assert_eq!(diff.diff_count, 3);
+    assert!((diff.max_angle - 45.0).abs() < 1.0, "max {}", diff.max_angle);
+    assert!((diff.mean_angle - 87.0 / 64.0).abs() < 0.1, "mean {}", diff.mean_angle);
+    assert!(!diff.identical);
+    assert_eq!(compare_normals(&flat, &bumped, None, 8, 8, 30.0, &options).unwrap().diff_count, 1);
+    assert_eq!(compare_normals(&flat, &bumped, None, 8, 8, 1.0, &options).unwrap().diff_count, 4);
+
+    let same = compare_normals(&flat, &flat, None, 8, 8, 0.0, &options).unwrap();
+    assert_eq!((same.diff_count, same.max_angle, same.identical), (0, 0.0, true));
+
+    let map = angle_map(&flat, &bumped, 8, 8).unwrap();
+    assert!((map[3] - 20.0).abs() < 1.0 && map[0] == 0.0);
+}
+
+#[test]
+fn test_compare_normals_output_and_ignore() {
+    let flat = normal_map(8, 8, &[]);
+    let bumped = normal_map(8, 8, &[(3, 20.0), (40, 45.0)]);
+    let ignored = Options { ignore_regions: vec![Rect::new(0, 5, 8, 3)], ..Default::default() };
+    let mut out = vec![0u8; flat.len()];
+    let diff = compare_normals(&flat, &bumped, Some(&mut out), 8, 8, 5.0, &ignored).unwrap();
+    assert_eq!(diff.diff_count, 1);
+    assert!((diff.max_angle - 20.0).abs() < 1.0);
+    assert_eq!(out[3 * 4..][..4], [255, 0, 0, 255]);
+    assert_ne!(out[40 * 4..][..4], [255, 0, 0, 255]);
+}
+
+#[test]
+fn test_compare_normals_rejects_invalid_angle() {
+    let flat = normal_map(2, 2, &[]);
+    for angle in [-1.0, 181.0, f64::NAN] {
+        let result = compare_normals(&flat, &flat, None, 2, 2, angle, &Options::default());
+        assert!(matches!(result, Err(PixelmatchError::InvalidAngle(_))), "angle {angle}");
+    }
+}
@@ -0,0 +1,48 @@
+use pixelmatch::phash::{HashKind, ImageHash};
+use pixelmatch::resize::resize_box;
+
+const KINDS: [HashKind; 3] = [HashKind::Average, HashKind::Difference, HashKind::Perceptual];
+
+/// A `size`×`size` RGBA image of a diagonal gradient with a dark block in a top corner.
+fn scene(size: u32, block_left: bool) -> Vec<u8> {
+    let mut img = Vec::with_capacity(size as usize * size as usize * 4);
+    for y in 0..size {
+        for x in 0..size {
+            let block_x = if block_left { x < size / 2 } else { x >= size / 2 };
+            let v = if block_x && y < size / 2 { 20 } else { (128 + (x + y) * 100 / size) as u8 };
+            img.extend_from_slice(&[v, v, v, 255]);
+        }
+    }
+    img
+}
+
+#[test]
+fn test_hash_identical_and_resized() {
+    let img = scene(96, true);
+    let resized = resize_box(&img, 96, 96, 40, 40);
+    for kind in KINDS {
+        let hash = ImageHash::of(kind, &img, 96, 96);
+        assert_eq!(hash, ImageHash::of(kind, &img, 96, 96));
+        assert_ne!(hash.0, 0, "{kind:?}");
+        assert!(hash.distance(ImageHash::of(kind, &resized, 40, 40)) <= 6, "{kind:?}");
+    }
+}
+
+#[test]
+fn test_hash_distinguishes_layouts() {
+    let (left, right) = (scene(64, true), scene(64, false));
+    for kind in KINDS {
+        let distance = ImageHash::of(kind, &left, 64, 64).distance(ImageHash::of(kind, &right, 64, 64));
+        assert!(distance > 6, "{kind:?}: {distance}");
+    }
+}
+
+#[test]
+fn test_hash_text_round_trip() {
+    let hash = ImageHash(0x00ff_0000_1234_abcd);
+    assert_eq!(hash.to_string(), "00ff00001234abcd");
+    assert_eq!("00ff00001234abcd".parse(), Ok(hash));
+    assert!("not hex".parse::<ImageHash>().is_err());
+    assert_eq!(hash.distance(ImageHash(!hash.0)), 64);
+    assert_eq!(hash.distance(ImageHash(hash.0 ^ 0b101)), 2);
+}
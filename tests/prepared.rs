@@ -0,0 +1,50 @@
+use pixelmatch::align::align_rows;
+use pixelmatch::prepared::PreparedImage;
+use pixelmatch::ssim::{dssim_map, ssim};
+use pixelmatch::{pixelmatch, Options, PixelmatchError};
+
+fn gradient(w: u32, h: u32, seed: u32) -> Vec<u8> {
+    (0..w * h).flat_map(|i| [(i * 7 + seed) as u8, (i * 13) as u8, (i * 3 + seed * 5) as u8, 255]).collect()
+}
+
+#[test]
+fn test_prepared_image_matches_unprepared_results() {
+    let (w, h) = (16u32, 12u32);
+    let base = gradient(w, h, 0);
+    let mut changed = base.clone();
+    changed[(5 * w as usize + 3) * 4..][..3].fill(0);
+    let options = Options::default();
+
+    let prepared = PreparedImage::new(&base, w, h).unwrap();
+    let other = PreparedImage::new(&changed, w, h).unwrap();
+    let copy = base.clone();
+    let same = PreparedImage::new(&copy, w, h).unwrap();
+
+    assert!(prepared.same_as(&same));
+    assert_eq!(prepared.content_hash(), same.content_hash());
+    assert!(!prepared.same_as(&other));
+    assert_ne!(prepared.content_hash(), other.content_hash());
+    assert_eq!(prepared.row_hashes().len(), h as usize);
+    assert_eq!(prepared.luma().len(), (w * h) as usize);
+
+    let expected = pixelmatch(&base, &changed, None, w, h, &options).unwrap();
+    assert_eq!(prepared.compare(&other, None, &options).unwrap(), expected);
+    assert_eq!(prepared.align_rows(&other), align_rows(&base, h, &changed, h, w));
+    assert_eq!(prepared.ssim(&other).unwrap(), ssim(&base, &changed, w, h).unwrap());
+    assert_eq!(prepared.dssim_map(&other).unwrap(), dssim_map(&base, &changed, w, h).unwrap());
+    // The cached luma is reused across comparisons.
+    assert_eq!(prepared.ssim(&same).unwrap(), 1.0);
+}
+
+#[test]
+fn test_prepared_image_rejects_bad_sizes() {
+    let data = vec![0u8; 10];
+    assert!(matches!(PreparedImage::new(&data, 2, 2), Err(PixelmatchError::BufferLengthMismatch { .. })));
+
+    let (small, large) = (gradient(2, 2, 0), gradient(4, 4, 0));
+    let small = PreparedImage::new(&small, 2, 2).unwrap();
+    let large = PreparedImage::new(&large, 4, 4).unwrap();
+    assert!(!small.same_as(&large));
+    assert!(small.ssim(&large).is_err());
+    assert!(small.compare(&large, None, &Options::default()).is_err());
+}
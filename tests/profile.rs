@@ -0,0 +1,32 @@
+use std::ops::Range;
+
+use pixelmatch::profile::{diff_profile, DiffProfile};
+use pixelmatch::Options;
+
+#[test]
+fn test_diff_profile_counts_rows_and_columns() {
+    let (w, h) = (6u32, 4u32);
+    let white = vec![255u8; (w * h * 4) as usize];
+    let mut shifted = white.clone();
+    // A sidebar edge moved into columns 2 and 3 on every row, plus a stray pixel.
+    for y in 0..h {
+        for x in 2..4 {
+            shifted[((y * w + x) * 4) as usize..][..3].fill(0);
+        }
+    }
+    shifted[(w + 5) as usize * 4..][..3].fill(0);
+
+    let options = Options { detect_anti_aliasing: false, ..Default::default() };
+    let profile = diff_profile(&white, &shifted, w, h, &options).unwrap();
+    assert_eq!(profile.rows, [2, 3, 2, 2]);
+    assert_eq!(profile.columns, [0, 0, 4, 4, 0, 1]);
+    let ends = |bands: Vec<Range<u32>>| bands.into_iter().map(|b| (b.start, b.end)).collect::<Vec<_>>();
+    assert_eq!(ends(profile.column_bands(1)), [(2, 4), (5, 6)]);
+    assert_eq!(ends(profile.column_bands(h)), [(2, 4)]);
+    assert_eq!(ends(profile.row_bands(3)), [(1, 2)]);
+
+    let none = diff_profile(&white, &white, w, h, &options).unwrap();
+    assert_eq!(none, DiffProfile { rows: vec![0; 4], columns: vec![0; 6] });
+    assert!(none.column_bands(0).is_empty());
+    assert!(diff_profile(&white, &shifted[4..], w, h, &options).is_err());
+}
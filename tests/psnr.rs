@@ -0,0 +1,41 @@
+use pixelmatch::psnr::{mse, psnr};
+
+const WIDTH: u32 = 8;
+const HEIGHT: u32 = 4;
+
+fn solid(rgba: [u8; 4]) -> Vec<u8> {
+    rgba.repeat((WIDTH * HEIGHT) as usize)
+}
+
+#[test]
+fn test_mse_and_psnr() {
+    let white = solid([255, 255, 255, 255]);
+    assert_eq!(mse(&white, &white, WIDTH, HEIGHT).unwrap(), 0.0);
+    assert_eq!(psnr(&white, &white, WIDTH, HEIGHT).unwrap(), f64::INFINITY);
+
+    let black = solid([0, 0, 0, 255]);
+    assert_eq!(mse(&white, &black, WIDTH, HEIGHT).unwrap(), 65025.0);
+    assert_eq!(psnr(&white, &black, WIDTH, HEIGHT).unwrap(), 0.0);
+
+    // One channel off by 10 in every pixel: MSE 100 / 3.
+    let tinted = solid([245, 255, 255, 255]);
+    assert!((mse(&white, &tinted, WIDTH, HEIGHT).unwrap() - 100.0 / 3.0).abs() < 1e-9);
+    let db = psnr(&white, &tinted, WIDTH, HEIGHT).unwrap();
+    assert!((db - 10.0 * (65025.0 * 3.0 / 100.0f64).log10()).abs() < 1e-9, "{db}");
+}
+
+#[test]
+fn test_mse_blends_over_white() {
+    let white = solid([255, 255, 255, 255]);
+    assert_eq!(mse(&white, &solid([0, 0, 0, 0]), WIDTH, HEIGHT).unwrap(), 0.0);
+    assert_eq!(mse(&solid([255, 0, 0, 0]), &solid([0, 0, 255, 0]), WIDTH, HEIGHT).unwrap(), 0.0);
+    assert!(mse(&white, &solid([0, 0, 0, 128]), WIDTH, HEIGHT).unwrap() > 0.0);
+}
+
+#[test]
+fn test_mse_validates_buffers() {
+    let white = solid([255, 255, 255, 255]);
+    assert!(mse(&white, &white[4..], WIDTH, HEIGHT).is_err());
+    assert!(psnr(&white, &white, WIDTH, HEIGHT + 1).is_err());
+    assert_eq!(mse(&[], &[], 0, 0).unwrap(), 0.0);
+}
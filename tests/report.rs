@@ -0,0 +1,65 @@
+use pixelmatch::report::{HtmlReport, MarkdownReport, ReportEntry};
+use pixelmatch::MatchResult;
+
+fn entry(name: &str, diff_count: u32) -> ReportEntry {
+    let result = MatchResult { diff_count, identical: diff_count == 0, total_pixels: 10_000, ..Default::default() };
+    let mut entry = ReportEntry::new(name, result);
+    entry.diff_url = Some(format!("https://example.com/{name}-diff.png"));
+    entry
+}
+
+#[test]
+fn test_markdown_lists_failures_first() {
+    let entries = vec![entry("ok", 0), entry("broken", 250)];
+    let md = MarkdownReport { show_passing: true, ..Default::default() }.render(&entries);
+
+    assert!(md.contains("**2** compared · **1** passed · **1** failed"));
+    assert!(md.contains("1%20failed-red"), "failing badge expected:\n{md}");
+    let broken = md.find("| ❌ | `broken` | 250 | 2.50% |").expect("failing row");
+    let ok = md.find("| ✅ | `ok` | 0 | 0.00% |").expect("passing row");
+    assert!(broken < ok, "failures should be listed first");
+    assert!(md.contains("![broken](https://example.com/broken-diff.png)"));
+}
+
+#[test]
+fn test_markdown_encodes_link_delimiters_in_urls() {
+    let md = MarkdownReport { inline_images: true, ..Default::default() }.render(&[entry("home (dark) |2", 1)]);
+    let url = "https://example.com/home%20%28dark%29%20%7C2-diff.png";
+    assert!(md.contains(&format!("[diff]({url})")), "{md}");
+    assert!(md.contains(&format!("]({url})\n")), "{md}");
+}
+
+#[test]
+fn test_markdown_all_passing_has_no_table() {
+    let md = MarkdownReport::default().render(&[entry("a", 0), entry("b", 0)]);
+    assert!(md.contains("passed-brightgreen"));
+    assert!(!md.contains("| Name |"));
+}
+
+#[test]
+fn test_markdown_respects_size_limit() {
+    let entries: Vec<_> = (0..2000).map(|i| entry(&format!("screen-{i}"), 10)).collect();
+    let report = MarkdownReport { max_len: 4096, ..Default::default() };
+    let md = report.render(&entries);
+
+    assert!(md.len() <= 4096, "report length {} exceeds limit", md.len());
+    assert!(md.contains("more not shown"));
+}
+
+#[test]
+fn test_html_report_escapes_and_links_thumbnails() {
+    let mut failing = entry("<login>", 250);
+    failing.diff_thumbnail_url = Some("thumbnails/diff/login.png".to_string());
+    let mut missing = entry("new", 0);
+    missing.passed = false;
+    missing.message = Some("No baseline".to_string());
+
+    let html = HtmlReport::default().render(&[entry("ok", 0), failing, missing]);
+    assert!(html.starts_with("<!DOCTYPE html>"));
+    assert!(html.contains("&lt;login&gt;"));
+    assert!(!html.contains("<login>"));
+    let link = r#"<a href="https://example.com/&lt;login&gt;-diff.png"><img src="thumbnails/diff/login.png""#;
+    assert!(html.contains(link));
+    assert!(html.contains("<p>No baseline</p>"));
+    assert!(!html.contains("<h2><span class=\"badge\">passed</span> ok</h2>"));
+}
@@ -0,0 +1,57 @@
+use pixelmatch::review::{Review, ReviewEntry, ReviewStatus};
+
+mod common;
+
+use common::temp_dir;
+
+#[test]
+fn test_review_manifest_round_trip_and_decisions() {
+    let dir = temp_dir("review-decisions");
+    let manifest = dir.join("review.json");
+    for (name, baseline, candidate) in [("a", "old a", "new a"), ("b", "old b", "new b")] {
+        std::fs::write(dir.join(format!("{name}.base")), baseline).unwrap();
+        std::fs::write(dir.join(format!("{name}.cand")), candidate).unwrap();
+    }
+
+    let mut review = Review::open(&manifest).unwrap();
+    assert!(review.entries().is_empty());
+    for name in ["a", "b"] {
+        let mut entry = ReviewEntry::new(name, dir.join(format!("{name}.base")), dir.join(format!("{name}.cand")));
+        entry.diff_count = Some(7);
+        review.record(entry);
+    }
+    review.save().unwrap();
+
+    let mut review = Review::open(&manifest).unwrap();
+    assert_eq!(review.pending().count(), 2);
+    assert_eq!(review.get("a").unwrap().diff_count, Some(7));
+    review.approve("a").unwrap();
+    review.reject("b").unwrap();
+    assert!(review.approve("missing").is_err());
+
+    let read = |name: &str| std::fs::read_to_string(dir.join(name)).unwrap();
+    assert_eq!(read("a.base"), "new a");
+    assert_eq!(read("b.base"), "old b");
+    let names: Vec<_> = std::fs::read_dir(&dir).unwrap().map(|e| e.unwrap().file_name()).collect();
+    assert!(names.iter().all(|n| !n.to_string_lossy().ends_with(".tmp")), "{names:?}");
+
+    let mut review = Review::open(&manifest).unwrap();
+    assert_eq!(review.get("a").unwrap().status, ReviewStatus::Approved);
+    assert_eq!(review.get("b").unwrap().status, ReviewStatus::Rejected);
+
+    // Recording a failure again reopens it.
+    review.record(ReviewEntry::new("b", dir.join("b.base"), dir.join("b.cand")));
+    review.prune();
+    assert_eq!(review.entries().iter().map(|e| e.name.as_str()).collect::<Vec<_>>(), ["b"]);
+
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn test_review_rejects_newer_manifest_versions() {
+    let dir = temp_dir("review-version");
+    let manifest = dir.join("review.json");
+    std::fs::write(&manifest, r#"{"version": 99, "entries": []}"#).unwrap();
+    assert_eq!(Review::open(&manifest).unwrap_err().kind(), std::io::ErrorKind::InvalidData);
+    let _ = std::fs::remove_dir_all(&dir);
+}
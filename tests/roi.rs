@@ -0,0 +1,70 @@
+use pixelmatch::roi::pixelmatch_roi;
+use pixelmatch::{diff_mask, pixelmatch, Options, PixelmatchError, Rect};
+
+mod common;
+
+use common::read_image;
+
+fn crop(data: &[u8], width: u32, channels: usize, r: Rect) -> Vec<u8> {
+    let (w, x, rw) = (width as usize * channels, r.x as usize * channels, r.width as usize * channels);
+    (r.y as usize..(r.y + r.height) as usize).flat_map(|y| &data[y * w + x..][..rw]).copied().collect()
+}
+
+#[test]
+fn test_roi_matches_full_comparison() {
+    let (img1, w, h) = read_image("1a");
+    let (img2, _, _) = read_image("1b");
+    let options = Options::default();
+    let mut full_diff = vec![0u8; img1.len()];
+    let full = pixelmatch(&img1, &img2, Some(&mut full_diff), w, h, &options).unwrap();
+    let mask: Vec<u8> = diff_mask(&img1, &img2, w, h, &options).unwrap().into_iter().map(u8::from).collect();
+
+    // The top-left quarter of the changed area, cutting through changes.
+    let (x0, y0, x1, y1) = full.diff_bounds.unwrap();
+    let roi = Rect::new(x0, y0, (x1 - x0) / 2 + 1, (y1 - y0) / 2 + 1);
+    let mut diff = vec![0u8; (roi.width * roi.height * 4) as usize];
+    let result = pixelmatch_roi(&img1, &img2, Some(&mut diff), w, h, roi, &options).unwrap();
+    let expected = crop(&mask, w, 1, roi).iter().filter(|&&m| m != 0).count() as u32;
+    assert!(expected > 0);
+    assert_eq!(result.diff_count, expected);
+    assert_eq!(result.total_pixels, (roi.width * roi.height) as u64);
+    assert!(!result.identical);
+    assert_eq!(diff, crop(&full_diff, w, 4, roi));
+    let (x0, y0, x1, y1) = result.diff_bounds.unwrap();
+    assert!(x0 >= roi.x && y0 >= roi.y && x1 < roi.x + roi.width && y1 < roi.y + roi.height);
+
+    assert_eq!(pixelmatch_roi(&img1, &img2, None, w, h, roi, &options).unwrap(), result);
+    let whole = pixelmatch_roi(&img1, &img2, None, w, h, Rect::new(0, 0, w, h), &options).unwrap();
+    assert_eq!(whole, pixelmatch(&img1, &img2, None, w, h, &options).unwrap());
+}
+
+#[test]
+fn test_roi_of_unchanged_area() {
+    let (w, h) = (8u32, 8u32);
+    let img1 = vec![200u8; (w * h * 4) as usize];
+    let mut img2 = img1.clone();
+    img2[..4].copy_from_slice(&[0, 0, 0, 255]);
+    let result = pixelmatch_roi(&img1, &img2, None, w, h, Rect::new(2, 2, 4, 4), &Options::default()).unwrap();
+    assert!(result.identical);
+    assert_eq!((result.diff_count, result.diff_bounds), (0, None));
+}
+
+#[test]
+fn test_roi_rejects_bad_input() {
+    let img = vec![0u8; 4 * 4 * 4];
+    let options = Options::default();
+    assert!(matches!(
+        pixelmatch_roi(&img, &img, None, 4, 4, Rect::new(2, 2, 3, 1), &options),
+        Err(PixelmatchError::RegionOutOfBounds { .. })
+    ));
+    let mut small = vec![0u8; 4];
+    assert!(matches!(
+        pixelmatch_roi(&img, &img, Some(&mut small), 4, 4, Rect::new(0, 0, 2, 2), &options),
+        Err(PixelmatchError::OutputSizeMismatch { .. })
+    ));
+    let blurred = Options { pre_blur: Some(1.0), ..Default::default() };
+    assert!(matches!(
+        pixelmatch_roi(&img, &img, None, 4, 4, Rect::new(0, 0, 2, 2), &blurred),
+        Err(PixelmatchError::ConflictingOptions(_))
+    ));
+}
@@ -0,0 +1,34 @@
+use pixelmatch::rows::{changed_rows, ChangedRows};
+use pixelmatch::{pixelmatch, Options};
+
+#[test]
+fn test_changed_rows_round_trip() {
+    let (w, h) = (4u32, 5u32);
+    let white = vec![255u8; (w * h * 4) as usize];
+    let mut changed = white.clone();
+    changed[(w + 1) as usize * 4..][..3].fill(0);
+    changed[(3 * w + 2) as usize * 4..][..3].fill(0);
+
+    let options = Options { detect_anti_aliasing: false, diff_mask: true, ..Default::default() };
+    let (result, rows) = changed_rows(&white, &changed, w, h, &options).unwrap();
+    assert_eq!(result.diff_count, 2);
+    assert_eq!(rows.rows.iter().map(|r| r.y).collect::<Vec<_>>(), [1, 3]);
+    assert_eq!(rows.rows[0].pixels.len(), 16);
+
+    // With diff_mask the untouched rows are transparent, so the full diff comes back exactly.
+    let mut diff = vec![0u8; white.len()];
+    pixelmatch(&white, &changed, Some(&mut diff), w, h, &options).unwrap();
+    assert_eq!(rows.to_image(), diff);
+
+    let mut bytes = Vec::new();
+    rows.write(&mut bytes).unwrap();
+    assert_eq!(bytes.len(), 8 + 12 + 2 * (4 + 16));
+    assert_eq!(ChangedRows::read(bytes.as_slice()).unwrap(), rows);
+    assert!(ChangedRows::read(&bytes[..bytes.len() - 1]).is_err());
+    bytes[0] = b'X';
+    assert!(ChangedRows::read(bytes.as_slice()).is_err());
+
+    let (result, rows) = changed_rows(&white, &white, w, h, &options).unwrap();
+    assert!(result.identical && rows.rows.is_empty());
+    assert!(changed_rows(&white, &changed[4..], w, h, &options).is_err());
+}
@@ -0,0 +1,55 @@
+use pixelmatch::snapshot::{Snapshot, SnapshotError, SnapshotOutcome};
+use pixelmatch::{Options, PixelmatchError};
+
+mod common;
+
+use common::{solid, temp_dir};
+
+#[test]
+fn test_snapshot_create_match_and_reject() {
+    let dir = temp_dir("snapshot-lifecycle");
+    let snapshot = Snapshot::new("group/button").root(&dir).update(false);
+    let white = solid(4, 4, [255, 255, 255, 255]);
+    let mut changed = white.clone();
+    changed.data[..4].copy_from_slice(&[0, 0, 0, 255]);
+
+    assert_eq!(snapshot.check(&white).unwrap(), SnapshotOutcome::Created);
+    assert!(dir.join("group/button.png").exists());
+    assert!(matches!(snapshot.check(&white).unwrap(), SnapshotOutcome::Matched(r) if r.identical));
+
+    match snapshot.check(&changed) {
+        Err(SnapshotError::Mismatch { result, candidate, .. }) => {
+            assert_eq!(result.diff_count, 1);
+            assert_eq!(candidate, dir.join("group/button.new.png"));
+            assert!(candidate.exists());
+        }
+        other => panic!("expected a mismatch, got {other:?}"),
+    }
+    let tolerant = snapshot.clone().max_diff_pixels(1);
+    assert!(matches!(tolerant.check(&changed).unwrap(), SnapshotOutcome::Matched(r) if r.diff_count == 1));
+    assert!(!snapshot.candidate_path().exists(), "a passing check removes the stale candidate");
+
+    let resized = solid(2, 2, [255, 255, 255, 255]);
+    assert!(matches!(snapshot.check(&resized), Err(SnapshotError::DimensionMismatch { .. })));
+
+    let invalid = snapshot.clone().options(Options { threshold: 2.0, ..Options::default() });
+    let err = invalid.check(&white).unwrap_err();
+    assert!(matches!(err, SnapshotError::Compare(PixelmatchError::InvalidThreshold(_))), "{err:?}");
+
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn test_snapshot_update_mode_overwrites_baseline() {
+    let dir = temp_dir("snapshot-update");
+    let snapshot = Snapshot::new("page").root(&dir).update(false);
+    snapshot.check(&solid(2, 2, [255, 0, 0, 255])).unwrap();
+
+    let blue = solid(2, 2, [0, 0, 255, 255]);
+    let outcome = snapshot.clone().update(true).check(&blue).unwrap();
+    assert!(matches!(outcome, SnapshotOutcome::Updated(r) if r.diff_count == 4));
+    assert_eq!(pixelmatch::io::read_png(snapshot.path()).unwrap(), blue);
+    assert!(matches!(snapshot.assert(&blue), SnapshotOutcome::Matched(_)));
+
+    let _ = std::fs::remove_dir_all(&dir);
+}
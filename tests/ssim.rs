@@ -0,0 +1,89 @@
+use pixelmatch::ssim::{ms_ssim, ssim, MS_SSIM_WEIGHTS};
+
+const WIDTH: u32 = 64;
+const HEIGHT: u32 = 48;
+
+/// A textured gray image: diagonal stripes plus a checkerboard, darkened by `darken`, with
+/// a deterministic noise of up to `noise` levels.
+fn image(darken: u8, noise: u8) -> Vec<u8> {
+    (0..WIDTH * HEIGHT)
+        .flat_map(|i| {
+            let (x, y) = (i % WIDTH, i / WIDTH);
+            let base = if (x + y) / 4 % 2 == 0 { 200 } else { 60 } + if (x / 8 + y / 8) % 2 == 0 { 30 } else { 0 };
+            let jitter = if noise == 0 { 0 } else { (i.wrapping_mul(2654435761) >> 13) % u32::from(noise) };
+            let v = (base - u32::from(darken) + jitter).min(255) as u8;
+            [v, v, v, 255]
+        })
+        .collect()
+}
+
+#[test]
+fn test_ssim_orders_distortions() {
+    let original = image(0, 0);
+    assert!((ssim(&original, &original, WIDTH, HEIGHT).unwrap() - 1.0).abs() < 1e-6);
+    let slight = ssim(&original, &image(0, 8), WIDTH, HEIGHT).unwrap();
+    let heavy = ssim(&original, &image(0, 60), WIDTH, HEIGHT).unwrap();
+    assert!(1.0 > slight && slight > heavy, "{slight} {heavy}");
+    assert!(ssim(&original, &original[4..], WIDTH, HEIGHT).is_err());
+}
+
+#[test]
+fn test_ms_ssim() {
+    let original = image(0, 0);
+    let score = |other: &[u8], weights: &[f64]| ms_ssim(&original, other, WIDTH, HEIGHT, weights).unwrap();
+    assert!((score(&original, &MS_SSIM_WEIGHTS) - 1.0).abs() < 1e-6);
+
+    let (slight, heavy) = (image(0, 8), image(0, 60));
+    let (slight_ms, heavy_ms) = (score(&slight, &MS_SSIM_WEIGHTS), score(&heavy, &MS_SSIM_WEIGHTS));
+    assert!(1.0 > slight_ms && slight_ms > heavy_ms && heavy_ms >= 0.0, "{slight_ms} {heavy_ms}");
+    // Fine-grained noise matters less at coarse scales than in single-scale SSIM.
+    assert!(heavy_ms > ssim(&original, &heavy, WIDTH, HEIGHT).unwrap());
+    // A one-level pyramid is plain SSIM.
+    let single = score(&heavy, &[1.0]);
+    assert!((single - ssim(&original, &heavy, WIDTH, HEIGHT).unwrap()).abs() < 1e-6);
+    // 48 rows only allow three levels of at least 11 rows (48, 24 and 12); more weights are
+    // dropped.
+    assert_eq!(score(&heavy, &MS_SSIM_WEIGHTS[..3]), score(&heavy, &MS_SSIM_WEIGHTS));
+    assert_eq!(score(&heavy, &[]), 1.0);
+}
+
+#[test]
+fn test_dssim_map_and_heatmap() {
+    use pixelmatch::ssim::{dssim_map, heatmap};
+    let original = image(0, 0);
+    // Replace the texture of the top-left quarter with its average gray: the mean colour there
+    // barely changes, the structure does.
+    let mut flattened = original.clone();
+    for y in 0..HEIGHT / 2 {
+        for x in 0..WIDTH / 2 {
+            flattened[((y * WIDTH + x) * 4) as usize..][..3].fill(145);
+        }
+    }
+    let map = dssim_map(&original, &flattened, WIDTH, HEIGHT).unwrap();
+    assert_eq!(map.len(), (WIDTH * HEIGHT) as usize);
+    let at = |x: u32, y: u32| map[(y * WIDTH + x) as usize];
+    assert!(at(8, 8) > 0.2, "{}", at(8, 8));
+    assert!(at(50, 40) < 1e-6, "{}", at(50, 40));
+    assert!(dssim_map(&original, &original, WIDTH, HEIGHT).unwrap().iter().all(|&v| v < 1e-6));
+
+    let colours = heatmap(&[0.0, 0.05, 0.1, 0.5], 0.1);
+    assert_eq!(colours.len(), 16);
+    assert_eq!(colours[..4], [0, 0, 4, 255]);
+    assert_eq!(colours[4..8], [188, 55, 84, 255]);
+    assert_eq!(colours[8..12], [252, 255, 164, 255]);
+    assert_eq!(colours[12..], colours[8..12], "values above the maximum saturate");
+}
+
+#[test]
+fn test_pixelmatch_ssim() {
+    use pixelmatch::ssim::{dssim_map, heatmap, pixelmatch_ssim, DSSIM_MAP_MAX};
+    let (original, noisy) = (image(0, 0), image(0, 60));
+    let mut output = vec![0; original.len()];
+    let score = pixelmatch_ssim(&original, &noisy, Some(&mut output), WIDTH, HEIGHT).unwrap();
+    assert_eq!(score, ssim(&original, &noisy, WIDTH, HEIGHT).unwrap());
+    let map = dssim_map(&original, &noisy, WIDTH, HEIGHT).unwrap();
+    assert_eq!(output, heatmap(&map, DSSIM_MAP_MAX));
+
+    assert!((pixelmatch_ssim(&original, &original, None, WIDTH, HEIGHT).unwrap() - 1.0).abs() < 1e-6);
+    assert!(pixelmatch_ssim(&original, &noisy, Some(&mut output[4..]), WIDTH, HEIGHT).is_err());
+}
@@ -0,0 +1,44 @@
+use std::sync::Arc;
+
+use pixelmatch::stats::{FlakyImage, SuiteStats, RATIO_BUCKETS};
+use pixelmatch::MatchResult;
+
+fn result(diff_count: u32) -> MatchResult {
+    MatchResult { diff_count, identical: diff_count == 0, ..Default::default() }
+}
+
+#[test]
+fn test_suite_stats_summary() {
+    let stats = Arc::new(SuiteStats::new());
+    let handles: Vec<_> = (0..4u32)
+        .map(|i| {
+            let stats = Arc::clone(&stats);
+            std::thread::spawn(move || stats.record(format!("page-{i}"), &result(i * 10), 1000, i == 0))
+        })
+        .collect();
+    for h in handles {
+        h.join().unwrap();
+    }
+    // Retries of a flaky page: fails once, then passes.
+    stats.record("retry", &result(5), 1000, false);
+    stats.record("retry", &result(0), 1000, true);
+
+    let summary = stats.summary(2);
+    assert_eq!((summary.comparisons, summary.images, summary.passed, summary.failed), (6, 5, 2, 4));
+    assert_eq!(summary.total_pixels, 6000);
+    assert_eq!(summary.diff_pixels, 65);
+    let worst: Vec<_> = summary.worst.iter().map(|o| (o.name.as_str(), o.diff_count)).collect();
+    assert_eq!(worst, [("page-3", 30), ("page-2", 20)]);
+
+    let counts: Vec<_> = summary.ratio_histogram.iter().map(|b| b.count).collect();
+    assert_eq!(summary.ratio_histogram.len(), RATIO_BUCKETS.len());
+    assert_eq!(counts, [2, 0, 0, 2, 2, 0]);
+    assert_eq!(
+        summary.flaky,
+        [FlakyImage { name: "retry".into(), runs: 2, failures: 1, min_diff_count: 0, max_diff_count: 5 }]
+    );
+
+    let json = serde_json::to_value(&summary).unwrap();
+    assert_eq!(json["worst"][0]["name"], "page-3");
+    assert_eq!(json["flaky"][0]["runs"], 2);
+}
@@ -0,0 +1,72 @@
+use std::path::Path;
+
+use pixelmatch::io::write_png;
+use pixelmatch::tiles::{compare_tile_trees, compare_tile_trees_with, TileId, TileOutcome};
+use pixelmatch::Options;
+
+mod common;
+
+use common::temp_dir;
+
+/// Write a 4x4 tile at `root/z/x/y.png`, white with the first `dark` pixels black.
+fn tile(root: &Path, z: u32, x: u32, y: u32, dark: usize) {
+    let dir = root.join(z.to_string()).join(x.to_string());
+    std::fs::create_dir_all(&dir).unwrap();
+    let data: Vec<u8> = (0..16).flat_map(|i| if i < dark { [0, 0, 0, 255] } else { [255; 4] }).collect();
+    write_png(dir.join(format!("{y}.png")), &data, 4, 4).unwrap();
+}
+
+#[test]
+fn test_tile_id_from_path() {
+    assert_eq!(TileId::from_path(Path::new("14/8192/5461.png")), Some(TileId { z: 14, x: 8192, y: 5461 }));
+    assert_eq!(TileId { z: 3, x: 1, y: 2 }.to_string(), "3/1/2");
+    for path in ["14/8192.png", "a/1/2.png", "1/2/3.jpg", "0/1/2/3.png", "1/2/x.png"] {
+        assert_eq!(TileId::from_path(Path::new(path)), None, "{path}");
+    }
+}
+
+#[test]
+fn test_compare_tile_trees() {
+    let dir = temp_dir("tiles-trees");
+    let (base, cand) = (dir.join("base"), dir.join("cand"));
+    for (z, x, y) in [(0, 0, 0), (1, 0, 0), (1, 0, 1), (1, 1, 0)] {
+        tile(&base, z, x, y, 0);
+    }
+    tile(&cand, 0, 0, 0, 0);
+    tile(&cand, 1, 0, 0, 3);
+    tile(&cand, 1, 0, 1, 0);
+    tile(&cand, 1, 1, 1, 0);
+    std::fs::write(cand.join("README.txt"), "not a tile").unwrap();
+
+    let options = Options { detect_anti_aliasing: false, ..Default::default() };
+    let report = compare_tile_trees(&base, &cand, &options).unwrap();
+    let ids: Vec<String> = report.comparisons.iter().map(|c| c.id.to_string()).collect();
+    assert_eq!(ids, ["0/0/0", "1/0/0", "1/0/1"]);
+    assert_eq!(report.comparisons[1].diff_count(), Some(3));
+    assert_eq!(report.missing_baseline, [TileId { z: 1, x: 1, y: 1 }]);
+    assert_eq!(report.missing_candidate, [TileId { z: 1, x: 1, y: 0 }]);
+    assert!(!report.passed());
+
+    let stats = report.zoom_stats();
+    assert_eq!(stats.len(), 2);
+    assert_eq!((stats[0].zoom, stats[0].compared, stats[0].differing, stats[0].diff_pixels), (0, 1, 0, 0));
+    let z1 = stats[1];
+    assert_eq!((z1.compared, z1.differing, z1.missing_baseline, z1.missing_candidate), (2, 1, 1, 1));
+    assert_eq!((z1.diff_pixels, z1.total_pixels), (3, 32));
+    assert!((z1.diff_ratio() - 3.0 / 32.0).abs() < 1e-12);
+
+    // Per-tile options, e.g. ignoring the changed corner at zoom 1 only.
+    let lenient = compare_tile_trees_with(&base, &cand, |id| Options {
+        ignore_regions: if id.z == 1 { vec![pixelmatch::Rect::new(0, 0, 4, 1)] } else { Vec::new() },
+        ..options.clone()
+    })
+    .unwrap();
+    assert!(lenient.passed(), "missing tiles are skipped");
+
+    // Tiles of different sizes are reported, not compared.
+    write_png(cand.join("0/0/0.png"), &[255; 4], 1, 1).unwrap();
+    let report = compare_tile_trees(&base, &cand, &options).unwrap();
+    assert!(matches!(report.comparisons[0].outcome, TileOutcome::DimensionMismatch { .. }));
+    assert_eq!(report.zoom_stats()[0].errors, 1);
+    let _ = std::fs::remove_dir_all(&dir);
+}
@@ -0,0 +1,75 @@
+use std::fmt::Debug;
+use std::sync::{Arc, Mutex};
+
+use pixelmatch::{pixelmatch, Options};
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Id, Record};
+use tracing::{Event, Metadata, Subscriber};
+
+/// Records span names and event messages, in order.
+#[derive(Clone, Default)]
+struct Recorder(Arc<Mutex<Vec<String>>>);
+
+struct Message<'a>(&'a mut String);
+
+impl Visit for Message<'_> {
+    fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+        if field.name() == "message" {
+            *self.0 = format!("{value:?}");
+        }
+    }
+}
+
+impl Subscriber for Recorder {
+    fn enabled(&self, metadata: &Metadata<'_>) -> bool {
+        metadata.target() == "pixelmatch"
+    }
+    fn new_span(&self, span: &Attributes<'_>) -> Id {
+        let mut log = self.0.lock().unwrap();
+        log.push(format!("span {}", span.metadata().name()));
+        Id::from_u64(log.len() as u64)
+    }
+    fn record(&self, _: &Id, _: &Record<'_>) {}
+    fn record_follows_from(&self, _: &Id, _: &Id) {}
+    fn event(&self, event: &Event<'_>) {
+        let mut message = String::new();
+        event.record(&mut Message(&mut message));
+        self.0.lock().unwrap().push(format!("event {message}"));
+    }
+    fn enter(&self, _: &Id) {}
+    fn exit(&self, _: &Id) {}
+}
+
+fn record(f: impl FnOnce()) -> Vec<String> {
+    let recorder = Recorder::default();
+    tracing::subscriber::with_default(recorder.clone(), f);
+    let log = recorder.0.lock().unwrap().clone();
+    log
+}
+
+#[test]
+fn test_tracing_spans_and_events() {
+    let white = vec![255u8; 16 * 16 * 4];
+    let mut changed = white.clone();
+    changed[..3].fill(0);
+
+    let log = record(|| {
+        let options = Options { median_filter: true, isolation_radius: 1, ..Default::default() };
+        pixelmatch(&white, &changed, None, 16, 16, &options).unwrap();
+    });
+    let expected = ["span pixelmatch", "span prefilter", "span refine_mask", "span compare_rows"];
+    let spans: Vec<&str> = log.iter().filter(|l| l.starts_with("span")).map(String::as_str).collect();
+    assert_eq!(spans, expected);
+    assert_eq!(log.last().unwrap(), "event comparison complete");
+
+    let log = record(|| {
+        pixelmatch(&white, &white, None, 16, 16, &Options::default()).unwrap();
+    });
+    assert_eq!(log, ["span pixelmatch", "event images are identical"]);
+
+    let log = record(|| {
+        let invalid = Options { threshold: 2.0, ..Default::default() };
+        assert!(pixelmatch(&white, &changed, None, 16, 16, &invalid).is_err());
+    });
+    assert_eq!(log, ["span pixelmatch", "event comparison rejected"]);
+}
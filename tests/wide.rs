@@ -0,0 +1,53 @@
+use pixelmatch::wide::pixelmatch_wide;
+use pixelmatch::{pixelmatch, Options, PixelmatchError};
+
+mod common;
+
+use common::read_image;
+
+#[test]
+fn test_wide_components_match_bytes() {
+    let (img1, w, h) = read_image("1a");
+    let (img2, _, _) = read_image("1b");
+    let options = Options::default();
+    let mut expected_diff = vec![0u8; img1.len()];
+    let expected = pixelmatch(&img1, &img2, Some(&mut expected_diff), w, h, &options).unwrap();
+    assert!(expected.diff_count > 0 && expected.aa_count > 0);
+
+    let mut diff = vec![0u8; img1.len()];
+    assert_eq!(pixelmatch_wide(&img1, &img2, Some(&mut diff), w, h, &options).unwrap(), expected);
+    assert_eq!(diff, expected_diff);
+
+    let widen = |img: &[u8]| img.iter().map(|&c| c as u16 * 257).collect::<Vec<u16>>();
+    let result = pixelmatch_wide(&widen(&img1), &widen(&img2), None, w, h, &options).unwrap();
+    assert_eq!((result.diff_count, result.aa_count), (expected.diff_count, expected.aa_count));
+
+    let float = |img: &[u8]| img.iter().map(|&c| c as f32 / 255.0).collect::<Vec<f32>>();
+    let result = pixelmatch_wide(&float(&img1), &float(&img2), None, w, h, &options).unwrap();
+    assert_eq!((result.diff_count, result.aa_count), (expected.diff_count, expected.aa_count));
+}
+
+#[test]
+fn test_wide_components_keep_detail() {
+    // Two greys one 16-bit step apart are the same 8-bit colour.
+    let img1 = [30000u16, 30000, 30000, 65535].repeat(4);
+    let mut img2 = img1.clone();
+    img2[..3].fill(30100);
+    let options = Options { threshold: 0.0, detect_anti_aliasing: false, ..Default::default() };
+    assert_eq!(pixelmatch_wide(&img1, &img2, None, 2, 2, &options).unwrap().diff_count, 1);
+    assert!(pixelmatch_wide(&img1, &img1, None, 2, 2, &options).unwrap().identical);
+}
+
+#[test]
+fn test_wide_components_reject_byte_options() {
+    let img = vec![0.5f32; 16];
+    let options = Options { shift_tolerance: 1, ..Default::default() };
+    assert!(matches!(
+        pixelmatch_wide(&img, &img, None, 2, 2, &options),
+        Err(PixelmatchError::ConflictingOptions(_))
+    ));
+    assert!(matches!(
+        pixelmatch_wide(&img, &img[..12], None, 2, 2, &Options::default()),
+        Err(PixelmatchError::BufferLengthMismatch { .. } | PixelmatchError::ImageSizeMismatch { .. })
+    ));
+}